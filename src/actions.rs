@@ -0,0 +1,508 @@
+// Every user-facing command in one place, so keyboard shortcuts, controllers, the shortcut
+// overlay and the command palette all dispatch through VideoApp::perform.
+use crate::i18n::tr;
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    PlayPause,
+    PrevFrame,
+    NextFrame,
+    SetStart,
+    SetEnd,
+    PreviewRange,
+    CutCheck,
+    TrimPreview,
+    AddRange,
+    ChaptersToRanges,
+    RemoveRange,
+    PrevRange,
+    NextRange,
+    ClearCrop,
+    SetDefaultCrop,
+    AutoCrop,
+    MatchCrop,
+    ResetZoom,
+    ScanText,
+    Transcribe,
+    RandomizeSplits,
+    ClassBalance,
+    SessionStats,
+    PrevFile,
+    NextFile,
+    OpenInputFolder,
+    AddRemoteSource,
+    OpenOutputFolder,
+    SaveProject,
+    OpenProject,
+    MergeAnnotations,
+    Export,
+    PreviewCommands,
+    ExportMarked,
+    RetryFailed,
+    RebuildDataset,
+    AuditOutput,
+    RenameOutputs,
+    ProjectRules,
+    LinkedProjects,
+    DatasetVersions,
+    PackageDataset,
+    VerifyChecksums,
+    UploadDataset,
+    EncoderSettings,
+    QaExport,
+    ReelFile,
+    ReelFolder,
+    ShowLog,
+    CheckForUpdates,
+    ShowShortcuts,
+    CommandPalette,
+}
+
+impl Action {
+    pub const ALL: &[Action] = &[
+        Action::PlayPause,
+        Action::PrevFrame,
+        Action::NextFrame,
+        Action::SetStart,
+        Action::SetEnd,
+        Action::PreviewRange,
+        Action::CutCheck,
+        Action::TrimPreview,
+        Action::AddRange,
+        Action::ChaptersToRanges,
+        Action::RemoveRange,
+        Action::PrevRange,
+        Action::NextRange,
+        Action::ClearCrop,
+        Action::SetDefaultCrop,
+        Action::AutoCrop,
+        Action::MatchCrop,
+        Action::ResetZoom,
+        Action::ScanText,
+        Action::Transcribe,
+        Action::RandomizeSplits,
+        Action::ClassBalance,
+        Action::SessionStats,
+        Action::PrevFile,
+        Action::NextFile,
+        Action::OpenInputFolder,
+        Action::AddRemoteSource,
+        Action::OpenOutputFolder,
+        Action::SaveProject,
+        Action::OpenProject,
+        Action::MergeAnnotations,
+        Action::Export,
+        Action::PreviewCommands,
+        Action::ExportMarked,
+        Action::RetryFailed,
+        Action::RebuildDataset,
+        Action::AuditOutput,
+        Action::RenameOutputs,
+        Action::ProjectRules,
+        Action::LinkedProjects,
+        Action::DatasetVersions,
+        Action::PackageDataset,
+        Action::VerifyChecksums,
+        Action::UploadDataset,
+        Action::EncoderSettings,
+        Action::QaExport,
+        Action::ReelFile,
+        Action::ReelFolder,
+        Action::ShowLog,
+        Action::CheckForUpdates,
+        Action::ShowShortcuts,
+        Action::CommandPalette,
+    ];
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::PlayPause => "Play / Pause",
+            Action::PrevFrame => "Previous Frame",
+            Action::NextFrame => "Next Frame",
+            Action::SetStart => "Set Start (In-point)",
+            Action::SetEnd => "Set End (Out-point)",
+            Action::PreviewRange => "Preview Range",
+            Action::CutCheck => "Cut Check",
+            Action::TrimPreview => "Preview Edge Trim",
+            Action::AddRange => "Add Range / Crop",
+            Action::ChaptersToRanges => "Convert Chapters to Ranges",
+            Action::RemoveRange => "Remove Current Range / Crop",
+            Action::PrevRange => "Previous Range",
+            Action::NextRange => "Next Range",
+            Action::ClearCrop => "Clear Crop",
+            Action::SetDefaultCrop => "Use Crop as File Default",
+            Action::AutoCrop => "Auto Crop",
+            Action::MatchCrop => "Match Neighbour Range's Crop",
+            Action::ResetZoom => "Reset Zoom",
+            Action::ScanText => "Scan for Burned-in Text",
+            Action::Transcribe => "Transcribe Range Speech",
+            Action::RandomizeSplits => "Randomize Remaining Splits",
+            Action::ClassBalance => "Show Class Balance",
+            Action::SessionStats => "Show Session Statistics",
+            Action::PrevFile => "Previous File",
+            Action::NextFile => "Next File",
+            Action::OpenInputFolder => "Open Input Folder…",
+            Action::AddRemoteSource => "Add Remote Source…",
+            Action::OpenOutputFolder => "Choose Output Folder…",
+            Action::SaveProject => "Save Project",
+            Action::OpenProject => "Open Project…",
+            Action::MergeAnnotations => "Merge Annotations…",
+            Action::Export => "Run Export",
+            Action::PreviewCommands => "Preview Export Commands…",
+            Action::ExportMarked => "Export Marked Files",
+            Action::RetryFailed => "Retry Failed Exports",
+            Action::RebuildDataset => "Rebuild Dataset",
+            Action::AuditOutput => "Audit Output Folder",
+            Action::RenameOutputs => "Rename / Re-caption Outputs…",
+            Action::ProjectRules => "Project Rules…",
+            Action::LinkedProjects => "Linked Projects…",
+            Action::DatasetVersions => "Dataset Versions…",
+            Action::PackageDataset => "Package Dataset…",
+            Action::VerifyChecksums => "Verify Checksums…",
+            Action::UploadDataset => "Upload to Object Storage…",
+            Action::EncoderSettings => "Encoder Settings…",
+            Action::QaExport => "QA Sample Export",
+            Action::ReelFile => "Export Reel of This File",
+            Action::ReelFolder => "Export Reel of All Files",
+            Action::ShowLog => "Show Log",
+            Action::CheckForUpdates => "Check for Updates",
+            Action::ShowShortcuts => "Show Keyboard Shortcuts",
+            Action::CommandPalette => "Command Palette",
+        }
+    }
+
+    pub fn shortcut(self) -> Option<KeyboardShortcut> {
+        let key = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+        match self {
+            Action::PlayPause => key(Key::Space),
+            Action::PrevFrame => key(Key::ArrowLeft),
+            Action::NextFrame => key(Key::ArrowRight),
+            Action::SetStart => key(Key::I),
+            Action::SetEnd => key(Key::O),
+            Action::PreviewRange => key(Key::R),
+            Action::AutoCrop => key(Key::A),
+            Action::PrevRange => key(Key::OpenBracket),
+            Action::NextRange => key(Key::CloseBracket),
+            Action::PrevFile => key(Key::PageUp),
+            Action::NextFile => key(Key::PageDown),
+            Action::ShowShortcuts => key(Key::F1),
+            Action::CommandPalette => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::P)),
+            _ => None,
+        }
+    }
+
+    /// Actions that change annotations or export the dataset, disabled in read-only review mode.
+    /// QA sample exports and saved projects only read annotations, so reviewers can run them.
+    pub fn modifies(self) -> bool {
+        matches!(
+            self,
+            Action::SetStart
+                | Action::SetEnd
+                | Action::AddRange
+                | Action::ChaptersToRanges
+                | Action::RemoveRange
+                | Action::ClearCrop
+                | Action::SetDefaultCrop
+                | Action::AutoCrop
+                | Action::MatchCrop
+                | Action::ScanText
+                | Action::Transcribe
+                | Action::RandomizeSplits
+                | Action::AddRemoteSource
+                | Action::OpenProject
+                | Action::MergeAnnotations
+                | Action::Export
+                | Action::ExportMarked
+                | Action::RetryFailed
+                | Action::RebuildDataset
+        )
+    }
+
+    /// Export and dataset actions, left out of the annotator layout.
+    pub fn engineering(self) -> bool {
+        matches!(
+            self,
+            Action::OpenOutputFolder
+                | Action::MergeAnnotations
+                | Action::RandomizeSplits
+                | Action::ClassBalance
+                | Action::Export
+                | Action::PreviewCommands
+                | Action::ExportMarked
+                | Action::RetryFailed
+                | Action::RebuildDataset
+                | Action::AuditOutput
+                | Action::RenameOutputs
+                | Action::ProjectRules
+                | Action::LinkedProjects
+                | Action::DatasetVersions
+                | Action::PackageDataset
+                | Action::VerifyChecksums
+                | Action::UploadDataset
+                | Action::EncoderSettings
+                | Action::QaExport
+                | Action::ReelFile
+                | Action::ReelFolder
+        )
+    }
+
+    /// Timeline actions that do nothing for still images.
+    pub fn video_only(self) -> bool {
+        matches!(
+            self,
+            Action::PlayPause
+                | Action::PrevFrame
+                | Action::NextFrame
+                | Action::ChaptersToRanges
+                | Action::Transcribe
+                | Action::MatchCrop
+                | Action::SetStart
+                | Action::SetEnd
+                | Action::PreviewRange
+                | Action::CutCheck
+                | Action::TrimPreview
+        )
+    }
+}
+
+/// Frame steps per press while an arrow key is held: single frames for the first presses,
+/// then jumps of 5 and 10, so moving a few hundred frames takes a second or two.
+#[derive(Default)]
+pub struct KeyRepeat {
+    held: Option<Key>,
+    presses: u32,
+}
+
+impl KeyRepeat {
+    /// Frames to step for a press of `key`, counting OS key repeats as further presses.
+    pub fn press(&mut self, key: Key) -> i32 {
+        if self.held != Some(key) {
+            self.held = Some(key);
+            self.presses = 0;
+        }
+        self.presses += 1;
+        match self.presses {
+            0..=8 => 1,
+            9..=20 => 5,
+            _ => 10,
+        }
+    }
+
+    /// Starts over once the held key is let go.
+    pub fn release(&mut self, is_down: impl Fn(Key) -> bool) {
+        if self.held.is_some_and(|key| !is_down(key)) {
+            self.held = None;
+        }
+    }
+}
+
+/// Scores `text` against a palette query: every query character has to appear in order, and
+/// runs of consecutive characters and matches at word starts rank higher. None if no match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev_match = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if prev_match == Some(found.wrapping_sub(1)) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 6;
+        }
+        // Small penalty for skipped characters so tighter matches win
+        score -= (found - pos).min(5) as i32;
+        prev_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Ctrl+P palette listing every action that is currently available, filtered by fuzzy search.
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Draws the palette while open and returns the action picked this frame, if any.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        available: impl Fn(Action) -> bool,
+    ) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(i32, Action)> = Action::ALL
+            .iter()
+            .filter(|a| **a != Action::CommandPalette && available(**a))
+            .filter_map(|a| fuzzy_score(&self.query, tr(a.label())).map(|s| (s, *a)))
+            .collect();
+        // Stable sort keeps the declaration order for equal scores (e.g. an empty query)
+        matches.sort_by_key(|(score, _)| -score);
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut picked = enter
+            .then(|| matches.get(self.selected).map(|(_, a)| *a))
+            .flatten();
+        egui::Window::new(tr("Command Palette"))
+            .id(egui::Id::new("command_palette"))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size(egui::vec2(360.0, 0.0))
+            .show(ctx, |ui| {
+                let edit = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr("Type a command…"))
+                        .desired_width(f32::INFINITY),
+                );
+                edit.request_focus();
+                if edit.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (i, (_, action)) in matches.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                let item =
+                                    ui.selectable_label(i == self.selected, tr(action.label()));
+                                if i == self.selected {
+                                    item.scroll_to_me(None);
+                                }
+                                if item.clicked() {
+                                    picked = Some(*action);
+                                }
+                                if let Some(shortcut) = action.shortcut() {
+                                    ui.with_layout(
+                                        egui::Layout::right_to_left(egui::Align::Center),
+                                        |ui| {
+                                            ui.weak(ctx.format_shortcut(&shortcut));
+                                        },
+                                    );
+                                }
+                            });
+                        }
+                        if matches.is_empty() {
+                            ui.weak(tr("No matching command"));
+                        }
+                    });
+            });
+
+        if picked.is_some() || escape {
+            self.toggle();
+        }
+        picked
+    }
+}
+
+/// Overlay listing every action that has a shortcut.
+pub fn show_shortcuts(ctx: &egui::Context, open: &mut bool) {
+    egui::Window::new(tr("⌨ Keyboard Shortcuts"))
+        .id(egui::Id::new("keyboard_shortcuts"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    if let Some(shortcut) = action.shortcut() {
+                        ui.strong(ctx.format_shortcut(&shortcut));
+                        ui.label(tr(action.label()));
+                        ui.end_row();
+                    }
+                }
+                ui.strong(tr("Drag"));
+                ui.label(tr("Draw a crop (mouse, pen or one finger)"));
+                ui.end_row();
+                ui.strong(tr("Pinch / Ctrl + Scroll"));
+                ui.label(tr("Zoom the preview"));
+                ui.end_row();
+                ui.strong(tr("Tab / Shift + Tab"));
+                ui.label(tr(
+                    "Move focus between controls; Space or Enter activates them",
+                ));
+                ui.end_row();
+            });
+            ui.add_space(4.0);
+            ui.label(tr("With the video focused (Tab to it):"));
+            egui::Grid::new("crop_keys").striped(true).show(ui, |ui| {
+                for (keys, what) in [
+                    ("Arrows", "Move the crop by 1 px (Shift: 10 px)"),
+                    ("Ctrl + Arrows", "Resize the crop"),
+                    ("Enter", "Create a centered crop"),
+                    ("Delete", "Clear the crop"),
+                ] {
+                    ui.strong(tr(keys));
+                    ui.label(tr(what));
+                    ui.end_row();
+                }
+            });
+            ui.add_space(4.0);
+            ui.weak(tr(
+                "Every other command is available from the Command Palette.",
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_prefers_word_starts_and_runs() {
+        assert_eq!(fuzzy_score("xyz", "Set Start"), None);
+        assert!(fuzzy_score("", "Next File").is_some());
+        let word_starts = fuzzy_score("nf", "Next File").unwrap();
+        let scattered = fuzzy_score("nf", "Scan for Burned-in Text").unwrap_or(i32::MIN);
+        assert!(word_starts > scattered);
+        assert!(fuzzy_score("export", "Run Export") > fuzzy_score("export", "Open Input Folder…"));
+    }
+
+    #[test]
+    fn held_keys_accelerate() {
+        let mut repeat = KeyRepeat::default();
+        let steps: Vec<i32> = (0..22).map(|_| repeat.press(Key::ArrowRight)).collect();
+        assert_eq!(steps[..8], [1; 8]);
+        assert_eq!(steps[8..20], [5; 12]);
+        assert_eq!(steps[21], 10);
+        // Turning around or letting go starts with single frames again
+        assert_eq!(repeat.press(Key::ArrowLeft), 1);
+        repeat.release(|_| false);
+        assert_eq!(repeat.press(Key::ArrowLeft), 1);
+        assert_eq!(repeat.press(Key::ArrowLeft), 1);
+    }
+}
@@ -0,0 +1,411 @@
+// Background pre-analysis of the input folder. Right after a folder is opened, one worker goes
+// through every file and computes what the analysis features need (keyframe index, thumbnail,
+// scene cuts, audio peaks, sharpness and a content hash), caching each result per source, so
+// a feature asking for it later finds it ready instead of blocking the UI. The file list shows
+// how far each file got. Opening another folder abandons the run after the current task.
+use crate::dataset::{FNV1A_OFFSET, fnv1a_update};
+use crate::i18n::{tr, trf};
+use crate::{cache, is_image_file, keyframes, logs, probe, range_preview, remote};
+use eframe::egui;
+use opencv::{core, imgcodecs, imgproc, prelude::*, videoio};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Thumbnails are this wide; scene cuts are where FFmpeg's scene score exceeds the threshold.
+const THUMBNAIL_WIDTH: u32 = 160;
+const SCENE_THRESHOLD: f64 = 0.4;
+/// Audio peaks are kept per slice of this many seconds
+const PEAK_SECONDS: f64 = 0.05;
+const PEAK_RATE: u32 = 8000;
+/// Frames sampled for the sharpness score
+const SHARPNESS_FRAMES: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Task {
+    Keyframes,
+    Thumbnail,
+    Scenes,
+    Waveform,
+    Sharpness,
+    Hash,
+}
+
+impl Task {
+    pub const ALL: [Task; 6] = [
+        Task::Keyframes,
+        Task::Thumbnail,
+        Task::Scenes,
+        Task::Waveform,
+        Task::Sharpness,
+        Task::Hash,
+    ];
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Task::Keyframes => "keyframe index",
+            Task::Thumbnail => "thumbnail",
+            Task::Scenes => "scene detection",
+            Task::Waveform => "waveform",
+            Task::Sharpness => "sharpness",
+            Task::Hash => "content hash",
+        }
+    }
+
+    fn applies_to_images(self) -> bool {
+        matches!(self, Task::Thumbnail | Task::Sharpness | Task::Hash)
+    }
+
+    /// Whether the task runs for remote sources. The others read the whole file, which would
+    /// download it; the hash of a link is that of its URL.
+    fn applies_to_links(self) -> bool {
+        matches!(self, Task::Thumbnail | Task::Hash)
+    }
+}
+
+/// What the analysis found out about one file.
+#[derive(Clone, Default)]
+pub struct Report {
+    pub thumbnail: Option<PathBuf>,
+    pub scene_cuts: Option<usize>,
+    /// Loudest audio sample, 0 to 1; None without an audio track
+    pub peak: Option<f32>,
+    /// Mean variance of the Laplacian over sampled frames; low values mean blurry footage
+    pub sharpness: Option<f64>,
+    /// FNV-1a hash of the file's content
+    pub hash: Option<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone)]
+pub enum Status {
+    Queued,
+    Running(Task),
+    Done(Report),
+}
+
+#[derive(Default)]
+struct State {
+    /// Bumped by every new run; workers of older runs stop
+    generation: u64,
+    files: HashMap<PathBuf, Status>,
+    /// Worker threads still running, including those of older runs finishing their last task
+    workers: usize,
+}
+
+/// Shared between the UI and the worker.
+#[derive(Clone, Default)]
+pub struct Analyzer {
+    state: Arc<Mutex<State>>,
+}
+
+impl Analyzer {
+    /// Analyzes `files` in order on a worker thread, replacing any earlier run.
+    pub fn start(&self, files: Vec<PathBuf>) {
+        let generation = {
+            let mut state = self.state.lock().unwrap();
+            state.generation += 1;
+            state.files = files.iter().map(|f| (f.clone(), Status::Queued)).collect();
+            state.workers += 1;
+            state.generation
+        };
+        let state = self.state.clone();
+        let finished = self.state.clone();
+        let set = move |file: &Path, status: Status| {
+            let mut state = state.lock().unwrap();
+            let current = state.generation == generation;
+            if current {
+                state.files.insert(file.to_path_buf(), status);
+            }
+            current
+        };
+        let analyze = move || {
+            for file in files {
+                let mut report = Report::default();
+                let image = is_image_file(&file);
+                let link = remote::is_link(&file);
+                for task in Task::ALL {
+                    if (image && !task.applies_to_images()) || (link && !task.applies_to_links()) {
+                        continue;
+                    }
+                    if !set(&file, Status::Running(task)) {
+                        return;
+                    }
+                    if let Err(e) = run(task, &file, &mut report) {
+                        tracing::warn!(
+                            target: logs::ANALYSIS,
+                            file = ?file,
+                            task = task.label(),
+                            "{}",
+                            e
+                        );
+                        report.errors.push(format!("{}: {}", tr(task.label()), e));
+                    }
+                }
+                if !set(&file, Status::Done(report)) {
+                    return;
+                }
+            }
+        };
+        std::thread::spawn(move || {
+            analyze();
+            finished.lock().unwrap().workers -= 1;
+        });
+    }
+
+    /// Stops the run after the task in progress, e.g. before quitting.
+    pub fn stop(&self) {
+        self.state.lock().unwrap().generation += 1;
+    }
+
+    /// Whether a worker is still busy, possibly with the last task of a stopped run.
+    pub fn is_running(&self) -> bool {
+        self.state.lock().unwrap().workers > 0
+    }
+
+    pub fn status(&self, file: &Path) -> Option<Status> {
+        self.state.lock().unwrap().files.get(file).cloned()
+    }
+
+    /// Files analyzed so far and files in the run.
+    pub fn progress(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        let done = state
+            .files
+            .values()
+            .filter(|s| matches!(s, Status::Done(_)))
+            .count();
+        (done, state.files.len())
+    }
+}
+
+/// Cache file for result `name` of `source`.
+fn cached(source: &Path, name: &str) -> PathBuf {
+    cache::dir("analysis").join(format!("{}.{}", cache::source_key(source), name))
+}
+
+/// The cached result at `path`, or the one `compute` writes there.
+fn cached_or(path: &Path, compute: impl FnOnce(&Path) -> Result<(), String>) -> Result<(), String> {
+    if path.exists() {
+        cache::touch(path);
+        return Ok(());
+    }
+    std::fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+    compute(path)?;
+    cache::evict(Some(path));
+    Ok(())
+}
+
+fn write(path: &Path, data: impl AsRef<[u8]>) -> Result<(), String> {
+    std::fs::write(path, data).map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+}
+
+fn ffmpeg(cmd: &mut Command) -> Result<std::process::Output, String> {
+    cmd.output()
+        .map_err(|e| trf("Failed to start FFmpeg: {}", &[&e]))
+}
+
+fn run(task: Task, source: &Path, report: &mut Report) -> Result<(), String> {
+    match task {
+        Task::Keyframes => keyframes::build(source).map(|_| ()),
+        Task::Thumbnail => {
+            let path = cached(source, "thumb.jpg");
+            cached_or(&path, |path| {
+                let at = probe::duration(source).map_or(0.0, |d| d * 0.1);
+                let output = ffmpeg(
+                    Command::new("ffmpeg")
+                        .args(["-y", "-v", "error", "-ss"])
+                        .arg(at.to_string())
+                        .arg("-i")
+                        .arg(remote::input(source))
+                        .arg("-vf")
+                        .arg(format!("scale={}:-2", THUMBNAIL_WIDTH))
+                        .args(["-frames:v", "1"])
+                        .arg(path),
+                )?;
+                if !output.status.success() || !path.exists() {
+                    return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+                }
+                Ok(())
+            })?;
+            report.thumbnail = Some(path);
+            Ok(())
+        }
+        Task::Scenes => {
+            let path = cached(source, "scenes.txt");
+            cached_or(&path, |path| {
+                let output = ffmpeg(
+                    Command::new("ffmpeg")
+                        .args(["-hide_banner", "-nostats", "-i"])
+                        .arg(source)
+                        .arg("-vf")
+                        .arg(format!("select='gt(scene,{})',showinfo", SCENE_THRESHOLD))
+                        .args(["-an", "-f", "null", "-"]),
+                )?;
+                if !output.status.success() {
+                    return Err(trf("FFmpeg could not analyze {}", &[&source.display()]));
+                }
+                let times = scene_times(&String::from_utf8_lossy(&output.stderr));
+                write(
+                    path,
+                    times.iter().map(|t| format!("{}\n", t)).collect::<String>(),
+                )
+            })?;
+            report.scene_cuts = Some(read_text(&path)?.lines().count());
+            Ok(())
+        }
+        Task::Waveform => {
+            let path = cached(source, "peaks");
+            cached_or(&path, |path| {
+                let output = ffmpeg(
+                    Command::new("ffmpeg")
+                        .args(["-v", "error", "-i"])
+                        .arg(source)
+                        .args(["-vn", "-ac", "1", "-ar", &PEAK_RATE.to_string()])
+                        .args(["-f", "s16le", "-"]),
+                )?;
+                // Sources without an audio track fail with no output and get no peaks
+                write(path, peaks(&output.stdout))
+            })?;
+            let peaks = std::fs::read(&path).map_err(|e| e.to_string())?;
+            report.peak = peaks.iter().max().map(|p| *p as f32 / 255.0);
+            Ok(())
+        }
+        Task::Sharpness => {
+            let path = cached(source, "sharpness.txt");
+            cached_or(&path, |path| {
+                let score = sharpness(source).map_err(|e| e.to_string())?;
+                write(path, score.to_string())
+            })?;
+            report.sharpness = read_text(&path)?.trim().parse().ok();
+            Ok(())
+        }
+        Task::Hash => {
+            report.hash = Some(source_hash(source)?);
+            Ok(())
+        }
+    }
+}
+
+/// Times of the frames FFmpeg's `showinfo` filter logged, from its stderr.
+fn scene_times(log: &str) -> Vec<f64> {
+    log.lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| line.split("pts_time:").nth(1))
+        .filter_map(|rest| rest.split_whitespace().next()?.parse().ok())
+        .collect()
+}
+
+/// Peak level per `PEAK_SECONDS` of mono 16-bit little-endian `samples`, scaled to 0-255.
+fn peaks(samples: &[u8]) -> Vec<u8> {
+    let per_slice = (PEAK_RATE as f64 * PEAK_SECONDS) as usize * 2;
+    samples
+        .chunks(per_slice)
+        .map(|slice| {
+            let peak = slice
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]).unsigned_abs())
+                .max()
+                .unwrap_or(0);
+            (peak as u32 * 255 / i16::MAX as u32).min(255) as u8
+        })
+        .collect()
+}
+
+fn laplacian_variance(frame: &core::Mat) -> opencv::Result<f64> {
+    let mut gray = core::Mat::default();
+    let frame = crate::color::to_bgr8(frame)?;
+    imgproc::cvt_color_def(&frame, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    let mut laplacian = core::Mat::default();
+    imgproc::laplacian_def(&gray, &mut laplacian, core::CV_64F)?;
+    let (mut mean, mut stddev) = (core::Vector::<f64>::new(), core::Vector::<f64>::new());
+    core::mean_std_dev_def(&laplacian, &mut mean, &mut stddev)?;
+    Ok(stddev.get(0)?.powi(2))
+}
+
+/// Mean Laplacian variance of frames spread over `source`.
+fn sharpness(source: &Path) -> opencv::Result<f64> {
+    let name = source.to_string_lossy();
+    let frames = if is_image_file(source) {
+        vec![imgcodecs::imread(&name, imgcodecs::IMREAD_UNCHANGED)?]
+    } else {
+        let mut cap = videoio::VideoCapture::from_file(&name, videoio::CAP_ANY)?;
+        let duration = probe::duration(source).unwrap_or(0.0);
+        let mut frames = Vec::new();
+        for time in range_preview::frame_times(0.0, duration, SHARPNESS_FRAMES) {
+            let mut frame = core::Mat::default();
+            let _ = cap.set(videoio::CAP_PROP_POS_MSEC, time * 1000.0);
+            if cap.read(&mut frame).unwrap_or(false) && !frame.empty() {
+                frames.push(frame);
+            }
+        }
+        frames
+    };
+    let scores = frames
+        .iter()
+        .filter(|f| !f.empty())
+        .map(laplacian_variance)
+        .collect::<opencv::Result<Vec<_>>>()?;
+    Ok(scores.iter().sum::<f64>() / scores.len().max(1) as f64)
+}
+
+/// FNV-1a hash of the content of `source` as hex, cached like the other results so a file the
+/// analysis got to isn't read again. A remote source is hashed by its link file, which holds the
+/// URL.
+pub fn source_hash(source: &Path) -> Result<String, String> {
+    let path = cached(source, "hash.txt");
+    cached_or(&path, |path| {
+        write(path, format!("{:016x}", content_hash(source)?))
+    })?;
+    Ok(read_text(&path)?.trim().to_string())
+}
+
+fn content_hash(source: &Path) -> Result<u64, String> {
+    let mut file = std::fs::File::open(source).map_err(|e| e.to_string())?;
+    let mut buf = vec![0; 1 << 20];
+    let mut hash = FNV1A_OFFSET;
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(n) => hash = fnv1a_update(hash, &buf[..n]),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// The thumbnail at `path` for display.
+pub fn thumbnail_image(path: &Path) -> opencv::Result<egui::ColorImage> {
+    let frame = imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR)?;
+    let mut rgb = core::Mat::default();
+    imgproc::cvt_color_def(&frame, &mut rgb, imgproc::COLOR_BGR2RGB)?;
+    let size = [rgb.cols() as usize, rgb.rows() as usize];
+    Ok(egui::ColorImage::from_rgb(size, rgb.data_bytes()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scene_cuts_and_audio_peaks() {
+        let log = "[Parsed_showinfo_1 @ 0x5] n:   0 pts:  38400 pts_time:2.5     duration:512\n\
+                   frame=  2 fps=0.0 q=-0.0 size=N/A time=00:00:04.00\n\
+                   [Parsed_showinfo_1 @ 0x5] n:   1 pts:  76800 pts_time:5       duration:512\n";
+        assert_eq!(scene_times(log), [2.5, 5.0]);
+
+        let per_slice = (PEAK_RATE as f64 * PEAK_SECONDS) as usize;
+        let mut samples: Vec<i16> = vec![0; per_slice * 2];
+        samples[3] = i16::MIN;
+        samples[per_slice + 1] = i16::MAX / 2;
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        assert_eq!(peaks(&bytes), [255, 127]);
+        assert!(peaks(&[]).is_empty());
+    }
+}
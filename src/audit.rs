@@ -0,0 +1,437 @@
+// Output folder audit: cross-checks the manifest and the files in the output folder against
+// the annotations. Media without a sidecar (and not open) has unknown annotations, so its
+// outputs are never reported; nothing is flagged that can't be judged from the annotations.
+use crate::dataset::{
+    audio_file, caption_file, caption_text, output_file, preview_file, relative, remove_output,
+    sprite_file, without_increment,
+};
+use crate::export::{FRAMES_EXTENSION, Profile};
+use crate::i18n::{tr, trf};
+use crate::manifest::Manifest;
+use crate::{VideoRange, is_image_file};
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub enum Issue {
+    /// An output whose media or range no longer exists
+    Orphan { file: String },
+    /// An annotated range without its output
+    Missing { source: String, range: usize },
+    /// A caption whose text differs from the range's note (under the caption template)
+    CaptionMismatch {
+        file: String,
+        source: String,
+        range: usize,
+    },
+    /// An output under a name the range no longer exports to (split, range count or container
+    /// changed)
+    Stale { file: String, expected: String },
+}
+
+impl Issue {
+    fn describe(&self) -> String {
+        match self {
+            Issue::Orphan { file } => trf("Orphan: {} has no annotation", &[file]),
+            Issue::Missing { source, range } => {
+                trf("Missing: range {} of {} was not exported", &[range, source])
+            }
+            Issue::CaptionMismatch { file, .. } => {
+                trf("Caption: {} no longer matches the note", &[file])
+            }
+            Issue::Stale { file, expected } => {
+                trf("Stale: {} is now exported as {}", &[file, expected])
+            }
+        }
+    }
+
+    /// Whether cleaning up fixes this issue; missing outputs need an export.
+    fn fixable(&self) -> bool {
+        !matches!(self, Issue::Missing { .. })
+    }
+}
+
+pub struct Report {
+    pub issues: Vec<Issue>,
+    pub errors: Vec<String>,
+    /// Cleanup result shown after the report is refreshed
+    pub cleaned: Option<String>,
+}
+
+/// Annotations to audit against. `media` lists everything in the input folder; `annotated`
+/// holds the ranges of the files whose annotations are known.
+pub struct Project<'a> {
+    pub media: &'a [PathBuf],
+    pub annotated: &'a [(PathBuf, Vec<VideoRange>)],
+    /// Container, caption template and rules the outputs are expected in
+    pub profile: &'a Profile,
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Output and caption files (and frame sequence folders) of the folder and its split
+/// subfolders, relative to `out_dir`.
+fn output_files(out_dir: &Path) -> Vec<String> {
+    let dirs = std::iter::once(out_dir.to_path_buf()).chain(
+        crate::split::Split::ALL
+            .iter()
+            .map(|s| out_dir.join(s.name())),
+    );
+    dirs.filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file() || p.is_dir() && p.extension().is_some_and(|e| e == FRAMES_EXTENSION)
+        })
+        .map(|p| relative(out_dir, &p))
+        .collect()
+}
+
+/// Extensions an output named just like `media` has under `profile`: the clip (or still, or
+/// frame folder), its caption and its audio.
+fn output_extensions(media: &Path, profile: &Profile) -> Vec<String> {
+    let clip = output_file(Path::new(""), media, &VideoRange::default(), 0, 1, profile);
+    let clip = clip.extension().unwrap_or_default().to_string_lossy();
+    let mut extensions = vec![clip.to_string(), "txt".to_string()];
+    extensions.extend(profile.audio.map(|format| format.extension().to_string()));
+    extensions
+}
+
+/// Whether `file` (an output-folder relative path) is named like an output of `stem`. A file
+/// named just `stem` only counts with one of `extensions`, so the media itself doesn't.
+fn is_output_of(file: &str, stem: &str, extensions: &[String]) -> bool {
+    let base = file.rsplit('/').next().unwrap_or(file);
+    let Some((base, ext)) = base.rsplit_once('.') else {
+        return false;
+    };
+    (base == stem && extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        || base
+            .strip_prefix(stem)
+            .and_then(|rest| rest.strip_prefix("_range"))
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+pub fn run(out_dir: &Path, project: &Project) -> Report {
+    let mut report = Report {
+        issues: Vec::new(),
+        errors: Vec::new(),
+        cleaned: None,
+    };
+    let manifest = match Manifest::load(out_dir) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            report.errors.push(e);
+            Manifest::default()
+        }
+    };
+    let present: HashSet<String> = project.media.iter().map(|m| name(m)).collect();
+    let annotated: HashMap<String, &(PathBuf, Vec<VideoRange>)> =
+        project.annotated.iter().map(|a| (name(&a.0), a)).collect();
+    // The media and its notes are never outputs, even with the input folder as the output folder
+    let inputs: HashSet<PathBuf> = project
+        .media
+        .iter()
+        .chain(project.annotated.iter().map(|(media, _)| media))
+        .flat_map(|media| [media.clone(), media.with_extension("txt")])
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect();
+    let on_disk: HashSet<String> = output_files(out_dir)
+        .into_iter()
+        .filter(|file| !fs::canonicalize(out_dir.join(file)).is_ok_and(|p| inputs.contains(&p)))
+        .collect();
+
+    // Every file the annotations export to now
+    let mut expected = HashSet::new();
+    for (media, ranges) in project.annotated {
+        for (i, range) in ranges.iter().enumerate() {
+            let file = output_file(out_dir, media, range, i, ranges.len(), project.profile);
+            let rel = relative(out_dir, &file);
+            // An output numbered to dodge a collision stands in for the name
+            let numbered = manifest.entries.iter().any(|e| {
+                e.source == name(media)
+                    && e.range == i
+                    && without_increment(&e.file).as_deref() == Some(rel.as_str())
+                    && out_dir.join(&e.file).exists()
+            });
+            if !file.exists() && !numbered {
+                report.issues.push(Issue::Missing {
+                    source: name(media),
+                    range: i,
+                });
+            }
+            let caption = caption_file(&file);
+            let wanted = caption_text(project.profile, media, i, range);
+            if let Some(wanted) = &wanted
+                && let Ok(text) = fs::read_to_string(&caption)
+                && text != *wanted
+            {
+                report.issues.push(Issue::CaptionMismatch {
+                    file: relative(out_dir, &caption),
+                    source: name(media),
+                    range: i,
+                });
+            }
+            expected.insert(rel);
+            if wanted.is_some() {
+                expected.insert(relative(out_dir, &caption));
+            }
+            // Not reported when missing: media without an audio track has none
+            if let Some(format) = project.profile.audio
+                && !is_image_file(media)
+            {
+                expected.insert(relative(out_dir, &audio_file(&file, format)));
+            }
+            if let Some(format) = project.profile.preview
+                && !is_image_file(media)
+            {
+                expected.insert(relative(out_dir, &preview_file(&file, format)));
+            }
+            if let Some(grid) = project.profile.sprite_grid
+                && !is_image_file(media)
+            {
+                expected.insert(relative(out_dir, &sprite_file(&file, grid)));
+            }
+        }
+    }
+
+    let mut reported = HashSet::new();
+    for entry in &manifest.entries {
+        let known = annotated.get(&entry.source);
+        for file in entry.files() {
+            let numbered = without_increment(file).is_some_and(|base| expected.contains(&base));
+            if expected.contains(file)
+                || numbered
+                || !on_disk.contains(file)
+                || !reported.insert(file.clone())
+            {
+                continue;
+            }
+            if !present.contains(&entry.source) {
+                report.issues.push(Issue::Orphan { file: file.clone() });
+            } else if let Some((media, ranges)) = known {
+                let is_caption = entry.caption.as_ref() == Some(file);
+                let is_audio = entry.audio.as_ref() == Some(file);
+                let is_preview = entry.preview.as_ref() == Some(file);
+                let is_sprite = entry.sprite.as_ref() == Some(file);
+                match ranges.get(entry.range) {
+                    // A caption left over after the note was cleared counts as an orphan too,
+                    // and so do audio, previews and sprite sheets after their export was turned off
+                    Some(range)
+                        if (is_caption
+                            && caption_text(project.profile, media, entry.range, range)
+                                .is_none())
+                            || (is_audio && project.profile.audio.is_none())
+                            || (is_preview && project.profile.preview.is_none())
+                            || (is_sprite && project.profile.sprite_grid.is_none()) =>
+                    {
+                        report.issues.push(Issue::Orphan { file: file.clone() })
+                    }
+                    None => report.issues.push(Issue::Orphan { file: file.clone() }),
+                    Some(range) => {
+                        let now = output_file(
+                            out_dir,
+                            media,
+                            range,
+                            entry.range,
+                            ranges.len(),
+                            project.profile,
+                        );
+                        let profile = project.profile;
+                        let now = if is_caption {
+                            caption_file(&now)
+                        } else if let Some(format) = profile.audio.filter(|_| is_audio) {
+                            audio_file(&now, format)
+                        } else if let Some(format) = profile.preview.filter(|_| is_preview) {
+                            preview_file(&now, format)
+                        } else if let Some(grid) = profile.sprite_grid.filter(|_| is_sprite) {
+                            sprite_file(&now, grid)
+                        } else {
+                            now
+                        };
+                        report.issues.push(Issue::Stale {
+                            file: file.clone(),
+                            expected: relative(out_dir, &now),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Files named like outputs of annotated media that no manifest entry accounts for
+    for file in &on_disk {
+        if expected.contains(file) || reported.contains(file) {
+            continue;
+        }
+        let listed = manifest
+            .entries
+            .iter()
+            .any(|e| e.files().any(|f| f == file));
+        let stem_match = project.annotated.iter().any(|(media, _)| {
+            let stem = media.file_stem().unwrap_or_default().to_string_lossy();
+            is_output_of(file, &stem, &output_extensions(media, project.profile))
+        });
+        if !listed && stem_match {
+            report.issues.push(Issue::Orphan { file: file.clone() });
+        }
+    }
+    report.issues.sort_by_key(|i| i.describe());
+    report
+}
+
+/// Deletes orphan and stale files, rewrites mismatched captions and drops the manifest entries
+/// of deleted outputs. Returns the number of fixed issues.
+pub fn clean(out_dir: &Path, report: &Report, project: &Project) -> Result<usize, String> {
+    let mut manifest = Manifest::load(out_dir)?;
+    let mut fixed = 0;
+    let mut removed = HashSet::new();
+    for issue in &report.issues {
+        match issue {
+            Issue::Orphan { file } | Issue::Stale { file, .. } => {
+                let path = out_dir.join(file);
+                remove_output(&path)
+                    .map_err(|e| trf("Could not remove {}: {}", &[&path.display(), &e]))?;
+                removed.insert(file.clone());
+            }
+            Issue::CaptionMismatch {
+                file,
+                source,
+                range,
+            } => {
+                let text = project
+                    .annotated
+                    .iter()
+                    .find(|(media, _)| name(media) == *source)
+                    .and_then(|(media, ranges)| {
+                        caption_text(project.profile, media, *range, ranges.get(*range)?)
+                    });
+                if let Some(text) = text {
+                    let path = out_dir.join(file);
+                    fs::write(&path, text)
+                        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))?;
+                }
+            }
+            Issue::Missing { .. } => continue,
+        }
+        fixed += 1;
+    }
+    manifest.entries.retain(|e| !removed.contains(&e.file));
+    for entry in &mut manifest.entries {
+        if entry.caption.as_ref().is_some_and(|c| removed.contains(c)) {
+            entry.caption = None;
+        }
+        if entry.audio.as_ref().is_some_and(|a| removed.contains(a)) {
+            entry.audio = None;
+        }
+        if entry.preview.as_ref().is_some_and(|p| removed.contains(p)) {
+            entry.preview = None;
+        }
+        if entry.sprite.as_ref().is_some_and(|s| removed.contains(s)) {
+            entry.sprite = None;
+        }
+    }
+    manifest.save(out_dir)?;
+    crate::checksum::record(out_dir, &manifest, &[]);
+    Ok(fixed)
+}
+
+/// Draws the audit report; returns true when the user asks to clean up.
+pub fn show(ctx: &egui::Context, open: &mut bool, report: &Report, can_clean: bool) -> bool {
+    let mut clean = false;
+    egui::Window::new(tr("🩺 Output Audit"))
+        .id(egui::Id::new("output_audit"))
+        .open(open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            if let Some(cleaned) = &report.cleaned {
+                ui.label(cleaned);
+            }
+            if report.issues.is_empty() {
+                ui.label(tr("✅ The output folder matches the annotations."));
+            } else {
+                ui.label(trf("{} issue(s) found:", &[&report.issues.len()]));
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for issue in &report.issues {
+                        ui.label(issue.describe());
+                    }
+                });
+            }
+            for error in &report.errors {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(4.0);
+            let fixable = report.issues.iter().filter(|i| i.fixable()).count();
+            let button = ui.add_enabled(
+                can_clean && fixable > 0,
+                egui::Button::new(trf("🧹 Clean Up ({})", &[&fixable])),
+            );
+            clean = button
+                .on_hover_text(tr(
+                    "Delete orphan and stale outputs and rewrite outdated captions. Missing outputs need an export.",
+                ))
+                .clicked();
+        });
+    clean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_output_names() {
+        let extensions = output_extensions(Path::new("in/clip.mov"), &Profile::default());
+        assert!(is_output_of("clip.mp4", "clip", &extensions));
+        assert!(is_output_of("clip.txt", "clip", &extensions));
+        assert!(is_output_of("train/clip_range12.txt", "clip", &extensions));
+        assert!(!is_output_of("clip.mov", "clip", &extensions));
+        assert!(!is_output_of("clip_range.mp4", "clip", &extensions));
+        assert!(!is_output_of("clip2.mp4", "clip", &extensions));
+        assert!(!is_output_of("notes.txt", "clip", &extensions));
+    }
+
+    #[test]
+    fn the_input_folder_as_output_folder_keeps_the_media_and_notes() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_audit_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "clip.mov",
+            "clip.txt",
+            "clip.mp4",
+            "clip_range3.mp4",
+            "other.mov",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let media = [dir.join("clip.mov"), dir.join("other.mov")];
+        let annotated = [
+            (dir.join("clip.mov"), vec![VideoRange::default()]),
+            (dir.join("other.mov"), vec![VideoRange::default()]),
+        ];
+        let profile = Profile::default();
+        let project = Project {
+            media: &media,
+            annotated: &annotated,
+            profile: &profile,
+        };
+        let report = run(&dir, &project);
+        let orphans: Vec<_> = report
+            .issues
+            .iter()
+            .filter_map(|issue| match issue {
+                Issue::Orphan { file } => Some(file.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(orphans, ["clip_range3.mp4"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
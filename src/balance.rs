@@ -0,0 +1,167 @@
+// Class balance: clip count and duration per tag and split across the folder, with tags whose
+// share of a split is far off that split's overall share flagged before training starts.
+use crate::VideoRange;
+use crate::i18n::{tr, trf};
+use crate::split::Split;
+use eframe::egui;
+
+/// A tag counts as imbalanced in a split when its share there is below half or above twice
+/// the split's share of all ranges.
+const IMBALANCE_FACTOR: f64 = 2.0;
+/// Columns: one per split, then ranges without a split.
+const COLUMNS: usize = Split::ALL.len() + 1;
+
+#[derive(Clone, Copy, Default)]
+pub struct Cell {
+    pub count: usize,
+    pub duration: f64,
+}
+
+impl Cell {
+    fn add(&mut self, range: &VideoRange) {
+        self.count += 1;
+        self.duration += range.end_time - range.start_time;
+    }
+}
+
+pub struct Balance {
+    /// Sorted by tag; untagged ranges are listed under an empty tag.
+    pub rows: Vec<(String, [Cell; COLUMNS])>,
+    pub totals: [Cell; COLUMNS],
+    /// Sidecars that could not be read
+    pub errors: Vec<String>,
+}
+
+fn column(range: &VideoRange) -> usize {
+    range.split.map_or(COLUMNS - 1, |s| s as usize)
+}
+
+pub fn compute<'a>(ranges: impl IntoIterator<Item = &'a VideoRange>) -> Balance {
+    let mut rows = std::collections::BTreeMap::<String, [Cell; COLUMNS]>::new();
+    let mut totals = [Cell::default(); COLUMNS];
+    for range in ranges {
+        let col = column(range);
+        totals[col].add(range);
+        if range.tags.is_empty() {
+            rows.entry(String::new()).or_default()[col].add(range);
+        }
+        for tag in &range.tags {
+            rows.entry(tag.clone()).or_default()[col].add(range);
+        }
+    }
+    Balance {
+        rows: rows.into_iter().collect(),
+        totals,
+        errors: Vec::new(),
+    }
+}
+
+impl Balance {
+    /// Whether the tag in `row` is badly under- or over-represented in `split`, by count.
+    pub fn imbalanced(&self, row: usize, split: Split) -> bool {
+        let assigned =
+            |cells: &[Cell; COLUMNS]| cells[..COLUMNS - 1].iter().map(|c| c.count).sum::<usize>();
+        let cells = &self.rows[row].1;
+        let (tag_total, all_total) = (assigned(cells), assigned(&self.totals));
+        let expected = self.totals[split as usize].count as f64 / all_total.max(1) as f64;
+        if tag_total == 0 || expected == 0.0 {
+            return false;
+        }
+        let share = cells[split as usize].count as f64 / tag_total as f64;
+        share * IMBALANCE_FACTOR < expected || share > expected * IMBALANCE_FACTOR
+    }
+}
+
+fn cell_text(cell: &Cell) -> String {
+    if cell.count == 0 {
+        return "–".to_string();
+    }
+    trf("{} · {}s", &[&cell.count, &format!("{:.1}", cell.duration)])
+}
+
+/// Draws the balance table; returns true when the user asks for a refresh.
+pub fn show(ctx: &egui::Context, open: &mut bool, balance: &Balance) -> bool {
+    let mut refresh = false;
+    egui::Window::new(tr("📊 Class Balance"))
+        .id(egui::Id::new("class_balance"))
+        .open(open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.label(tr(
+                "Ranges and total duration per tag and split, across the open file and the sidecars in the input folder.",
+            ));
+            ui.add_space(4.0);
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                egui::Grid::new("balance_grid").striped(true).show(ui, |ui| {
+                    ui.strong(tr("Tag"));
+                    for split in Split::ALL {
+                        ui.strong(tr(split.label()));
+                    }
+                    ui.strong(tr("No split"));
+                    ui.end_row();
+                    for (i, (tag, cells)) in balance.rows.iter().enumerate() {
+                        if tag.is_empty() {
+                            ui.weak(tr("(untagged)"));
+                        } else {
+                            ui.label(tag);
+                        }
+                        for (split, cell) in Split::ALL.iter().zip(cells) {
+                            if balance.imbalanced(i, *split) {
+                                let warn = egui::Color32::from_rgb(255, 150, 60);
+                                ui.colored_label(warn, format!("⚠ {}", cell_text(cell)))
+                                    .on_hover_text(tr(
+                                        "This tag's share of the split is far from the split's overall share",
+                                    ));
+                            } else {
+                                ui.label(cell_text(cell));
+                            }
+                        }
+                        ui.label(cell_text(&cells[COLUMNS - 1]));
+                        ui.end_row();
+                    }
+                    ui.strong(tr("Total"));
+                    for cell in &balance.totals {
+                        ui.strong(cell_text(cell));
+                    }
+                    ui.end_row();
+                });
+            });
+            for error in &balance.errors {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(4.0);
+            refresh = ui.button(tr("🔄 Refresh")).clicked();
+        });
+    refresh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(tags: &[&str], split: Option<Split>) -> VideoRange {
+        VideoRange {
+            end_time: 2.0,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            split,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_tags_missing_from_a_split() {
+        let mut ranges: Vec<VideoRange> = (0..8)
+            .map(|_| range(&["cat"], Some(Split::Train)))
+            .collect();
+        ranges.push(range(&["cat"], Some(Split::Val)));
+        ranges.extend((0..8).map(|_| range(&["dog"], Some(Split::Train))));
+        ranges.push(range(&["dog"], None));
+        ranges.push(range(&[], Some(Split::Val)));
+        let balance = compute(&ranges);
+        let row = |tag: &str| balance.rows.iter().position(|(t, _)| t == tag).unwrap();
+        assert_eq!(balance.rows[row("dog")].1[COLUMNS - 1].count, 1);
+        assert!((balance.rows[row("cat")].1[0].duration - 16.0).abs() < 1e-9);
+        assert!(!balance.imbalanced(row("cat"), Split::Val));
+        assert!(balance.imbalanced(row("dog"), Split::Val));
+    }
+}
@@ -0,0 +1,88 @@
+// Cut check: the frames on both sides of each range boundary side by side. A cut meant to end
+// just before a scene change (or a fade to black) is easy to get one frame wrong while
+// scrubbing; comparing the last frame in the range with the first one after it shows it at once.
+use crate::color;
+use crate::i18n::{tr, trf};
+use eframe::egui;
+use opencv::{core, imgproc, prelude::*};
+
+/// Width of the frames in the window
+const WIDTH: f64 = 320.0;
+
+/// Frame before the start, first and last frame of the range and the frame after its end.
+pub fn frames(start_frame: i32, end_frame: i32) -> [i32; 4] {
+    [start_frame - 1, start_frame, end_frame, end_frame + 1]
+}
+
+pub struct Check {
+    /// Range start and end the frames belong to
+    pub times: (f64, f64),
+    pub frames: [i32; 4],
+    /// None for frames outside the video
+    pub textures: [Option<egui::TextureHandle>; 4],
+}
+
+/// `frame` color-corrected by `pipeline` and scaled for the window, as an RGB image.
+pub fn image(frame: &core::Mat, pipeline: color::Pipeline) -> opencv::Result<egui::ColorImage> {
+    let frame = pipeline.apply(color::to_bgr8(frame)?)?;
+    let height = ((frame.rows() as f64 * WIDTH / frame.cols().max(1) as f64).round() as i32).max(1);
+    let mut scaled = core::Mat::default();
+    let size = core::Size::new(WIDTH as i32, height);
+    imgproc::resize(&frame, &mut scaled, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    let mut rgb = core::Mat::default();
+    imgproc::cvt_color_def(&scaled, &mut rgb, imgproc::COLOR_BGR2RGB)?;
+    let size = [rgb.cols() as usize, rgb.rows() as usize];
+    Ok(egui::ColorImage::from_rgb(size, rgb.data_bytes()?))
+}
+
+fn pair(ui: &mut egui::Ui, check: &Check, (a, b): (usize, usize), labels: [&'static str; 2]) {
+    ui.horizontal(|ui| {
+        for (i, label) in [(a, labels[0]), (b, labels[1])] {
+            ui.vertical(|ui| {
+                ui.label(trf(label, &[&check.frames[i]]));
+                match &check.textures[i] {
+                    Some(texture) => {
+                        ui.image((texture.id(), texture.size_vec2()));
+                    }
+                    None => {
+                        ui.weak(tr("Outside the video"));
+                    }
+                }
+            });
+        }
+    });
+}
+
+pub fn show(ctx: &egui::Context, open: &mut bool, check: &Check) {
+    egui::Window::new(tr("🔲 Cut Check"))
+        .id(egui::Id::new("cut_check"))
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.strong(tr("Start"));
+            pair(
+                ui,
+                check,
+                (0, 1),
+                ["Before the range ({})", "First frame ({})"],
+            );
+            ui.separator();
+            ui.strong(tr("End"));
+            pair(
+                ui,
+                check,
+                (2, 3),
+                ["Last frame ({})", "After the range ({})"],
+            );
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_straddle_both_cuts() {
+        assert_eq!(frames(10, 50), [9, 10, 50, 51]);
+    }
+}
@@ -0,0 +1,194 @@
+// One cache directory for everything derived from the media (preview proxies and future
+// thumbnails, waveforms or frame caches), kept on a local disk rather than next to the sources
+// and trimmed to a size limit by evicting the least recently used files.
+use crate::i18n::trf;
+use crate::logs;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+const DEFAULT_LIMIT_MB: u64 = 10 * 1024;
+/// Subfolders the cache owns. Size accounting, eviction and clearing only ever touch these, so
+/// pointing the cache at a folder that holds other files is harmless.
+const KINDS: &[&str] = &["proxies", "keyframes", "subtitles", "analysis", "remote"];
+
+static ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+static LIMIT_MB: AtomicU64 = AtomicU64::new(DEFAULT_LIMIT_MB);
+
+/// The platform's per-user cache folder, falling back to the temp folder.
+fn default_root() -> PathBuf {
+    let env = |var| {
+        std::env::var_os(var)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        env("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|h| h.join("Library").join("Caches"))
+    } else {
+        env("XDG_CACHE_HOME").or_else(|| env("HOME").map(|h| h.join(".cache")))
+    };
+    base.unwrap_or_else(std::env::temp_dir)
+        .join("viddatatraincrop")
+}
+
+/// Reads VIDDATATRAINCROP_CACHE_DIR and VIDDATATRAINCROP_CACHE_MAX_MB.
+pub fn configure_from_env() {
+    if let Some(dir) = std::env::var_os("VIDDATATRAINCROP_CACHE_DIR").filter(|v| !v.is_empty()) {
+        set_root(PathBuf::from(dir));
+    }
+    if let Some(mb) = std::env::var("VIDDATATRAINCROP_CACHE_MAX_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        set_limit_mb(mb);
+    }
+}
+
+pub fn root() -> PathBuf {
+    ROOT.lock().unwrap().clone().unwrap_or_else(default_root)
+}
+
+pub fn set_root(dir: PathBuf) {
+    *ROOT.lock().unwrap() = Some(dir);
+}
+
+pub fn limit_mb() -> u64 {
+    LIMIT_MB.load(Ordering::Relaxed)
+}
+
+pub fn set_limit_mb(mb: u64) {
+    LIMIT_MB.store(mb, Ordering::Relaxed);
+}
+
+/// Subfolder for one kind of cached data, e.g. "proxies". The kind must be listed in KINDS.
+pub fn dir(kind: &str) -> PathBuf {
+    debug_assert!(KINDS.contains(&kind));
+    root().join(kind)
+}
+
+/// Cache key for data derived from `source`: path, size and mtime, so an edited source gets
+/// fresh data.
+pub fn source_key(source: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    if let Ok(meta) = std::fs::metadata(source) {
+        meta.len().hash(&mut hasher);
+        meta.modified().ok().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Marks a cached file as just used, so eviction keeps it longest.
+pub fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Every cached file with its size and last use.
+fn entries() -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files = Vec::new();
+    let mut dirs: Vec<PathBuf> = KINDS.iter().map(|kind| dir(kind)).collect();
+    while let Some(dir) = dirs.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read.filter_map(|e| e.ok()) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                let used = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((entry.path(), meta.len(), used));
+            }
+        }
+    }
+    files
+}
+
+/// Total size of the cache in bytes.
+pub fn size() -> u64 {
+    entries().iter().map(|(_, len, _)| len).sum()
+}
+
+/// Which files to delete, oldest first, to get `files` below `limit` bytes. `keep` is never
+/// picked, so a file that was just built survives even if it alone exceeds the limit.
+fn eviction_order(
+    mut files: Vec<(PathBuf, u64, SystemTime)>,
+    limit: u64,
+    keep: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, used)| *used);
+    let mut evict = Vec::new();
+    for (path, len, _) in files {
+        if total <= limit {
+            break;
+        }
+        if Some(path.as_path()) == keep {
+            continue;
+        }
+        total -= len;
+        evict.push(path);
+    }
+    evict
+}
+
+/// Deletes the least recently used files until the cache fits the size limit.
+pub fn evict(keep: Option<&Path>) {
+    let limit = limit_mb().saturating_mul(1024 * 1024);
+    for path in eviction_order(entries(), limit, keep) {
+        tracing::debug!(target: logs::DECODE, "Evicting {:?} from the cache", path);
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Deletes everything in the cache and returns how many bytes were freed.
+pub fn clear() -> Result<u64, String> {
+    let freed = size();
+    for kind in KINDS {
+        let dir = dir(kind);
+        match std::fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(trf("Could not remove {}: {}", &[&dir.display(), &e]));
+            }
+            _ => {}
+        }
+    }
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn evicts_oldest_first_but_keeps_the_new_file() {
+        let at = |s| SystemTime::UNIX_EPOCH + Duration::from_secs(s);
+        let files = vec![
+            (PathBuf::from("new"), 60, at(3)),
+            (PathBuf::from("old"), 30, at(1)),
+            (PathBuf::from("mid"), 30, at(2)),
+        ];
+        assert_eq!(
+            eviction_order(files.clone(), 70, None),
+            [PathBuf::from("old"), PathBuf::from("mid")]
+        );
+        assert_eq!(
+            eviction_order(files.clone(), 200, None),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(
+            eviction_order(files, 10, Some(Path::new("new"))),
+            [PathBuf::from("old"), PathBuf::from("mid")]
+        );
+    }
+}
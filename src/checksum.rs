@@ -0,0 +1,339 @@
+// SHA-256 of exported files, in the format `sha256sum` prints and checks, so a dataset copied to
+// a training cluster can be verified there with standard tools. Written out here (FIPS 180-4)
+// like `dataset::fnv1a`, rather than pulling in a crate for one function.
+// Every export updates `checksums.txt` in the output folder with the files the manifest lists;
+// verifying the folder against it catches files damaged on their way over a NAS or to the cloud
+// before a training job trips over them hours in.
+use crate::i18n::{tr, trf};
+use crate::logs;
+use crate::manifest::{self, Manifest};
+use crate::schedule::Schedule;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+pub const FILE_NAME: &str = "checksums.txt";
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// A running SHA-256: feed it with `update`, read the digest with `finish`.
+pub struct Sha256 {
+    state: [u32; 8],
+    /// Bytes of an incomplete block
+    block: Vec<u8>,
+    length: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: Vec::with_capacity(64),
+            length: 0,
+        }
+    }
+}
+
+impl Sha256 {
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.block.len()).min(data.len());
+            self.block.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.block.len() == 64 {
+                let block: [u8; 64] = self.block[..].try_into().unwrap();
+                self.compress(&block);
+                self.block.clear();
+            }
+        }
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    /// The digest as lowercase hex.
+    pub fn finish(mut self) -> String {
+        let bits = self.length.wrapping_mul(8);
+        let mut padding = vec![0x80];
+        padding.resize((119 - self.block.len()) % 64 + 1, 0);
+        padding.extend_from_slice(&bits.to_be_bytes());
+        self.update(&padding);
+        self.state
+            .iter()
+            .map(|word| format!("{:08x}", word))
+            .collect()
+    }
+}
+
+/// SHA-256 of the file at `path`, as lowercase hex.
+pub fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::default();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.finish()),
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+}
+
+/// One line of a `sha256sum` file: the digest, two spaces and the file name.
+pub fn line(digest: &str, name: &str) -> String {
+    format!("{}  {}\n", digest, name)
+}
+
+/// The `(name, digest)` pairs of a `sha256sum` file. Lines that aren't one are skipped.
+fn parse(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (digest, name) = line.split_once(' ')?;
+            // Binary mode marks the name with `*`
+            let name = name.strip_prefix([' ', '*'])?;
+            let valid = digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit());
+            valid.then(|| (name.to_string(), digest.to_ascii_lowercase()))
+        })
+        .collect()
+}
+
+/// The files `file` (relative to `out_dir`) stands for: itself, or the files in it and its
+/// subfolders for a frame sequence folder. Nothing if it is gone.
+fn files(out_dir: &Path, file: &str) -> Vec<String> {
+    let path = out_dir.join(file);
+    if !path.is_dir() {
+        return if path.is_file() {
+            vec![file.to_string()]
+        } else {
+            Vec::new()
+        };
+    }
+    let Ok(children) = std::fs::read_dir(&path) else {
+        return Vec::new();
+    };
+    let mut names: Vec<_> = children
+        .filter_map(|c| c.ok())
+        .map(|c| format!("{}/{}", file, c.file_name().to_string_lossy()))
+        .collect();
+    names.sort();
+    names.iter().flat_map(|name| files(out_dir, name)).collect()
+}
+
+/// The files of the dataset in `out_dir`: those `manifest` lists, and the manifest itself.
+pub fn dataset_files(out_dir: &Path, manifest: &Manifest) -> Vec<String> {
+    let listed = manifest.entries.iter().flat_map(|e| e.files());
+    std::iter::once(manifest::FILE_NAME)
+        .chain(listed.map(String::as_str))
+        .flat_map(|file| files(out_dir, file))
+        .collect()
+}
+
+/// Brings `checksums.txt` in `out_dir` up to date with `manifest`, which was just saved: files
+/// it no longer lists are dropped, `fresh` ones (just written, relative to `out_dir`) and those
+/// without a checksum yet are hashed, the others keep theirs. Returns how many were hashed.
+pub fn update(out_dir: &Path, manifest: &Manifest, fresh: &[String]) -> Result<usize, String> {
+    let path = out_dir.join(FILE_NAME);
+    let known = std::fs::read_to_string(&path)
+        .map(|text| parse(&text))
+        .unwrap_or_default();
+    let fresh: Vec<_> = fresh.iter().flat_map(|file| files(out_dir, file)).collect();
+    let mut sums = String::new();
+    let mut hashed = 0;
+    for file in dataset_files(out_dir, manifest) {
+        let digest = match known.get(&file) {
+            Some(digest) if file != manifest::FILE_NAME && !fresh.contains(&file) => digest.clone(),
+            _ => {
+                hashed += 1;
+                let path = out_dir.join(&file);
+                file_sha256(&path)
+                    .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?
+            }
+        };
+        sums.push_str(&line(&digest, &file));
+    }
+    std::fs::write(&path, sums)
+        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))?;
+    Ok(hashed)
+}
+
+/// `update`, for after an export: a failure only leaves the checksums behind the dataset until
+/// the next one, so it is logged rather than failing the export.
+pub fn record(out_dir: &Path, manifest: &Manifest, fresh: &[String]) {
+    if let Err(e) = update(out_dir, manifest, fresh) {
+        tracing::warn!(target: logs::EXPORT, "Could not update the checksums: {}", e);
+    }
+}
+
+/// The files of a dataset checked against `checksums.txt`.
+#[derive(Default)]
+pub struct Verification {
+    pub checked: usize,
+    /// Files whose content no longer matches their checksum
+    pub corrupt: Vec<String>,
+    /// Files with a checksum that are gone
+    pub missing: Vec<String>,
+    /// Files of the manifest without a checksum
+    pub unlisted: Vec<String>,
+}
+
+impl Verification {
+    /// What went wrong, one file per line; empty if nothing did.
+    pub fn problems(&self) -> Vec<String> {
+        let corrupt = self.corrupt.iter().map(|f| trf("Corrupt: {}", &[f]));
+        let missing = self.missing.iter().map(|f| trf("Missing: {}", &[f]));
+        let unlisted = self.unlisted.iter().map(|f| trf("No checksum: {}", &[f]));
+        corrupt.chain(missing).chain(unlisted).collect()
+    }
+}
+
+/// Checks the files in `out_dir` against its `checksums.txt`, stopping between files once
+/// `schedule` is cancelled.
+pub fn verify(out_dir: &Path, schedule: &Schedule) -> Result<Verification, String> {
+    let path = out_dir.join(FILE_NAME);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+    let sums = parse(&text);
+    let mut verification = Verification::default();
+    for (file, digest) in &sums {
+        if schedule.is_cancelled() {
+            return Err(tr("Verification cancelled").to_string());
+        }
+        let path = out_dir.join(file);
+        match file_sha256(&path) {
+            Ok(actual) if actual == *digest => verification.checked += 1,
+            Ok(_) => verification.corrupt.push(file.clone()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                verification.missing.push(file.clone())
+            }
+            Err(e) => return Err(trf("Could not read {}: {}", &[&path.display(), &e])),
+        }
+    }
+    let manifest = Manifest::load(out_dir)?;
+    verification.unlisted = dataset_files(out_dir, &manifest)
+        .into_iter()
+        .filter(|file| !sums.contains_key(file))
+        .collect();
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256(data: &[u8]) -> String {
+        let mut hasher = Sha256::default();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    #[test]
+    fn matches_the_fips_test_vectors() {
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // Fed in pieces that don't line up with the blocks
+        let mut hasher = Sha256::default();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            hasher.finish(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn verification_catches_changed_missing_and_unlisted_files() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_checksum_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("b.frames")).unwrap();
+        std::fs::write(dir.join("a.mp4"), "clip").unwrap();
+        std::fs::write(dir.join("b.frames/0001.png"), "frame").unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"version": 1, "entries": [
+                {"file": "a.mp4", "source": "a.mp4", "range": 0, "start_time": 0, "end_time": 1},
+                {"file": "b.frames", "source": "b.mp4", "range": 0, "start_time": 0, "end_time": 1}
+            ]}"#,
+        )
+        .unwrap();
+        manifest.save(&dir).unwrap();
+        assert_eq!(update(&dir, &manifest, &[]).unwrap(), 3);
+        // The manifest is hashed every time, the others when they are fresh
+        assert_eq!(
+            update(&dir, &manifest, &["b.frames".to_string()]).unwrap(),
+            2
+        );
+        let sums = std::fs::read_to_string(dir.join(FILE_NAME)).unwrap();
+        assert!(sums.contains("  b.frames/0001.png\n"));
+        let verification = verify(&dir, &Schedule::default()).unwrap();
+        assert_eq!(verification.checked, 3);
+        assert!(verification.problems().is_empty());
+
+        std::fs::write(dir.join("a.mp4"), "damaged").unwrap();
+        std::fs::remove_file(dir.join("b.frames/0001.png")).unwrap();
+        std::fs::write(dir.join("b.frames/0002.png"), "frame").unwrap();
+        let verification = verify(&dir, &Schedule::default()).unwrap();
+        assert_eq!(verification.checked, 1);
+        assert_eq!(verification.corrupt, ["a.mp4"]);
+        assert_eq!(verification.missing, ["b.frames/0001.png"]);
+        assert_eq!(verification.unlisted, ["b.frames/0002.png"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
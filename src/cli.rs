@@ -0,0 +1,104 @@
+// Command-line arguments for the state the app starts in, so a script preparing the day's
+// annotation batches can launch annotators straight into the right folders and file.
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: viddatatraincrop [--input <dir>] [--output <dir>] [--file <name>] [--project <file>] [--read-only] [--portable]";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    /// File name of the media to open, inside the input folder
+    pub file: Option<String>,
+    /// Project file to open instead of an input folder; `--output` overrides its output folder
+    pub project: Option<PathBuf>,
+    pub read_only: bool,
+    /// Keep the app's data next to the executable, see `portable`
+    pub portable: bool,
+}
+
+/// Parses the arguments after the program name. Options take their value as the next
+/// argument or after `=`.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        match name.as_str() {
+            "--read-only" if inline.is_none() => {
+                parsed.read_only = true;
+                continue;
+            }
+            "--portable" if inline.is_none() => {
+                parsed.portable = true;
+                continue;
+            }
+            _ => {}
+        }
+        if !matches!(
+            name.as_str(),
+            "--input" | "--output" | "--file" | "--project"
+        ) {
+            return Err(format!("Unknown argument {}", name));
+        }
+        let Some(value) = inline.or_else(|| args.next()) else {
+            return Err(format!("{} needs a value", name));
+        };
+        match name.as_str() {
+            "--input" => parsed.input = Some(PathBuf::from(value)),
+            "--output" => parsed.output = Some(PathBuf::from(value)),
+            "--project" => parsed.project = Some(PathBuf::from(value)),
+            _ => parsed.file = Some(value),
+        }
+    }
+    if parsed.input.is_some() && parsed.project.is_some() {
+        return Err("--input and --project can't be combined".to_string());
+    }
+    if parsed.file.is_some() && parsed.input.is_none() && parsed.project.is_none() {
+        return Err("--file needs --input or --project".to_string());
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse(list.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_folders_file_and_flags() {
+        let parsed = args(&[
+            "--input",
+            "/data/batch",
+            "--output=/data/out",
+            "--file",
+            "clip 01.mp4",
+            "--read-only",
+            "--portable",
+        ])
+        .unwrap();
+        assert_eq!(
+            parsed,
+            Args {
+                input: Some(PathBuf::from("/data/batch")),
+                output: Some(PathBuf::from("/data/out")),
+                file: Some("clip 01.mp4".to_string()),
+                project: None,
+                read_only: true,
+                portable: true,
+            }
+        );
+        assert_eq!(args(&[]).unwrap(), Args::default());
+        assert!(args(&["--input"]).is_err());
+        assert!(args(&["--file", "a.mp4"]).is_err());
+        assert!(args(&["--project", "p.vdtc", "--file", "a.mp4"]).is_ok());
+        assert!(args(&["--project", "p.vdtc", "--input", "/data"]).is_err());
+        assert!(args(&["--verbose"]).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+// Per-clip statistics for the manifest: brightness, contrast, colorfulness and motion. Sampling
+// and balancing scripts filter on them (dropping dark or static clips, evening out colorful
+// ones) and would otherwise have to decode the whole dataset again. They are measured on the
+// written output, so crop and frame rate are already applied, from a few frames spread over it.
+use crate::range_preview;
+use opencv::{core, imgcodecs, imgproc, prelude::*, videoio};
+use std::path::Path;
+
+/// Positions sampled per clip; each is a pair of consecutive frames, for the motion
+const SAMPLES: usize = 8;
+/// Frames are scaled to this width first; the statistics hardly change and decoding dominates
+const WIDTH: f64 = 160.0;
+
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Stats {
+    /// Mean luma, 0 (black) to 255
+    pub luma: f64,
+    /// Standard deviation of the luma (RMS contrast), 0 for a flat frame
+    pub contrast: f64,
+    /// Hasler and Süsstrunk's colorfulness: 0 for gray, around 100 for very colorful footage
+    pub colorfulness: f64,
+    /// Mean absolute luma change from one frame to the next; None for stills
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motion: Option<f64>,
+}
+
+/// Luma of the 8-bit BGR `frame`.
+fn luma(frame: &core::Mat) -> opencv::Result<core::Mat> {
+    let mut gray = core::Mat::default();
+    imgproc::cvt_color_def(frame, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    Ok(gray)
+}
+
+/// `frame` as 8-bit BGR, at most `WIDTH` wide.
+fn scaled(frame: &core::Mat) -> opencv::Result<core::Mat> {
+    let frame = crate::color::to_bgr8(frame)?;
+    let scale = (WIDTH / frame.cols().max(1) as f64).min(1.0);
+    let mut out = core::Mat::default();
+    let size = core::Size::new(
+        ((frame.cols() as f64 * scale).round() as i32).max(1),
+        ((frame.rows() as f64 * scale).round() as i32).max(1),
+    );
+    imgproc::resize(&frame, &mut out, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    Ok(out)
+}
+
+fn mean_std_dev(image: &core::Mat) -> opencv::Result<(f64, f64)> {
+    let (mut mean, mut stddev) = (core::Vector::<f64>::new(), core::Vector::<f64>::new());
+    core::mean_std_dev_def(image, &mut mean, &mut stddev)?;
+    Ok((mean.get(0)?, stddev.get(0)?))
+}
+
+/// Colorfulness after Hasler and Süsstrunk (2003), from the opponent channels R - G and
+/// (R + G) / 2 - B of the 8-bit BGR `frame`.
+fn colorfulness(frame: &core::Mat) -> opencv::Result<f64> {
+    let mut float = core::Mat::default();
+    frame.convert_to(&mut float, core::CV_32F, 1.0, 0.0)?;
+    // Both channels as weighted sums of B, G and R
+    let opponent = |weights: [f64; 3]| -> opencv::Result<(f64, f64)> {
+        let mut channel = core::Mat::default();
+        let m = core::Mat::from_slice_2d(&[weights])?;
+        core::transform(&float, &mut channel, &m)?;
+        mean_std_dev(&channel)
+    };
+    let (rg_mean, rg_std) = opponent([0.0, -1.0, 1.0])?;
+    let (yb_mean, yb_std) = opponent([-1.0, 0.5, 0.5])?;
+    Ok(rg_std.hypot(yb_std) + 0.3 * rg_mean.hypot(yb_mean))
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Averages the statistics of `frames`, each a sample and, for videos, the frame after it.
+fn combine(frames: &[(core::Mat, Option<core::Mat>)]) -> opencv::Result<Option<Stats>> {
+    if frames.is_empty() {
+        return Ok(None);
+    }
+    let (mut luma_sum, mut contrast_sum, mut color_sum) = (0.0, 0.0, 0.0);
+    let mut motion = Vec::new();
+    for (frame, next) in frames {
+        let frame = scaled(frame)?;
+        let gray = luma(&frame)?;
+        let (mean, stddev) = mean_std_dev(&gray)?;
+        luma_sum += mean;
+        contrast_sum += stddev;
+        color_sum += colorfulness(&frame)?;
+        if let Some(next) = next {
+            let next = luma(&scaled(next)?)?;
+            if next.size()? == gray.size()? {
+                let mut diff = core::Mat::default();
+                core::absdiff(&gray, &next, &mut diff)?;
+                motion.push(core::mean_def(&diff)?[0]);
+            }
+        }
+    }
+    let n = frames.len() as f64;
+    Ok(Some(Stats {
+        luma: round(luma_sum / n),
+        contrast: round(contrast_sum / n),
+        colorfulness: round(color_sum / n),
+        motion: (!motion.is_empty())
+            .then(|| round(motion.iter().sum::<f64>() / motion.len() as f64)),
+    }))
+}
+
+/// Statistics of the exported `output`: a still, or a clip or frame sequence pattern that
+/// OpenCV can open. None when nothing could be decoded.
+pub fn measure(output: &Path, is_still: bool) -> opencv::Result<Option<Stats>> {
+    let name = output.to_string_lossy();
+    if is_still {
+        let image = imgcodecs::imread(&name, imgcodecs::IMREAD_UNCHANGED)?;
+        if image.empty() {
+            return Ok(None);
+        }
+        return combine(&[(image, None)]);
+    }
+    let mut cap = videoio::VideoCapture::from_file(&name, videoio::CAP_ANY)?;
+    let count = cap
+        .get(videoio::CAP_PROP_FRAME_COUNT)
+        .unwrap_or(0.0)
+        .max(1.0);
+    let mut frames = Vec::new();
+    for position in range_preview::frame_times(0.0, count, SAMPLES) {
+        let _ = cap.set(videoio::CAP_PROP_POS_FRAMES, position.floor());
+        let (mut frame, mut next) = (core::Mat::default(), core::Mat::default());
+        if !cap.read(&mut frame).unwrap_or(false) || frame.empty() {
+            continue;
+        }
+        let next = (cap.read(&mut next).unwrap_or(false) && !next.empty()).then_some(next);
+        frames.push((frame, next));
+    }
+    combine(&frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(b: f64, g: f64, r: f64) -> core::Mat {
+        core::Mat::new_rows_cols_with_default(
+            90,
+            160,
+            core::CV_8UC3,
+            core::Scalar::new(b, g, r, 0.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn measures_brightness_color_and_motion() {
+        let gray = combine(&[(solid(128.0, 128.0, 128.0), Some(solid(128.0, 128.0, 128.0)))])
+            .unwrap()
+            .unwrap();
+        assert_eq!(gray.luma, 128.0);
+        assert_eq!(gray.contrast, 0.0);
+        assert_eq!(gray.colorfulness, 0.0);
+        assert_eq!(gray.motion, Some(0.0));
+
+        let red = combine(&[(solid(0.0, 0.0, 255.0), Some(solid(0.0, 0.0, 0.0)))])
+            .unwrap()
+            .unwrap();
+        assert!(red.colorfulness > 80.0, "{:?}", red);
+        assert!(red.motion.unwrap() > 50.0, "{:?}", red);
+
+        let still = combine(&[(solid(10.0, 10.0, 10.0), None)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(still.motion, None);
+        assert!(combine(&[]).unwrap().is_none());
+    }
+}
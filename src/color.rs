@@ -0,0 +1,292 @@
+// Normalizes decoded frames to 8-bit BGR for display and analysis, and corrects the colors of
+// the preview. OpenCV's FFmpeg backend turns every video into RGB with the BT.601 matrix and
+// shows it as if it had sRGB's primaries, whatever the file says: HD (BT.709) sources come out
+// with shifted hues and SD sources (SMPTE 170M, EBU) too saturated. The preview re-does
+// the conversion with the source's own matrix and moves its primaries to sRGB's, which is
+// what egui textures are; the window system maps sRGB to wide-gamut (Display P3) screens.
+use crate::probe;
+use opencv::{core, imgproc, prelude::*};
+
+/// 4x4 Bayer matrix, used to dither high bit depth frames down to 8 bit instead of truncating.
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// Tiled dither offsets in [-0.5, 0.5) with the same size and channel count as `like`.
+fn dither_pattern(like: &core::Mat) -> opencv::Result<core::Mat> {
+    let cell: Vec<Vec<f32>> = BAYER_4X4
+        .iter()
+        .map(|row| row.iter().map(|v| (v + 0.5) / 16.0 - 0.5).collect())
+        .collect();
+    let cell = core::Mat::from_slice_2d(&cell)?;
+    let tiled = core::repeat(&cell, like.rows() / 4 + 1, like.cols() / 4 + 1)?;
+    let tiled = tiled
+        .roi(core::Rect::new(0, 0, like.cols(), like.rows()))?
+        .try_clone()?;
+    let planes =
+        core::Vector::<core::Mat>::from_iter(std::iter::repeat_n(tiled, like.channels() as usize));
+    let mut pattern = core::Mat::default();
+    core::merge(&planes, &mut pattern)?;
+    Ok(pattern)
+}
+
+/// Converts any depth to 8 bit, dithering when the source has more than 8 bits per channel.
+fn to_8bit(frame: &core::Mat) -> opencv::Result<core::Mat> {
+    let scale = match frame.depth() {
+        core::CV_8U => return frame.try_clone(),
+        core::CV_16U => 255.0 / 65535.0,
+        // Float frames are expected in 0.0-1.0
+        core::CV_32F | core::CV_64F => 255.0,
+        _ => 1.0,
+    };
+    let mut scaled = core::Mat::default();
+    frame.convert_to(&mut scaled, core::CV_32F, scale, 0.0)?;
+    let mut dithered = core::Mat::default();
+    core::add_def(&scaled, &dither_pattern(&scaled)?, &mut dithered)?;
+    let mut out = core::Mat::default();
+    dithered.convert_to(&mut out, core::CV_8U, 1.0, 0.0)?;
+    Ok(out)
+}
+
+/// 8-bit, 3 channel BGR version of a decoded frame of any channel count and depth.
+pub fn to_bgr8(frame: &core::Mat) -> opencv::Result<core::Mat> {
+    let frame = to_8bit(frame)?;
+    let code = match frame.channels() {
+        1 => imgproc::COLOR_GRAY2BGR,
+        4 => imgproc::COLOR_BGRA2BGR,
+        _ => return Ok(frame),
+    };
+    let mut bgr = core::Mat::default();
+    imgproc::cvt_color_def(&frame, &mut bgr, code)?;
+    Ok(bgr)
+}
+
+/// Row-major 3 × 3 matrix, applied to column vectors.
+type Matrix3 = [[f64; 3]; 3];
+
+fn multiply(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn invert(m: &Matrix3) -> Matrix3 {
+    // Cyclic indices give the cofactors their signs
+    let cofactor = |r: usize, c: usize| {
+        let (r1, r2, c1, c2) = ((r + 1) % 3, (r + 2) % 3, (c + 1) % 3, (c + 2) % 3);
+        m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+    };
+    let det: f64 = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum();
+    std::array::from_fn(|i| std::array::from_fn(|j| cofactor(j, i) / det))
+}
+
+/// The same matrix for BGR instead of RGB vectors.
+fn bgr(m: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[2 - i][2 - j]))
+}
+
+/// YCbCr matrix coefficients of a video.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Matrix {
+    Bt601,
+    Bt709,
+    Bt2020,
+}
+
+impl Matrix {
+    /// From ffprobe's `color_space`. Untagged files follow the usual convention: BT.709 above
+    /// 576 lines, BT.601 up to it.
+    pub fn from_tag(tag: &str, height: i32) -> Matrix {
+        match tag {
+            "bt709" => Matrix::Bt709,
+            "bt2020nc" | "bt2020c" => Matrix::Bt2020,
+            "bt470bg" | "smpte170m" => Matrix::Bt601,
+            _ if height > 576 => Matrix::Bt709,
+            _ => Matrix::Bt601,
+        }
+    }
+
+    /// R'G'B' to Y'CbCr with the chroma centered on 0.
+    fn rgb_to_ycbcr(self) -> Matrix3 {
+        let (kr, kb) = match self {
+            Matrix::Bt601 => (0.299, 0.114),
+            Matrix::Bt709 => (0.2126, 0.0722),
+            Matrix::Bt2020 => (0.2627, 0.0593),
+        };
+        let kg = 1.0 - kr - kb;
+        [
+            [kr, kg, kb],
+            [-kr / (2.0 * (1.0 - kb)), -kg / (2.0 * (1.0 - kb)), 0.5],
+            [0.5, -kg / (2.0 * (1.0 - kr)), -kb / (2.0 * (1.0 - kr))],
+        ]
+    }
+}
+
+/// Color primaries of a video, all with a D65 white point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Primaries {
+    /// BT.709, the same as sRGB
+    Bt709,
+    /// SMPTE 170M, NTSC and 480-line SD
+    Ntsc,
+    /// BT.470 B/G (EBU), PAL and 576-line SD
+    Pal,
+    DisplayP3,
+    Bt2020,
+}
+
+impl Primaries {
+    /// From ffprobe's `color_primaries`; untagged SD files get the primaries of their line
+    /// count, everything else BT.709.
+    pub fn from_tag(tag: &str, height: i32) -> Primaries {
+        match tag {
+            "bt709" => Primaries::Bt709,
+            "smpte170m" | "smpte240m" => Primaries::Ntsc,
+            "bt470bg" => Primaries::Pal,
+            "smpte432" => Primaries::DisplayP3,
+            "bt2020" => Primaries::Bt2020,
+            _ if height == 576 => Primaries::Pal,
+            _ if height <= 480 => Primaries::Ntsc,
+            _ => Primaries::Bt709,
+        }
+    }
+
+    /// Chromaticities (x, y) of red, green and blue.
+    fn xy(self) -> [(f64, f64); 3] {
+        match self {
+            Primaries::Bt709 => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)],
+            Primaries::Ntsc => [(0.63, 0.34), (0.31, 0.595), (0.155, 0.07)],
+            Primaries::Pal => [(0.64, 0.33), (0.29, 0.60), (0.15, 0.06)],
+            Primaries::DisplayP3 => [(0.68, 0.32), (0.265, 0.69), (0.15, 0.06)],
+            Primaries::Bt2020 => [(0.708, 0.292), (0.17, 0.797), (0.131, 0.046)],
+        }
+    }
+
+    /// Linear RGB with these primaries to CIE XYZ.
+    fn to_xyz(self) -> Matrix3 {
+        let xyz = |(x, y): (f64, f64)| [x / y, 1.0, (1.0 - x - y) / y];
+        let columns = self.xy().map(xyz);
+        let primaries: Matrix3 = std::array::from_fn(|i| std::array::from_fn(|j| columns[j][i]));
+        let white = xyz((0.3127, 0.3290));
+        let inverse = invert(&primaries);
+        let scale: [f64; 3] =
+            std::array::from_fn(|i| (0..3).map(|k| inverse[i][k] * white[k]).sum());
+        std::array::from_fn(|i| std::array::from_fn(|j| primaries[i][j] * scale[j]))
+    }
+}
+
+/// Display gamma the primaries are converted under (BT.1886)
+const GAMMA: f64 = 2.4;
+
+/// The corrections the preview of one source needs; the default leaves frames as decoded.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Pipeline {
+    /// Undoes OpenCV's BT.601 conversion and applies the source's matrix, on gamma-encoded BGR
+    matrix: Option<Matrix3>,
+    /// Linear BGR in the source's primaries to linear BGR in sRGB's
+    gamut: Option<Matrix3>,
+}
+
+impl Pipeline {
+    pub fn new(matrix: Matrix, primaries: Primaries) -> Pipeline {
+        let matrix = (matrix != Matrix::Bt601).then(|| {
+            bgr(&multiply(
+                &invert(&matrix.rgb_to_ycbcr()),
+                &Matrix::Bt601.rgb_to_ycbcr(),
+            ))
+        });
+        let gamut = (primaries != Primaries::Bt709).then(|| {
+            bgr(&multiply(
+                &invert(&Primaries::Bt709.to_xyz()),
+                &primaries.to_xyz(),
+            ))
+        });
+        Pipeline { matrix, gamut }
+    }
+
+    /// The corrections for a video with `info` that is `height` lines high. HDR sources are
+    /// previewed through a proxy tonemapped to BT.709.
+    pub fn for_video(info: Option<&probe::ColorInfo>, height: i32) -> Pipeline {
+        match info {
+            Some(info) if info.is_hdr() => Pipeline::new(Matrix::Bt709, Primaries::Bt709),
+            Some(info) => Pipeline::new(
+                Matrix::from_tag(&info.matrix, height),
+                Primaries::from_tag(&info.primaries, height),
+            ),
+            None => Pipeline::new(
+                Matrix::from_tag("", height),
+                Primaries::from_tag("", height),
+            ),
+        }
+    }
+
+    /// Corrects an 8-bit BGR frame.
+    pub fn apply(&self, frame: core::Mat) -> opencv::Result<core::Mat> {
+        if self.matrix.is_none() && self.gamut.is_none() {
+            return Ok(frame);
+        }
+        let transform = |image: &core::Mat, m: &Matrix3| -> opencv::Result<core::Mat> {
+            let mut out = core::Mat::default();
+            core::transform(image, &mut out, &core::Mat::from_slice_2d(m)?)?;
+            Ok(out)
+        };
+        // With non-integer powers OpenCV takes absolute values, so out-of-gamut values are
+        // clipped to 0 first
+        let power = |image: &core::Mat, exponent: f64| -> opencv::Result<core::Mat> {
+            let mut clipped = core::Mat::default();
+            imgproc::threshold(image, &mut clipped, 0.0, 0.0, imgproc::THRESH_TOZERO)?;
+            let mut out = core::Mat::default();
+            core::pow(&clipped, exponent, &mut out)?;
+            Ok(out)
+        };
+        let mut image = core::Mat::default();
+        frame.convert_to(&mut image, core::CV_32F, 1.0 / 255.0, 0.0)?;
+        if let Some(m) = &self.matrix {
+            image = transform(&image, m)?;
+        }
+        if let Some(m) = &self.gamut {
+            let linear = transform(&power(&image, GAMMA)?, m)?;
+            image = power(&linear, 1.0 / GAMMA)?;
+        }
+        let mut out = core::Mat::default();
+        image.convert_to(&mut out, core::CV_8U, 255.0, 0.0)?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(m: &Matrix3, v: [f64; 3]) -> [f64; 3] {
+        std::array::from_fn(|i| (0..3).map(|k| m[i][k] * v[k]).sum())
+    }
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3)
+    }
+
+    #[test]
+    fn corrections_keep_gray_and_fix_the_matrix() {
+        assert_eq!(
+            Pipeline::new(Matrix::Bt601, Primaries::Bt709),
+            Pipeline::default()
+        );
+        let hd = Pipeline::new(Matrix::Bt709, Primaries::Bt709);
+        let sd = Pipeline::new(Matrix::Bt601, Primaries::Pal);
+        for m in [hd.matrix.unwrap(), sd.gamut.unwrap()] {
+            assert!(close(apply(&m, [0.5, 0.5, 0.5]), [0.5, 0.5, 0.5]));
+        }
+        // Pure BT.709 red, decoded by OpenCV with BT.601 coefficients, is red again
+        let red_ycbcr = apply(&Matrix::Bt709.rgb_to_ycbcr(), [1.0, 0.0, 0.0]);
+        let decoded = apply(&invert(&Matrix::Bt601.rgb_to_ycbcr()), red_ycbcr);
+        let [b, g, r] = apply(&hd.matrix.unwrap(), [decoded[2], decoded[1], decoded[0]]);
+        assert!(close([r, g, b], [1.0, 0.0, 0.0]));
+
+        assert_eq!(Matrix::from_tag("unknown", 1080), Matrix::Bt709);
+        assert_eq!(Matrix::from_tag("", 480), Matrix::Bt601);
+        assert_eq!(Primaries::from_tag("", 576), Primaries::Pal);
+        assert_eq!(Primaries::from_tag("bt709", 576), Primaries::Bt709);
+    }
+}
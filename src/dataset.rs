@@ -0,0 +1,1596 @@
+// Writing annotated ranges into the output folder: one media file at a time for a normal
+// export, or every annotated file in the folder for a full rebuild.
+use crate::i18n::{tr, trf};
+use crate::manifest::{self, Manifest};
+use crate::progress::{self, Progress};
+use crate::schedule::Schedule;
+use crate::{
+    VideoRange, checksum, clip_stats, export, is_image_file, keyframes, logs, material, pool,
+    probe, remote, timing, trim, verify,
+};
+use opencv::{imgcodecs, prelude::*, videoio};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// One media file and the ranges to export from it.
+pub struct Job {
+    pub input: PathBuf,
+    pub ranges: Vec<VideoRange>,
+    pub fps: f64,
+    /// Stored size in pixels
+    pub frame_size: (f64, f64),
+    pub pixel_aspect: f64,
+    pub profile: export::Profile,
+}
+
+impl Job {
+    /// Reads what the export needs from the file itself, for media that isn't open in the UI.
+    /// Mirrors the fallbacks used when a file is opened.
+    pub fn probe(
+        input: PathBuf,
+        ranges: Vec<VideoRange>,
+        profile: export::Profile,
+    ) -> Result<Job, String> {
+        let path = input.to_string_lossy().to_string();
+        let failed = || trf("Could not open {}", &[&input.display()]);
+        if is_image_file(&input) {
+            let mat =
+                imgcodecs::imread(&path, imgcodecs::IMREAD_UNCHANGED).map_err(|_| failed())?;
+            let size = mat.size().map_err(|_| failed())?;
+            if size.width == 0 {
+                return Err(failed());
+            }
+            return Ok(Job {
+                input,
+                ranges,
+                fps: 1.0,
+                frame_size: (size.width as f64, size.height as f64),
+                pixel_aspect: 1.0,
+                profile,
+            });
+        }
+        let stream = remote::input(&input);
+        let mut cap = videoio::VideoCapture::from_file(&stream.to_string_lossy(), videoio::CAP_ANY)
+            .ok()
+            .filter(|c| c.is_opened().unwrap_or(false))
+            .ok_or_else(failed)?;
+        let fps = cap.get(videoio::CAP_PROP_FPS).unwrap_or(0.0);
+        let fps = if probe::is_valid_fps(fps) {
+            fps
+        } else {
+            probe::video_fps(&input)
+                .or_else(|| probe::fps_from_timestamps(&mut cap))
+                .unwrap_or(30.0)
+        };
+        let frame_size = (
+            cap.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap_or(1920.0),
+            cap.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(1080.0),
+        );
+        let pixel_aspect = probe::sample_aspect_ratio(&input);
+        Ok(Job {
+            input,
+            ranges,
+            fps,
+            frame_size,
+            pixel_aspect,
+            profile,
+        })
+    }
+
+    /// The media as the export commands see it.
+    pub fn source(&self) -> export::Source<'_> {
+        export::Source {
+            path: &self.input,
+            is_img: is_image_file(&self.input),
+            fps: self.fps,
+            frame_size: self.frame_size,
+            pixel_aspect: self.pixel_aspect,
+        }
+    }
+
+    /// File name of the media, which is how the manifest refers to it.
+    pub fn source_name(&self) -> String {
+        self.input
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// What exporting one job produced.
+pub struct Outcome {
+    /// Manifest entries of the ranges that were written
+    pub entries: Vec<manifest::Entry>,
+    /// Set when the export stopped: it was cancelled or FFmpeg could not be started. The ranges
+    /// after it are skipped.
+    pub error: Option<String>,
+    /// Ranges that failed, with why; the others are exported regardless
+    pub failures: Vec<String>,
+    /// Ranges whose first exported frame is not the previewed one
+    pub start_mismatches: Vec<String>,
+    /// Ranges whose output was up to date and not encoded again
+    pub unchanged: usize,
+}
+
+impl Outcome {
+    /// The message to show for this export, if any. A stopped export wins over failed ranges,
+    /// and those over start mismatches.
+    pub fn message(&self) -> Option<String> {
+        let failures = (!self.failures.is_empty()).then(|| self.failures.join("\n"));
+        self.error.clone().or(failures).or_else(|| {
+            (!self.start_mismatches.is_empty()).then(|| {
+                trf(
+                    "Exported clips start off the previewed frame: {}",
+                    &[&self.start_mismatches.join(", ")],
+                )
+            })
+        })
+    }
+}
+
+pub fn relative(out_dir: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(out_dir).unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Folder names made from a note are cut to this many characters.
+const NOTE_FOLDER_CHARS: usize = 64;
+
+/// Folder name for the class `tag`: characters that aren't safe in file names become `_`.
+/// None when nothing usable is left.
+fn class_folder(tag: &str) -> Option<String> {
+    let name: String = tag
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(name).filter(|n| n.chars().any(|c| c != '_'))
+}
+
+/// `name` with the characters that aren't allowed in file names on some system replaced by `_`.
+fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Base name of range `i` from the profile's name template, None without one. Tokens: `{stem}`,
+/// `{range}`, `{start_frame}` and `{end_frame}` (native frames), `{tag}` (the first tag, as its
+/// class folder is named) and `{date}` (the day the range was last changed, UTC). A template
+/// without `{range}` gets `_range<i>` appended for media with several ranges, so no two ranges
+/// share a file.
+fn templated_name(
+    profile: &export::Profile,
+    media: &Path,
+    range: &VideoRange,
+    i: usize,
+    count: usize,
+) -> Option<String> {
+    let template = profile.name_template.trim();
+    if template.is_empty() {
+        return None;
+    }
+    let stem = media.file_stem().unwrap().to_string_lossy().to_string();
+    let is_img = is_image_file(media);
+    // Only probed when a frame token needs it; the same rate for the export and the audit
+    let fps =
+        (!is_img && template.contains("_frame}")).then(|| probe::video_fps(media).unwrap_or(30.0));
+    let frame = |time: f64| fps.map_or(0, |fps| timing::frame_at(time, fps));
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name += &rest[..open];
+        rest = &rest[open..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let value = match &rest[1..close] {
+            "stem" => stem.clone(),
+            "range" => i.to_string(),
+            "start_frame" => frame(range.start_time).to_string(),
+            "end_frame" => frame(range.end_time).to_string(),
+            "tag" => profile
+                .rules
+                .apply(range)
+                .tags
+                .first()
+                .and_then(|t| class_folder(t))
+                .unwrap_or_default(),
+            "date" => range
+                .modified_at
+                .as_deref()
+                .and_then(|at| at.get(..10))
+                .unwrap_or_default()
+                .to_string(),
+            _ => rest[..=close].to_string(),
+        };
+        name += &value;
+        rest = &rest[close + 1..];
+    }
+    name += rest;
+    let mut name = file_name_safe(name.trim());
+    if name.is_empty() {
+        name = stem;
+    }
+    if count > 1 && !template.contains("{range}") {
+        name += &format!("_range{}", i);
+    }
+    Some(name)
+}
+
+/// Where range `i` of the `count` ranges of `media` is exported to. Stills keep their format,
+/// clips get the extension of the profile's container and frame sequences are a `.frames`
+/// folder. Files are named after the profile's name template, or `<stem>_range<i>` (just the
+/// stem for a single range) without one. Ranges assigned to a split go into its
+/// subfolder and, with folders on, into a folder named after their first tag or the first line
+/// of their note within it (created by the export).
+pub fn output_file(
+    out_dir: &Path,
+    media: &Path,
+    range: &VideoRange,
+    i: usize,
+    count: usize,
+    profile: &export::Profile,
+) -> PathBuf {
+    let mut dir = match range.split {
+        Some(split) => out_dir.join(split.name()),
+        None => out_dir.to_path_buf(),
+    };
+    let folder = match profile.folders {
+        export::Folders::None => None,
+        // The project rules decide which tag comes first, and so the class
+        export::Folders::FirstTag => profile
+            .rules
+            .apply(range)
+            .tags
+            .first()
+            .and_then(|t| class_folder(t)),
+        export::Folders::NoteLine => {
+            let line = range.note.lines().next().unwrap_or_default().trim();
+            class_folder(&line.chars().take(NOTE_FOLDER_CHARS).collect::<String>())
+        }
+    };
+    if let Some(folder) = folder {
+        dir.push(folder);
+    }
+    let stem = media.file_stem().unwrap().to_string_lossy().to_string();
+    let out_base = if let Some(name) = templated_name(profile, media, range, i, count) {
+        dir.join(name)
+    } else if count > 1 {
+        dir.join(format!("{}_range{}", &stem, i))
+    } else {
+        dir.join(&stem)
+    };
+    let ext = media
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let out_ext = if is_image_file(media) {
+        ext.to_string()
+    } else if profile.frames.is_some() {
+        export::FRAMES_EXTENSION.to_string()
+    } else {
+        profile.container.extension().to_string()
+    };
+    out_base.with_added_extension(&out_ext)
+}
+
+/// `out_file` with `_001`-style number `n` added to its name: `clip_range0_002.mp4`.
+fn incremented(out_file: &Path, n: usize) -> PathBuf {
+    let stem = out_file.file_stem().unwrap().to_string_lossy();
+    let name = match out_file.extension() {
+        Some(ext) => format!("{}_{:03}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{:03}", stem, n),
+    };
+    out_file.with_file_name(name)
+}
+
+/// The output-folder relative `file` without the number an export added to dodge a collision,
+/// so `train/clip_002.mp4` (or its caption `train/clip_002.txt`) gives `train/clip.mp4`. None
+/// when the name has no number.
+pub fn without_increment(file: &str) -> Option<String> {
+    let name_start = file.rfind('/').map_or(0, |i| i + 1);
+    let name = &file[name_start..];
+    let at = name.match_indices('_').map(|(i, _)| i).rev().find(|&i| {
+        let number = name.get(i + 1..i + 4).unwrap_or_default();
+        number.len() == 3
+            && number.bytes().all(|b| b.is_ascii_digit())
+            && matches!(name.as_bytes().get(i + 4), None | Some(b'.'))
+    })?;
+    Some(format!(
+        "{}{}{}",
+        &file[..name_start],
+        &name[..at],
+        &name[at + 4..]
+    ))
+}
+
+/// Whether the output `file` of range `i` was written by that range's previous export, which
+/// `previous` (the source's manifest entries) records. Those are replaced as usual; any other
+/// file at that name is a collision.
+fn owns(out_dir: &Path, previous: &[manifest::Entry], i: usize, file: &Path) -> bool {
+    let file = relative(out_dir, file);
+    previous.iter().any(|p| p.range == i && p.file == file)
+}
+
+/// Where range `i` goes when its output name is `out_file`: there, unless another file is
+/// already in the way, in which case the profile's collision policy decides. None skips the
+/// range.
+fn resolve_collision(
+    out_dir: &Path,
+    out_file: PathBuf,
+    previous: &[manifest::Entry],
+    i: usize,
+    policy: export::Collisions,
+) -> Option<PathBuf> {
+    let free = |file: &Path| !file.exists() || owns(out_dir, previous, i, file);
+    if free(&out_file) {
+        return Some(out_file);
+    }
+    match policy {
+        export::Collisions::Overwrite => Some(out_file),
+        export::Collisions::Skip => None,
+        export::Collisions::Increment => (1..)
+            .map(|n| incremented(&out_file, n))
+            .find(|file| free(file)),
+    }
+}
+
+/// Outputs of `ranges` whose name is already taken by a file their previous export (in
+/// `previous`) didn't write, relative to `out_dir`. Shown before an export so the collision
+/// policy doesn't surprise.
+pub fn collisions(
+    out_dir: &Path,
+    media: &Path,
+    ranges: &[VideoRange],
+    previous: &[manifest::Entry],
+    profile: &export::Profile,
+) -> Vec<String> {
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (i, output_file(out_dir, media, r, i, ranges.len(), profile)))
+        .filter(|(i, file)| file.exists() && !owns(out_dir, previous, *i, file))
+        .map(|(_, file)| relative(out_dir, &file))
+        .collect()
+}
+
+/// Removes an output: a file, or a frame sequence folder with its frames.
+pub fn remove_output(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// The caption written next to an output file.
+pub fn caption_file(out_file: &Path) -> PathBuf {
+    out_file.with_extension("txt")
+}
+
+/// The audio file written next to an output clip.
+pub fn audio_file(out_file: &Path, format: export::AudioFormat) -> PathBuf {
+    out_file.with_extension(format.extension())
+}
+
+/// The animated preview written next to an output clip: `clip.preview.gif` for `clip.mp4`.
+pub fn preview_file(out_file: &Path, format: export::PreviewFormat) -> PathBuf {
+    out_file.with_extension(format!("preview.{}", format.extension()))
+}
+
+/// The sprite sheet written next to an output clip: `clip.sprite4x4.jpg` for `clip.mp4`.
+pub fn sprite_file(out_file: &Path, grid: u32) -> PathBuf {
+    out_file.with_extension(format!("sprite{}x{}.jpg", grid, grid))
+}
+
+/// Writes `file`, the `what` of a clip, with the FFmpeg `command` unless it is there already
+/// and `rewrite` is off. Returns the file if it exists afterwards.
+fn export_companion(
+    file: PathBuf,
+    what: &str,
+    rewrite: bool,
+    command: impl FnOnce(&Path) -> std::process::Command,
+) -> Option<PathBuf> {
+    if rewrite || !file.exists() {
+        match command(&file).status() {
+            Ok(status) if status.success() => {}
+            Ok(_) => {
+                tracing::warn!(target: logs::EXPORT, file = ?file, "No {} written", what);
+                let _ = fs::remove_file(&file);
+            }
+            Err(e) => {
+                tracing::error!(target: logs::EXPORT, "Failed to start FFmpeg for the {}: {}", what, e)
+            }
+        }
+    }
+    file.exists().then_some(file)
+}
+
+/// Writes the audio of `range` next to `out_file` unless it is there already and `rewrite` is
+/// off. Media without an audio track gets none. Returns the file if it exists afterwards.
+fn export_audio(
+    source: &export::Source,
+    range: &VideoRange,
+    out_file: &Path,
+    format: export::AudioFormat,
+    profile: &export::Profile,
+    rewrite: bool,
+) -> Option<PathBuf> {
+    let audio = audio_file(out_file, format);
+    if rewrite || !audio.exists() {
+        match export::audio_command(source, range, &audio, format, profile).status() {
+            Ok(status) if status.success() => {}
+            Ok(_) => {
+                tracing::warn!(target: logs::EXPORT, file = ?out_file, "No audio written");
+                let _ = fs::remove_file(&audio);
+            }
+            Err(e) => {
+                tracing::error!(target: logs::EXPORT, "Failed to start FFmpeg for audio: {}", e)
+            }
+        }
+    }
+    audio.exists().then_some(audio)
+}
+
+/// Caption of range `i` of `media` under the profile's template, which may use `{note}`,
+/// `{tags}` (comma separated), `{source}` (the media's file stem), `{range}` and `{split}`.
+/// Unknown tokens are kept as written. The project rules filter and order the tags and put
+/// missing trigger words in front. None when the result is blank; then no caption file is
+/// written.
+pub fn caption_text(
+    profile: &export::Profile,
+    media: &Path,
+    i: usize,
+    range: &VideoRange,
+) -> Option<String> {
+    let range = &profile.rules.apply(range);
+    let mut text = String::new();
+    let mut rest = profile.caption_template.as_str();
+    while let Some(open) = rest.find('{') {
+        text += &rest[..open];
+        rest = &rest[open..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let value = match &rest[1..close] {
+            "note" => range.note.clone(),
+            "tags" => range.tags.join(", "),
+            "source" => media
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            "range" => i.to_string(),
+            "split" => range.split.map_or("", |s| s.name()).to_string(),
+            _ => rest[..=close].to_string(),
+        };
+        text += &value;
+        rest = &rest[close + 1..];
+    }
+    text += rest;
+    profile
+        .rules
+        .caption((!text.trim().is_empty()).then_some(text))
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions and platforms.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_update(FNV1A_OFFSET, bytes)
+}
+
+pub const FNV1A_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues the FNV-1a `hash` over `bytes`, for data read in chunks.
+pub fn fnv1a_update(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Fingerprint of everything that determines an output clip: the FFmpeg arguments, so times,
+/// crop, frame rate and encoder settings all count. The media is identified by its file name
+/// and the output by `name`, its path relative to the output folder when it was encoded, so
+/// moving either folder keeps the hashes. A remote source counts by its link's name too, not
+/// by the local stream address that changes with every start.
+fn command_hash(cmd: &std::process::Command, job: &Job, out_file: &Path, name: &str) -> String {
+    let stream = remote::input(&job.input);
+    let mut text = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        let arg = if arg == job.input.as_os_str() || arg == stream.as_os_str() {
+            job.source_name()
+        } else if arg == out_file.as_os_str() {
+            name.to_string()
+        } else if let Ok(frames) = Path::new(arg).strip_prefix(out_file) {
+            // The file pattern of a frame sequence
+            format!("{}/{}", name, frames.to_string_lossy())
+        } else {
+            arg.to_string_lossy().to_string()
+        };
+        text.push('\0');
+        text += &arg;
+    }
+    format!("{:016x}", fnv1a(text.as_bytes()))
+}
+
+/// `key=value` tags that keep a clip traceable once it is separated from the manifest: the
+/// source, the range, a fingerprint of the caption and the app version.
+fn clip_metadata(source: &str, range: usize, caption: &str) -> Vec<String> {
+    vec![
+        format!("title={} range {}", source, range),
+        format!(
+            "comment=source={}; range={}; caption_fnv1a={:016x}; app=viddatatraincrop {}",
+            source,
+            range,
+            fnv1a(caption.as_bytes()),
+            env!("CARGO_PKG_VERSION")
+        ),
+    ]
+}
+
+/// Statistics of the output `decodable` (a frame sequence's file pattern); None, logged, when
+/// it can't be decoded.
+fn measure(decodable: &Path, is_still: bool, i: usize) -> Option<clip_stats::Stats> {
+    match clip_stats::measure(decodable, is_still) {
+        Ok(stats) => stats,
+        Err(e) => {
+            tracing::warn!(target: logs::EXPORT, range = i, "Could not measure the output: {}", e);
+            None
+        }
+    }
+}
+
+/// Replaces the tags of an exported clip through a stream copy into a temporary file.
+fn retag(clip: &Path, profile: &export::Profile, metadata: &[String]) -> Result<(), String> {
+    let ext = clip.extension().unwrap_or_default().to_string_lossy();
+    let tmp = clip.with_extension(format!("retag.{}", ext));
+    let status = export::retag_command(clip, &tmp, profile, metadata)
+        .status()
+        .map_err(|e| trf("Failed to start FFmpeg: {}", &[&e]))?;
+    if !status.success() {
+        let _ = fs::remove_file(&tmp);
+        return Err(format!("exit code {:?}", status.code()));
+    }
+    fs::rename(&tmp, clip).map_err(|e| e.to_string())
+}
+
+/// `range` without the black and frozen frames at its ends, or None when there are none (or
+/// they couldn't be found, which is logged).
+fn trim_edges(source: &Path, range: &VideoRange, fps: f64, i: usize) -> Option<VideoRange> {
+    match trim::edges(source, range, fps) {
+        Ok(trim) if trim.is_empty() => None,
+        Ok(trim) => {
+            tracing::info!(target: logs::EXPORT, range = i, start = trim.start, end = trim.end, "Trimming edge frames");
+            Some(trim.apply(range, fps))
+        }
+        Err(e) => {
+            tracing::warn!(target: logs::EXPORT, range = i, "{}", e);
+            None
+        }
+    }
+}
+
+/// Keyframes of a source and its duration, for stream copies.
+type Keyframes = Result<(keyframes::Index, Option<f64>), String>;
+
+/// `range` with its times moved onto the nearest keyframes, for a stream copy. None when the
+/// source has no keyframe index to snap to; the range is then encoded.
+fn snap_to_keyframes(keyframes: &Keyframes, range: &VideoRange, i: usize) -> Option<VideoRange> {
+    let snapped = match keyframes {
+        Ok((index, duration)) => index.snap(range.start_time, range.end_time, *duration),
+        Err(e) => {
+            tracing::warn!(target: logs::EXPORT, range = i, "No keyframes to stream-copy at: {}", e);
+            return None;
+        }
+    };
+    let Some((start_time, end_time)) = snapped else {
+        tracing::warn!(target: logs::EXPORT, range = i, "No keyframes to stream-copy at");
+        return None;
+    };
+    tracing::info!(target: logs::EXPORT, range = i, start_time, end_time, "Stream copy snapped to keyframes");
+    Some(VideoRange {
+        start_time,
+        end_time,
+        ..range.clone()
+    })
+}
+
+/// What exporting one range comes down to, worked out before anything is written.
+struct Plan {
+    out_file: PathBuf,
+    /// Caption text, None if the range gets no caption file
+    text: Option<String>,
+    /// Tags written into the clip
+    metadata: Vec<String>,
+    /// The clip's own times: trimmed and snapped to keyframes as the profile says. The extras
+    /// and the start check follow them.
+    range: VideoRange,
+    /// The FFmpeg command without the tags, which the export hash is taken of
+    untagged: std::process::Command,
+    cmd: std::process::Command,
+}
+
+/// Plans range `i` of `job`. None when its output name is taken by a file it didn't write and
+/// the collision policy skips it. The keyframe index is built on first use and kept in
+/// `keyframes` for the following ranges.
+fn plan(
+    job: &Job,
+    out_dir: &Path,
+    previous: &[manifest::Entry],
+    i: usize,
+    keyframes: &mut Option<Keyframes>,
+) -> Option<Plan> {
+    let input_path = &job.input;
+    let source = job.source();
+    let annotated = &job.ranges[i];
+    let out_file = output_file(
+        out_dir,
+        input_path,
+        annotated,
+        i,
+        job.ranges.len(),
+        &job.profile,
+    );
+    let out_file = resolve_collision(out_dir, out_file, previous, i, job.profile.collisions)?;
+    let text = caption_text(&job.profile, input_path, i, annotated);
+    // Tags stay out of the hash, new tags only need a stream copy. Frames carry none.
+    let frames = job.profile.frames.filter(|_| !source.is_img);
+    let metadata = if source.is_img || frames.is_some() {
+        Vec::new()
+    } else {
+        clip_metadata(&job.source_name(), i, text.as_deref().unwrap_or_default())
+    };
+    let trimmed = (job.profile.trim_edges && !source.is_img)
+        .then(|| trim_edges(input_path, annotated, job.fps, i))
+        .flatten();
+    let range = trimmed.as_ref().unwrap_or(annotated);
+    let copy = if export::copies(&source, range, &job.profile) {
+        let keyframes = keyframes.get_or_insert_with(|| {
+            keyframes::build(input_path).map(|index| (index, probe::duration(input_path)))
+        });
+        snap_to_keyframes(keyframes, range, i)
+    } else {
+        None
+    };
+    let range = copy.as_ref().unwrap_or(range);
+    let command = |metadata: &[String]| match copy {
+        Some(_) => export::copy_command(&source, range, &out_file, &job.profile, metadata),
+        None => export::ffmpeg_command(&source, range, &out_file, &job.profile, metadata),
+    };
+    let untagged = command(&[]);
+    let cmd = command(&metadata);
+    let range = range.clone();
+    Some(Plan {
+        out_file,
+        text,
+        metadata,
+        range,
+        untagged,
+        cmd,
+    })
+}
+
+/// The entry in `previous` that range `i`, encoded into `out_file` (`file` in the manifest) by
+/// `untagged`, would write again unchanged.
+fn up_to_date<'a>(
+    previous: &'a [manifest::Entry],
+    job: &Job,
+    i: usize,
+    out_file: &Path,
+    file: &str,
+    untagged: &std::process::Command,
+) -> Option<&'a manifest::Entry> {
+    // A renamed clip was hashed under the name it was encoded as
+    previous.iter().find(|p| {
+        let name = p.exported_as.as_deref().unwrap_or(&p.file);
+        p.range == i && p.file == file && p.hash == command_hash(untagged, job, out_file, name)
+    })
+}
+
+/// The FFmpeg commands exporting `job` into `out_dir` would run, as shell lines, each range led
+/// by a comment naming its output; nothing is written. Ranges that are up to date or whose name
+/// is taken are listed without commands. Edge trimming and keyframe snapping still read the
+/// source, so the commands are the exact ones.
+pub fn dry_run(job: &Job, out_dir: &Path, previous: &[manifest::Entry]) -> Vec<String> {
+    let source = job.source();
+    let mut keyframes: Option<Keyframes> = None;
+    let mut lines = Vec::new();
+    for i in 0..job.ranges.len() {
+        let Some(plan) = plan(job, out_dir, previous, i, &mut keyframes) else {
+            lines.push(trf("# range {}: output name taken, skipped", &[&i]));
+            continue;
+        };
+        let file = relative(out_dir, &plan.out_file);
+        let unchanged = up_to_date(previous, job, i, &plan.out_file, &file, &plan.untagged);
+        if unchanged.is_some() && plan.out_file.exists() {
+            lines.push(trf("# range {}: {} is up to date", &[&i, &file]));
+            continue;
+        }
+        lines.push(trf("# range {}: {}", &[&i, &file]));
+        let range = &plan.range;
+        let mut commands = vec![plan.cmd];
+        if let Some(format) = job.profile.audio.filter(|_| !source.is_img) {
+            let audio = audio_file(&plan.out_file, format);
+            commands.push(export::audio_command(
+                &source,
+                range,
+                &audio,
+                format,
+                &job.profile,
+            ));
+        }
+        if let Some(format) = job.profile.preview.filter(|_| !source.is_img) {
+            let preview = preview_file(&plan.out_file, format);
+            commands.push(export::preview_command(&source, range, &preview, format));
+        }
+        if let Some(grid) = job.profile.sprite_grid.filter(|_| !source.is_img) {
+            let sprite = sprite_file(&plan.out_file, grid);
+            let rate = export::frame_rate(&job.profile, range);
+            commands.push(export::sprite_command(&source, range, &sprite, grid, rate));
+        }
+        lines.extend(commands.iter().map(progress::command_line));
+    }
+    lines
+}
+
+/// Exports every range of `job` into `out_dir`. File names only depend on the media name and
+/// the range's position, so the same annotations always produce the same files. Ranges whose
+/// entry in `previous` has the same hash and whose file still exists are not encoded again;
+/// only their caption and tags are brought up to date. Names taken by files the range didn't
+/// write are overwritten, skipped or numbered as the profile's collision policy says. With edge
+/// trimming, ranges lose their black and frozen frames at both ends first; ranges the profile
+/// stream-copies are cut at the keyframes nearest to their times. The manifest keeps the
+/// annotated times.
+/// A range that fails is reported in the outcome and the others are exported regardless, so
+/// exporting again only encodes the failed ones.
+/// Waits on `schedule` before encoding each clip, stops when it is cancelled and reports how far
+/// it is to `progress`.
+pub fn export_media(
+    job: &Job,
+    out_dir: &Path,
+    previous: &[manifest::Entry],
+    schedule: &Schedule,
+    progress: &Progress,
+) -> Outcome {
+    let mut outcome = Outcome {
+        entries: Vec::new(),
+        error: None,
+        failures: Vec::new(),
+        start_mismatches: Vec::new(),
+        unchanged: 0,
+    };
+    let input_path = &job.input;
+    let is_img = is_image_file(input_path);
+    let source_name = job.source_name();
+    let (ranges, fps) = (&job.ranges, job.fps);
+    let durations = ranges
+        .iter()
+        .map(|r| {
+            if is_img {
+                0.0
+            } else {
+                timing::export_end(r.end_time, fps) - timing::export_start(r.start_time, fps)
+            }
+        })
+        .collect();
+    progress.start_file(source_name.clone(), durations);
+    let mut keyframes: Option<Keyframes> = None;
+
+    for (i, annotated) in ranges.iter().enumerate() {
+        progress.start_range(i);
+        let Some(plan) = plan(job, out_dir, previous, i, &mut keyframes) else {
+            tracing::info!(target: logs::EXPORT, range = i, "Output name taken, range skipped");
+            continue;
+        };
+        let Plan {
+            out_file,
+            text,
+            metadata,
+            range,
+            untagged,
+            cmd,
+        } = plan;
+        let range = &range;
+        let dir = out_file.parent().unwrap();
+        if let Err(e) = fs::create_dir_all(dir) {
+            let reason = trf("Could not write {}: {}", &[&dir.display(), &e]);
+            outcome.failures.push(trf("range {}: {}", &[&i, &reason]));
+            continue;
+        }
+
+        let caption = caption_file(&out_file);
+        if let Some(text) = &text
+            && fs::read_to_string(&caption).ok().as_ref() != Some(text)
+        {
+            let _ = std::fs::write(&caption, text);
+        }
+
+        let source = job.source();
+        let frames = job.profile.frames.filter(|_| !is_img);
+        let decodable = match frames {
+            Some(format) => out_file.join(format.pattern()),
+            None => out_file.clone(),
+        };
+        let mut entry = manifest::Entry {
+            file: relative(out_dir, &out_file),
+            caption: text.is_some().then(|| relative(out_dir, &caption)),
+            source: source_name.clone(),
+            range: i,
+            start_time: annotated.start_time,
+            end_time: annotated.end_time,
+            crop: annotated.crop_rect_norm.clone(),
+            split: range.split,
+            hash: command_hash(&untagged, job, &out_file, &relative(out_dir, &out_file)),
+            tags: format!("{:016x}", fnv1a(metadata.join("\0").as_bytes())),
+            exported_as: None,
+            audio: None,
+            preview: None,
+            sprite: None,
+            stats: None,
+        };
+        let audio_format = job.profile.audio.filter(|_| !is_img);
+        let preview_format = job.profile.preview.filter(|_| !is_img);
+        let sprite_grid = job.profile.sprite_grid.filter(|_| !is_img);
+        let export_extras = |entry: &mut manifest::Entry, rewrite: bool| {
+            if let Some(format) = preview_format {
+                let preview = export_companion(
+                    preview_file(&out_file, format),
+                    "preview",
+                    rewrite,
+                    |file| export::preview_command(&source, range, file, format),
+                );
+                entry.preview = preview.map(|p| relative(out_dir, &p));
+            }
+            if let Some(grid) = sprite_grid {
+                let sprite = export_companion(
+                    sprite_file(&out_file, grid),
+                    "sprite sheet",
+                    rewrite,
+                    |file| {
+                        let rate = export::frame_rate(&job.profile, range);
+                        export::sprite_command(&source, range, file, grid, rate)
+                    },
+                );
+                entry.sprite = sprite.map(|s| relative(out_dir, &s));
+            }
+        };
+        if let Some(previous) = up_to_date(previous, job, i, &out_file, &entry.file, &untagged)
+            && out_file.exists()
+        {
+            tracing::info!(target: logs::EXPORT, range = i, file = ?out_file, "Range unchanged");
+            entry.hash = previous.hash.clone();
+            entry.exported_as = previous.exported_as.clone();
+            // Manifests from before the statistics get them now
+            entry.stats = previous.stats.or_else(|| measure(&decodable, is_img, i));
+            if previous.tags != entry.tags
+                && let Err(e) = retag(&out_file, &job.profile, &metadata)
+            {
+                tracing::warn!(target: logs::EXPORT, file = ?out_file, "Could not update the tags: {}", e);
+                // Keep the old fingerprint so the next export tries again
+                entry.tags = previous.tags.clone();
+            }
+            if let Some(format) = audio_format {
+                let audio = export_audio(&source, range, &out_file, format, &job.profile, false);
+                entry.audio = audio.map(|a| relative(out_dir, &a));
+            }
+            export_extras(&mut entry, false);
+            outcome.unchanged += 1;
+            outcome.entries.push(entry);
+            continue;
+        }
+
+        schedule.wait();
+        if schedule.is_cancelled() {
+            outcome.error = Some(tr("Export cancelled").to_string());
+            break;
+        }
+        tracing::info!(target: logs::EXPORT, range = i, file = ?out_file, "Exporting range");
+        tracing::debug!(target: logs::EXPORT, command = ?cmd, "Running FFmpeg");
+        // Frames of an earlier, longer export would otherwise stay behind
+        if frames.is_some()
+            && let Err(e) = remove_output(&out_file)
+                .or_else(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        Ok(())
+                    } else {
+                        Err(e)
+                    }
+                })
+                .and_then(|_| fs::create_dir_all(&out_file))
+        {
+            let reason = trf("Could not write {}: {}", &[&out_file.display(), &e]);
+            outcome.failures.push(trf("range {}: {}", &[&i, &reason]));
+            continue;
+        }
+
+        match progress.run(&cmd) {
+            // A clip that still finished is kept; quitting after the current clip cancels too
+            Ok(status) if !status.success() && schedule.is_cancelled() => {
+                tracing::info!(target: logs::EXPORT, range = i, "Export cancelled");
+                // Killed halfway, the clip is unusable
+                let _ = remove_output(&out_file);
+                outcome.error = Some(tr("Export cancelled").to_string());
+                break;
+            }
+            Ok(status) if !status.success() => {
+                tracing::error!(
+                    target: logs::EXPORT,
+                    range = i,
+                    code = ?status.code(),
+                    command = ?cmd,
+                    "FFmpeg failed"
+                );
+                // A partial clip would pass for an export of the range
+                let _ = remove_output(&out_file);
+                outcome.failures.push(trf(
+                    "FFmpeg failed on range {} with exit code: {}",
+                    &[&i, &format!("{:?}", status.code())],
+                ));
+                continue;
+            }
+            Err(e) => {
+                tracing::error!(target: logs::EXPORT, "Failed to start FFmpeg: {}", e);
+                outcome.error = Some(trf("Failed to start FFmpeg: {}", &[&e]));
+                break;
+            }
+            _ => {}
+        }
+        if let Some(format) = audio_format {
+            let audio = export_audio(&source, range, &out_file, format, &job.profile, true);
+            entry.audio = audio.map(|a| relative(out_dir, &a));
+        }
+        export_extras(&mut entry, true);
+        entry.stats = measure(&decodable, is_img, i);
+        outcome.entries.push(entry);
+
+        if !is_img {
+            let start_frame = timing::frame_at(range.start_time, fps);
+            let crop = range.crop_rect_norm.as_ref();
+            match verify::start_offset(input_path, start_frame, crop, &decodable) {
+                Ok(Some(0)) => {}
+                Ok(Some(offset)) => outcome.start_mismatches.push(trf(
+                    "range {}: {} frame(s)",
+                    &[&i, &format!("{:+}", offset)],
+                )),
+                Ok(None) => {
+                    tracing::warn!(target: logs::EXPORT, range = i, "Could not verify the start frame")
+                }
+                Err(e) => tracing::warn!(
+                    target: logs::EXPORT,
+                    range = i,
+                    "Start frame verification failed: {}",
+                    e
+                ),
+            }
+        }
+    }
+    outcome
+}
+
+/// Manifest entries exported from `media` that none of its `ranges` exports to any more,
+/// because the range was deleted or its output name (or container) changed. A numbered output
+/// (see `without_increment`) still counts as its range's. Entries are matched to ranges by their
+/// times and crop, so removing a range doesn't make the later ones look stale because their
+/// index moved; only while the number of ranges is unchanged does an edited range keep the
+/// entry at its index.
+pub fn stale_entries(
+    out_dir: &Path,
+    media: &Path,
+    ranges: &[VideoRange],
+    manifest: &Manifest,
+    profile: &export::Profile,
+) -> Vec<manifest::Entry> {
+    let source = media.file_name().unwrap().to_string_lossy();
+    let exported = manifest.entries.iter().filter(|e| e.source == source);
+    let same_count = exported.clone().count() == ranges.len();
+    let same_content = |e: &manifest::Entry, r: &VideoRange| {
+        e.start_time == r.start_time && e.end_time == r.end_time && e.crop == r.crop_rect_norm
+    };
+    exported
+        .filter(|&e| {
+            let base = without_increment(&e.file);
+            let names = |r: &VideoRange, i: usize| {
+                let file = relative(
+                    out_dir,
+                    &output_file(out_dir, media, r, i, ranges.len(), profile),
+                );
+                e.file == file || base.as_ref() == Some(&file)
+            };
+            !ranges.iter().enumerate().any(|(i, r)| {
+                if same_content(e, r) {
+                    names(r, i) || names(r, e.range)
+                } else {
+                    same_count && i == e.range && names(r, i)
+                }
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// `file`, a path from the manifest, within `out_dir`. Absolute paths and `..` are refused, so a
+/// shared or hand-edited manifest can't make a discard or rebuild remove files elsewhere.
+pub fn manifest_path(out_dir: &Path, file: &str) -> Result<PathBuf, String> {
+    let path = Path::new(file);
+    let inside = !file.is_empty()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(trf(
+            "The manifest lists {}, which is not inside the output folder",
+            &[&file],
+        ));
+    }
+    Ok(out_dir.join(path))
+}
+
+/// Removes the files of `entries` (output, caption and audio) and drops them from the manifest. With
+/// `trash` they are moved into `.trash/<time>/` in the output folder instead of deleted, keeping
+/// their relative paths. Returns how many files were moved or deleted.
+pub fn discard(out_dir: &Path, entries: &[manifest::Entry], trash: bool) -> Result<usize, String> {
+    let trash_dir = out_dir
+        .join(".trash")
+        .join(chrono::Local::now().format("%Y%m%d_%H%M%S").to_string());
+    // All checked before anything is moved or removed
+    let files = entries
+        .iter()
+        .flat_map(|e| e.files())
+        .map(|file| Ok((file, manifest_path(out_dir, file)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut count = 0;
+    for (file, path) in files {
+        if !path.exists() {
+            continue;
+        }
+        let result = if trash {
+            let target = trash_dir.join(file);
+            fs::create_dir_all(target.parent().unwrap()).and_then(|_| fs::rename(&path, &target))
+        } else {
+            remove_output(&path)
+        };
+        result.map_err(|e| trf("Could not remove {}: {}", &[&path.display(), &e]))?;
+        count += 1;
+    }
+    let mut manifest = Manifest::load(out_dir)?;
+    manifest.entries.retain(|e| {
+        !entries
+            .iter()
+            .any(|d| d.source == e.source && d.file == e.file)
+    });
+    manifest.save(out_dir)?;
+    checksum::record(out_dir, &manifest, &[]);
+    Ok(count)
+}
+
+/// Progress of one file of a batch export.
+#[derive(Clone)]
+pub enum BatchStatus {
+    Queued,
+    Exporting,
+    /// Exported into this many clips
+    Done(usize),
+    Failed(String),
+}
+
+/// Exports the ranges of several files, `parallel` of them at a time, updating the manifest
+/// after each so an interrupted batch keeps what it finished. `report` is told how each file is
+/// doing; once `schedule` is cancelled the remaining files are reported as failed. Returns the
+/// messages of failed files, in the order of `sources`.
+pub fn export_batch(
+    sources: Vec<(PathBuf, Result<Vec<VideoRange>, String>)>,
+    profile: &export::Profile,
+    out_dir: &Path,
+    parallel: usize,
+    schedule: &Schedule,
+    progress: &Progress,
+    report: impl Fn(&Path, BatchStatus) + Sync,
+) -> Vec<String> {
+    // Workers take turns with the manifest and the material registry
+    let saving = Mutex::new(());
+    let messages = pool::run(sources, parallel, |worker, (media, ranges)| {
+        if schedule.is_cancelled() {
+            report(
+                &media,
+                BatchStatus::Failed(tr("Export cancelled").to_string()),
+            );
+            return None;
+        }
+        report(&media, BatchStatus::Exporting);
+        let job = ranges.and_then(|ranges| Job::probe(media.clone(), ranges, profile.clone()));
+        let result = job.and_then(|job| {
+            let source = job.source_name();
+            let previous: Vec<_> = {
+                let _saving = saving.lock().unwrap();
+                Manifest::load(out_dir)?
+                    .entries
+                    .into_iter()
+                    .filter(|e| e.source == source)
+                    .collect()
+            };
+            let progress = progress.worker(worker);
+            let outcome = export_media(&job, out_dir, &previous, schedule, &progress);
+            let message = outcome.message();
+            let clips = outcome.entries.len();
+            let fresh: Vec<_> = outcome
+                .entries
+                .iter()
+                .flat_map(|e| e.files())
+                .cloned()
+                .collect();
+            let _saving = saving.lock().unwrap();
+            material::record(out_dir, &job.input, &outcome.entries);
+            let mut manifest = Manifest::load(out_dir)?;
+            manifest.replace_source(&source, outcome.entries);
+            manifest.save(out_dir)?;
+            checksum::record(out_dir, &manifest, &fresh);
+            message.map_or(Ok(clips), Err)
+        });
+        match result {
+            Ok(clips) => {
+                report(&media, BatchStatus::Done(clips));
+                None
+            }
+            Err(e) => {
+                let name = media.file_name().unwrap_or_default().to_string_lossy();
+                let message = format!("{}: {}", name, e);
+                report(&media, BatchStatus::Failed(e));
+                Some(message)
+            }
+        }
+    });
+    messages.into_iter().flatten().collect()
+}
+
+/// Regenerates the whole dataset from `jobs`: removes everything the previous manifest lists,
+/// then exports the jobs in file name order, `parallel` at a time, and writes a fresh manifest
+/// and material registry. Files in the output folder that no manifest ever listed are left
+/// alone. Returns the messages of failed files.
+pub fn rebuild(
+    mut jobs: Vec<Job>,
+    out_dir: &Path,
+    parallel: usize,
+    schedule: &Schedule,
+    progress: &Progress,
+) -> Result<Vec<String>, String> {
+    let old = Manifest::load(out_dir)?;
+    // All checked before anything is removed
+    let paths = old
+        .entries
+        .iter()
+        .flat_map(|e| e.files())
+        .map(|file| manifest_path(out_dir, file))
+        .collect::<Result<Vec<_>, String>>()?;
+    for path in paths {
+        if let Err(e) = remove_output(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(trf("Could not remove {}: {}", &[&path.display(), &e]));
+        }
+        // Split subfolders that end up empty go too; fails harmlessly if not empty
+        if let Some(parent) = path.parent()
+            && parent != out_dir
+        {
+            let _ = fs::remove_dir(parent);
+        }
+    }
+
+    jobs.sort_by_key(|job| job.source_name());
+    let manifest = Manifest::default();
+    // The links stay, the spans are recorded afresh
+    let mut registry = material::Registry::load(out_dir)?;
+    registry.spans.clear();
+    let collected = Mutex::new((manifest, registry));
+    let messages = pool::run(jobs, parallel, |worker, job| {
+        if schedule.is_cancelled() {
+            return None;
+        }
+        let outcome = export_media(&job, out_dir, &[], schedule, &progress.worker(worker));
+        let message = outcome.message();
+        let spans = material::spans(&job.input, &outcome.entries);
+        let (manifest, registry) = &mut *collected.lock().unwrap();
+        match spans {
+            Ok(spans) => registry.replace_source(&job.source_name(), spans),
+            Err(e) => {
+                tracing::warn!(target: logs::EXPORT, file = ?job.input, "Could not update the material registry: {}", e)
+            }
+        }
+        manifest.replace_source(&job.source_name(), outcome.entries);
+        message.map(|m| format!("{}: {}", job.source_name(), m))
+    });
+    let (manifest, registry) = collected.into_inner().unwrap();
+    manifest.save(out_dir)?;
+    registry.save(out_dir)?;
+    let fresh: Vec<_> = manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.files())
+        .cloned()
+        .collect();
+    checksum::record(out_dir, &manifest, &fresh);
+    Ok(messages.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SerializableRect, fixtures};
+    use opencv::core;
+
+    type ColorCheck = fn(&core::Mat) -> bool;
+
+    fn half(left: bool) -> SerializableRect {
+        let min_x = if left { 0.0 } else { 0.5 };
+        SerializableRect {
+            min_x,
+            min_y: 0.0,
+            max_x: min_x + 0.5,
+            max_y: 1.0,
+        }
+    }
+
+    #[test]
+    fn class_folders_follow_the_split_the_tag_order_and_the_note() {
+        let mut profile = export::Profile {
+            folders: export::Folders::FirstTag,
+            ..Default::default()
+        };
+        profile.rules.tag_order = vec!["dog".to_string()];
+        let range = VideoRange {
+            split: Some(crate::split::Split::Train),
+            tags: vec!["cat/kitten".to_string(), "dog".to_string()],
+            ..Default::default()
+        };
+        let (out, media) = (Path::new("out"), Path::new("in/clip.mp4"));
+        let file = |range: &VideoRange, profile: &export::Profile| {
+            relative(out, &output_file(out, media, range, 0, 2, profile))
+        };
+        assert_eq!(file(&range, &profile), "train/dog/clip_range0.mp4");
+        profile.rules.tag_order.clear();
+        assert_eq!(file(&range, &profile), "train/cat_kitten/clip_range0.mp4");
+        let untagged = VideoRange::default();
+        assert_eq!(file(&untagged, &profile), "clip_range0.mp4");
+        profile.folders = export::Folders::None;
+        assert_eq!(file(&range, &profile), "train/clip_range0.mp4");
+        profile.folders = export::Folders::NoteLine;
+        let noted = VideoRange {
+            note: "  person walking\nin the rain".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(file(&noted, &profile), "person walking/clip_range0.mp4");
+        assert_eq!(file(&range, &profile), "train/clip_range0.mp4");
+    }
+
+    #[test]
+    fn name_templates_fill_tokens_and_keep_ranges_apart() {
+        let (out, media) = (Path::new("out"), Path::new("in/clip.png"));
+        let range = VideoRange {
+            tags: vec!["cat/kitten".to_string()],
+            modified_at: Some("2026-03-04T05:06:07Z".to_string()),
+            ..Default::default()
+        };
+        let file = |template: &str, i: usize, count: usize| {
+            let profile = export::Profile {
+                name_template: template.to_string(),
+                ..Default::default()
+            };
+            relative(out, &output_file(out, media, &range, i, count, &profile))
+        };
+        assert_eq!(file("", 1, 2), "clip_range1.png");
+        assert_eq!(file("{tag}-{stem}", 1, 2), "cat_kitten-clip_range1.png");
+        assert_eq!(file("{date}_{stem}_{range}", 1, 2), "2026-03-04_clip_1.png");
+        assert_eq!(
+            file("{stem}_f{start_frame}-{end_frame}", 0, 1),
+            "clip_f0-0.png"
+        );
+        assert_eq!(file("a/b", 0, 2), "a_b_range0.png");
+        assert_eq!(file("{tag}", 0, 1), "cat_kitten.png");
+    }
+
+    #[test]
+    fn taken_names_are_overwritten_skipped_or_numbered() {
+        let out = fixtures::dir("collisions");
+        let file = out.join("clip.mp4");
+        fs::write(&file, "someone else's").unwrap();
+        fs::write(out.join("clip_001.mp4"), "someone else's too").unwrap();
+        let resolve = |previous: &[manifest::Entry], policy| {
+            resolve_collision(&out, file.clone(), previous, 0, policy).map(|f| relative(&out, &f))
+        };
+        use export::Collisions::*;
+        assert_eq!(resolve(&[], Overwrite).as_deref(), Some("clip.mp4"));
+        assert_eq!(resolve(&[], Skip), None);
+        assert_eq!(resolve(&[], Increment).as_deref(), Some("clip_002.mp4"));
+        let own = manifest::Entry {
+            file: "clip.mp4".to_string(),
+            caption: None,
+            source: "clip.mp4".to_string(),
+            range: 0,
+            start_time: 0.0,
+            end_time: 1.0,
+            crop: None,
+            split: None,
+            hash: String::new(),
+            tags: String::new(),
+            exported_as: None,
+            audio: None,
+            preview: None,
+            sprite: None,
+            stats: None,
+        };
+        assert_eq!(resolve(&[own], Skip).as_deref(), Some("clip.mp4"));
+
+        assert_eq!(
+            without_increment("train/clip_002.mp4").as_deref(),
+            Some("train/clip.mp4")
+        );
+        assert_eq!(
+            without_increment("a.b_range0_001.sprite4x4.jpg").as_deref(),
+            Some("a.b_range0.sprite4x4.jpg")
+        );
+        assert_eq!(without_increment("clip_range1.mp4"), None);
+        assert_eq!(without_increment("clip_0001.mp4"), None);
+    }
+
+    #[test]
+    fn removing_a_middle_range_only_flags_its_clip() {
+        let (out, media) = (Path::new("out"), Path::new("in/clip.mp4"));
+        let profile = export::Profile::default();
+        let range = |start_time: f64| VideoRange {
+            start_time,
+            end_time: start_time + 1.0,
+            ..Default::default()
+        };
+        let entry = |i: usize, r: &VideoRange| manifest::Entry {
+            file: format!("clip_range{}.mp4", i),
+            caption: None,
+            source: "clip.mp4".to_string(),
+            range: i,
+            start_time: r.start_time,
+            end_time: r.end_time,
+            crop: r.crop_rect_norm.clone(),
+            split: None,
+            hash: String::new(),
+            tags: String::new(),
+            exported_as: None,
+            audio: None,
+            preview: None,
+            sprite: None,
+            stats: None,
+        };
+        let ranges = [range(0.0), range(5.0), range(10.0)];
+        let manifest = Manifest {
+            entries: ranges
+                .iter()
+                .enumerate()
+                .map(|(i, r)| entry(i, r))
+                .collect(),
+            ..Default::default()
+        };
+        let stale = |ranges: &[VideoRange]| {
+            stale_entries(out, media, ranges, &manifest, &profile)
+                .into_iter()
+                .map(|e| e.file)
+                .collect::<Vec<_>>()
+        };
+        assert!(stale(&ranges).is_empty());
+        assert_eq!(stale(&[range(0.0), range(10.0)]), ["clip_range1.mp4"]);
+        assert_eq!(stale(&[range(5.0), range(10.0)]), ["clip_range0.mp4"]);
+        // An edited range still owns the clip at its index
+        assert!(stale(&[range(0.0), range(6.0), range(10.0)]).is_empty());
+        let split = VideoRange {
+            split: Some(crate::split::Split::Val),
+            ..range(5.0)
+        };
+        assert_eq!(
+            stale(&[range(0.0), split, range(10.0)]),
+            ["clip_range1.mp4"]
+        );
+    }
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn manifest_paths_stay_inside_the_output_folder() {
+        let out = Path::new("out");
+        assert_eq!(
+            manifest_path(out, "train/clip_range0.mp4"),
+            Ok(out.join("train/clip_range0.mp4"))
+        );
+        for file in ["", "/etc/passwd", "../clip.mp4", "train/../../clip.mp4"] {
+            assert!(manifest_path(out, file).is_err(), "{file}");
+        }
+    }
+
+    #[test]
+    fn exports_cropped_clips_end_to_end() {
+        let dir = fixtures::dir("export_clips");
+        let Some(input) = fixtures::video(&dir, "split.mp4", &fixtures::split_pattern(30), &[])
+        else {
+            eprintln!("FFmpeg not available, skipping clip export test");
+            return;
+        };
+        let ranges = vec![
+            VideoRange {
+                start_time: 0.5,
+                end_time: 1.5,
+                crop_rect_norm: Some(half(false)),
+                note: "blue half".to_string(),
+                ..Default::default()
+            },
+            VideoRange {
+                start_time: 2.0,
+                end_time: 2.5,
+                crop_rect_norm: Some(half(true)),
+                ..Default::default()
+            },
+        ];
+        let profile = export::Profile {
+            sprite_grid: Some(2),
+            ..Default::default()
+        };
+        let job = Job::probe(input, ranges, profile).unwrap();
+        let out_dir = dir.join("out");
+        // A dry run lists the clip and sprite sheet commands of each range and writes nothing
+        let planned = dry_run(&job, &out_dir, &[]);
+        assert_eq!(planned.len(), 6, "{:?}", planned);
+        assert!(planned[0].starts_with("# range 0: "));
+        assert!(planned[1].starts_with("$ ffmpeg ") && planned[1].contains("crop="));
+        assert!(!out_dir.exists());
+        let progress = Progress::default();
+        let outcome = export_media(&job, &out_dir, &[], &Schedule::default(), &progress);
+        assert_eq!(outcome.error, None);
+        assert!(outcome.failures.is_empty());
+        assert!(outcome.start_mismatches.is_empty());
+        assert_eq!(outcome.entries.len(), 2);
+        // FFmpeg reported the last range up to its end
+        let reported = progress.get().unwrap();
+        assert_eq!(reported.range, 1);
+        assert!(reported.fraction() > 0.9, "{}", reported.fraction());
+
+        let expected: [(f64, ColorCheck); 2] = [(1.0, fixtures::is_blue), (0.5, fixtures::is_red)];
+        for (entry, (duration, color)) in outcome.entries.iter().zip(expected) {
+            let frames = fixtures::frames(&out_dir.join(&entry.file));
+            let count = (duration * export::DEFAULT_FPS as f64) as i64;
+            // The cut points sit half a source frame outside the range
+            assert!(
+                (frames.len() as i64 - count).abs() <= 1,
+                "{} has {} frames",
+                entry.file,
+                frames.len()
+            );
+            for frame in &frames {
+                let size = frame.size().unwrap();
+                assert_eq!(
+                    (size.width, size.height),
+                    (fixtures::WIDTH / 2, fixtures::HEIGHT)
+                );
+                assert!(
+                    color(frame),
+                    "{}: {:?}",
+                    entry.file,
+                    fixtures::mean_bgr(frame)
+                );
+            }
+        }
+        let caption = out_dir.join(outcome.entries[0].caption.as_ref().unwrap());
+        assert_eq!(fs::read_to_string(caption).unwrap(), "blue half");
+        let sprite = out_dir.join(outcome.entries[0].sprite.as_ref().unwrap());
+        let sheet = imgcodecs::imread(&sprite.to_string_lossy(), imgcodecs::IMREAD_COLOR).unwrap();
+        let side = 2 * export::SPRITE_TILE as i32;
+        assert_eq!((sheet.cols(), sheet.rows()), (side, side));
+
+        // Nothing changed, so nothing is encoded again
+        let again = export_media(
+            &job,
+            &out_dir,
+            &outcome.entries,
+            &Schedule::default(),
+            &Progress::default(),
+        );
+        assert_eq!(again.unchanged, 2);
+        let planned = dry_run(&job, &out_dir, &outcome.entries);
+        assert!(planned.iter().all(|line| line.ends_with(" is up to date")));
+    }
+
+    #[test]
+    fn batch_export_reports_every_file() {
+        let dir = fixtures::dir("export_batch");
+        let Some(input) = fixtures::video(&dir, "split.mp4", &fixtures::split_pattern(30), &[])
+        else {
+            eprintln!("FFmpeg not available, skipping batch export test");
+            return;
+        };
+        let range = VideoRange {
+            start_time: 0.5,
+            end_time: 1.5,
+            ..Default::default()
+        };
+        let sources = vec![
+            (
+                dir.join("unannotated.mp4"),
+                Err("No saved ranges".to_string()),
+            ),
+            (input.clone(), Ok(vec![range.clone(), range])),
+        ];
+        let out_dir = dir.join("out");
+        let statuses = std::sync::Mutex::new(Vec::new());
+        let messages = export_batch(
+            sources,
+            &export::Profile::default(),
+            &out_dir,
+            1,
+            &Schedule::default(),
+            &Progress::default(),
+            |media, status| {
+                let name = media.file_name().unwrap().to_string_lossy().into_owned();
+                statuses.lock().unwrap().push((name, status));
+            },
+        );
+        assert_eq!(messages, ["unannotated.mp4: No saved ranges"]);
+        let statuses = statuses.into_inner().unwrap();
+        assert!(matches!(
+            statuses.as_slice(),
+            [
+                (_, BatchStatus::Exporting),
+                (_, BatchStatus::Failed(_)),
+                (_, BatchStatus::Exporting),
+                (_, BatchStatus::Done(2)),
+            ]
+        ));
+        assert_eq!(Manifest::load(&out_dir).unwrap().entries.len(), 2);
+    }
+
+    #[test]
+    fn exports_cropped_still_end_to_end() {
+        let dir = fixtures::dir("export_still");
+        let Some(input) = fixtures::image(&dir, "split.png", &fixtures::split_pattern(1)) else {
+            eprintln!("FFmpeg not available, skipping still export test");
+            return;
+        };
+        let ranges = vec![VideoRange {
+            crop_rect_norm: Some(half(true)),
+            ..Default::default()
+        }];
+        let job = Job::probe(input, ranges, export::Profile::default()).unwrap();
+        let out_dir = dir.join("out");
+        let outcome = export_media(
+            &job,
+            &out_dir,
+            &[],
+            &Schedule::default(),
+            &Progress::default(),
+        );
+        assert_eq!(outcome.error, None);
+        assert!(outcome.failures.is_empty());
+        assert_eq!(outcome.entries[0].file, "split.png");
+
+        let still = out_dir.join(&outcome.entries[0].file);
+        let still = imgcodecs::imread(&still.to_string_lossy(), imgcodecs::IMREAD_COLOR).unwrap();
+        let size = still.size().unwrap();
+        assert_eq!(
+            (size.width, size.height),
+            (fixtures::WIDTH / 2, fixtures::HEIGHT)
+        );
+        assert!(fixtures::is_red(&still), "{:?}", fixtures::mean_bgr(&still));
+    }
+}
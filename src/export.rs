@@ -0,0 +1,1104 @@
+// FFmpeg command construction for exporting a single range.
+use crate::hardware::Hardware;
+use crate::i18n::tr;
+use crate::{SerializableRect, VideoRange, remote, rules, timing};
+use std::path::Path;
+use std::process::Command;
+
+/// What the command builder needs to know about the media being exported.
+pub struct Source<'a> {
+    pub path: &'a Path,
+    pub is_img: bool,
+    pub fps: f64,
+    /// Stored size in pixels, used to turn the normalized crop into pixels.
+    pub frame_size: (f64, f64),
+    /// Sample aspect ratio; anything but 1.0 is resampled to square pixels on export.
+    pub pixel_aspect: f64,
+}
+
+/// Container of exported clips, with its mux flags. WebM only takes VP8/VP9/AV1, so clips in it
+/// are always VP9; MP4 and MKV hold whichever codec the encoder settings pick.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    #[default]
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    pub const ALL: [Container; 3] = [Container::Mp4, Container::Mkv, Container::Webm];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Container::Mp4 => "MP4",
+            Container::Mkv => "MKV",
+            Container::Webm => "WebM",
+        }
+    }
+
+    /// Codec used when the chosen one doesn't fit the container
+    fn default_codec(self) -> Codec {
+        match self {
+            Container::Mp4 | Container::Mkv => Codec::H264,
+            Container::Webm => Codec::Vp9,
+        }
+    }
+}
+
+/// Video codec of exported clips.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    H264,
+    H265,
+    Vp9,
+}
+
+impl Codec {
+    pub const ALL: [Codec; 3] = [Codec::H264, Codec::H265, Codec::Vp9];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Codec::H264 => "H.264 (libx264)",
+            Codec::H265 => "H.265 (libx265)",
+            Codec::Vp9 => "VP9 (libvpx-vp9)",
+        }
+    }
+
+    fn encoder(self) -> &'static str {
+        match self {
+            Codec::H264 => "libx264",
+            Codec::H265 => "libx265",
+            Codec::Vp9 => "libvpx-vp9",
+        }
+    }
+
+    pub fn fits(self, container: Container) -> bool {
+        container != Container::Webm || self == Codec::Vp9
+    }
+
+    /// Highest CRF, the worst quality
+    pub fn max_crf(self) -> u32 {
+        match self {
+            Codec::H264 | Codec::H265 => 51,
+            Codec::Vp9 => 63,
+        }
+    }
+}
+
+/// Encoding speed, in x264's terms: slower presets make smaller files at the same quality.
+/// VP9 has no presets and gets the nearest deadline and `-cpu-used` instead.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Ultrafast,
+    Superfast,
+    Veryfast,
+    Faster,
+    Fast,
+    Medium,
+    Slow,
+    Slower,
+    Veryslow,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 9] = [
+        Preset::Ultrafast,
+        Preset::Superfast,
+        Preset::Veryfast,
+        Preset::Faster,
+        Preset::Fast,
+        Preset::Medium,
+        Preset::Slow,
+        Preset::Slower,
+        Preset::Veryslow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Ultrafast => "ultrafast",
+            Preset::Superfast => "superfast",
+            Preset::Veryfast => "veryfast",
+            Preset::Faster => "faster",
+            Preset::Fast => "fast",
+            Preset::Medium => "medium",
+            Preset::Slow => "slow",
+            Preset::Slower => "slower",
+            Preset::Veryslow => "veryslow",
+        }
+    }
+
+    fn vp9_args(self) -> [&'static str; 4] {
+        let (deadline, cpu_used) = match self {
+            Preset::Ultrafast => ("realtime", "8"),
+            Preset::Superfast => ("realtime", "7"),
+            Preset::Veryfast => ("realtime", "6"),
+            Preset::Faster => ("good", "5"),
+            Preset::Fast => ("good", "4"),
+            Preset::Medium => ("good", "3"),
+            Preset::Slow => ("good", "2"),
+            Preset::Slower => ("good", "1"),
+            Preset::Veryslow => ("good", "0"),
+        };
+        ["-deadline", deadline, "-cpu-used", cpu_used]
+    }
+}
+
+/// How the encoder trades size for quality.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    /// The encoder's own default (CRF 23 for x264)
+    Default,
+    /// Constant quality; lower is better and larger
+    Crf(u32),
+    /// Average bitrate in kbit/s
+    Bitrate(u32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PixelFormat {
+    Yuv420p,
+    Yuv422p,
+    Yuv444p,
+    Yuv420p10le,
+}
+
+impl PixelFormat {
+    pub const ALL: [PixelFormat; 4] = [
+        PixelFormat::Yuv420p,
+        PixelFormat::Yuv422p,
+        PixelFormat::Yuv444p,
+        PixelFormat::Yuv420p10le,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PixelFormat::Yuv420p => "yuv420p",
+            PixelFormat::Yuv422p => "yuv422p",
+            PixelFormat::Yuv444p => "yuv444p",
+            PixelFormat::Yuv420p10le => "yuv420p10le",
+        }
+    }
+}
+
+/// Encoder settings of exported clips. The defaults encode as fast as possible, which suits
+/// trying things out; archived training clips want a slower preset and a CRF.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Encoder {
+    pub codec: Codec,
+    pub preset: Preset,
+    pub quality: Quality,
+    /// None keeps the format the encoder picks for the source
+    pub pixel_format: Option<PixelFormat>,
+    /// Encode on the graphics card instead of the CPU, for H.264 and H.265
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<Hardware>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self {
+            codec: Codec::H264,
+            preset: Preset::Ultrafast,
+            quality: Quality::Default,
+            pixel_format: None,
+            hardware: None,
+        }
+    }
+}
+
+impl Encoder {
+    /// The codec clips in `container` are encoded with.
+    pub fn codec_for(&self, container: Container) -> Codec {
+        if self.codec.fits(container) {
+            self.codec
+        } else {
+            container.default_codec()
+        }
+    }
+
+    /// The hardware clips in `container` are encoded on, if any: VP9 always goes to the CPU.
+    pub fn hardware_for(&self, container: Container) -> Option<Hardware> {
+        let codec = self.codec_for(container);
+        self.hardware.filter(|h| h.encoder(codec).is_some())
+    }
+
+    /// Options before the input, for hardware that needs a device opened.
+    pub fn input_args(&self, container: Container) -> &'static [&'static str] {
+        self.hardware_for(container).map_or(&[], |h| h.input_args())
+    }
+
+    /// Filter to append to the clip's filters, for hardware that needs the frames uploaded.
+    pub fn upload_filter(&self, container: Container) -> Option<String> {
+        self.hardware_for(container)?
+            .upload_filter(self.pixel_format)
+    }
+
+    /// The FFmpeg options encoding video for `container`.
+    pub fn args(&self, container: Container) -> Vec<String> {
+        let codec = self.codec_for(container);
+        if let Some(hardware) = self.hardware_for(container) {
+            let mut args = vec![
+                "-c:v".to_string(),
+                hardware.encoder(codec).unwrap().to_string(),
+            ];
+            args.extend(hardware.preset_args(self.preset));
+            args.extend(hardware.quality_args(self.quality));
+            args.extend(hardware.pixel_format_args(self.pixel_format));
+            return tagged(args, codec, container);
+        }
+        let mut args = vec!["-c:v".to_string(), codec.encoder().to_string()];
+        match codec {
+            Codec::H264 | Codec::H265 => {
+                args.extend(["-preset", self.preset.name()].map(String::from))
+            }
+            Codec::Vp9 => args.extend(self.preset.vp9_args().map(String::from)),
+        }
+        match self.quality {
+            Quality::Default => {}
+            Quality::Crf(crf) => {
+                args.extend(["-crf".to_string(), crf.min(codec.max_crf()).to_string()]);
+                // Without a zero bitrate, libvpx treats the CRF as a cap on a default bitrate
+                if codec == Codec::Vp9 {
+                    args.extend(["-b:v", "0"].map(String::from));
+                }
+            }
+            Quality::Bitrate(kbps) => args.extend(["-b:v".to_string(), format!("{}k", kbps)]),
+        }
+        if let Some(format) = self.pixel_format {
+            args.extend(["-pix_fmt", format.name()].map(String::from));
+        }
+        tagged(args, codec, container)
+    }
+}
+
+fn tagged(mut args: Vec<String>, codec: Codec, container: Container) -> Vec<String> {
+    // Apple's players only take H.265 in MP4 under this tag
+    if codec == Codec::H265 && container == Container::Mp4 {
+        args.extend(["-tag:v", "hvc1"].map(String::from));
+    }
+    args
+}
+
+/// Format of the audio written next to each clip, for audio-visual and lip-sync datasets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 2] = [AudioFormat::Wav, AudioFormat::Flac];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Flac => "FLAC",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "pcm_s16le",
+            AudioFormat::Flac => "flac",
+        }
+    }
+}
+
+/// Animated preview written next to each clip, small enough to embed in wikis and PR
+/// descriptions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PreviewFormat {
+    Gif,
+    Webp,
+}
+
+impl PreviewFormat {
+    pub const ALL: [PreviewFormat; 2] = [PreviewFormat::Gif, PreviewFormat::Webp];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PreviewFormat::Gif => "gif",
+            PreviewFormat::Webp => "webp",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PreviewFormat::Gif => "GIF",
+            PreviewFormat::Webp => "WebP",
+        }
+    }
+}
+
+/// Image format of ranges exported as frame sequences instead of clips, for training pipelines
+/// that read frame folders.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameFormat {
+    Png,
+    Jpeg,
+}
+
+impl FrameFormat {
+    pub const ALL: [FrameFormat; 2] = [FrameFormat::Png, FrameFormat::Jpeg];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FrameFormat::Png => "PNG frames",
+            FrameFormat::Jpeg => "JPEG frames",
+        }
+    }
+
+    /// File names of the frames in a sequence folder, numbered from 0 like the frames of a clip
+    pub fn pattern(self) -> &'static str {
+        match self {
+            FrameFormat::Png => "%06d.png",
+            FrameFormat::Jpeg => "%06d.jpg",
+        }
+    }
+}
+
+/// What the folder each output goes into (inside its split folder) is named after, so a
+/// dataset comes out sorted by class or description instead of needing sorting by hand.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Folders {
+    #[default]
+    None,
+    /// The first tag in the project rules' tag order, the folder-per-class layout
+    FirstTag,
+    /// The first line of the note
+    NoteLine,
+}
+
+impl Folders {
+    pub const ALL: [Folders; 3] = [Folders::None, Folders::FirstTag, Folders::NoteLine];
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Folders::None => "No folders",
+            Folders::FirstTag => "Folder per tag",
+            Folders::NoteLine => "Folder per note",
+        }
+    }
+}
+
+/// What an export does when a range's output name is taken by a file it didn't write, such as
+/// another file's output under a name template without `{stem}` or a file put there by hand.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Collisions {
+    #[default]
+    Overwrite,
+    Skip,
+    /// Write to the first free name with `_001`, `_002`, … added
+    Increment,
+}
+
+impl Collisions {
+    pub const ALL: [Collisions; 3] = [
+        Collisions::Overwrite,
+        Collisions::Skip,
+        Collisions::Increment,
+    ];
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Collisions::Overwrite => "Overwrite",
+            Collisions::Skip => "Skip",
+            Collisions::Increment => "Number new files",
+        }
+    }
+
+    /// What happens to a taken name, for the summary before an export; pass it through `tr`.
+    pub fn consequence(self) -> &'static str {
+        match self {
+            Collisions::Overwrite => "They will be overwritten.",
+            Collisions::Skip => "Their ranges will be skipped.",
+            Collisions::Increment => "The new outputs get a number (_001, …) instead.",
+        }
+    }
+}
+
+/// Extension of the folder a frame sequence is written to: `clip_range0.frames/000000.png`
+pub const FRAMES_EXTENSION: &str = "frames";
+
+/// Frame rate of exported clips.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameRate {
+    /// Keep every frame of the source
+    Native,
+    Fixed(u32),
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate::Fixed(DEFAULT_FPS)
+    }
+}
+
+impl FrameRate {
+    /// Frames per second of a clip cut from a source running at `source_fps`.
+    pub fn resolve(self, source_fps: f64) -> f64 {
+        match self {
+            FrameRate::Native => source_fps,
+            FrameRate::Fixed(fps) => fps as f64,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            FrameRate::Native => tr("native").to_string(),
+            FrameRate::Fixed(fps) => format!("{} FPS", fps),
+        }
+    }
+}
+
+/// Previews are this wide, play at this frame rate and stop after this many seconds.
+const PREVIEW_WIDTH: u32 = 320;
+const PREVIEW_FPS: u32 = 10;
+const PREVIEW_SECONDS: f64 = 4.0;
+
+/// Sprite sheet tiles are this many pixels square; the grid has 2 to this many columns.
+pub const SPRITE_TILE: u32 = 224;
+pub const SPRITE_MAX_GRID: u32 = 8;
+
+/// How clips are encoded; stills always keep their format.
+#[derive(Clone)]
+pub struct Profile {
+    pub container: Container,
+    /// MP4 only: index up front and a keyframe every second, for streaming and scrubbing
+    pub web_optimized: bool,
+    /// Caption file contents, see `dataset::caption_text` for the tokens
+    pub caption_template: String,
+    /// Output file names, see `dataset::output_file` for the tokens; empty for the default
+    pub name_template: String,
+    /// Also write each clip's audio to a file of this format next to it
+    pub audio: Option<AudioFormat>,
+    /// EBU R128 loudness normalization of the clip's audio (and the audio file)
+    pub loudnorm: bool,
+    /// Also write an animated preview of each clip next to it
+    pub preview: Option<PreviewFormat>,
+    /// Also write a sprite sheet of each clip with this many columns and rows of frames
+    pub sprite_grid: Option<u32>,
+    /// Project rules applied to tags and captions
+    pub rules: rules::Rules,
+    /// Subfolder each output goes into, e.g. per class
+    pub folders: Folders,
+    /// Outputs whose name is taken by a file the export didn't write
+    pub collisions: Collisions,
+    /// Frame rate of clips whose range doesn't set its own
+    pub frame_rate: FrameRate,
+    pub encoder: Encoder,
+    /// Ranges that need no crop or frame rate change are cut at keyframes without encoding
+    pub stream_copy: bool,
+    /// Write each range of a video as a folder of numbered frames instead of a clip
+    pub frames: Option<FrameFormat>,
+    /// Drop nearly black or frozen frames at both ends of each range, see `trim`
+    pub trim_edges: bool,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            container: Container::default(),
+            web_optimized: true,
+            caption_template: "{note}".to_string(),
+            name_template: String::new(),
+            audio: None,
+            loudnorm: false,
+            preview: None,
+            sprite_grid: None,
+            rules: rules::Rules::default(),
+            folders: Folders::None,
+            collisions: Collisions::Overwrite,
+            frame_rate: FrameRate::default(),
+            encoder: Encoder::default(),
+            stream_copy: false,
+            frames: None,
+            trim_edges: false,
+        }
+    }
+}
+
+/// Output frames per second unless the profile or the range sets another rate. Reels always run
+/// at it, so their ranges can be joined.
+pub const DEFAULT_FPS: u32 = 16;
+
+/// Frame rate `range` exports at under `profile`.
+pub fn frame_rate(profile: &Profile, range: &VideoRange) -> FrameRate {
+    range.fps.unwrap_or(profile.frame_rate)
+}
+
+fn faststart(profile: &Profile) -> bool {
+    profile.web_optimized && profile.container == Container::Mp4
+}
+
+/// Single-pass `loudnorm` to the EBU R128 target of -23 LUFS. The filter works at 192 kHz
+/// internally, so the output goes back to 48 kHz.
+fn loudnorm_args(profile: &Profile) -> &'static [&'static str] {
+    if profile.loudnorm {
+        &["-af", "loudnorm=I=-23:LRA=7:TP=-2", "-ar", "48000"]
+    } else {
+        &[]
+    }
+}
+
+fn is_anamorphic(source: &Source) -> bool {
+    (source.pixel_aspect - 1.0).abs() > 1e-3
+}
+
+/// The video filters cutting `range` out of `source`: frame rate `fps`, crop and the stretch
+/// of anamorphic sources to their display shape.
+pub fn video_filters(source: &Source, range: &VideoRange, fps: FrameRate) -> Vec<String> {
+    let mut filters = vec![];
+    if !source.is_img
+        && let FrameRate::Fixed(fps) = fps
+    {
+        filters.push(format!("fps={}", fps));
+    }
+
+    if let Some(ref norm) = range.crop_rect_norm {
+        let (cw, ch, cx, cy) = norm.to_pixels(source.frame_size.0, source.frame_size.1);
+        filters.push(format!("crop={}:{}:{}:{}", cw, ch, cx, cy));
+    }
+
+    // Crop in stored pixels first, then stretch anamorphic sources to their display shape
+    if is_anamorphic(source) {
+        filters.push("scale=trunc(iw*sar/2)*2:ih,setsar=1".to_string());
+    }
+    filters
+}
+
+/// Size in pixels of the clip `range` of `source` exports to, as displayed.
+pub fn output_size(source: &Source, range: &VideoRange) -> (i32, i32) {
+    let (w, h) = source.frame_size;
+    let (cw, ch, _, _) = range
+        .crop_rect_norm
+        .as_ref()
+        .unwrap_or(&SerializableRect::FULL)
+        .to_pixels(w, h);
+    if is_anamorphic(source) {
+        (((cw as f64 * source.pixel_aspect / 2.0) as i32) * 2, ch)
+    } else {
+        (cw, ch)
+    }
+}
+
+/// Whether `range` of `source` is stream-copied under `profile`: only when nothing has to touch
+/// the frames, so no crop, no frame rate change and no anamorphic stretch. Remote sources are
+/// always encoded; finding their keyframes would mean reading all of them.
+pub fn copies(source: &Source, range: &VideoRange, profile: &Profile) -> bool {
+    profile.stream_copy
+        && profile.frames.is_none()
+        && !source.is_img
+        && !remote::is_link(source.path)
+        && range.crop_rect_norm.is_none()
+        && frame_rate(profile, range) == FrameRate::Native
+        && !is_anamorphic(source)
+}
+
+/// Builds the FFmpeg invocation that copies `range` of `source` to `out_file` without encoding,
+/// tagged with `metadata`. The range has to be snapped to keyframes first (see
+/// `keyframes::Index::snap`): the clip starts on the keyframe at its start and ends before the
+/// one at its end. Audio is copied too, so loudness normalization doesn't apply, and the
+/// source's codecs have to fit the container (MKV takes nearly all).
+pub fn copy_command(
+    source: &Source,
+    range: &VideoRange,
+    out_file: &Path,
+    profile: &Profile,
+    metadata: &[String],
+) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y")
+        .arg("-ss")
+        .arg(range.start_time.to_string())
+        .arg("-to")
+        .arg((range.end_time - 0.5 / source.fps).to_string())
+        .arg("-i")
+        .arg(remote::input(source.path))
+        .args([
+            "-c",
+            "copy",
+            "-sn",
+            "-dn",
+            "-avoid_negative_ts",
+            "make_zero",
+        ]);
+    if faststart(profile) {
+        cmd.args(["-movflags", "+faststart"]);
+    }
+    for tag in metadata {
+        cmd.arg("-metadata").arg(tag);
+    }
+    cmd.arg(out_file);
+    cmd
+}
+
+/// Builds the FFmpeg invocation that writes `range` of `source` to `out_file`, tagged with
+/// `metadata` (`key=value` pairs). With a frame format in the profile, `out_file` is the folder
+/// the frames of a video go into; it has to exist.
+pub fn ffmpeg_command(
+    source: &Source,
+    range: &VideoRange,
+    out_file: &Path,
+    profile: &Profile,
+    metadata: &[String],
+) -> Command {
+    let (is_img, fps) = (source.is_img, source.fps);
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y");
+
+    if !is_img {
+        cmd.arg("-ss")
+            .arg(timing::export_start(range.start_time, fps).to_string())
+            .arg("-to")
+            .arg(timing::export_end(range.end_time, fps).to_string());
+    }
+
+    let encoder = &profile.encoder;
+    let frames = profile.frames.filter(|_| !is_img);
+    let encodes = !is_img && frames.is_none();
+    if encodes {
+        cmd.args(encoder.input_args(profile.container));
+    }
+    cmd.arg("-i").arg(remote::input(source.path));
+
+    let rate = frame_rate(profile, range);
+    let mut filters = video_filters(source, range, rate);
+    if encodes {
+        filters.extend(encoder.upload_filter(profile.container));
+    }
+    if !filters.is_empty() {
+        cmd.arg("-vf").arg(filters.join(","));
+    }
+
+    if let Some(format) = frames {
+        cmd.args(["-an", "-f", "image2", "-start_number", "0"]);
+        if format == FrameFormat::Jpeg {
+            cmd.args(["-q:v", "2"]);
+        }
+        cmd.arg(out_file.join(format.pattern()));
+        return cmd;
+    }
+
+    if encodes {
+        cmd.args(encoder.args(profile.container));
+        cmd.args(loudnorm_args(profile));
+        if faststart(profile) {
+            // Browsers and loaders reading over HTTP can start without fetching the whole file
+            // and seek with at most a second to decode. No scene-cut keyframes keeps it regular.
+            let gop = (rate.resolve(fps).round() as u32).max(1).to_string();
+            cmd.args(["-movflags", "+faststart", "-g", &gop, "-keyint_min", &gop])
+                .args(["-sc_threshold", "0"]);
+        }
+    }
+
+    for tag in metadata {
+        cmd.arg("-metadata").arg(tag);
+    }
+    cmd.arg(out_file);
+    cmd
+}
+
+/// Writes the audio of `range` of `source` to `out_file`. Cut at the same times as the clip, so
+/// both start together.
+pub fn audio_command(
+    source: &Source,
+    range: &VideoRange,
+    out_file: &Path,
+    format: AudioFormat,
+    profile: &Profile,
+) -> Command {
+    let fps = source.fps;
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error"])
+        .arg("-ss")
+        .arg(timing::export_start(range.start_time, fps).to_string())
+        .arg("-to")
+        .arg(timing::export_end(range.end_time, fps).to_string())
+        .arg("-i")
+        .arg(remote::input(source.path))
+        .args(["-map", "0:a:0", "-vn", "-c:a", format.codec()])
+        .args(loudnorm_args(profile))
+        .arg(out_file);
+    cmd
+}
+
+/// Writes the first seconds of `range` of `source` to the looping animation `out_file`, cropped
+/// like the clip and scaled down. GIFs get a palette made for the clip.
+pub fn preview_command(
+    source: &Source,
+    range: &VideoRange,
+    out_file: &Path,
+    format: PreviewFormat,
+) -> Command {
+    let fps = source.fps;
+    let end = timing::export_end(range.end_time, fps).min(range.start_time + PREVIEW_SECONDS);
+    let mut filters = video_filters(source, range, FrameRate::Fixed(PREVIEW_FPS));
+    filters.push(format!("scale={}:-2:flags=lanczos", PREVIEW_WIDTH));
+    if format == PreviewFormat::Gif {
+        filters.push("split[a][b];[a]palettegen[p];[b][p]paletteuse".to_string());
+    }
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error"])
+        .arg("-ss")
+        .arg(timing::export_start(range.start_time, fps).to_string())
+        .arg("-to")
+        .arg(end.to_string())
+        .arg("-i")
+        .arg(remote::input(source.path))
+        .arg("-vf")
+        .arg(filters.join(","))
+        .args(["-an", "-loop", "0"]);
+    if format == PreviewFormat::Webp {
+        cmd.args(["-c:v", "libwebp", "-quality", "75"]);
+    }
+    cmd.arg(out_file);
+    cmd
+}
+
+/// Writes a `grid` × `grid` sheet of frames spread evenly over `range` of `source` to the
+/// image `out_file`, cropped like the clip and each letterboxed into a square tile. Ranges
+/// shorter than the grid leave the last tiles black.
+pub fn sprite_command(
+    source: &Source,
+    range: &VideoRange,
+    out_file: &Path,
+    grid: u32,
+    rate: FrameRate,
+) -> Command {
+    let fps = source.fps;
+    let (start, end) = (
+        timing::export_start(range.start_time, fps),
+        timing::export_end(range.end_time, fps),
+    );
+    let frames = ((end - start) * rate.resolve(fps)).round() as u32;
+    let every = (frames / (grid * grid)).max(1);
+    let mut filters = video_filters(source, range, rate);
+    filters.push(format!("select='not(mod(n\\,{}))'", every));
+    filters.push(format!(
+        "scale={t}:{t}:force_original_aspect_ratio=decrease,pad={t}:{t}:(ow-iw)/2:(oh-ih)/2,setsar=1",
+        t = SPRITE_TILE
+    ));
+    filters.push(format!("tile={g}x{g}", g = grid));
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error"])
+        .arg("-ss")
+        .arg(start.to_string())
+        .arg("-to")
+        .arg(end.to_string())
+        .arg("-i")
+        .arg(remote::input(source.path))
+        .arg("-vf")
+        .arg(filters.join(","))
+        .args(["-an", "-frames:v", "1", "-q:v", "2"])
+        .arg(out_file);
+    cmd
+}
+
+/// Copies `clip` into `out_file` with its tags replaced by `metadata`, without encoding.
+pub fn retag_command(
+    clip: &Path,
+    out_file: &Path,
+    profile: &Profile,
+    metadata: &[String],
+) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error", "-i"]).arg(clip).args([
+        "-map",
+        "0",
+        "-c",
+        "copy",
+        "-map_metadata",
+        "-1",
+    ]);
+    if faststart(profile) {
+        cmd.args(["-movflags", "+faststart"]);
+    }
+    for tag in metadata {
+        cmd.arg("-metadata").arg(tag);
+    }
+    cmd.arg(out_file);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SerializableRect, fixtures, verify};
+    use opencv::{prelude::*, videoio};
+
+    fn assert_export_starts_on_preview_frame(
+        source: &Path,
+        start_time: f64,
+        crop: Option<SerializableRect>,
+    ) {
+        let cap =
+            videoio::VideoCapture::from_file(&source.to_string_lossy(), videoio::CAP_ANY).unwrap();
+        let fps = cap.get(videoio::CAP_PROP_FPS).unwrap();
+        let size = (
+            cap.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap(),
+            cap.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap(),
+        );
+        let range = VideoRange {
+            start_time,
+            end_time: start_time + 0.5,
+            crop_rect_norm: crop,
+            ..Default::default()
+        };
+        let out = source.with_extension(format!("{}.out.mp4", start_time));
+        let export_source = Source {
+            path: source,
+            is_img: false,
+            fps,
+            frame_size: size,
+            pixel_aspect: 1.0,
+        };
+        let status = ffmpeg_command(&export_source, &range, &out, &Profile::default(), &[])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let start_frame = timing::frame_at(start_time, fps);
+        let offset =
+            verify::start_offset(source, start_frame, range.crop_rect_norm.as_ref(), &out).unwrap();
+        assert_eq!(offset, Some(0), "{:?} starting at {}s", source, start_time);
+    }
+
+    #[test]
+    fn export_first_frame_matches_preview() {
+        let dir = fixtures::dir("seek_test");
+        let sources = [
+            fixtures::video(&dir, "cfr30.mp4", "testsrc2=size=320x240:rate=30", &[]),
+            fixtures::video(
+                &dir,
+                "ntsc.mp4",
+                "testsrc2=size=320x240:rate=30000/1001",
+                &[],
+            ),
+            // Every third frame dropped while keeping the original timestamps
+            fixtures::video(
+                &dir,
+                "vfr.mp4",
+                "testsrc2=size=320x240:rate=30",
+                &["-vf", "select='mod(n\\,3)'", "-fps_mode", "vfr"],
+            ),
+        ];
+        let Some(sources) = sources.into_iter().collect::<Option<Vec<_>>>() else {
+            eprintln!("FFmpeg not available, skipping export seek test");
+            return;
+        };
+
+        let crop = SerializableRect {
+            min_x: 0.25,
+            min_y: 0.1,
+            max_x: 0.75,
+            max_y: 0.6,
+        };
+        for source in &sources {
+            for start in [0.0, 0.77, 1.234, 2.0] {
+                assert_export_starts_on_preview_frame(source, start, None);
+            }
+            assert_export_starts_on_preview_frame(source, 1.1, Some(crop.clone()));
+        }
+    }
+
+    #[test]
+    fn ranges_can_override_the_frame_rate() {
+        let source = Source {
+            path: Path::new("in.mp4"),
+            is_img: false,
+            fps: 29.97,
+            frame_size: (640.0, 480.0),
+            pixel_aspect: 1.0,
+        };
+        let args = |range: &VideoRange| -> Vec<String> {
+            ffmpeg_command(
+                &source,
+                range,
+                Path::new("out.mp4"),
+                &Profile::default(),
+                &[],
+            )
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+        };
+        let arg_after = |args: &[String], name: &str| {
+            let at = args.iter().position(|a| a == name).unwrap();
+            args[at + 1].clone()
+        };
+        let default = args(&VideoRange::default());
+        assert_eq!(arg_after(&default, "-vf"), "fps=16");
+        assert_eq!(arg_after(&default, "-g"), "16");
+        let native = args(&VideoRange {
+            fps: Some(FrameRate::Native),
+            ..Default::default()
+        });
+        assert!(!native.contains(&"-vf".to_string()));
+        assert_eq!(arg_after(&native, "-g"), "30");
+    }
+
+    #[test]
+    fn encoder_settings_fit_the_container() {
+        let args = |encoder: Encoder, container| encoder.args(container).join(" ");
+        // The defaults keep the commands (and hashes) of earlier exports
+        assert_eq!(
+            args(Encoder::default(), Container::Mp4),
+            "-c:v libx264 -preset ultrafast"
+        );
+        assert_eq!(
+            args(Encoder::default(), Container::Webm),
+            "-c:v libvpx-vp9 -deadline realtime -cpu-used 8"
+        );
+        let archive = Encoder {
+            codec: Codec::H265,
+            preset: Preset::Slow,
+            quality: Quality::Crf(24),
+            pixel_format: Some(PixelFormat::Yuv420p10le),
+            hardware: None,
+        };
+        assert_eq!(
+            args(archive, Container::Mp4),
+            "-c:v libx265 -preset slow -crf 24 -pix_fmt yuv420p10le -tag:v hvc1"
+        );
+        assert_eq!(
+            args(archive, Container::Webm),
+            "-c:v libvpx-vp9 -deadline good -cpu-used 2 -crf 24 -b:v 0 -pix_fmt yuv420p10le"
+        );
+        let streaming = Encoder {
+            quality: Quality::Bitrate(2500),
+            ..Encoder::default()
+        };
+        assert_eq!(
+            args(streaming, Container::Mkv),
+            "-c:v libx264 -preset ultrafast -b:v 2500k"
+        );
+        let nvenc = Encoder {
+            hardware: Some(Hardware::Nvenc),
+            ..archive
+        };
+        assert_eq!(
+            args(nvenc, Container::Mp4),
+            "-c:v hevc_nvenc -preset p5 -rc vbr -cq 24 -b:v 0 -pix_fmt p010le -tag:v hvc1"
+        );
+        // WebM falls back to VP9 on the CPU
+        assert_eq!(args(nvenc, Container::Webm), args(archive, Container::Webm));
+    }
+
+    #[test]
+    fn frame_sequences_skip_the_encoder() {
+        let source = Source {
+            path: Path::new("in.mp4"),
+            is_img: false,
+            fps: 30.0,
+            frame_size: (640.0, 480.0),
+            pixel_aspect: 1.0,
+        };
+        let frames = Profile {
+            frames: Some(FrameFormat::Jpeg),
+            encoder: Encoder {
+                hardware: Some(Hardware::Vaapi),
+                ..Encoder::default()
+            },
+            ..Profile::default()
+        };
+        let out = Path::new("out").join("clip.frames");
+        let cmd = ffmpeg_command(&source, &VideoRange::default(), &out, &frames, &[]);
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args[args.len() - 10..].join(" "),
+            format!(
+                "-vf fps=16 -an -f image2 -start_number 0 -q:v 2 {}",
+                out.join("%06d.jpg").display()
+            )
+        );
+        assert!(!args.contains(&"-vaapi_device".to_string()));
+    }
+
+    #[test]
+    fn stream_copy_only_takes_untouched_ranges() {
+        let source = Source {
+            path: Path::new("in.mkv"),
+            is_img: false,
+            fps: 25.0,
+            frame_size: (1920.0, 1080.0),
+            pixel_aspect: 1.0,
+        };
+        let profile = Profile {
+            stream_copy: true,
+            frame_rate: FrameRate::Native,
+            container: Container::Mkv,
+            ..Profile::default()
+        };
+        let range = VideoRange {
+            start_time: 2.0,
+            end_time: 6.0,
+            ..Default::default()
+        };
+        assert!(copies(&source, &range, &profile));
+        let cropped = VideoRange {
+            crop_rect_norm: Some(SerializableRect {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 0.5,
+                max_y: 1.0,
+            }),
+            ..range.clone()
+        };
+        assert!(!copies(&source, &cropped, &profile));
+        assert!(!copies(
+            &source,
+            &range,
+            &Profile {
+                frame_rate: FrameRate::Fixed(16),
+                ..profile.clone()
+            }
+        ));
+        assert!(!copies(
+            &source,
+            &range,
+            &Profile {
+                stream_copy: false,
+                ..profile.clone()
+            }
+        ));
+
+        let args: Vec<String> = copy_command(&source, &range, Path::new("out.mkv"), &profile, &[])
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args.join(" "),
+            "-y -ss 2 -to 5.98 -i in.mkv -c copy -sn -dn -avoid_negative_ts make_zero out.mkv"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+// Synthetic media for the end-to-end tests. Clips and stills are rendered with FFmpeg's lavfi
+// sources when a test runs, so no binary fixtures live in the repository; every helper returns
+// None when FFmpeg is unavailable and the tests using it skip themselves.
+use opencv::{core, prelude::*, videoio};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Size of the split pattern
+pub const WIDTH: i32 = 320;
+pub const HEIGHT: i32 = 240;
+
+/// A fresh, empty scratch folder for the test `name`.
+pub fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("viddatatraincrop_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Left half red, right half blue, so the content of a crop tells where it was taken.
+pub fn split_pattern(rate: u32) -> String {
+    format!(
+        "color=c=red:size={w}x{h}:rate={rate},drawbox=x={x}:y=0:w={x}:h={h}:color=blue:t=fill",
+        w = WIDTH,
+        h = HEIGHT,
+        x = WIDTH / 2,
+    )
+}
+
+/// Renders three seconds of the lavfi `filter` to `dir/name`.
+pub fn video(dir: &Path, name: &str, filter: &str, extra: &[&str]) -> Option<PathBuf> {
+    let path = dir.join(name);
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-v", "error", "-f", "lavfi", "-i", filter])
+        .args(extra)
+        .args(["-t", "3", "-pix_fmt", "yuv420p", "-c:v", "libx264"])
+        .arg(&path)
+        .status()
+        .ok()?;
+    status.success().then_some(path)
+}
+
+/// Renders the first frame of the lavfi `filter` to the still `dir/name`.
+pub fn image(dir: &Path, name: &str, filter: &str) -> Option<PathBuf> {
+    let path = dir.join(name);
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-v", "error", "-f", "lavfi", "-i", filter])
+        .args(["-frames:v", "1"])
+        .arg(&path)
+        .status()
+        .ok()?;
+    status.success().then_some(path)
+}
+
+/// Every frame of the video at `path`, decoded.
+pub fn frames(path: &Path) -> Vec<core::Mat> {
+    let mut cap =
+        videoio::VideoCapture::from_file(&path.to_string_lossy(), videoio::CAP_ANY).unwrap();
+    let mut frames = Vec::new();
+    let mut frame = core::Mat::default();
+    while cap.read(&mut frame).unwrap() && !frame.empty() {
+        frames.push(frame.try_clone().unwrap());
+    }
+    frames
+}
+
+/// Mean blue, green and red of `frame`.
+pub fn mean_bgr(frame: &core::Mat) -> [f64; 3] {
+    let mean = core::mean(frame, &core::no_array()).unwrap();
+    [mean[0], mean[1], mean[2]]
+}
+
+pub fn is_red(frame: &core::Mat) -> bool {
+    let [b, g, r] = mean_bgr(frame);
+    r > 200.0 && g < 40.0 && b < 40.0
+}
+
+pub fn is_blue(frame: &core::Mat) -> bool {
+    let [b, g, r] = mean_bgr(frame);
+    b > 200.0 && g < 40.0 && r < 40.0
+}
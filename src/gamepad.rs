@@ -0,0 +1,98 @@
+// Optional gamepad and jog/shuttle controller input, built with `--features gamepad`.
+// Shuttle controllers that enumerate as HID joysticks report the spring-loaded ring as a stick
+// axis and the jog wheel as d-pad presses, so both kinds of device share one mapping:
+//
+//   left stick / shuttle ring   scrub, faster the further it is turned
+//   d-pad left / right, jog     previous / next frame
+//   d-pad up / down             previous / next range
+//   south (A / Cross)           play / pause
+//   north (Y / Triangle)        preview range
+//   left / right shoulder       set start / end of the current range
+use crate::actions::Action;
+use crate::logs;
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+/// Stick deflection below this is treated as centered.
+const DEADZONE: f32 = 0.15;
+/// Scrub speed at full deflection, in multiples of real time.
+const MAX_SHUTTLE_SPEED: f64 = 8.0;
+
+pub struct Gamepad {
+    gilrs: Gilrs,
+}
+
+impl Gamepad {
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs }),
+            Err(e) => {
+                tracing::warn!(target: logs::UI, "Gamepad input unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.gilrs.gamepads().next().is_some()
+    }
+
+    /// Drains pending controller events into actions. Also keeps the axis state current.
+    pub fn poll(&mut self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Some(gilrs::Event { event, .. }) = self.gilrs.next_event() {
+            let (EventType::ButtonPressed(button, _) | EventType::ButtonRepeated(button, _)) =
+                event
+            else {
+                continue;
+            };
+            let repeated = matches!(event, EventType::ButtonRepeated(..));
+            let action = match button {
+                Button::DPadLeft => Action::PrevFrame,
+                Button::DPadRight => Action::NextFrame,
+                // Only frame stepping auto-repeats while held
+                _ if repeated => continue,
+                Button::DPadUp => Action::PrevRange,
+                Button::DPadDown => Action::NextRange,
+                Button::South => Action::PlayPause,
+                Button::North => Action::PreviewRange,
+                Button::LeftTrigger => Action::SetStart,
+                Button::RightTrigger => Action::SetEnd,
+                _ => continue,
+            };
+            actions.push(action);
+        }
+        actions
+    }
+
+    /// Signed scrub speed in multiples of real time from the most deflected shuttle/stick.
+    pub fn shuttle_speed(&self) -> f64 {
+        let x = self
+            .gilrs
+            .gamepads()
+            .map(|(_, pad)| pad.value(Axis::LeftStickX))
+            .fold(0.0f32, |a, b| if b.abs() > a.abs() { b } else { a });
+        shuttle_curve(x)
+    }
+}
+
+/// Quadratic response so small deflections allow slow, precise scrubbing.
+fn shuttle_curve(x: f32) -> f64 {
+    if x.abs() < DEADZONE {
+        return 0.0;
+    }
+    let t = ((x.abs() - DEADZONE) / (1.0 - DEADZONE)).min(1.0) as f64;
+    t * t * MAX_SHUTTLE_SPEED * x.signum() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuttle_has_deadzone_and_symmetric_response() {
+        assert_eq!(shuttle_curve(0.1), 0.0);
+        assert_eq!(shuttle_curve(1.0), MAX_SHUTTLE_SPEED);
+        assert_eq!(shuttle_curve(-1.0), -MAX_SHUTTLE_SPEED);
+        assert!(shuttle_curve(0.5) > 0.0 && shuttle_curve(0.5) < MAX_SHUTTLE_SPEED / 2.0);
+    }
+}
@@ -0,0 +1,201 @@
+// Hardware encoders. x264 on the CPU is what limits batch exports of hundreds of clips, so
+// H.264 and H.265 can be encoded on the graphics card instead: NVENC on NVIDIA, Quick Sync on
+// Intel and VAAPI on Linux. Whether one works depends on the FFmpeg build, the driver and the
+// card, so at startup each is tried on a single black frame; the encoder settings grey out the
+// ones that failed.
+use crate::export::{Codec, PixelFormat, Preset, Quality};
+use crate::logs;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// Render node VAAPI encodes on; the first GPU on nearly every machine
+pub const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hardware {
+    Nvenc,
+    Qsv,
+    Vaapi,
+}
+
+impl Hardware {
+    pub const ALL: [Hardware; 3] = [Hardware::Nvenc, Hardware::Qsv, Hardware::Vaapi];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Hardware::Nvenc => "NVENC (NVIDIA)",
+            Hardware::Qsv => "Quick Sync (Intel)",
+            Hardware::Vaapi => "VAAPI",
+        }
+    }
+
+    /// FFmpeg's encoder for `codec` on this hardware; VP9 is always encoded on the CPU.
+    pub fn encoder(self, codec: Codec) -> Option<&'static str> {
+        match (self, codec) {
+            (_, Codec::Vp9) => None,
+            (Hardware::Nvenc, Codec::H264) => Some("h264_nvenc"),
+            (Hardware::Nvenc, Codec::H265) => Some("hevc_nvenc"),
+            (Hardware::Qsv, Codec::H264) => Some("h264_qsv"),
+            (Hardware::Qsv, Codec::H265) => Some("hevc_qsv"),
+            (Hardware::Vaapi, Codec::H264) => Some("h264_vaapi"),
+            (Hardware::Vaapi, Codec::H265) => Some("hevc_vaapi"),
+        }
+    }
+
+    /// The nearest speed setting to the x264 `preset`. VAAPI drivers have none.
+    pub fn preset_args(self, preset: Preset) -> Vec<String> {
+        let name = match self {
+            Hardware::Nvenc => match preset {
+                Preset::Ultrafast | Preset::Superfast => "p1",
+                Preset::Veryfast => "p2",
+                Preset::Faster | Preset::Fast => "p3",
+                Preset::Medium => "p4",
+                Preset::Slow => "p5",
+                Preset::Slower => "p6",
+                Preset::Veryslow => "p7",
+            },
+            // Quick Sync starts at veryfast
+            Hardware::Qsv => match preset {
+                Preset::Ultrafast | Preset::Superfast => "veryfast",
+                other => other.name(),
+            },
+            Hardware::Vaapi => return Vec::new(),
+        };
+        vec!["-preset".to_string(), name.to_string()]
+    }
+
+    /// Rate control for `quality`. Each encoder has its own constant-quality mode; the CRF
+    /// scale is close enough to all of them.
+    pub fn quality_args(self, quality: Quality) -> Vec<String> {
+        let crf = match quality {
+            Quality::Default => return Vec::new(),
+            Quality::Bitrate(kbps) => return vec!["-b:v".to_string(), format!("{}k", kbps)],
+            Quality::Crf(crf) => crf.min(51).to_string(),
+        };
+        match self {
+            Hardware::Nvenc => vec!["-rc", "vbr", "-cq", &crf, "-b:v", "0"],
+            Hardware::Qsv => vec!["-global_quality", &crf],
+            Hardware::Vaapi => vec!["-rc_mode", "CQP", "-qp", &crf],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    /// The pixel format the encoder takes closest to `format`. Quick Sync and VAAPI only
+    /// encode 4:2:0, in their own layouts.
+    fn pixel_format(self, format: PixelFormat) -> &'static str {
+        match (self, format) {
+            (_, PixelFormat::Yuv420p10le) => "p010le",
+            (Hardware::Nvenc, PixelFormat::Yuv444p) => "yuv444p",
+            (Hardware::Nvenc, _) => "yuv420p",
+            (Hardware::Qsv | Hardware::Vaapi, _) => "nv12",
+        }
+    }
+
+    pub fn pixel_format_args(self, format: Option<PixelFormat>) -> Vec<String> {
+        match (self, format) {
+            // VAAPI gets its format from the upload filter
+            (Hardware::Vaapi, _) | (_, None) => Vec::new(),
+            (_, Some(format)) => vec![
+                "-pix_fmt".to_string(),
+                self.pixel_format(format).to_string(),
+            ],
+        }
+    }
+
+    /// Options before the input, opening the device.
+    pub fn input_args(self) -> &'static [&'static str] {
+        match self {
+            Hardware::Vaapi => &["-vaapi_device", VAAPI_DEVICE],
+            Hardware::Nvenc | Hardware::Qsv => &[],
+        }
+    }
+
+    /// Filter appended to the clip's filters that moves the frames onto the card, for encoders
+    /// that don't take frames from memory.
+    pub fn upload_filter(self, format: Option<PixelFormat>) -> Option<String> {
+        (self == Hardware::Vaapi).then(|| {
+            let format = self.pixel_format(format.unwrap_or(PixelFormat::Yuv420p));
+            format!("format={},hwupload", format)
+        })
+    }
+}
+
+/// Hardware whose H.264 encoder worked at startup; None until the probe is done.
+static AVAILABLE: Mutex<Option<Vec<Hardware>>> = Mutex::new(None);
+
+pub fn available() -> Option<Vec<Hardware>> {
+    AVAILABLE.lock().unwrap().clone()
+}
+
+/// Encodes one black frame with `hardware`'s H.264 encoder to nowhere.
+fn test_command(hardware: Hardware) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-v", "error"])
+        .args(hardware.input_args())
+        .args(["-f", "lavfi", "-i", "color=black:size=256x256:rate=1"])
+        .args(["-frames:v", "1"]);
+    if let Some(filter) = hardware.upload_filter(None) {
+        cmd.arg("-vf").arg(filter);
+    }
+    cmd.args(["-c:v", hardware.encoder(Codec::H264).unwrap()])
+        .args(["-f", "null", "-"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    cmd
+}
+
+/// Tries every hardware encoder on a background thread. Without FFmpeg none is available.
+pub fn start_probe() {
+    std::thread::spawn(|| {
+        let working: Vec<Hardware> = Hardware::ALL
+            .into_iter()
+            .filter(|h| test_command(*h).status().is_ok_and(|s| s.success()))
+            .collect();
+        let names: Vec<&str> = working.iter().map(|h| h.label()).collect();
+        tracing::info!(target: logs::EXPORT, "Hardware encoders available: {:?}", names);
+        *AVAILABLE.lock().unwrap() = Some(working);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_follow_the_hardware() {
+        let join = |args: Vec<String>| args.join(" ");
+        assert_eq!(Hardware::Nvenc.encoder(Codec::H265), Some("hevc_nvenc"));
+        assert_eq!(Hardware::Qsv.encoder(Codec::Vp9), None);
+        assert_eq!(
+            join(Hardware::Nvenc.preset_args(Preset::Slow)),
+            "-preset p5"
+        );
+        assert_eq!(
+            join(Hardware::Qsv.preset_args(Preset::Ultrafast)),
+            "-preset veryfast"
+        );
+        assert!(Hardware::Vaapi.preset_args(Preset::Slow).is_empty());
+        assert_eq!(
+            join(Hardware::Nvenc.quality_args(Quality::Crf(23))),
+            "-rc vbr -cq 23 -b:v 0"
+        );
+        assert_eq!(
+            join(Hardware::Vaapi.quality_args(Quality::Crf(60))),
+            "-rc_mode CQP -qp 51"
+        );
+        assert_eq!(
+            join(Hardware::Qsv.pixel_format_args(Some(PixelFormat::Yuv444p))),
+            "-pix_fmt nv12"
+        );
+        assert_eq!(
+            Hardware::Vaapi
+                .upload_filter(Some(PixelFormat::Yuv420p10le))
+                .as_deref(),
+            Some("format=p010le,hwupload")
+        );
+        assert!(Hardware::Nvenc.upload_filter(None).is_none());
+    }
+}
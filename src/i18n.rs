@@ -0,0 +1,1234 @@
+// Gettext-style localization: UI strings are written in English at the call site and looked up
+// in the active language's table at runtime, falling back to English when a string is missing.
+// To add a language, add a variant and a table; the test below keeps placeholders consistent.
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: &[Language] = &[Language::English, Language::German];
+
+    /// Name of the language in itself, for the language picker.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// Picks the language from VIDDATATRAINCROP_LANG or the usual POSIX locale variables.
+    pub fn from_env() -> Self {
+        ["VIDDATATRAINCROP_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .map(|v| {
+                if v.to_ascii_lowercase().starts_with("de") {
+                    Language::German
+                } else {
+                    Language::English
+                }
+            })
+            .unwrap_or(Language::English)
+    }
+
+    fn table(self) -> Option<&'static HashMap<&'static str, &'static str>> {
+        static GERMAN_TABLE: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        match self {
+            Language::English => None,
+            Language::German => Some(GERMAN_TABLE.get_or_init(|| GERMAN.iter().copied().collect())),
+        }
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn language() -> Language {
+    Language::ALL[CURRENT.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_language(language: Language) {
+    let idx = Language::ALL.iter().position(|l| *l == language).unwrap();
+    CURRENT.store(idx as u8, Ordering::Relaxed);
+}
+
+/// Translates `text` into the active language.
+pub fn tr(text: &'static str) -> &'static str {
+    language()
+        .table()
+        .and_then(|t| t.get(text).copied())
+        .unwrap_or(text)
+}
+
+/// Translates `text` and fills its `{}` placeholders with `args` in order.
+pub fn trf(text: &'static str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut out = String::new();
+    let mut parts = tr(text).split("{}");
+    out.push_str(parts.next().unwrap_or_default());
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+static GERMAN: &[(&str, &str)] = &[
+    // Top bar
+    ("📁 Input Folder", "📁 Eingabeordner"),
+    ("💾 Output Folder", "💾 Ausgabeordner"),
+    ("In: {}", "Ein: {}"),
+    ("Out: {}", "Aus: {}"),
+    ("None", "Keiner"),
+    ("⌨ Shortcuts (F1)", "⌨ Tastenkürzel (F1)"),
+    ("📜 Log", "📜 Protokoll"),
+    (
+        "What the app logged, e.g. why an export failed",
+        "Was die App protokolliert hat, z. B. warum ein Export fehlschlug",
+    ),
+    // Log viewer
+    ("Level:", "Stufe:"),
+    ("Subsystem:", "Bereich:"),
+    ("All", "Alle"),
+    ("📋 Copy", "📋 Kopieren"),
+    ("Export log ({} lines)", "Exportprotokoll ({} Zeilen)"),
+    (
+        "Copy the log to the clipboard, e.g. for a bug report",
+        "Das Protokoll in die Zwischenablage kopieren, z. B. für einen Fehlerbericht",
+    ),
+    ("Log file: {}", "Protokolldatei: {}"),
+    ("🔎 Commands", "🔎 Befehle"),
+    // Side panels
+    ("Files", "Dateien"),
+    ("Active Crops", "Zuschnitte"),
+    ("Active Ranges", "Bereiche"),
+    ("➕ Add Crop", "➕ Zuschnitt hinzufügen"),
+    ("➕ Add Range", "➕ Bereich hinzufügen"),
+    (
+        "Length of new ranges: they end this long after the playhead, or at the end of the video",
+        "Länge neuer Bereiche: Sie enden so lange nach dem Abspielkopf oder am Ende des Videos",
+    ),
+    ("📑 Chapters to Ranges ({})", "📑 Kapitel zu Bereichen ({})"),
+    (
+        "Add a range for every chapter of the video, with the chapter title as note",
+        "Für jedes Kapitel des Videos einen Bereich mit dem Kapiteltitel als Notiz anlegen",
+    ),
+    ("🔤 Scan for Text", "🔤 Nach Text suchen"),
+    (
+        "Select an EAST model (.pb) first",
+        "Zuerst ein EAST-Modell (.pb) auswählen",
+    ),
+    ("EAST model…", "EAST-Modell…"),
+    ("🎙 Transcribe", "🎙 Transkribieren"),
+    (
+        "Transcribe the speech of the current range with whisper.cpp and append it to the note",
+        "Die Sprache des aktuellen Bereichs mit whisper.cpp transkribieren und an die Notiz anhängen",
+    ),
+    (
+        "Select a Whisper model (ggml .bin) first",
+        "Zuerst ein Whisper-Modell (ggml .bin) auswählen",
+    ),
+    ("Whisper model…", "Whisper-Modell…"),
+    ("✂ Trim Preview", "✂ Kantenschnitt-Vorschau"),
+    (
+        "Find the nearly black or frozen frames at the ends of the current range, which edge trimming drops at export",
+        "Die fast schwarzen oder eingefrorenen Bilder an den Enden des aktuellen Bereichs finden, die der Kantenschnitt beim Export entfernt",
+    ),
+    ("Apply to Range", "Auf Bereich anwenden"),
+    (
+        "Move the range's start and end past the trimmed frames",
+        "Anfang und Ende des Bereichs hinter die entfernten Bilder verschieben",
+    ),
+    (
+        "No black or frozen frames at the edges",
+        "Keine schwarzen oder eingefrorenen Bilder an den Enden",
+    ),
+    ("Trims {} frames at the start", "Entfernt {} Bilder am Anfang"),
+    ("Trims {} frames at the end", "Entfernt {} Bilder am Ende"),
+    (
+        "Trims {} frames at the start and {} at the end",
+        "Entfernt {} Bilder am Anfang und {} am Ende",
+    ),
+    (
+        "Could not check the edges of {}: {}",
+        "Konnte die Enden von {} nicht prüfen: {}",
+    ),
+    ("No speech recognized.", "Keine Sprache erkannt."),
+    (
+        "FFmpeg could not extract the audio of {}",
+        "FFmpeg konnte den Ton von {} nicht extrahieren",
+    ),
+    (
+        "Failed to start whisper.cpp: {}",
+        "whisper.cpp konnte nicht gestartet werden: {}",
+    ),
+    ("whisper.cpp failed: {}", "whisper.cpp ist fehlgeschlagen: {}"),
+    ("Crop {}", "Zuschnitt {}"),
+    (
+        "R{}: {}s - {}s ({}s)\n      {} - {} ({} frames)",
+        "B{}: {}s - {}s ({}s)\n      {} - {} ({} Frames)",
+    ),
+    (
+        "\n⚠ Text in {}/{} sampled frames",
+        "\n⚠ Text in {}/{} Stichproben-Frames",
+    ),
+    (
+        "\n⚠ Untouched: the whole video, no crop or note",
+        "\n⚠ Unbearbeitet: ganzes Video, kein Zuschnitt, keine Notiz",
+    ),
+    (
+        "Range(s) {} still cover the whole video without crop, note or tags. Export anyway?",
+        "Bereich(e) {} umfassen noch das ganze Video ohne Zuschnitt, Notiz oder Tags. Trotzdem exportieren?",
+    ),
+    // Viewport and timeline
+    ("Reset Zoom", "Zoom zurücksetzen"),
+    (
+        "Or double-click the video",
+        "Oder doppelt auf das Video klicken",
+    ),
+    ("Native Frame:", "Nativer Frame:"),
+    (
+        "A native frame, +N / -N native frames from the range start, or rK:N for frame N of range K's exported clip",
+        "Ein nativer Frame, +N / -N native Frames ab Bereichsbeginn oder rK:N für Frame N des exportierten Clips von Bereich K",
+    ),
+    ("Whole Timeline", "Ganze Zeitleiste"),
+    ("Subtitles", "Untertitel"),
+    (
+        "Show the current subtitle under the video",
+        "Den aktuellen Untertitel unter dem Video anzeigen",
+    ),
+    ("📋 To Note", "📋 In Notiz"),
+    (
+        "Append the subtitle to the note of the current range",
+        "Den Untertitel an die Notiz des aktuellen Bereichs anhängen",
+    ),
+    ("Target {} FPS: {}", "Ziel {} FPS: {}"),
+    ("Set Start", "Start setzen"),
+    ("Set End", "Ende setzen"),
+    ("Clear Crop", "Zuschnitt löschen"),
+    ("Use Crop as File Default", "Zuschnitt als Dateivorgabe verwenden"),
+    ("📌 File Default", "📌 Dateivorgabe"),
+    (
+        "New ranges of this file start with this crop. Click again to stop.",
+        "Neue Bereiche dieser Datei beginnen mit diesem Zuschnitt. Erneut klicken zum Beenden.",
+    ),
+    ("Ghost crops", "Nachbar-Zuschnitte"),
+    (
+        "Outline the crops of the previous and next range",
+        "Zuschnitte des vorherigen und nächsten Bereichs umranden",
+    ),
+    ("Tiles", "Kacheln"),
+    (
+        "Grid of training-resolution tiles over the crop: one tile is what the short side is scaled down to",
+        "Raster aus Kacheln der Trainingsauflösung über dem Zuschnitt: eine Kachel ist das, worauf die kurze Seite verkleinert wird",
+    ),
+    ("Training resolution (short side)", "Trainingsauflösung (kurze Seite)"),
+    ("Training view", "Trainingsansicht"),
+    (
+        "Show the crop scaled down to the training resolution and back up, as the model sees it",
+        "Den Zuschnitt auf die Trainingsauflösung verkleinert und wieder vergrößert zeigen, so wie das Modell ihn sieht",
+    ),
+    ("{} × {} px → {} × {} (×{})", "{} × {} px → {} × {} (×{})"),
+    ("✨ Auto Crop (A)", "✨ Auto-Zuschnitt (A)"),
+    ("Match Neighbour Range's Crop", "Zuschnitt des Nachbarbereichs suchen"),
+    ("🎯 Match Crop", "🎯 Zuschnitt suchen"),
+    (
+        "Find the previous (or next) range's crop in this frame and propose it moved there, for slight reframing between shots",
+        "Den Zuschnitt des vorherigen (oder nächsten) Bereichs in diesem Bild suchen und dorthin verschoben vorschlagen, für leichte Bildausschnittänderungen zwischen Einstellungen",
+    ),
+    (
+        "Neither neighbour range has a crop to match",
+        "Keiner der Nachbarbereiche hat einen Zuschnitt zum Suchen",
+    ),
+    (
+        "The crop of range {} was not found in this frame",
+        "Der Zuschnitt von Bereich {} wurde in diesem Bild nicht gefunden",
+    ),
+    ("Proposed crop (match {} %)", "Vorgeschlagener Zuschnitt (Übereinstimmung {} %)"),
+    ("✔ Accept", "✔ Übernehmen"),
+    ("✖ Dismiss", "✖ Verwerfen"),
+    ("🔁 Play Range (R)", "🔁 Bereich abspielen (R)"),
+    ("Cut Check", "Schnittprüfung"),
+    ("🔲 Cut Check", "🔲 Schnittprüfung"),
+    (
+        "Show the frames on both sides of the range's start and end, to check a cut lands right before a scene change",
+        "Die Frames zu beiden Seiten von Anfang und Ende des Bereichs zeigen, um zu prüfen, ob ein Schnitt direkt vor einem Szenenwechsel liegt",
+    ),
+    ("Start", "Anfang"),
+    ("End", "Ende"),
+    ("Before the range ({})", "Vor dem Bereich ({})"),
+    ("First frame ({})", "Erster Frame ({})"),
+    ("Last frame ({})", "Letzter Frame ({})"),
+    ("After the range ({})", "Nach dem Bereich ({})"),
+    ("Outside the video", "Außerhalb des Videos"),
+    ("Note for Crop {}:", "Notiz für Zuschnitt {}:"),
+    ("Note for Range {}:", "Notiz für Bereich {}:"),
+    ("⏳ Exporting...", "⏳ Exportiere..."),
+    ("🚀 RUN EXPORT ALL", "🚀 ALLES EXPORTIEREN"),
+    (
+        "Processing ranges with FFmpeg...",
+        "Verarbeite Bereiche mit FFmpeg...",
+    ),
+    ("⏹ Cancel Export", "⏹ Export abbrechen"),
+    (
+        "Stops FFmpeg and skips the remaining ranges; clips already written are kept",
+        "Stoppt FFmpeg und überspringt die restlichen Bereiche; bereits geschriebene Clips bleiben erhalten",
+    ),
+    ("Export cancelled", "Export abgebrochen"),
+    // Quitting while jobs run
+    ("⚠ Jobs Still Running", "⚠ Aufgaben laufen noch"),
+    ("Export", "Export"),
+    ("QA sample export", "QA-Stichprobenexport"),
+    ("Reel export", "Reel-Export"),
+    ("File analysis", "Dateianalyse"),
+    ("Still running: {}", "Läuft noch: {}"),
+    ("Quitting once these are done: {}", "Beenden, sobald diese fertig sind: {}"),
+    (
+        "Finish Current Clip, Then Quit",
+        "Aktuellen Clip fertigstellen, dann beenden",
+    ),
+    (
+        "Skips the remaining ranges; QA samples and reels run to the end",
+        "Überspringt die restlichen Bereiche; QA-Stichproben und Reels laufen zu Ende",
+    ),
+    ("⏹ Stop Now and Quit", "⏹ Sofort stoppen und beenden"),
+    (
+        "Kills FFmpeg and removes the clip it was writing",
+        "Beendet FFmpeg und entfernt den Clip, den es gerade schrieb",
+    ),
+    ("Keep Running", "Weiterlaufen lassen"),
+    ("{}: range {} of {}", "{}: Bereich {} von {}"),
+    ("{} left", "noch {}"),
+    // Preview proxies
+    (
+        "Building 8-bit preview proxy…",
+        "Erzeuge 8-Bit-Vorschau-Proxy…",
+    ),
+    (
+        "Preview: 8-bit proxy (export uses the original)",
+        "Vorschau: 8-Bit-Proxy (der Export nutzt das Original)",
+    ),
+    (
+        "Could not open preview proxy {}",
+        "Vorschau-Proxy {} konnte nicht geöffnet werden",
+    ),
+    (
+        "Failed to create proxy folder: {}",
+        "Proxy-Ordner konnte nicht angelegt werden: {}",
+    ),
+    (
+        "Failed to store proxy: {}",
+        "Proxy konnte nicht gespeichert werden: {}",
+    ),
+    (
+        "FFmpeg could not create a preview proxy for {}",
+        "FFmpeg konnte keinen Vorschau-Proxy für {} erzeugen",
+    ),
+    // Export and analysis errors
+    (
+        "FFmpeg failed on range {} with exit code: {}",
+        "FFmpeg ist bei Bereich {} mit Exit-Code {} fehlgeschlagen",
+    ),
+    (
+        "Failed to start FFmpeg: {}",
+        "FFmpeg konnte nicht gestartet werden: {}",
+    ),
+    ("range {}: {} frame(s)", "Bereich {}: {} Frame(s)"),
+    (
+        "Exported clips start off the previewed frame: {}",
+        "Exportierte Clips beginnen nicht am Vorschau-Frame: {}",
+    ),
+    (
+        "Failed to load EAST model: {}",
+        "EAST-Modell konnte nicht geladen werden: {}",
+    ),
+    (
+        "Failed to read image: {}",
+        "Bild konnte nicht gelesen werden: {}",
+    ),
+    (
+        "Failed to open video: {}",
+        "Video konnte nicht geöffnet werden: {}",
+    ),
+    // Actions (command palette and shortcut overlay)
+    ("Play / Pause", "Abspielen / Pause"),
+    ("Previous Frame", "Vorheriger Frame"),
+    ("Next Frame", "Nächster Frame"),
+    ("Set Start (In-point)", "Start setzen (In-Punkt)"),
+    ("Set End (Out-point)", "Ende setzen (Out-Punkt)"),
+    ("Preview Range", "Bereich ansehen"),
+    ("Add Range / Crop", "Bereich / Zuschnitt hinzufügen"),
+    ("Convert Chapters to Ranges", "Kapitel in Bereiche umwandeln"),
+    (
+        "Remove Current Range / Crop",
+        "Aktuellen Bereich / Zuschnitt entfernen",
+    ),
+    ("Previous Range", "Vorheriger Bereich"),
+    ("Next Range", "Nächster Bereich"),
+    ("Auto Crop", "Auto-Zuschnitt"),
+    ("Scan for Burned-in Text", "Nach eingebranntem Text suchen"),
+    ("Transcribe Range Speech", "Sprache des Bereichs transkribieren"),
+    ("Preview Edge Trim", "Kantenschnitt-Vorschau"),
+    ("Previous File", "Vorherige Datei"),
+    ("Next File", "Nächste Datei"),
+    ("Open Input Folder…", "Eingabeordner öffnen…"),
+    ("Choose Output Folder…", "Ausgabeordner wählen…"),
+    ("Run Export", "Export starten"),
+    ("Show Log", "Protokoll anzeigen"),
+    ("Check for Updates", "Nach Updates suchen"),
+    ("Encoder Settings…", "Encoder-Einstellungen…"),
+    ("Show Keyboard Shortcuts", "Tastenkürzel anzeigen"),
+    ("Command Palette", "Befehlspalette"),
+    ("Type a command…", "Befehl eingeben…"),
+    ("No matching command", "Kein passender Befehl"),
+    ("⌨ Keyboard Shortcuts", "⌨ Tastenkürzel"),
+    ("Drag", "Ziehen"),
+    (
+        "Draw a crop (mouse, pen or one finger)",
+        "Zuschnitt zeichnen (Maus, Stift oder ein Finger)",
+    ),
+    ("Pinch / Ctrl + Scroll", "Zwei Finger / Strg + Mausrad"),
+    ("Zoom the preview", "Vorschau zoomen"),
+    (
+        "Every other command is available from the Command Palette.",
+        "Alle weiteren Befehle sind über die Befehlspalette erreichbar.",
+    ),
+    ("Tab / Shift + Tab", "Tab / Umschalt + Tab"),
+    (
+        "Move focus between controls; Space or Enter activates them",
+        "Fokus zwischen Bedienelementen wechseln; Leertaste oder Enter lösen sie aus",
+    ),
+    (
+        "With the video focused (Tab to it):",
+        "Bei fokussiertem Video (per Tab):",
+    ),
+    ("Arrows", "Pfeiltasten"),
+    (
+        "Move the crop by 1 px (Shift: 10 px)",
+        "Zuschnitt um 1 px verschieben (Umschalt: 10 px)",
+    ),
+    ("Ctrl + Arrows", "Strg + Pfeiltasten"),
+    ("Resize the crop", "Zuschnittgröße ändern"),
+    ("Enter", "Enter"),
+    ("Create a centered crop", "Zentrierten Zuschnitt anlegen"),
+    ("Delete", "Entf"),
+    ("Clear the crop", "Zuschnitt löschen"),
+    // Review mode
+    ("👁 Read-only review", "👁 Nur-Lese-Prüfmodus"),
+    (
+        "Started with --read-only: annotations can't be changed or exported",
+        "Mit --read-only gestartet: Annotationen können weder geändert noch exportiert werden",
+    ),
+    // Sidecars and merging
+    (
+        "Could not read {}: {}",
+        "{} konnte nicht gelesen werden: {}",
+    ),
+    (
+        "Could not write {}: {}",
+        "{} konnte nicht geschrieben werden: {}",
+    ),
+    (
+        "written by a newer version (format {})",
+        "von einer neueren Version geschrieben (Format {})",
+    ),
+    ("🔀 Merge…", "🔀 Zusammenführen…"),
+    (
+        "Merge another annotator's annotations for the same files",
+        "Annotationen einer anderen Person für dieselben Dateien zusammenführen",
+    ),
+    ("Merge Annotations…", "Annotationen zusammenführen…"),
+    ("🔀 Merge Annotations", "🔀 Annotationen zusammenführen"),
+    ("From: {}", "Aus: {}"),
+    (
+        "No annotations for files in the input folder were found there.",
+        "Dort wurden keine Annotationen zu Dateien des Eingabeordners gefunden.",
+    ),
+    (
+        "{} added, {} identical, {} conflicting",
+        "{} neu, {} identisch, {} im Konflikt",
+    ),
+    ("Mine: {}", "Meine: {}"),
+    ("Theirs: {}", "Deren: {}"),
+    ("Keep mine", "Meine behalten"),
+    ("Take theirs", "Deren übernehmen"),
+    ("Keep both", "Beide behalten"),
+    ("Apply Merge", "Zusammenführen"),
+    (
+        "Writes the merged .vdtc.json sidecars into the input folder",
+        "Schreibt die zusammengeführten .vdtc.json-Dateien in den Eingabeordner",
+    ),
+    ("crop", "Zuschnitt"),
+    ("no crop", "kein Zuschnitt"),
+    // Background analysis
+    ("Analyzing files: {} / {}", "Dateien werden analysiert: {} / {}"),
+    ("Waiting for analysis", "Wartet auf Analyse"),
+    ("Analyzing: {}", "Analysiere: {}"),
+    ("FFmpeg could not analyze {}", "FFmpeg konnte {} nicht analysieren"),
+    ("keyframe index", "Keyframe-Index"),
+    ("thumbnail", "Vorschaubild"),
+    ("scene detection", "Szenenerkennung"),
+    ("waveform", "Wellenform"),
+    ("sharpness", "Schärfe"),
+    ("content hash", "Inhalts-Hash"),
+    ("Scene cuts: {}", "Szenenschnitte: {}"),
+    ("Audio peak: {} %", "Tonspitze: {} %"),
+    ("No audio track", "Keine Tonspur"),
+    ("Sharpness: {}", "Schärfe: {}"),
+    ("Content hash: {}", "Inhalts-Hash: {}"),
+    // Crash recovery
+    ("⚠ Unsaved Annotations", "⚠ Ungespeicherte Annotationen"),
+    (
+        "The last session ended without saving {} ranges of {} (autosaved {}).",
+        "Die letzte Sitzung endete, ohne {} Bereiche von {} zu speichern (automatisch gesichert {}).",
+    ),
+    (
+        "Start without --read-only to restore them.",
+        "Ohne --read-only starten, um sie wiederherzustellen.",
+    ),
+    ("Restore", "Wiederherstellen"),
+    (
+        "Write them to the file's sidecar and open the file",
+        "In die .vdtc.json-Datei der Datei schreiben und die Datei öffnen",
+    ),
+    ("Discard", "Verwerfen"),
+    // Project files
+    ("Project", "Projekt"),
+    ("Save Project", "Projekt speichern"),
+    ("Open Project…", "Projekt öffnen…"),
+    ("🗂 Save Project", "🗂 Projekt speichern"),
+    ("📂 Open Project…", "📂 Projekt öffnen…"),
+    ("📂 Open Project", "📂 Projekt öffnen"),
+    (
+        "Save the folders and every file's annotations to one project file",
+        "Die Ordner und die Annotationen aller Dateien in einer Projektdatei speichern",
+    ),
+    ("Input folder {} not found", "Eingabeordner {} nicht gefunden"),
+    ("{} is not in the input folder", "{} liegt nicht im Eingabeordner"),
+    (
+        "These files have other annotations than the project, which will be replaced:",
+        "Diese Dateien haben andere Annotationen als das Projekt, sie werden ersetzt:",
+    ),
+    (
+        "These annotated files of the project are not in the input folder:",
+        "Diese annotierten Dateien des Projekts liegen nicht im Eingabeordner:",
+    ),
+    ("Restore Project Annotations", "Projektannotationen wiederherstellen"),
+    (
+        "Writes the project's annotations into the .vdtc.json sidecars",
+        "Schreibt die Annotationen des Projekts in die .vdtc.json-Dateien",
+    ),
+    // Update check
+    ("ℹ About", "ℹ Über"),
+    ("⚠ Update Available", "⚠ Update verfügbar"),
+    (
+        "Ask GitHub for the latest release; nothing is downloaded",
+        "GitHub nach der neuesten Version fragen; es wird nichts heruntergeladen",
+    ),
+    ("Release notes and downloads", "Versionshinweise und Downloads"),
+    ("Version {} is the latest release.", "Version {} ist die neueste Version."),
+    (
+        "⚠ Version {} is out, this build is {}. Please update.",
+        "⚠ Version {} ist erschienen, dieser Build ist {}. Bitte aktualisieren.",
+    ),
+    ("Unrecognized version {}", "Unbekannte Version {}"),
+    ("Could not run curl: {}", "curl konnte nicht gestartet werden: {}"),
+    ("Could not reach GitHub: {}", "GitHub ist nicht erreichbar: {}"),
+    ("Unexpected answer from GitHub: {}", "Unerwartete Antwort von GitHub: {}"),
+    // Layouts
+    ("Annotator", "Bearbeiter"),
+    ("Engineer", "Ingenieur"),
+    (
+        "Annotator shows only ranges, crops and notes; Engineer adds the export, encoder and dataset controls",
+        "Bearbeiter zeigt nur Bereiche, Zuschnitte und Notizen; Ingenieur ergänzt die Export-, Encoder- und Datensatz-Einstellungen",
+    ),
+    // Author stamps
+    ("Annotator:", "Bearbeiter:"),
+    ("your name", "Ihr Name"),
+    (
+        "Recorded on every range you change",
+        "Wird bei jeder Bereichsänderung vermerkt",
+    ),
+    ("unknown", "unbekannt"),
+    // QA sampling
+    ("QA Sample Export", "QA-Stichprobe exportieren"),
+    ("🧪 QA Sample Export", "🧪 QA-Stichprobe exportieren"),
+    ("QA sample:", "QA-Stichprobe:"),
+    (
+        "Randomly pick this share of all annotated ranges in the folder and write a review list with contact sheets",
+        "Zufällig diesen Anteil aller annotierten Bereiche im Ordner wählen und eine Prüfliste mit Kontaktbögen schreiben",
+    ),
+    ("Writing contact sheets...", "Kontaktbögen werden geschrieben..."),
+    ("Choose an output folder first.", "Zuerst einen Ausgabeordner wählen."),
+    ("No annotated ranges to sample.", "Keine annotierten Bereiche für eine Stichprobe."),
+    (
+        "Sampled {} of {} ranges into {}",
+        "{} von {} Bereichen wurden nach {} übernommen",
+    ),
+    // Review reels
+    ("Export Reel of This File", "Rolle dieser Datei exportieren"),
+    ("Export Reel of All Files", "Rolle aller Dateien exportieren"),
+    ("Reel:", "Rolle:"),
+    ("Black frame between ranges", "Schwarzbild zwischen Bereichen"),
+    ("🎞 This File", "🎞 Diese Datei"),
+    ("🎞 All Files", "🎞 Alle Dateien"),
+    (
+        "Cut all ranges of this file into one clip in the output folder, e.g. for a review session",
+        "Alle Bereiche dieser Datei zu einem Clip im Ausgabeordner zusammenschneiden, z. B. für eine Sichtung",
+    ),
+    (
+        "Cut the ranges of every annotated file in the folder into one clip in the output folder, e.g. for a single-file eval set",
+        "Die Bereiche aller annotierten Dateien im Ordner zu einem Clip im Ausgabeordner zusammenschneiden, z. B. für ein Eval-Set in einer Datei",
+    ),
+    ("Writing reel...", "Rolle wird geschrieben..."),
+    ("No ranges to put in a reel.", "Keine Bereiche für eine Rolle."),
+    ("the separator frame", "das Trennbild"),
+    ("range {} of {}", "Bereich {} von {}"),
+    (
+        "FFmpeg failed on {} with exit code: {}",
+        "FFmpeg ist bei {} mit Exit-Code {} fehlgeschlagen",
+    ),
+    (
+        "Wrote a reel of {} ranges to {}",
+        "Rolle mit {} Bereichen nach {} geschrieben",
+    ),
+    // Dataset splits
+    ("Split:", "Aufteilung:"),
+    ("Train", "Training"),
+    ("Validation", "Validierung"),
+    ("Test", "Test"),
+    ("Apply to File", "Für Datei übernehmen"),
+    (
+        "Give every range of this file the same split",
+        "Allen Bereichen dieser Datei dieselbe Aufteilung geben",
+    ),
+    ("Ratios:", "Anteile:"),
+    ("Randomize Remaining Splits", "Restliche zufällig aufteilen"),
+    // Tags and class balance
+    ("Tags:", "Tags:"),
+    ("add tag, Enter", "Tag hinzufügen, Enter"),
+    ("Remove tag {}", "Tag {} entfernen"),
+    ("Show Class Balance", "Klassenverteilung anzeigen"),
+    ("📊 Balance", "📊 Verteilung"),
+    (
+        "Clip count and duration per tag and split",
+        "Anzahl und Dauer der Clips je Tag und Aufteilung",
+    ),
+    ("📊 Class Balance", "📊 Klassenverteilung"),
+    (
+        "Ranges and total duration per tag and split, across the open file and the sidecars in the input folder.",
+        "Bereiche und Gesamtdauer je Tag und Aufteilung, über die geöffnete Datei und die Sidecar-Dateien im Eingabeordner.",
+    ),
+    ("Tag", "Tag"),
+    ("No split", "Ohne Aufteilung"),
+    ("(untagged)", "(ohne Tag)"),
+    ("{} · {}s", "{} · {} s"),
+    (
+        "This tag's share of the split is far from the split's overall share",
+        "Der Anteil dieses Tags an der Aufteilung weicht stark vom Gesamtanteil der Aufteilung ab",
+    ),
+    ("Total", "Gesamt"),
+    ("🔄 Refresh", "🔄 Aktualisieren"),
+    // Dataset rebuild
+    ("Rebuild Dataset", "Datensatz neu erzeugen"),
+    ("📦 Export Marked ({})", "📦 Markierte exportieren ({})"),
+    ("Export Marked Files", "Markierte Dateien exportieren"),
+    ("Retry Failed Exports", "Fehlgeschlagene Exporte wiederholen"),
+    ("🔁 Retry Failed ({})", "🔁 Fehlgeschlagene wiederholen ({})"),
+    (
+        "Export the files whose last batch export failed again; only their failed ranges are encoded",
+        "Dateien, deren letzter Stapelexport fehlschlug, erneut exportieren; nur ihre fehlgeschlagenen Bereiche werden kodiert",
+    ),
+    ("range {}: {}", "Bereich {}: {}"),
+    (
+        "Export the files ticked in the file list one after the other, each with its saved ranges",
+        "Die in der Dateiliste angehakten Dateien nacheinander exportieren, jede mit ihren gespeicherten Bereichen",
+    ),
+    ("Mark for export", "Zum Export markieren"),
+    ("queued", "wartet"),
+    ("{} clips", "{} Clips"),
+    ("failed", "fehlgeschlagen"),
+    ("No saved ranges", "Keine gespeicherten Bereiche"),
+    ("♻ Rebuild Dataset", "♻ Datensatz neu erzeugen"),
+    (
+        "Delete the files listed in the manifest and export every annotated file of the folder again",
+        "Die im Manifest aufgeführten Dateien löschen und alle annotierten Dateien des Ordners neu exportieren",
+    ),
+    (
+        "Delete every file listed in the manifest in {} and export all annotated files again?",
+        "Alle im Manifest in {} aufgeführten Dateien löschen und alle annotierten Dateien neu exportieren?",
+    ),
+    ("Could not open {}", "{} konnte nicht geöffnet werden"),
+    ("Could not remove {}: {}", "{} konnte nicht entfernt werden: {}"),
+    (
+        "The manifest lists {}, which is not inside the output folder",
+        "Das Manifest nennt {}, das nicht im Ausgabeordner liegt",
+    ),
+    // Output audit
+    ("Audit Output Folder", "Ausgabeordner prüfen"),
+    ("🩺 Audit Output", "🩺 Ausgabe prüfen"),
+    (
+        "Check the output folder for orphan, missing and stale files and outdated captions",
+        "Den Ausgabeordner auf verwaiste, fehlende und veraltete Dateien sowie veraltete Beschriftungen prüfen",
+    ),
+    ("🩺 Output Audit", "🩺 Ausgabeprüfung"),
+    ("Orphan: {} has no annotation", "Verwaist: {} hat keine Annotation"),
+    (
+        "Missing: range {} of {} was not exported",
+        "Fehlt: Bereich {} von {} wurde nicht exportiert",
+    ),
+    (
+        "Caption: {} no longer matches the note",
+        "Beschriftung: {} passt nicht mehr zur Notiz",
+    ),
+    ("Stale: {} is now exported as {}", "Veraltet: {} wird jetzt als {} exportiert"),
+    (
+        "✅ The output folder matches the annotations.",
+        "✅ Der Ausgabeordner entspricht den Annotationen.",
+    ),
+    ("{} issue(s) found:", "{} Problem(e) gefunden:"),
+    ("🧹 Clean Up ({})", "🧹 Aufräumen ({})"),
+    (
+        "Delete orphan and stale outputs and rewrite outdated captions. Missing outputs need an export.",
+        "Verwaiste und veraltete Ausgaben löschen und veraltete Beschriftungen neu schreiben. Fehlende Ausgaben erfordern einen Export.",
+    ),
+    ("Fixed {} issue(s).", "{} Problem(e) behoben."),
+    // Stale outputs
+    (
+        "⚠ {} earlier export(s) of this file no longer match a range: {}",
+        "⚠ {} frühere(r) Export(e) dieser Datei passen zu keinem Bereich mehr: {}",
+    ),
+    ("🗑 Move to Trash", "🗑 In den Papierkorb"),
+    (
+        "Move them to .trash in the output folder",
+        "In .trash im Ausgabeordner verschieben",
+    ),
+    ("Delete Files", "Dateien löschen"),
+    ("Keep", "Behalten"),
+    // Cache
+    ("🗄 Cache", "🗄 Cache"),
+    ("Folder: {}", "Ordner: {}"),
+    ("Size: {} MB", "Größe: {} MB"),
+    ("Limit:", "Obergrenze:"),
+    ("Choose Folder…", "Ordner wählen…"),
+    ("🧹 Clear Cache", "🧹 Cache leeren"),
+    ("Cleared {} MB of cached data", "{} MB zwischengespeicherter Daten gelöscht"),
+    // Keyframe index
+    ("Failed to start ffprobe: {}", "ffprobe konnte nicht gestartet werden: {}"),
+    ("ffprobe could not index {}", "ffprobe konnte {} nicht indizieren"),
+    // Large stills
+    (
+        "Preview: downscaled from {} × {} (crop and export use the original)",
+        "Vorschau: verkleinert von {} × {} (Zuschnitt und Export nutzen das Original)",
+    ),
+    // Preview quality
+    ("Every frame", "Jedes Bild"),
+    ("1 in {} frames", "Jedes {}. Bild"),
+    (
+        "Playback preview quality. Showing fewer frames helps on slow machines; paused frames and exports are unaffected.",
+        "Vorschauqualität bei der Wiedergabe. Weniger Bilder helfen auf langsamen Rechnern; Standbilder und Exporte sind nicht betroffen.",
+    ),
+    (
+        "Reduced preview: showing 1 in {} frames (export unaffected)",
+        "Reduzierte Vorschau: jedes {}. Bild (Export nicht betroffen)",
+    ),
+    // Missing files
+    (
+        "These source files are missing; relink them before rebuilding:\n{}",
+        "Diese Quelldateien fehlen; verknüpfe sie vor dem Neuaufbau neu:\n{}",
+    ),
+    ("⚠ File Not Found", "⚠ Datei nicht gefunden"),
+    ("{} was moved or deleted.", "{} wurde verschoben oder gelöscht."),
+    (
+        "Locate it to keep working with its annotations.",
+        "Suche sie, um mit ihren Annotationen weiterzuarbeiten.",
+    ),
+    ("📂 Locate…", "📂 Suchen…"),
+    ("Cancel", "Abbrechen"),
+    ("Could not move {} to {}", "{} konnte nicht nach {} verschoben werden"),
+    ("File not found. Click to relink.", "Datei nicht gefunden. Klicken zum Neuverknüpfen."),
+    // Session statistics
+    ("Show Session Statistics", "Sitzungsstatistik anzeigen"),
+    ("⏱ Session", "⏱ Sitzung"),
+    (
+        "Files, ranges and annotated time since the app was started",
+        "Dateien, Bereiche und annotierte Zeit seit dem Programmstart",
+    ),
+    ("Session time", "Sitzungsdauer"),
+    ("Files reviewed", "Gesichtete Dateien"),
+    ("Ranges created", "Angelegte Bereiche"),
+    ("Ranges per hour", "Bereiche pro Stunde"),
+    ("Annotated duration", "Annotierte Dauer"),
+    ("📋 Copy Summary", "📋 Zusammenfassung kopieren"),
+    ("Record usage metrics", "Nutzungsstatistik aufzeichnen"),
+    (
+        "Counts exports, export problems and the commands used in {} in the input folder. Nothing is sent anywhere.",
+        "Zählt Exporte, Exportprobleme und verwendete Befehle in {} im Eingabeordner. Es wird nichts versendet.",
+    ),
+    // Export scheduling
+    ("Start at", "Starten um"),
+    (
+        "Hold exports until this time, e.g. to encode overnight",
+        "Exporte bis zu dieser Uhrzeit zurückhalten, z. B. um nachts zu kodieren",
+    ),
+    ("Parallel:", "Parallel:"),
+    (
+        "Files a batch export or rebuild encodes at once, one FFmpeg each. More use more cores on short clips.",
+        "Dateien, die ein Stapelexport oder Neuaufbau gleichzeitig kodiert, mit je einem FFmpeg. Mehr nutzen bei kurzen Clips mehr Kerne.",
+    ),
+    ("🕙 Export scheduled for {}", "🕙 Export geplant für {}"),
+    ("Start Now", "Jetzt starten"),
+    (
+        "Paused; the current clip finishes first",
+        "Pausiert; der aktuelle Clip wird noch fertiggestellt",
+    ),
+    ("▶ Resume Export", "▶ Export fortsetzen"),
+    ("⏸ Pause Export", "⏸ Export pausieren"),
+    // Export profile
+    ("Container:", "Container:"),
+    ("Output:", "Ausgabe:"),
+    ("PNG frames", "PNG-Einzelbilder"),
+    ("JPEG frames", "JPEG-Einzelbilder"),
+    ("Clips", "Clips"),
+    (
+        "Frame sequences put the numbered frames of each range (000000.png, …) into a folder of their own, cropped and at the export frame rate",
+        "Einzelbildfolgen legen die nummerierten Bilder jedes Bereichs (000000.png, …) in einen eigenen Ordner, zugeschnitten und mit der Export-Bildrate",
+    ),
+    ("Frame rate:", "Bildrate:"),
+    ("Keep native", "Original beibehalten"),
+    (
+        "Keep every frame of the source instead of resampling",
+        "Jedes Bild der Quelle behalten statt neu abzutasten",
+    ),
+    ("native", "original"),
+    ("Stream copy", "Stream kopieren"),
+    (
+        "Cut ranges without a crop at the native frame rate without encoding: instant and lossless, but the cuts move to the nearest keyframes",
+        "Bereiche ohne Zuschnitt mit originaler Bildrate ohne Neukodierung schneiden: sofort und verlustfrei, aber die Schnitte rücken auf die nächsten Keyframes",
+    ),
+    ("Trim edges", "Enden beschneiden"),
+    (
+        "Drop nearly black or frozen frames (fades, freeze frames) at both ends of each range, up to 3 seconds each; ✂ Trim Preview shows what goes",
+        "Fast schwarze oder eingefrorene Bilder (Blenden, Standbilder) an beiden Enden jedes Bereichs entfernen, je bis zu 3 Sekunden; ✂ Kantenschnitt-Vorschau zeigt, was wegfällt",
+    ),
+    ("Own frame rate", "Eigene Bildrate"),
+    (
+        "Export this range at another rate than the export setting ({})",
+        "Diesen Bereich mit einer anderen Bildrate als der Exporteinstellung ({}) exportieren",
+    ),
+    (
+        "WebM clips are always VP9; MP4 and MKV use the codec of the encoder settings",
+        "WebM-Clips sind immer VP9; MP4 und MKV verwenden den Codec der Encoder-Einstellungen",
+    ),
+    ("🎛 Encoder…", "🎛 Encoder…"),
+    ("Codec, quality, preset and pixel format", "Codec, Qualität, Preset und Pixelformat"),
+    // Encoder settings
+    ("🎛 Encoder Settings", "🎛 Encoder-Einstellungen"),
+    ("Codec:", "Codec:"),
+    ("{} clips are {}", "{}-Clips sind {}"),
+    ("Quality:", "Qualität:"),
+    ("Encoder default", "Standard des Encoders"),
+    ("Bitrate", "Bitrate"),
+    ("Constant quality (CRF)", "Konstante Qualität (CRF)"),
+    (
+        "Lower is better and larger; around 18 looks lossless",
+        "Niedriger ist besser und größer; um 18 wirkt verlustfrei",
+    ),
+    ("Preset:", "Preset:"),
+    (
+        "Slower presets make smaller files at the same quality; VP9 and hardware encoders get the nearest speed setting",
+        "Langsamere Presets ergeben bei gleicher Qualität kleinere Dateien; VP9 und Hardware-Encoder erhalten die nächstliegende Geschwindigkeitsstufe",
+    ),
+    ("Hardware:", "Hardware:"),
+    ("CPU", "CPU"),
+    (
+        "Encode H.264 and H.265 on the graphics card, much faster than on the CPU but larger at the same quality",
+        "H.264 und H.265 auf der Grafikkarte kodieren, viel schneller als auf der CPU, aber bei gleicher Qualität größer",
+    ),
+    (
+        "Not available with this FFmpeg, driver or graphics card",
+        "Mit diesem FFmpeg, Treiber oder dieser Grafikkarte nicht verfügbar",
+    ),
+    (
+        "Checking which hardware encoders work…",
+        "Prüfe, welche Hardware-Encoder funktionieren…",
+    ),
+    ("⚠ Not available here", "⚠ Hier nicht verfügbar"),
+    ("VP9 is encoded on the CPU", "VP9 wird auf der CPU kodiert"),
+    ("Pixel format:", "Pixelformat:"),
+    ("Encoder's choice", "Wahl des Encoders"),
+    (
+        "yuv420p plays everywhere; the others keep more color but not every player or loader takes them",
+        "yuv420p läuft überall; die anderen behalten mehr Farbe, werden aber nicht von jedem Player oder Loader gelesen",
+    ),
+    ("Reset to Defaults", "Auf Standard zurücksetzen"),
+    (
+        "Clips exported before are encoded again with new settings.",
+        "Bereits exportierte Clips werden mit neuen Einstellungen neu kodiert.",
+    ),
+    ("Web-optimized", "Web-optimiert"),
+    ("Audio:", "Ton:"),
+    ("No audio", "Kein Ton"),
+    ("Normalize loudness", "Lautheit normalisieren"),
+    ("Preview:", "Vorschau:"),
+    ("No preview", "Keine Vorschau"),
+    ("Sprite sheet", "Sprite-Sheet"),
+    (
+        "Also write a grid of frames spread over each clip, {} px per tile, for galleries and models that take sprite sheets",
+        "Zusätzlich ein Raster aus über jeden Clip verteilten Frames schreiben, {} px pro Kachel, für Galerien und Modelle, die Sprite-Sheets lesen",
+    ),
+    (
+        "Also write a small looping animation of each clip's first seconds, for dataset cards and quick browsing",
+        "Zusätzlich eine kleine Endlosanimation der ersten Sekunden jedes Clips schreiben, für Dataset-Karten und schnelles Durchsehen",
+    ),
+    (
+        "EBU R128 loudness normalization (-23 LUFS), so clips from different sources sound equally loud",
+        "Lautheitsnormalisierung nach EBU R128 (-23 LUFS), damit Clips aus verschiedenen Quellen gleich laut klingen",
+    ),
+    (
+        "Also write each clip's audio to a file with the same name, for audio-visual and lip-sync datasets",
+        "Den Ton jedes Clips zusätzlich in eine gleichnamige Datei schreiben, für audiovisuelle und Lippensynchron-Datensätze",
+    ),
+    (
+        "Put the MP4 index first and a keyframe every second, so clips stream and scrub over HTTP",
+        "MP4-Index an den Anfang und jede Sekunde ein Keyframe, damit Clips über HTTP streamen und springen können",
+    ),
+    ("No folders", "Keine Ordner"),
+    ("Folder per tag", "Ordner pro Tag"),
+    ("Folder per note", "Ordner pro Notiz"),
+    (
+        "Put each clip into a folder named after its first tag (in the project rules' tag order) or the first line of its note, inside its split folder",
+        "Jeden Clip in einen Ordner mit dem Namen seines ersten Tags (in der Tag-Reihenfolge der Projektregeln) oder der ersten Zeile seiner Notiz legen, innerhalb seines Split-Ordners",
+    ),
+    ("Existing:", "Vorhandene:"),
+    ("Overwrite", "Überschreiben"),
+    ("Skip", "Überspringen"),
+    ("Number new files", "Neue Dateien nummerieren"),
+    (
+        "What to do when an output name is taken by a file the range didn't write: overwrite it, skip the range or add _001, _002, … to the new name",
+        "Was geschieht, wenn ein Ausgabename von einer Datei belegt ist, die der Bereich nicht geschrieben hat: überschreiben, den Bereich überspringen oder _001, _002, … an den neuen Namen anhängen",
+    ),
+    ("They will be overwritten.", "Sie werden überschrieben."),
+    ("Their ranges will be skipped.", "Ihre Bereiche werden übersprungen."),
+    (
+        "The new outputs get a number (_001, …) instead.",
+        "Die neuen Ausgaben bekommen stattdessen eine Nummer (_001, …).",
+    ),
+    (
+        "{} output(s) already exist and were not written by these ranges:\n{}\n\n{} Export anyway?",
+        "{} Ausgabe(n) existieren bereits und wurden nicht von diesen Bereichen geschrieben:\n{}\n\n{} Trotzdem exportieren?",
+    ),
+    ("\n… and {} more", "\n… und {} weitere"),
+    ("Caption:", "Beschriftung:"),
+    (
+        "Caption file text. Tokens: {note}, {tags}, {source} (file name), {range}, {split}. Use Rename Outputs to apply a change to an existing export.",
+        "Text der Beschriftungsdatei. Platzhalter: {note}, {tags}, {source} (Dateiname), {range}, {split}. Mit „Ausgaben umbenennen“ wird eine Änderung auf einen bestehenden Export angewendet.",
+    ),
+    ("Names:", "Namen:"),
+    (
+        "Output file names. Tokens: {stem}, {range}, {start_frame}, {end_frame}, {tag}, {date} (last change). Empty for the default. Use Rename Outputs to apply a change to an existing export.",
+        "Namen der Ausgabedateien. Platzhalter: {stem}, {range}, {start_frame}, {end_frame}, {tag}, {date} (letzte Änderung). Leer für die Voreinstellung. Mit „Ausgaben umbenennen“ wird eine Änderung auf einen bestehenden Export angewendet.",
+    ),
+    (
+        "Without {stem}, outputs of different files can get the same name and overwrite each other",
+        "Ohne {stem} können Ausgaben verschiedener Dateien denselben Namen bekommen und sich überschreiben",
+    ),
+    // Rename outputs
+    ("Rename / Re-caption Outputs…", "Ausgaben umbenennen / neu beschriften…"),
+    ("✏ Rename Outputs", "✏ Ausgaben umbenennen"),
+    (
+        "Apply the current output names and caption template to the exported files without encoding them again",
+        "Aktuelle Ausgabenamen und Beschriftungsvorlage auf die exportierten Dateien anwenden, ohne sie neu zu kodieren",
+    ),
+    ("✏ Rename / Re-caption Outputs", "✏ Ausgaben umbenennen / neu beschriften"),
+    (
+        "✅ Output names and captions are up to date.",
+        "✅ Ausgabenamen und Beschriftungen sind aktuell.",
+    ),
+    ("{} output(s) to change:", "{} Ausgabe(n) zu ändern:"),
+    ("Caption of {}", "Beschriftung von {}"),
+    ("{} output(s) need an export:", "{} Ausgabe(n) brauchen einen Export:"),
+    ("✏ Apply ({})", "✏ Anwenden ({})"),
+    (
+        "Rename the outputs and rewrite their captions. Nothing is encoded again.",
+        "Ausgaben umbenennen und ihre Beschriftungen neu schreiben. Nichts wird neu kodiert.",
+    ),
+    (
+        "{}: the range changed since the export",
+        "{}: der Bereich hat sich seit dem Export geändert",
+    ),
+    ("{}: the container changed", "{}: der Container hat sich geändert"),
+    ("{}: the file is missing", "{}: die Datei fehlt"),
+    ("{}: {} is taken", "{}: {} ist schon vergeben"),
+    ("{}: {} already exists", "{}: {} existiert bereits"),
+    ("Could not rename {}: {}", "{} konnte nicht umbenannt werden: {}"),
+    ("Changed {} output(s).", "{} Ausgabe(n) geändert."),
+    // Project rules
+    ("Project Rules…", "Projektregeln…"),
+    ("📜 Project Rules", "📜 Projektregeln"),
+    (
+        "Trigger words, banned tags and tag order of the input folder, enforced at export",
+        "Triggerwörter, verbotene Tags und Tag-Reihenfolge des Eingabeordners, beim Export durchgesetzt",
+    ),
+    ("Trigger words:", "Triggerwörter:"),
+    (
+        "Put at the front of every caption that doesn't contain them",
+        "Werden jeder Beschriftung vorangestellt, die sie nicht enthält",
+    ),
+    ("Banned tags:", "Verbotene Tags:"),
+    ("Never exported, whoever added them", "Werden nie exportiert, egal wer sie vergeben hat"),
+    ("Tag order:", "Tag-Reihenfolge:"),
+    ("Tags written first, in this order", "Tags, die zuerst geschrieben werden, in dieser Reihenfolge"),
+    ("Stored in {} in the input folder.", "Gespeichert in {} im Eingabeordner."),
+    ("💾 Save Rules", "💾 Regeln speichern"),
+    ("Rules saved.", "Regeln gespeichert."),
+    (
+        "Banned by the project rules; not exported",
+        "Durch die Projektregeln verboten; wird nicht exportiert",
+    ),
+    // Material registry
+    (
+        "\n⚠ Already exported as {} in {}",
+        "\n⚠ Bereits exportiert als {} in {}",
+    ),
+    ("\n⚠ Already exported as {}", "\n⚠ Bereits exportiert als {}"),
+    ("Linked Projects…", "Verknüpfte Projekte…"),
+    ("🔗 Linked Projects", "🔗 Verknüpfte Projekte"),
+    (
+        "Other output folders whose exported material new ranges are checked against",
+        "Andere Ausgabeordner, mit deren exportiertem Material neue Bereiche abgeglichen werden",
+    ),
+    (
+        "New ranges are checked against the material exported into these output folders, e.g. earlier dataset versions.",
+        "Neue Bereiche werden mit dem Material abgeglichen, das in diese Ausgabeordner exportiert wurde, z. B. frühere Datensatzversionen.",
+    ),
+    ("No linked projects.", "Keine verknüpften Projekte."),
+    ("➕ Link Output Folder…", "➕ Ausgabeordner verknüpfen…"),
+    (
+        "{} has no {} yet; it counts once something is exported there.",
+        "{} hat noch keine {}; er zählt, sobald dort etwas exportiert wird.",
+    ),
+    ("Stored in {} in the output folder.", "Gespeichert in {} im Ausgabeordner."),
+    // Dataset versions
+    ("Dataset Versions…", "Datensatzversionen…"),
+    ("Package Dataset…", "Datensatz verpacken…"),
+    ("🏷 Dataset Versions", "🏷 Datensatzversionen"),
+    ("🏷 Versions", "🏷 Versionen"),
+    (
+        "Snapshot the dataset under a version label and compare versions",
+        "Den Datensatz unter einer Versionsbezeichnung festhalten und Versionen vergleichen",
+    ),
+    ("Label:", "Bezeichnung:"),
+    ("📸 Snapshot", "📸 Festhalten"),
+    (
+        "Freeze the annotations, the manifest and the captions under this label",
+        "Annotationen, Manifest und Beschriftungen unter dieser Bezeichnung festhalten",
+    ),
+    ("Current", "Aktuell"),
+    ("Compare", "Vergleichen"),
+    (
+        "{} → {}: {} added, {} removed, {} changed",
+        "{} → {}: {} hinzugefügt, {} entfernt, {} geändert",
+    ),
+    ("times", "Zeiten"),
+    ("split", "Split"),
+    ("tags", "Tags"),
+    ("note", "Notiz"),
+    ("caption", "Beschriftung"),
+    ("output", "Ausgabe"),
+    ("{} is not a valid version label", "{} ist keine gültige Versionsbezeichnung"),
+    ("Version {} exists already", "Version {} existiert bereits"),
+    ("Open an input folder first", "Zuerst einen Eingabeordner öffnen"),
+    ("Saved version {}.", "Version {} gespeichert."),
+    ("📦 Export Difference…", "📦 Unterschied exportieren…"),
+    (
+        "Copy the added and changed clips into an empty folder, with their manifest and a list of the removed files",
+        "Die hinzugefügten und geänderten Clips in einen leeren Ordner kopieren, mit ihrem Manifest und einer Liste der entfernten Dateien",
+    ),
+    ("Empty folder for the difference", "Leerer Ordner für den Unterschied"),
+    ("Wait for the running export to finish", "Auf das Ende des laufenden Exports warten"),
+    ("Copying the difference {} → {}…", "Unterschied {} → {} wird kopiert…"),
+    ("Copied {} clip(s) to {}.", "{} Clip(s) nach {} kopiert."),
+    ("{} is not empty", "{} ist nicht leer"),
+    ("{} R{}: not exported", "{} B{}: nicht exportiert"),
+    (
+        "{} R{}: exported differently since version {}",
+        "{} B{}: seit Version {} anders exportiert",
+    ),
+    // Packaging
+    ("📦 Package", "📦 Verpacken"),
+    (
+        "Zip or tar the exported files with the manifest, optionally in volumes with checksums, for upload",
+        "Die exportierten Dateien mit dem Manifest als Zip oder Tar verpacken, wahlweise in Teilen mit Prüfsummen, zum Hochladen",
+    ),
+    ("📦 Package Dataset", "📦 Datensatz verpacken"),
+    ("Format:", "Format:"),
+    ("Split into volumes of", "In Teile aufteilen zu je"),
+    (
+        "Each volume is a complete archive with the manifest of its own clips; a clip's files stay together",
+        "Jeder Teil ist ein vollständiges Archiv mit dem Manifest seiner eigenen Clips; die Dateien eines Clips bleiben zusammen",
+    ),
+    ("Write checksums", "Prüfsummen schreiben"),
+    (
+        "List the SHA-256 of every archive in {} next to them, for sha256sum -c",
+        "Die SHA-256 jedes Archivs in {} daneben auflisten, für sha256sum -c",
+    ),
+    ("Package…", "Verpacken…"),
+    (
+        "Packs the files the manifest lists, with manifest.json at the root. Clips are stored as they are.",
+        "Verpackt die im Manifest aufgeführten Dateien, mit manifest.json im Stammverzeichnis. Clips werden unverändert gespeichert.",
+    ),
+    (
+        "Packaged the dataset into {} archive(s) in {}.",
+        "Datensatz in {} Archiv(e) in {} verpackt.",
+    ),
+    ("The manifest lists no clips to package", "Das Manifest enthält keine Clips zum Verpacken"),
+    (
+        "{} is too large for a zip archive; use volumes or tar",
+        "{} ist zu groß für ein Zip-Archiv; Teile oder Tar verwenden",
+    ),
+    ("The archive", "Das Archiv"),
+    ("The file changed while it was packaged", "Die Datei hat sich beim Verpacken geändert"),
+    // Export dry run
+    ("Preview Export Commands…", "Exportbefehle anzeigen…"),
+    ("🔍 Preview Export Commands", "🔍 Exportbefehle anzeigen"),
+    (
+        "List the FFmpeg commands the export would run for the open file, without running them",
+        "Die FFmpeg-Befehle auflisten, die der Export für die offene Datei ausführen würde, ohne sie auszuführen",
+    ),
+    ("🔍 Export Commands", "🔍 Exportbefehle"),
+    ("Working out the commands...", "Befehle werden ermittelt..."),
+    (
+        "What exporting the open file would run; nothing has been run.",
+        "Was der Export der offenen Datei ausführen würde; nichts wurde ausgeführt.",
+    ),
+    ("# range {}: output name taken, skipped", "# Bereich {}: Ausgabename belegt, übersprungen"),
+    ("# range {}: {} is up to date", "# Bereich {}: {} ist aktuell"),
+    ("# range {}: {}", "# Bereich {}: {}"),
+    // Checksums
+    ("Verify Checksums…", "Prüfsummen prüfen…"),
+    ("🔐 Verify", "🔐 Prüfen"),
+    (
+        "Check the output folder or a copy of it against its checksums.txt, e.g. after a transfer",
+        "Den Ausgabeordner oder eine Kopie davon mit seiner checksums.txt abgleichen, z. B. nach einer Übertragung",
+    ),
+    ("Dataset folder to verify", "Zu prüfender Datensatzordner"),
+    (
+        "All {} files in {} match their checksums.",
+        "Alle {} Dateien in {} stimmen mit ihren Prüfsummen überein.",
+    ),
+    ("{} file(s) failed verification:", "{} Datei(en) haben die Prüfung nicht bestanden:"),
+    ("Corrupt: {}", "Beschädigt: {}"),
+    ("Missing: {}", "Fehlt: {}"),
+    ("No checksum: {}", "Keine Prüfsumme: {}"),
+    ("Verification cancelled", "Prüfung abgebrochen"),
+    // Upload
+    ("Upload to Object Storage…", "In Objektspeicher hochladen…"),
+    ("☁ Upload", "☁ Hochladen"),
+    (
+        "Push the exported files and the manifest to an S3-compatible bucket",
+        "Die exportierten Dateien und das Manifest in einen S3-kompatiblen Bucket hochladen",
+    ),
+    ("☁ Upload to Object Storage", "☁ In Objektspeicher hochladen"),
+    ("Endpoint:", "Endpunkt:"),
+    ("Region:", "Region:"),
+    ("Bucket:", "Bucket:"),
+    ("Prefix:", "Präfix:"),
+    ("Access key:", "Zugriffsschlüssel:"),
+    ("Secret key:", "Geheimer Schlüssel:"),
+    (
+        "Kept for this session only; set AWS_SECRET_ACCESS_KEY to have it filled in",
+        "Nur für diese Sitzung gespeichert; AWS_SECRET_ACCESS_KEY setzen, um ihn vorauszufüllen",
+    ),
+    ("Upload after every export", "Nach jedem Export hochladen"),
+    ("Upload Now", "Jetzt hochladen"),
+    (
+        "Sends the files the manifest lists, then the manifest and checksums, to {}. Files already there as they are are skipped.",
+        "Sendet die im Manifest aufgeführten Dateien, danach Manifest und Prüfsummen, nach {}. Bereits unverändert vorhandene Dateien werden übersprungen.",
+    ),
+    (
+        "Uploaded {} file(s) to {}, {} unchanged.",
+        "{} Datei(en) nach {} hochgeladen, {} unverändert.",
+    ),
+    (
+        "{} file(s) failed; the manifest was not uploaded:",
+        "{} Datei(en) fehlgeschlagen; das Manifest wurde nicht hochgeladen:",
+    ),
+    (
+        "The upload needs an endpoint, a bucket and an access key",
+        "Das Hochladen braucht einen Endpunkt, einen Bucket und einen Zugriffsschlüssel",
+    ),
+    ("Upload cancelled", "Hochladen abgebrochen"),
+    // Remote sources
+    ("Add Remote Source…", "Entfernte Quelle hinzufügen…"),
+    ("🌐 Add URL", "🌐 URL hinzufügen"),
+    (
+        "Add a video on an HTTP(S) server, streamed instead of downloaded",
+        "Ein Video auf einem HTTP(S)-Server hinzufügen, das gestreamt statt heruntergeladen wird",
+    ),
+    ("🌐 Add Remote Source", "🌐 Entfernte Quelle hinzufügen"),
+    ("URL:", "URL:"),
+    ("Add", "Hinzufügen"),
+    (
+        "Adds a link file to the input folder. The video is streamed for the preview and the export, and the parts that were watched are kept in the cache.",
+        "Legt eine Verknüpfung im Eingabeordner an. Das Video wird für Vorschau und Export gestreamt, die angesehenen Teile bleiben im Cache.",
+    ),
+    ("{} holds no HTTP(S) URL", "{} enthält keine HTTP(S)-URL"),
+    ("Not an HTTP(S) URL: {}", "Keine HTTP(S)-URL: {}"),
+    ("{} already exists", "{} existiert bereits"),
+    (
+        "Could not start the stream server: {}",
+        "Der Streaming-Server konnte nicht gestartet werden: {}",
+    ),
+    ("Could not create {}: {}", "{} konnte nicht angelegt werden: {}"),
+    (
+        "{} answered with {} bytes instead of {}; the server may not support range requests",
+        "{} lieferte {} statt {} Bytes; der Server unterstützt womöglich keine Bereichsanfragen",
+    ),
+    ("Could not reach {}: {}", "{} ist nicht erreichbar: {}"),
+    ("{} did not say how large it is", "{} hat seine Größe nicht angegeben"),
+    // Accessible labels
+    ("Remove range", "Bereich entfernen"),
+    ("Video preview", "Videovorschau"),
+    (
+        "Video preview, crop {} × {} px at {}, {}",
+        "Videovorschau, Zuschnitt {} × {} px bei {}, {}",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translations_keep_placeholders() {
+        let mut seen = std::collections::HashSet::new();
+        for (en, de) in GERMAN {
+            assert!(seen.insert(en), "duplicate entry {:?}", en);
+            assert_eq!(
+                en.matches("{}").count(),
+                de.matches("{}").count(),
+                "{:?}",
+                en
+            );
+        }
+    }
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        assert_eq!(
+            trf("range {}: {} frame(s)", &[&2, &"+1"]),
+            "range 2: +1 frame(s)"
+        );
+        assert_eq!(tr("not in any table"), "not in any table");
+    }
+}
@@ -0,0 +1,213 @@
+// Single instance. Two copies of the app on the same data folder overwrite each other's sidecars
+// and autosave journal and evict each other's cache files, so a second launch hands its folders,
+// file and project to the running instance and exits instead. The running instance listens on a
+// local port that it writes to `instance.json` in the data folder, together with a random token
+// every handoff has to repeat. Portable copies have data folders of their own and don't meet.
+use crate::{cache, cli};
+use eframe::egui;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const FILE_NAME: &str = "instance.json";
+/// How long a launch waits for the running instance to take its arguments
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+/// What a second launch asks the running instance to open.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Handoff {
+    token: String,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    file: Option<String>,
+    project: Option<PathBuf>,
+}
+
+/// The running instance's end: arguments handed over by later launches.
+pub struct Instance {
+    endpoint_file: PathBuf,
+    token: String,
+    requests: Arc<Mutex<Vec<cli::Args>>>,
+    /// Woken when a handoff arrives, once the UI is up
+    context: Arc<Mutex<Option<egui::Context>>>,
+}
+
+pub enum Start {
+    /// Another instance took the arguments; this one should exit
+    Forwarded,
+    Primary(Instance),
+    /// Neither worked, e.g. no local ports; the app runs unguarded
+    Unguarded(String),
+}
+
+/// The endpoint file in the app's data folder (next to the cache, so it follows portable mode).
+pub fn path() -> PathBuf {
+    cache::root().join(FILE_NAME)
+}
+
+/// Hands `args` to an instance already running on `endpoint_file`, or becomes that instance.
+pub fn start(endpoint_file: &Path, args: &cli::Args) -> Start {
+    if forward(endpoint_file, args).is_ok() {
+        return Start::Forwarded;
+    }
+    match listen(endpoint_file) {
+        Ok(instance) => Start::Primary(instance),
+        Err(e) => Start::Unguarded(e),
+    }
+}
+
+/// Relative paths mean nothing to an instance started in another working directory.
+fn absolute(path: &Option<PathBuf>) -> Option<PathBuf> {
+    path.as_ref()
+        .map(|p| std::path::absolute(p).unwrap_or_else(|_| p.clone()))
+}
+
+fn forward(endpoint_file: &Path, args: &cli::Args) -> Result<(), String> {
+    let text = std::fs::read_to_string(endpoint_file).map_err(|e| e.to_string())?;
+    let endpoint: Endpoint = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, endpoint.port));
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let handoff = Handoff {
+        token: endpoint.token,
+        input: absolute(&args.input),
+        output: absolute(&args.output),
+        file: args.file.clone(),
+        project: absolute(&args.project),
+    };
+    let line = serde_json::to_string(&handoff).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line).map_err(|e| e.to_string())?;
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| e.to_string())?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        other => Err(format!("Unexpected reply {:?}", other)),
+    }
+}
+
+fn listen(endpoint_file: &Path) -> Result<Instance, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let token = format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..));
+    let endpoint = Endpoint {
+        port,
+        token: token.clone(),
+    };
+    let json = serde_json::to_string(&endpoint).map_err(|e| e.to_string())?;
+    endpoint_file
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| write_private(endpoint_file, &json))
+        .map_err(|e| format!("Could not write {}: {}", endpoint_file.display(), e))?;
+
+    let instance = Instance {
+        endpoint_file: endpoint_file.to_path_buf(),
+        token: token.clone(),
+        requests: Arc::new(Mutex::new(Vec::new())),
+        context: Arc::new(Mutex::new(None)),
+    };
+    let (requests, context) = (instance.requests.clone(), instance.context.clone());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            receive(stream, &token, |args| {
+                requests.lock().unwrap().push(args);
+                if let Some(ctx) = context.lock().unwrap().as_ref() {
+                    ctx.request_repaint();
+                }
+            });
+        }
+    });
+    Ok(instance)
+}
+
+/// Only the user who started the app may read the token.
+fn write_private(path: &Path, text: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+/// Reads one handoff from `stream`, gives it to `deliver` and confirms it. Connections without
+/// the right token are dropped.
+fn receive(stream: TcpStream, token: &str, deliver: impl FnOnce(cli::Args)) -> Option<()> {
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    let handoff: Handoff = serde_json::from_str(&line).ok()?;
+    if handoff.token != token {
+        return None;
+    }
+    deliver(cli::Args {
+        input: handoff.input,
+        output: handoff.output,
+        file: handoff.file,
+        project: handoff.project,
+        ..Default::default()
+    });
+    (&stream).write_all(b"ok\n").ok()
+}
+
+impl Instance {
+    pub fn set_context(&self, ctx: egui::Context) {
+        *self.context.lock().unwrap() = Some(ctx);
+    }
+
+    /// Arguments handed over since the last call, oldest first.
+    pub fn take_requests(&self) -> Vec<cli::Args> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+
+    /// Removes the endpoint file on exit, unless a newer instance has taken it over.
+    pub fn release(&self) {
+        let ours = std::fs::read_to_string(&self.endpoint_file)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Endpoint>(&text).ok())
+            .is_some_and(|endpoint| endpoint.token == self.token);
+        if ours {
+            let _ = std::fs::remove_file(&self.endpoint_file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_launch_hands_its_folders_over() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_instance_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = dir.join(FILE_NAME);
+        let args = cli::Args {
+            input: Some(PathBuf::from("batch")),
+            file: Some("a.mp4".to_string()),
+            ..Default::default()
+        };
+        let Start::Primary(instance) = start(&file, &args) else {
+            panic!("no instance was running");
+        };
+        assert!(matches!(start(&file, &args), Start::Forwarded));
+        let requests = instance.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].input.as_ref().unwrap().is_absolute());
+        assert_eq!(requests[0].file.as_deref(), Some("a.mp4"));
+
+        instance.release();
+        assert!(!file.exists());
+        assert!(forward(&file, &args).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// Crash recovery. Sidecars are only written when switching files or closing the app, so a crash
+// (OpenCV aborting on a corrupt file, say) would lose everything marked in the open file since
+// it was opened. While the open file has unsaved annotations they are also written to a journal
+// in the app's data folder every half minute; a clean save or exit removes it again, so a
+// journal found at startup means the last session ended without saving.
+use crate::i18n::trf;
+use crate::{VideoRange, cache};
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "autosave.json";
+/// Seconds between journal writes
+pub const INTERVAL: f64 = 30.0;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Journal {
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub media: PathBuf,
+    /// Annotated ranges of `media` that were not saved to its sidecar yet
+    pub ranges: Vec<VideoRange>,
+    /// When the journal was written (RFC 3339, local time)
+    pub written_at: String,
+}
+
+/// The journal in the app's data folder (next to the cache, so it follows portable mode).
+pub fn path() -> PathBuf {
+    cache::root().join(FILE_NAME)
+}
+
+/// Writes `journal` to `path`, through a temporary file so a crash while writing leaves the
+/// previous journal intact.
+pub fn save(path: &Path, journal: &Journal) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(journal).map_err(|e| e.to_string())?;
+    let partial = path.with_extension("json.partial");
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&partial, json))
+        .and_then(|_| std::fs::rename(&partial, path))
+        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+/// The journal left behind by a session that didn't end cleanly, if any.
+pub fn load(path: &Path) -> Option<Result<Journal, String>> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(
+        serde_json::from_str(&text)
+            .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e])),
+    )
+}
+
+pub fn clear(path: &Path) {
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_round_trips_and_clears() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_journal_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join(FILE_NAME);
+        assert!(load(&path).is_none());
+        let journal = Journal {
+            input: Some(PathBuf::from("/data/batch")),
+            output: None,
+            media: PathBuf::from("/data/batch/a.mp4"),
+            ranges: vec![VideoRange {
+                end_time: 2.0,
+                note: "unsaved".to_string(),
+                ..Default::default()
+            }],
+            written_at: "2025-03-01T12:00:00+01:00".to_string(),
+        };
+        save(&path, &journal).unwrap();
+        let loaded = load(&path).unwrap().unwrap();
+        assert_eq!(loaded.media, journal.media);
+        assert_eq!(loaded.ranges[0].note, "unsaved");
+        clear(&path);
+        assert!(load(&path).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+// Keyframe index for scrubbing long recordings. Landing on a keyframe needs no decoding from
+// an earlier one, so while the timeline is dragged the preview jumps to the keyframe before
+// the slider position and only decodes the exact frame once the drag ends. Built with ffprobe
+// from packet flags (no decoding) on a worker thread and cached per source. Stream-copy exports
+// use the same index to move their cuts onto keyframes.
+use crate::i18n::trf;
+use crate::{cache, remote};
+use std::path::Path;
+use std::process::Command;
+
+pub struct Index {
+    /// Keyframe presentation times in seconds, sorted
+    times: Vec<f64>,
+}
+
+impl Index {
+    /// The last keyframe at or before `time`.
+    pub fn before(&self, time: f64) -> Option<f64> {
+        let idx = self.times.partition_point(|t| *t <= time + 1e-6);
+        idx.checked_sub(1).map(|i| self.times[i])
+    }
+
+    /// The first keyframe after `time`.
+    fn after(&self, time: f64) -> Option<f64> {
+        let idx = self.times.partition_point(|t| *t <= time + 1e-6);
+        self.times.get(idx).copied()
+    }
+
+    /// The keyframe closest to `time`.
+    fn nearest(&self, time: f64) -> Option<f64> {
+        [self.before(time), self.after(time)]
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+    }
+
+    /// `start`..`end` moved to the nearest keyframes, where a stream copy can cut without
+    /// encoding. The end of the source, `duration`, counts as a keyframe for the end; a range
+    /// that would shrink to nothing ends at the next keyframe instead. None without keyframes.
+    pub fn snap(&self, start: f64, end: f64, duration: Option<f64>) -> Option<(f64, f64)> {
+        let start = self.nearest(start)?;
+        let later = |t: &f64| *t > start + 1e-6;
+        let keyframe = self
+            .nearest(end)
+            .filter(later)
+            .or_else(|| self.after(start));
+        let end = [keyframe, duration.filter(later)]
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| (a - end).abs().total_cmp(&(b - end).abs()))?;
+        Some((start, end))
+    }
+}
+
+/// Keyframe times from `ffprobe -show_entries packet=pts_time,flags -of csv=p=0` output,
+/// where keyframe packets have a `K` in their flags.
+fn parse(output: &str) -> Vec<f64> {
+    let mut times: Vec<f64> = output
+        .lines()
+        .filter_map(|line| line.split_once(','))
+        .filter(|(_, flags)| flags.contains('K'))
+        .filter_map(|(pts, _)| pts.trim().parse().ok())
+        .collect();
+    times.sort_by(f64::total_cmp);
+    times.dedup();
+    times
+}
+
+/// Loads the cached index of `source` or builds it. Blocks; run on a worker thread.
+pub fn build(source: &Path) -> Result<Index, String> {
+    let path = cache::dir("keyframes").join(format!("{}.txt", cache::source_key(source)));
+    if let Ok(text) = std::fs::read_to_string(&path) {
+        cache::touch(&path);
+        let times = text.lines().filter_map(|l| l.parse().ok()).collect();
+        return Ok(Index { times });
+    }
+
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "packet=pts_time,flags", "-of", "csv=p=0"])
+        .arg(remote::input(source))
+        .output()
+        .map_err(|e| trf("Failed to start ffprobe: {}", &[&e]))?;
+    if !output.status.success() {
+        return Err(trf(
+            "ffprobe could not index {}",
+            &[&format!("{:?}", source)],
+        ));
+    }
+    let times = parse(&String::from_utf8_lossy(&output.stdout));
+
+    let text: String = times.iter().map(|t| format!("{}\n", t)).collect();
+    if std::fs::create_dir_all(path.parent().unwrap()).is_ok()
+        && std::fs::write(&path, text).is_ok()
+    {
+        cache::evict(Some(&path));
+    }
+    Ok(Index { times })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_keyframe_before_a_time() {
+        let times = parse("0.000000,K__\n0.033367,___\n2.002000,K_\nN/A,K__\n4.004000,K__\n");
+        assert_eq!(times, [0.0, 2.002, 4.004]);
+        let index = Index { times };
+        assert_eq!(index.before(3.0), Some(2.002));
+        assert_eq!(index.before(2.002), Some(2.002));
+        assert_eq!(index.before(10.0), Some(4.004));
+        assert_eq!(Index { times: vec![1.0] }.before(0.5), None);
+
+        assert_eq!(index.snap(1.2, 3.5, Some(5.0)), Some((2.002, 4.004)));
+        assert_eq!(index.snap(0.4, 4.9, Some(5.0)), Some((0.0, 5.0)));
+        // Shorter than a keyframe interval
+        assert_eq!(index.snap(2.1, 2.5, Some(5.0)), Some((2.002, 4.004)));
+        assert_eq!(index.snap(4.5, 4.6, None), None);
+        assert_eq!(Index { times: vec![] }.snap(1.0, 2.0, Some(3.0)), None);
+    }
+}
@@ -0,0 +1,345 @@
+// Logging. Events are emitted with `tracing`, one target per subsystem; they are written to
+// stderr as before, to a log file in the app's data folder that is rotated by size, and to a
+// buffer the in-app log viewer reads. Users can send the log file or look at the viewer when an
+// export fails, instead of having to run the app from a terminal.
+//
+// VIDDATATRAINCROP_LOG sets the levels that are recorded: a default level and per-subsystem
+// overrides, e.g. `info,export=debug`.
+use crate::cache;
+use crate::i18n::{tr, trf};
+use eframe::egui;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::Level;
+use tracing::field::{Field, Visit};
+
+/// Opening and reading media: capture, proxies, keyframes, subtitles and previews
+pub const DECODE: &str = "decode";
+pub const EXPORT: &str = "export";
+/// Background analysis, detection and crop suggestions
+pub const ANALYSIS: &str = "analysis";
+pub const UI: &str = "ui";
+pub const SUBSYSTEMS: &[&str] = &[DECODE, EXPORT, ANALYSIS, UI];
+
+pub const FILE_NAME: &str = "viddatatraincrop.log";
+/// The log file is rotated once it grows past this size
+const MAX_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated log files kept next to the current one (`.1.log` is the newest)
+const KEEP: usize = 3;
+/// Records the viewer holds; older ones are only in the log file
+const BUFFER: usize = 5000;
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+pub struct Record {
+    pub time: String,
+    pub level: Level,
+    pub target: &'static str,
+    pub message: String,
+}
+
+static RECORDS: Mutex<VecDeque<Record>> = Mutex::new(VecDeque::new());
+
+/// Levels recorded per target: the most verbose level for each, `default` for the rest.
+#[derive(Debug, PartialEq)]
+pub struct Filter {
+    default: Level,
+    targets: Vec<(String, Level)>,
+}
+
+impl Filter {
+    /// Parses `info,export=debug`. Unknown levels are an error so a typo doesn't silently log
+    /// nothing.
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter {
+            default: Level::INFO,
+            targets: Vec::new(),
+        };
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (target, level) = match part.split_once('=') {
+                Some((target, level)) => (Some(target.trim()), level.trim()),
+                None => (None, part),
+            };
+            let level: Level = level
+                .parse()
+                .map_err(|_| format!("Unknown log level {:?}", level))?;
+            match target {
+                Some(target) => filter.targets.push((target.to_string(), level)),
+                None => filter.default = level,
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn allows(&self, target: &str, level: Level) -> bool {
+        let max = self
+            .targets
+            .iter()
+            .rev()
+            .find(|(t, _)| t == target)
+            .map_or(self.default, |(_, level)| *level);
+        level <= max
+    }
+}
+
+/// Moves `path` to `.1.log`, `.1.log` to `.2.log` and so on, dropping the oldest.
+fn rotate(path: &Path) {
+    let numbered = |n: usize| path.with_extension(format!("{}.log", n));
+    let _ = std::fs::remove_file(numbered(KEEP));
+    for n in (1..KEEP).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = std::fs::rename(path, numbered(1));
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<LogFile> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(LogFile { path, file, len })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.len > MAX_BYTES {
+            rotate(&self.path);
+            match LogFile::open(self.path.clone()) {
+                Ok(fresh) => *self = fresh,
+                Err(e) => eprintln!("Could not reopen {}: {}", self.path.display(), e),
+            }
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.len += line.len() as u64 + 1;
+        }
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    file: Mutex<Option<LogFile>>,
+    next_span: AtomicU64,
+}
+
+/// Collects an event's message and its other fields as `name=value`.
+#[derive(Default)]
+struct Fields(String, String);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.push_str(value);
+        } else {
+            let _ = write!(self.1, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.1, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+impl tracing::Subscriber for Logger {
+    fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+        self.filter.allows(metadata.target(), *metadata.level())
+    }
+
+    fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let record = Record {
+            time: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            level: *metadata.level(),
+            target: metadata.target(),
+            message: fields.0 + &fields.1,
+        };
+        let line = format!(
+            "{} {:5} {}: {}",
+            record.time, record.level, record.target, record.message
+        );
+        eprintln!("{}", line);
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            file.write_line(&line);
+        }
+        let mut records = RECORDS.lock().unwrap();
+        if records.len() == BUFFER {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+/// The log file in the app's data folder (next to the cache, so it follows portable mode).
+pub fn path() -> PathBuf {
+    cache::root().join("logs").join(FILE_NAME)
+}
+
+/// Starts logging with the levels from VIDDATATRAINCROP_LOG. Call once the data folder is known.
+pub fn init() {
+    let spec = std::env::var("VIDDATATRAINCROP_LOG").unwrap_or_default();
+    let filter = Filter::parse(&spec).unwrap_or_else(|e| {
+        eprintln!("VIDDATATRAINCROP_LOG: {}", e);
+        Filter::parse("").unwrap()
+    });
+    let file = LogFile::open(path())
+        .inspect_err(|e| eprintln!("Could not write {}: {}", path().display(), e))
+        .ok();
+    let logger = Logger {
+        filter,
+        file: Mutex::new(file),
+        next_span: AtomicU64::new(0),
+    };
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+/// What the log viewer shows.
+pub struct View {
+    /// Least severe level shown
+    pub level: Level,
+    /// Only this subsystem, or all
+    pub subsystem: Option<&'static str>,
+}
+
+impl Default for View {
+    fn default() -> View {
+        View {
+            level: Level::INFO,
+            subsystem: None,
+        }
+    }
+}
+
+fn level_color(ui: &egui::Ui, level: Level) -> egui::Color32 {
+    match level {
+        Level::ERROR => ui.visuals().error_fg_color,
+        Level::WARN => ui.visuals().warn_fg_color,
+        Level::INFO => ui.visuals().text_color(),
+        _ => ui.visuals().weak_text_color(),
+    }
+}
+
+/// The log viewer: recorded events filtered by level and subsystem, newest at the bottom.
+pub fn show(ctx: &egui::Context, open: &mut bool, view: &mut View) {
+    egui::Window::new(tr("📜 Log"))
+        .id(egui::Id::new("log_viewer"))
+        .open(open)
+        .default_size([700.0, 400.0])
+        .show(ctx, |ui| {
+            let records = RECORDS.lock().unwrap();
+            let shown: Vec<&Record> = records
+                .iter()
+                .filter(|r| r.level <= view.level)
+                .filter(|r| view.subsystem.is_none_or(|s| s == r.target))
+                .collect();
+            ui.horizontal(|ui| {
+                ui.label(tr("Level:"));
+                egui::ComboBox::from_id_salt("log_level")
+                    .selected_text(view.level.as_str())
+                    .show_ui(ui, |ui| {
+                        for level in LEVELS {
+                            ui.selectable_value(&mut view.level, level, level.as_str());
+                        }
+                    });
+                ui.label(tr("Subsystem:"));
+                egui::ComboBox::from_id_salt("log_subsystem")
+                    .selected_text(view.subsystem.unwrap_or(tr("All")))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut view.subsystem, None, tr("All"));
+                        for subsystem in SUBSYSTEMS {
+                            ui.selectable_value(&mut view.subsystem, Some(*subsystem), *subsystem);
+                        }
+                    });
+                if ui.button(tr("📋 Copy")).clicked() {
+                    let lines: Vec<String> = shown
+                        .iter()
+                        .map(|r| format!("{} {} {}: {}", r.time, r.level, r.target, r.message))
+                        .collect();
+                    ctx.copy_text(lines.join("\n"));
+                }
+            });
+            ui.label(trf("Log file: {}", &[&path().display()]));
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for record in shown {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.weak(&record.time);
+                            ui.colored_label(level_color(ui, record.level), record.level.as_str());
+                            ui.weak(record.target);
+                            ui.label(&record.message);
+                        });
+                    }
+                });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_by_default_and_subsystem_level() {
+        let filter = Filter::parse("warn, export=debug").unwrap();
+        assert!(filter.allows(EXPORT, Level::DEBUG));
+        assert!(!filter.allows(EXPORT, Level::TRACE));
+        assert!(filter.allows(DECODE, Level::ERROR));
+        assert!(!filter.allows(DECODE, Level::INFO));
+        assert_eq!(Filter::parse("").unwrap(), Filter::parse("info").unwrap());
+        assert!(Filter::parse("export=loud").is_err());
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_files() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_logs_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FILE_NAME);
+        for n in 0..=KEEP {
+            std::fs::write(&path, n.to_string()).unwrap();
+            rotate(&path);
+        }
+        assert!(!path.exists());
+        let read = |n: usize| std::fs::read_to_string(path.with_extension(format!("{}.log", n)));
+        assert_eq!(read(1).unwrap(), KEEP.to_string());
+        assert_eq!(read(KEEP).unwrap(), "1");
+        assert!(read(KEEP + 1).is_err());
+    }
+}
@@ -1,13 +1,76 @@
+use actions::Action;
+use i18n::{tr, trf};
 use eframe::egui;
-use opencv::{core, imgcodecs, imgproc, opencv_has_inherent_feature_algorithm_hint, prelude::*, videoio};
-use std::cmp::Ordering;
+use eframe::egui::emath::GuiRounding;
+use opencv::{core, imgproc, opencv_has_inherent_feature_algorithm_hint, prelude::*, videoio};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex, atomic};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+mod actions;
+mod analysis;
+mod audit;
+mod balance;
+mod boundary;
+mod cache;
+mod checksum;
+mod cli;
+mod clip_stats;
+mod color;
+mod dataset;
+mod export;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod hardware;
+mod i18n;
+mod instance;
+mod journal;
+mod keyframes;
+mod logs;
+mod manifest;
+mod material;
+mod merge;
+mod metrics;
+mod migrate;
+mod package;
+mod pool;
+mod portable;
+mod probe;
+mod progress;
+mod project;
+mod propagate;
+mod proxy;
+mod qa;
+mod range_preview;
+mod reel;
+mod remote;
+mod rename;
+mod rules;
+mod saliency;
+mod schedule;
+mod settings;
+mod sidecar;
+mod split;
+mod stats;
+mod still;
+mod subtitles;
+mod text_detect;
+mod tiles;
+mod timeline;
+mod timing;
+mod transcribe;
+mod trim;
+mod update;
+mod upload;
+mod verify;
+mod versions;
+mod view;
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct SerializableRect {
     min_x: f32,
     min_y: f32,
@@ -15,14 +78,155 @@ struct SerializableRect {
     max_y: f32,
 }
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+impl SerializableRect {
+    /// The whole frame
+    const FULL: SerializableRect = SerializableRect { min_x: 0.0, min_y: 0.0, max_x: 1.0, max_y: 1.0 };
+
+    /// Crop in whole pixels as (width, height, x, y); sizes are kept even for the encoders.
+    /// Both edges are rounded to the nearest pixel so the crop matches what was drawn.
+    fn to_pixels(&self, w: f64, h: f64) -> (i32, i32, i32, i32) {
+        let edge = |v: f32, size: f64| (v as f64 * size).round().clamp(0.0, size) as i32;
+        let (x0, x1) = (edge(self.min_x.min(self.max_x), w), edge(self.min_x.max(self.max_x), w));
+        let (y0, y1) = (edge(self.min_y.min(self.max_y), h), edge(self.min_y.max(self.max_y), h));
+        ((x1 - x0) & !1, (y1 - y0) & !1, x0, y0)
+    }
+
+    /// Moves the rect by (dx, dy), or only its bottom-right corner when `resize` is set,
+    /// keeping it inside the frame.
+    fn nudged(&self, dx: f32, dy: f32, resize: bool) -> SerializableRect {
+        let mut r = self.clone();
+        if resize {
+            r.max_x = (r.max_x + dx).clamp(r.min_x + 0.01, 1.0);
+            r.max_y = (r.max_y + dy).clamp(r.min_y + 0.01, 1.0);
+        } else {
+            let dx = dx.clamp(-r.min_x, 1.0 - r.max_x);
+            let dy = dy.clamp(-r.min_y, 1.0 - r.max_y);
+            (r.min_x, r.max_x, r.min_y, r.max_y) = (r.min_x + dx, r.max_x + dx, r.min_y + dy, r.max_y + dy);
+        }
+        r
+    }
+
+    fn intersects(&self, other: &SerializableRect) -> bool {
+        self.min_x < other.max_x
+            && other.min_x < self.max_x
+            && self.min_y < other.max_y
+            && other.min_y < self.max_y
+    }
+}
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
 struct VideoRange {
     start_time: f64,
     end_time: f64,
     crop_rect_norm: Option<SerializableRect>,
     note: String,
+    #[serde(default)]
+    text_scan: Option<text_detect::TextScan>,
+    /// Annotator who last changed the range, and when (RFC 3339, UTC)
+    #[serde(default)]
+    modified_by: Option<String>,
+    #[serde(default)]
+    modified_at: Option<String>,
+    #[serde(default)]
+    split: Option<split::Split>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Export frame rate of this range instead of the export setting's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fps: Option<export::FrameRate>,
+}
+
+impl VideoRange {
+    /// Records `annotator` and the current time as the last change to this range.
+    fn stamp(&mut self, annotator: &str) {
+        self.modified_by = Some(annotator.trim().to_string()).filter(|n| !n.is_empty());
+        self.modified_at =
+            Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    }
+
+    /// "✎ name, local time" for the last change, if the range was ever stamped.
+    fn modified_label(&self) -> Option<String> {
+        let at = self.modified_at.as_ref()?;
+        let when = chrono::DateTime::parse_from_rfc3339(at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| at.clone());
+        let who = self.modified_by.as_deref().unwrap_or(tr("unknown"));
+        Some(format!("✎ {who}, {when}"))
+    }
+
+    /// Whether the range is still the placeholder a video opens with: the whole video, no crop,
+    /// no note, no tags, no split and no frame rate of its own. Exporting one is almost always a
+    /// forgotten range, and it isn't saved to the sidecar.
+    fn is_untouched(&self, duration: f64) -> bool {
+        self.start_time <= 0.0
+            && self.end_time >= duration - 1e-3
+            && self.crop_rect_norm.is_none()
+            && self.note.trim().is_empty()
+            && self.tags.is_empty()
+            && self.split.is_none()
+            && self.fps.is_none()
+    }
+}
+
+/// Stills are shown and exported as single frames; everything else goes through VideoCapture.
+fn is_image_file(path: &Path) -> bool {
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "webp")
+}
+
+/// Name recorded on range changes until changed in the top bar; defaults to the login name.
+fn default_annotator() -> String {
+    ["VIDDATATRAINCROP_ANNOTATOR", "USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default()
+}
+
+/// Pending merge of another annotator's sidecars: one plan per media file they annotated.
+struct MergeSession {
+    source: PathBuf,
+    plans: Vec<(PathBuf, merge::MergePlan)>,
+    errors: Vec<String>,
 }
 
+/// One-line description of a range for the merge dialog.
+fn range_summary(range: &VideoRange) -> String {
+    let crop = match &range.crop_rect_norm {
+        Some(c) => format!(
+            "{} {:.0}%,{:.0}% – {:.0}%,{:.0}%",
+            tr("crop"),
+            c.min_x * 100.0,
+            c.min_y * 100.0,
+            c.max_x * 100.0,
+            c.max_y * 100.0
+        ),
+        None => tr("no crop").to_string(),
+    };
+    let note: String = range.note.lines().next().unwrap_or_default().chars().take(40).collect();
+    let mut summary =
+        format!("{:.2}s – {:.2}s, {}, \"{}\"", range.start_time, range.end_time, crop, note);
+    if let Some(modified) = range.modified_label() {
+        summary += &format!(" ({})", modified);
+    }
+    summary
+}
+
+/// Longest side of a still's preview texture; larger images are shown downscaled.
+const MAX_STILL_PREVIEW_SIDE: usize = 4096;
+
+/// Most preview frames decoded per second while the timeline is dragged. Positions in between
+/// are coalesced, so the preview keeps up with the pointer instead of lagging behind it.
+const SCRUB_DECODES_PER_SECOND: f64 = 15.0;
+
+/// Aspect ratios offered for the saliency based auto crop, as width / height.
+const AUTO_CROP_ASPECTS: &[(&str, f32)] = &[
+    ("1:1", 1.0),
+    ("4:3", 4.0 / 3.0),
+    ("3:4", 3.0 / 4.0),
+    ("16:9", 16.0 / 9.0),
+    ("9:16", 9.0 / 16.0),
+];
+
 enum PlayState {
     Playing,
     PlayingUntil(f64),
@@ -32,9 +236,147 @@ enum PlayState {
 // 1. Introduce an enum to handle both Videos and static Images
 enum MediaSource {
     Video(videoio::VideoCapture),
-    Image(core::Mat),
+    Image(still::Still),
+}
+
+/// Media and ranges the scan ran on, and one result per range.
+type TextScanOutcome = (PathBuf, Vec<VideoRange>, Result<Vec<text_detect::TextScan>, String>);
+
+/// Media and range index the transcription ran on, and the transcript.
+type TranscriptOutcome = (PathBuf, usize, Result<String, String>);
+
+/// Media, range index and the range as checked, and the frames edge trimming would drop.
+type TrimOutcome = (PathBuf, usize, VideoRange, Result<trim::Trim, String>);
+
+/// Source the proxy was built for, and the proxy file.
+type ProxyOutcome = (PathBuf, Result<PathBuf, String>);
+
+/// A file opened on a worker thread, ready to replace the current one.
+struct OpenedMedia {
+    /// None when the file could not be opened (a video may still get a proxy)
+    media: Option<MediaSource>,
+    is_image: bool,
+    native_fps: f64,
+    pixel_aspect: f64,
+    duration: f64,
+    /// Caption from an existing `.txt` next to the media
+    note: String,
+    sidecar: Option<Result<Vec<VideoRange>, String>>,
+    /// Whether the preview needs an 8-bit proxy, and if so whether the source is HDR
+    proxy: Option<bool>,
+    preview_note: Option<String>,
+    chapters: Vec<probe::Chapter>,
+    /// Color corrections for the preview of a video
+    color: color::Pipeline,
+}
+
+/// Does everything opening `path` needs that can block: decoding or opening the capture,
+/// the ffprobe calls and reading the caption and sidecar.
+fn open_media(path: &Path, max_still_side: usize) -> OpenedMedia {
+    // Read note from .txt file if it already exists
+    let p = path.with_extension("txt");
+    let note = if p.exists() {
+        fs::read_to_string(p).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let mut opened = OpenedMedia {
+        media: None,
+        is_image: is_image_file(path),
+        native_fps: 1.0,
+        pixel_aspect: 1.0,
+        duration: 0.0,
+        note,
+        sidecar: sidecar::load(path),
+        proxy: None,
+        preview_note: None,
+        chapters: Vec::new(),
+        color: color::Pipeline::default(),
+    };
+
+    if opened.is_image {
+        // Load using imgcodecs instead of VideoCapture; huge stills get a smaller preview
+        match still::load(path, max_still_side as i32) {
+            Ok(still) => {
+                if still.is_downscaled() {
+                    let (w, h) = still.size;
+                    opened.preview_note = Some(trf(
+                        "Preview: downscaled from {} × {} (crop and export use the original)",
+                        &[&w, &h],
+                    ));
+                }
+                opened.media = Some(MediaSource::Image(still));
+            }
+            Err(e) => {
+                tracing::warn!(target: logs::DECODE, file = ?path, "{}", e);
+                opened.preview_note = Some(e);
+            }
+        }
+        return opened;
+    }
+
+    let input = remote::input(path);
+    let mut cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)
+        .ok()
+        .filter(|c| c.is_opened().unwrap_or(false));
+    let (fps, frame_count) = match &cap {
+        Some(c) => (
+            c.get(videoio::CAP_PROP_FPS).unwrap_or(0.0),
+            c.get(videoio::CAP_PROP_FRAME_COUNT).unwrap_or(0.0),
+        ),
+        None => (0.0, 0.0),
+    };
+    match &cap {
+        Some(_) => tracing::debug!(target: logs::DECODE, file = ?path, fps, frame_count, "Opened video"),
+        None => tracing::warn!(target: logs::DECODE, file = ?path, "OpenCV could not open the video"),
+    }
+
+    // Some webm/mkv files report 0 or NaN here; ask ffprobe, then the timestamps
+    opened.native_fps = if probe::is_valid_fps(fps) {
+        fps
+    } else {
+        probe::video_fps(path)
+            .or_else(|| cap.as_mut().and_then(probe::fps_from_timestamps))
+            .unwrap_or(30.0)
+    };
+    opened.pixel_aspect = probe::sample_aspect_ratio(path);
+    opened.chapters = probe::chapters(path);
+    opened.duration = if frame_count > 0.0 {
+        frame_count / opened.native_fps
+    } else {
+        probe::duration(path).unwrap_or(0.0)
+    };
+
+    // 10-bit and HDR sources band or wash out through OpenCV's 8-bit BGR
+    // conversion, and some cannot be opened at all; preview those via a proxy.
+    let color = probe::color_info(path);
+    let hdr = color.as_ref().is_some_and(|c| c.is_hdr());
+    let high_bit = color.as_ref().is_some_and(|c| c.is_high_bit_depth());
+    if cap.is_none() || hdr || high_bit {
+        opened.proxy = Some(hdr);
+    }
+    let height = match &cap {
+        Some(c) => c.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(0.0) as i32,
+        None => probe::frame_size(path).map_or(0, |(_, h)| h),
+    };
+    opened.color = color::Pipeline::for_video(color.as_ref(), height);
+    tracing::debug!(target: logs::DECODE, file = ?path, pipeline = ?opened.color, "Preview colors");
+    opened.media = cap.map(MediaSource::Video);
+    opened
 }
 
+/// File the keyframe index was built for (the source or its proxy), and the index.
+type KeyframeOutcome = (PathBuf, Result<keyframes::Index, String>);
+
+/// Media the subtitles were loaded for, and its subtitles if it has any.
+type SubtitleOutcome = (PathBuf, Result<Option<subtitles::Track>, String>);
+
+/// Source, range and the decoded hover preview frames of that range.
+type RangePreviewOutcome = (PathBuf, range_preview::Key, Result<Vec<egui::ColorImage>, String>);
+
+/// File being opened (index and path), and where the worker puts it.
+type Loading = (usize, PathBuf, Arc<Mutex<Option<OpenedMedia>>>);
+
 struct VideoApp {
     input_folder: Option<PathBuf>,
     output_folder: Option<PathBuf>,
@@ -43,16 +385,193 @@ struct VideoApp {
     media: Option<MediaSource>, // Replaced `cap` with `media`
     is_image: bool,             // Quick flag to toggle UI elements
     video_texture: Option<egui::TextureHandle>,
+    current_frame: Option<core::Mat>, // Last decoded BGR frame, used for analysis
+    /// Color corrections applied to every decoded preview frame
+    color: color::Pipeline,
     current_time: f64,
     duration: f64,
     play_state: PlayState,
     native_fps: f64,
+    pixel_aspect: f64,
     ranges: Vec<VideoRange>,
     current_range_idx: usize,
     drag_start_norm: Option<egui::Pos2>,
+    crop_before_drag: Option<SerializableRect>,
+    view: view::View,
+    timeline: timeline::Timeline,
+    auto_crop_aspect_idx: usize,
+    text_model_path: Option<PathBuf>,
+    is_scanning_text: Arc<AtomicBool>,
+    text_scan_result: Arc<Mutex<Option<TextScanOutcome>>>,
+    text_scan_error: Option<String>,
+    /// ggml model for whisper.cpp speech transcription
+    whisper_model: Option<PathBuf>,
+    is_transcribing: Arc<AtomicBool>,
+    transcript_result: Arc<Mutex<Option<TranscriptOutcome>>>,
+    transcript_error: Option<String>,
+    is_checking_trim: Arc<AtomicBool>,
+    /// Latest edge trim preview; shown while its range is current and unchanged
+    trim_preview: Arc<Mutex<Option<TrimOutcome>>>,
+    is_building_proxy: Arc<AtomicBool>,
+    proxy_result: Arc<Mutex<Option<ProxyOutcome>>>,
+    /// File the preview capture reads, i.e. the one `keyframes` has to describe
+    preview_file: Option<PathBuf>,
+    loading: Option<Loading>,
+    keyframes: Option<keyframes::Index>,
+    keyframe_result: Arc<Mutex<Option<KeyframeOutcome>>>,
+    /// Subtitles of the open video, shown under the viewport when `show_subtitles` is set
+    subtitles: Option<subtitles::Track>,
+    subtitle_result: Arc<Mutex<Option<SubtitleOutcome>>>,
+    show_subtitles: bool,
+    /// Outline the crops of the previous and next range while cropping
+    show_ghost_crops: bool,
+    /// Crop new ranges of the open file start with, e.g. for a static camera
+    default_crop: Option<SerializableRect>,
+    /// Crop proposed for a range by matching a neighbour's crop, awaiting accept or dismiss
+    crop_proposal: Option<(usize, propagate::Proposal)>,
+    /// Frames around the current range's cuts while the cut check is open
+    show_cut_check: bool,
+    cut_check: Option<boundary::Check>,
+    /// Hover previews of the open file's ranges, empty for ranges that couldn't be decoded
+    range_previews: HashMap<range_preview::Key, Vec<egui::TextureHandle>>,
+    /// Range whose preview is being decoded
+    range_preview_pending: Option<range_preview::Key>,
+    range_preview_result: Arc<Mutex<Option<RangePreviewOutcome>>>,
+    /// Short side trainers scale samples to; sets the tile grid over the crop
+    training_resolution: u32,
+    show_tiles: bool,
+    /// Show the crop at the training resolution, scaled back up to the viewport
+    show_training_view: bool,
+    /// Training view of `current_frame` for the crop and resolution it was made for
+    training_texture: Option<(Option<SerializableRect>, u32, egui::TextureHandle)>,
+    /// Frame the capture decoded last, so stepping forward can skip the seek
+    decoded_frame: Option<i32>,
+    /// The timeline is being dragged; preview keyframes only
+    scrubbing: bool,
+    /// While scrubbing: the position moved since the last decode, and when that decode was
+    scrub_pending: bool,
+    scrub_decoded_at: Option<f64>,
+    key_repeat: actions::KeyRepeat,
+    /// Show only every Nth frame during playback, for weak machines
+    preview_step: i32,
+    /// Seconds a new range runs from the playhead, so a forgotten end doesn't export everything
+    default_clip_length: f64,
+    preview_note: Option<String>,
+    /// Chapter markers of the open video
+    chapters: Vec<probe::Chapter>,
     is_exporting: Arc<AtomicBool>,
+    export_profile: export::Profile,
+    schedule: schedule::Schedule,
+    /// How far the running export is, reported by the export thread
+    progress: progress::Progress,
+    /// Hold the next export until `schedule_time` (hour, minute)
+    schedule_enabled: bool,
+    schedule_time: (u32, u32),
+    /// Files a batch export or rebuild runs at once
+    parallel_exports: usize,
     export_error: Arc<Mutex<Option<String>>>,
+    /// Share of all annotated ranges that goes into a QA sample, in percent
+    qa_percent: f32,
+    is_qa_exporting: Arc<AtomicBool>,
+    qa_status: Arc<Mutex<Option<String>>>,
+    /// Black frame between the ranges of a reel
+    reel_separators: bool,
+    is_reel_exporting: Arc<AtomicBool>,
+    reel_status: Arc<Mutex<Option<String>>>,
+    /// Lets quitting stop the FFmpeg of a running reel export
+    reel_progress: progress::Progress,
+    is_checking_update: Arc<AtomicBool>,
+    /// Result of the last update check, if one was asked for
+    update_check: Arc<Mutex<Option<Result<update::Outcome, String>>>>,
+    /// Closing the window was held back because jobs are still running
+    close_prompt: bool,
+    /// Close the window as soon as the running jobs are done
+    quit_when_idle: bool,
+    /// Receives the folders and files of later launches, see `instance`
+    instance: Option<instance::Instance>,
+    /// Target train / val / test shares for "randomize remaining"
+    split_ratios: [f32; 3],
+    /// Tag being typed in the range editor
+    new_tag: String,
+    /// Class balance table while its window is open
+    balance: Option<balance::Balance>,
+    session: stats::Session,
+    show_session: bool,
+    /// Opt-in usage counts of the input folder
+    metrics: metrics::Metrics,
+    metrics_status: Option<String>,
+    /// Output folder audit while its window is open
+    audit: Option<audit::Report>,
+    /// Batch rename of the outputs while its window is open
+    rename: Option<rename::Plan>,
+    /// Project rules window; edits apply to the export at once and are saved on request
+    show_rules: bool,
+    rules_status: Option<String>,
+    /// Output folder and the material exported into it and its linked projects, for the
+    /// duplicate warnings; the flag is set once an export may have added to it
+    material: Option<(PathBuf, bool, Vec<material::Known>)>,
+    /// Linked projects of the output folder while their window is open
+    links: Option<Vec<PathBuf>>,
+    links_status: Option<String>,
+    /// Dataset versions window while it is open
+    versions: Option<versions::Panel>,
+    /// Package window while it is open
+    package: Option<package::Options>,
+    /// Export commands window while it is open, with the commands once they are worked out
+    command_preview: Option<Arc<Mutex<Option<Vec<String>>>>>,
+    /// Earlier exports of the open file that its ranges no longer produce, offered for removal
+    stale_outputs: Option<Vec<manifest::Entry>>,
+    /// Sidecar JSON of the open file's annotations as last loaded or saved; None while its
+    /// sidecar couldn't be read, so it isn't overwritten
+    sidecar_snapshot: Option<String>,
+    /// Files of the list that no longer exist on disk
+    missing: HashSet<PathBuf>,
+    /// When `missing` was last refreshed, in egui time
+    missing_checked_at: f64,
+    /// File whose relink dialog is open
+    relink: Option<usize>,
     frame_text: String,
+    /// Files the batch export walks, see `run_batch_export`
+    marked: HashSet<PathBuf>,
+    /// Progress of the files of the last batch export
+    batch_status: Arc<Mutex<HashMap<PathBuf, dataset::BatchStatus>>>,
+    analyzer: analysis::Analyzer,
+    /// Thumbnails of the background analysis shown so far, by file
+    thumbnails: HashMap<PathBuf, egui::TextureHandle>,
+    /// Ranges last written to the crash recovery journal, while it holds unsaved annotations
+    journal: Option<String>,
+    journal_checked_at: f64,
+    /// Unsaved annotations left by a session that didn't end cleanly, offered for restoring
+    recovery: Option<journal::Journal>,
+    /// File to switch to at the end of this frame, set by actions outside the file list
+    pending_file: Option<usize>,
+    /// Review mode: ranges, crops and notes can be inspected and played but not changed or exported
+    read_only: bool,
+    annotator: String,
+    palette: actions::CommandPalette,
+    merge: Option<MergeSession>,
+    /// Project file last saved or opened; Save Project writes there without asking
+    project_file: Option<PathBuf>,
+    /// Opened project waiting for confirmation before replacing annotations
+    project_restore: Option<project::Restore>,
+    show_log: bool,
+    log_view: logs::View,
+    show_shortcuts: bool,
+    show_encoder: bool,
+    show_upload: bool,
+    /// Where uploads go, kept in the settings
+    upload_destination: upload::Destination,
+    /// Secret key of the upload destination, for this session only
+    upload_secret: String,
+    /// URL typed into the remote source window, while it is open
+    add_remote: Option<String>,
+    /// Why the last URL couldn't be added
+    add_remote_error: Option<String>,
+    layout: settings::Layout,
+    /// Settings as last written to the settings file, to notice changes
+    saved_settings: settings::Settings,
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
 }
 
 impl Default for VideoApp {
@@ -65,31 +584,140 @@ impl Default for VideoApp {
             media: None,
             is_image: false,
             video_texture: None,
+            current_frame: None,
+            color: color::Pipeline::default(),
             current_time: 0.0,
             duration: 0.0,
             play_state: PlayState::NotPlaying,
             native_fps: 30.0,
-            ranges: vec![VideoRange {
-                start_time: 0.0,
-                end_time: 0.0,
-                crop_rect_norm: None,
-                note: String::new(),
-            }],
+            pixel_aspect: 1.0,
+            ranges: vec![VideoRange::default()],
             current_range_idx: 0,
             drag_start_norm: None,
+            crop_before_drag: None,
+            view: view::View::default(),
+            timeline: timeline::Timeline::default(),
+            auto_crop_aspect_idx: 0,
+            text_model_path: None,
+            is_scanning_text: Arc::new(AtomicBool::new(false)),
+            text_scan_result: Arc::new(Mutex::new(None)),
+            text_scan_error: None,
+            whisper_model: transcribe::model_from_env(),
+            is_transcribing: Arc::new(AtomicBool::new(false)),
+            is_checking_trim: Arc::new(AtomicBool::new(false)),
+            trim_preview: Arc::new(Mutex::new(None)),
+            transcript_result: Arc::new(Mutex::new(None)),
+            transcript_error: None,
+            is_building_proxy: Arc::new(AtomicBool::new(false)),
+            proxy_result: Arc::new(Mutex::new(None)),
+            preview_file: None,
+            loading: None,
+            keyframes: None,
+            keyframe_result: Arc::new(Mutex::new(None)),
+            subtitles: None,
+            subtitle_result: Arc::new(Mutex::new(None)),
+            show_subtitles: true,
+            show_ghost_crops: false,
+            default_crop: None,
+            crop_proposal: None,
+            show_cut_check: false,
+            cut_check: None,
+            range_previews: HashMap::new(),
+            range_preview_pending: None,
+            range_preview_result: Arc::new(Mutex::new(None)),
+            training_resolution: tiles::DEFAULT_RESOLUTION,
+            show_tiles: false,
+            show_training_view: false,
+            training_texture: None,
+            decoded_frame: None,
+            scrubbing: false,
+            scrub_pending: false,
+            scrub_decoded_at: None,
+            key_repeat: actions::KeyRepeat::default(),
+            preview_step: 1,
+            default_clip_length: 5.0,
+            preview_note: None,
+            chapters: Vec::new(),
             is_exporting: Arc::new(AtomicBool::new(false)),
+            export_profile: export::Profile::default(),
+            schedule: schedule::Schedule::default(),
+            progress: progress::Progress::default(),
+            schedule_enabled: false,
+            schedule_time: (22, 0),
+            parallel_exports: pool::DEFAULT_LIMIT,
             export_error: Arc::new(Mutex::new(None)),
+            qa_percent: 10.0,
+            is_qa_exporting: Arc::new(AtomicBool::new(false)),
+            is_checking_update: Arc::new(AtomicBool::new(false)),
+            update_check: Arc::new(Mutex::new(None)),
+            qa_status: Arc::new(Mutex::new(None)),
+            reel_separators: false,
+            is_reel_exporting: Arc::new(AtomicBool::new(false)),
+            reel_progress: progress::Progress::default(),
+            close_prompt: false,
+            quit_when_idle: false,
+            instance: None,
+            reel_status: Arc::new(Mutex::new(None)),
+            split_ratios: [80.0, 10.0, 10.0],
+            new_tag: String::new(),
+            balance: None,
+            session: stats::Session::new(),
+            show_session: false,
+            metrics: metrics::Metrics::default(),
+            metrics_status: None,
+            audit: None,
+            rename: None,
+            show_rules: false,
+            rules_status: None,
+            material: None,
+            links: None,
+            links_status: None,
+            versions: None,
+            package: None,
+            command_preview: None,
+            stale_outputs: None,
+            sidecar_snapshot: None,
+            missing: HashSet::new(),
+            missing_checked_at: f64::NEG_INFINITY,
+            relink: None,
             frame_text: "0".to_string(),
+            marked: HashSet::new(),
+            batch_status: Arc::new(Mutex::new(HashMap::new())),
+            analyzer: analysis::Analyzer::default(),
+            thumbnails: HashMap::new(),
+            journal: None,
+            journal_checked_at: f64::NEG_INFINITY,
+            recovery: None,
+            pending_file: None,
+            read_only: false,
+            annotator: default_annotator(),
+            palette: actions::CommandPalette::default(),
+            merge: None,
+            project_file: None,
+            project_restore: None,
+            show_log: false,
+            log_view: logs::View::default(),
+            show_shortcuts: false,
+            show_encoder: false,
+            show_upload: false,
+            upload_destination: upload::Destination::default(),
+            upload_secret: std::env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default(),
+            add_remote: None,
+            add_remote_error: None,
+            layout: settings::Layout::default(),
+            saved_settings: settings::Settings::default(),
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::Gamepad::new(),
         }
     }
 }
 
 impl VideoApp {
     fn is_playing(&self) -> bool {
-        match self.play_state {
-            PlayState::Playing | PlayState::PlayingUntil(_) => true,
-            _ => false,
-        }
+        matches!(
+            self.play_state,
+            PlayState::Playing | PlayState::PlayingUntil(_)
+        )
     }
 
     fn pause_play(&mut self) {
@@ -100,288 +728,3134 @@ impl VideoApp {
         };
     }
 
-    fn prev_frame(&mut self, ctx: &egui::Context) {
-        self.current_time -= 1.0 / self.native_fps;
-        self.update_frame(ctx);
-    }
-    fn next_frame(&mut self, ctx: &egui::Context) {
-        self.current_time += 1.0 / self.native_fps;
-        self.update_frame(ctx);
-    }
+    #[cfg(feature = "gamepad")]
+    fn handle_gamepad(&mut self, ctx: &egui::Context) {
+        let Some(pad) = &mut self.gamepad else {
+            return;
+        };
+        let actions = pad.poll();
+        let speed = pad.shuttle_speed();
+        // Controller events don't wake egui, so keep polling while one is plugged in
+        if pad.is_connected() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(30));
+        }
+        for action in actions {
+            self.perform(ctx, action);
+        }
 
-    fn update_frame(&mut self, ctx: &egui::Context) {
-        let mut frame = core::Mat::default();
-        let mut valid_frame = false;
+        // Shuttle scrubbing takes over from normal playback while the ring is turned
+        if speed != 0.0 && !self.is_image {
+            self.play_state = PlayState::NotPlaying;
+            let dt = ctx.input(|i| i.stable_dt) as f64;
+            self.current_time = (self.current_time + speed * dt).clamp(0.0, self.duration);
+            self.update_frame(ctx);
+            ctx.request_repaint();
+        }
+    }
 
-        // 2. Safely read from either the VideoCapture or the static Image Mat
-        if let Some(ref mut media) = self.media {
-            match media {
-                MediaSource::Video(cap) => {
-                    let frame_pos = (self.current_time * self.native_fps) as i32;
-                    let _ = cap.set(videoio::CAP_PROP_POS_FRAMES, frame_pos as f64);
-                    if cap.read(&mut frame).unwrap_or(false) && !frame.empty() {
-                        valid_frame = true;
-                    }
+    fn perform(&mut self, ctx: &egui::Context, action: Action) {
+        if (action.video_only() && self.is_image)
+            || (action.modifies() && self.read_only)
+            || (action.engineering() && self.layout == settings::Layout::Annotator)
+        {
+            return;
+        }
+        self.metrics.count(&format!("action.{:?}", action));
+        match action {
+            Action::PlayPause => self.pause_play(),
+            Action::PrevFrame => self.prev_frame(ctx),
+            Action::NextFrame => self.next_frame(ctx),
+            Action::AddRange => {
+                self.ranges.push(VideoRange {
+                    start_time: self.current_time,
+                    end_time: (self.current_time + self.default_clip_length).min(self.duration),
+                    crop_rect_norm: self.default_crop.clone(),
+                    ..Default::default()
+                });
+                self.current_range_idx = self.ranges.len() - 1;
+                self.session.range_created();
+            }
+            Action::ChaptersToRanges if self.chapters.is_empty() => {}
+            Action::ChaptersToRanges => {
+                // The placeholder a video opens with makes way for the chapters
+                if self.ranges.len() == 1 && self.ranges[0].is_untouched(self.duration) {
+                    self.ranges.clear();
                 }
-                MediaSource::Image(mat) => {
-                    if !mat.empty() {
-                        mat.copy_to(&mut frame).unwrap();
-                        valid_frame = true;
+                for chapter in &self.chapters {
+                    let mut range = VideoRange {
+                        start_time: chapter.start.min(self.duration),
+                        end_time: chapter.end.min(self.duration),
+                        note: chapter.title.clone(),
+                        ..Default::default()
+                    };
+                    range.stamp(&self.annotator);
+                    self.ranges.push(range);
+                    self.session.range_created();
+                }
+                self.current_range_idx = self.ranges.len() - self.chapters.len();
+            }
+            Action::PrevRange => self.current_range_idx = self.current_range_idx.saturating_sub(1),
+            Action::NextRange => {
+                self.current_range_idx =
+                    (self.current_range_idx + 1).min(self.ranges.len().saturating_sub(1));
+            }
+            Action::AutoCrop => self.auto_crop(),
+            Action::ResetZoom => self.view = view::View::default(),
+            Action::ScanText => {
+                if !self.is_scanning_text.load(atomic::Ordering::SeqCst) {
+                    self.run_text_scan();
+                }
+            }
+            Action::Transcribe => {
+                if !self.is_transcribing.load(atomic::Ordering::SeqCst) {
+                    self.run_transcription();
+                }
+            }
+            Action::PrevFile | Action::NextFile if !self.videos.is_empty() => {
+                let last = self.videos.len() - 1;
+                // Step from the file being opened, so repeated presses don't wait for each load
+                let current = self.loading.as_ref().map(|l| l.0).or(self.selected_file_idx);
+                self.pending_file = Some(match (current, action) {
+                    (None, _) => 0,
+                    (Some(i), Action::PrevFile) => i.saturating_sub(1),
+                    (Some(i), _) => (i + 1).min(last),
+                });
+            }
+            Action::PrevFile | Action::NextFile => {}
+            Action::OpenInputFolder => {
+                if let Some(p) = rfd::FileDialog::new().pick_folder() {
+                    self.open_input_folder(p);
+                }
+            }
+            Action::AddRemoteSource => {
+                if self.input_folder.is_some() {
+                    self.add_remote = match self.add_remote {
+                        Some(_) => None,
+                        None => Some(String::new()),
+                    };
+                    self.add_remote_error = None;
+                }
+            }
+            Action::OpenOutputFolder => {
+                if let Some(p) = rfd::FileDialog::new().pick_folder() {
+                    self.output_folder = Some(p);
+                }
+            }
+            Action::SaveProject => {
+                let path = self.project_file.clone().or_else(|| {
+                    let dialog = rfd::FileDialog::new()
+                        .add_filter(tr("Project"), &[project::EXTENSION])
+                        .set_file_name(project::FILE_NAME);
+                    match &self.input_folder {
+                        Some(dir) => dialog.set_directory(dir),
+                        None => dialog,
                     }
+                    .save_file()
+                });
+                if let Some(path) = path {
+                    self.save_project(path);
                 }
             }
-        }
-
-        if valid_frame {
-            let mut rgb_frame = core::Mat::default();
-
-            opencv_has_inherent_feature_algorithm_hint! { {
-                    let _ = imgproc::cvt_color(
-                        &frame,
-                        &mut rgb_frame,
-                        imgproc::COLOR_BGR2RGB,
-                        0,
-                        core::AlgorithmHint::ALGO_HINT_DEFAULT,
-                    );
-                } else {
-                    let _ = imgproc::cvt_color(
-                        &frame,
-                        &mut rgb_frame,
-                        imgproc::COLOR_BGR2RGB,
-                        0
-                    );
+            Action::OpenProject => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter(tr("Project"), &[project::EXTENSION])
+                    .pick_file()
+                    && let Err(e) = self.open_project(path)
+                {
+                    *self.export_error.lock().unwrap() = Some(e);
                 }
             }
-            let size = rgb_frame.size().unwrap();
-            let data = rgb_frame.data_bytes().unwrap();
-            let color_image =
-                egui::ColorImage::from_rgb([size.width as usize, size.height as usize], data);
-            self.video_texture =
-                Some(ctx.load_texture("video-frame", color_image, Default::default()));
-        }
-    }
-
-    fn run_export(&self) {
-        let (Some(idx), Some(out_dir)) = (self.selected_file_idx, &self.output_folder) else {
-            return;
-        };
-        let input_path = self.videos[idx].clone();
-        let stem = input_path
-            .file_stem()
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
-
-        let ext = input_path
-            .extension()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_lowercase();
-        let is_img = matches!(
-            ext.as_str(),
-            "jpg" | "jpeg" | "png" | "bmp" | "webp"
-        );
-
-        let ranges = self.ranges.clone();
-        let out_dir = out_dir.clone();
-
-        // Get dimensions for crop math depending on media source
-        let (vid_w, vid_h) = if let Some(ref media) = self.media {
-            match media {
-                MediaSource::Video(cap) => (
-                    cap.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap_or(1920.0),
-                    cap.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(1080.0),
-                ),
-                MediaSource::Image(mat) => {
-                    let size = mat.size().unwrap();
-                    (size.width as f64, size.height as f64)
+            Action::MergeAnnotations => {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    self.start_merge(dir);
                 }
             }
-        } else {
-            (1920.0, 1080.0)
-        };
-
-        self.is_exporting
-            .store(true, std::sync::atomic::Ordering::SeqCst);
-        *self.export_error.lock().unwrap() = None;
-
-        let exp_err = self.export_error.clone();
-        struct DropGuard(Arc<AtomicBool>);
-        impl Drop for DropGuard {
-            fn drop(&mut self) {
-                self.0.store(false, std::sync::atomic::Ordering::SeqCst);
+            Action::Export => {
+                if !self.is_exporting.load(atomic::Ordering::SeqCst) {
+                    self.run_export();
+                }
             }
-        }
-        let guard = DropGuard(self.is_exporting.clone());
-
-        std::thread::spawn(move || {
-            let _guard = guard;
-
-            for (i, range) in ranges.iter().enumerate() {
-                let out_base = if ranges.len() > 1 {
-                    out_dir.join(format!("{}_range{}", &stem, i))
-                } else {
-                    out_dir.join(&stem)
-                };
-                println!("DBG: {:?}", out_base);
-
-                if !range.note.is_empty() {
-                    let _ = std::fs::write(out_base.with_added_extension("txt"), &range.note);
+            Action::ExportMarked => {
+                if !self.is_exporting.load(atomic::Ordering::SeqCst) {
+                    let marked: Vec<_> = self.marked.iter().cloned().collect();
+                    self.run_batch_export(&marked);
                 }
-
-                // 3. Conditional FFmpeg command construction based on if it's an image
-                let mut cmd = Command::new("ffmpeg");
-                cmd.arg("-y");
-
-                if !is_img {
-                    cmd.arg("-ss")
-                        .arg(range.start_time.to_string())
-                        .arg("-to")
-                        .arg(range.end_time.to_string());
+            }
+            Action::RetryFailed => {
+                if !self.is_exporting.load(atomic::Ordering::SeqCst) {
+                    let failed = self.failed_exports();
+                    self.run_batch_export(&failed);
                 }
-
-                cmd.arg("-i").arg(&input_path);
-
-                let mut filters = vec![];
-                if !is_img {
-                    filters.push("fps=16".to_string());
+            }
+            Action::QaExport => {
+                if !self.is_qa_exporting.load(atomic::Ordering::SeqCst) {
+                    self.run_qa_export();
                 }
-
-                if let Some(ref norm) = range.crop_rect_norm {
-                    let cw = ((norm.max_x - norm.min_x).abs() as f64 * vid_w) as i32 & !1;
-                    let ch = ((norm.max_y - norm.min_y).abs() as f64 * vid_h) as i32 & !1;
-                    let cx = (norm.min_x.min(norm.max_x) as f64 * vid_w) as i32;
-                    let cy = (norm.min_y.min(norm.max_y) as f64 * vid_h) as i32;
-                    filters.push(format!("crop={}:{}:{}:{}", cw, ch, cx, cy));
+            }
+            Action::ReelFile | Action::ReelFolder => {
+                if !self.is_reel_exporting.load(atomic::Ordering::SeqCst) {
+                    self.run_reel_export(action == Action::ReelFolder);
                 }
-
-                if !filters.is_empty() {
-                    cmd.arg("-vf").arg(filters.join(","));
+            }
+            Action::CheckForUpdates => {
+                if !self.is_checking_update.load(atomic::Ordering::SeqCst) {
+                    self.check_for_updates(ctx);
                 }
-
-                let out_ext = if is_img { ext.to_string() } else { "mp4".to_string() };
-                let out_file = out_base.with_added_extension(&out_ext);
-
-                if !is_img {
-                    cmd.arg("-c:v")
-                        .arg("libx264")
-                        .arg("-preset")
-                        .arg("ultrafast");
+            }
+            Action::RandomizeSplits => self.randomize_splits(),
+            Action::SessionStats => self.show_session = !self.show_session,
+            Action::ClassBalance => {
+                self.balance = match self.balance {
+                    Some(_) => None,
+                    None => Some(self.compute_balance()),
                 }
-
-                cmd.arg(&out_file);
-
-                println!("Exporting Range {}: file {:?}", i, out_file);
-
-                match cmd.status() {
-                    Ok(status) if !status.success() => {
-                        let err_msg = format!(
-                            "FFmpeg failed on range {} with exit code: {:?}",
-                            i,
-                            status.code()
-                        );
-                        *exp_err.lock().unwrap() = Some(err_msg);
-                        break;
-                    }
-                    Err(e) => {
-                        *exp_err.lock().unwrap() = Some(format!("Failed to start FFmpeg: {}", e));
-                        break;
-                    }
-                    _ => {}
+            }
+            Action::RebuildDataset => {
+                if !self.is_exporting.load(atomic::Ordering::SeqCst) {
+                    self.rebuild_dataset();
                 }
             }
-            println!("All exports finished.");
-        });
-    }
-}
-
-impl eframe::App for VideoApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut file_idx_to_load = None;
-
-        // Keyboard Logic (Disable for images to prevent accidental scrubbing)
-        if !ctx.wants_keyboard_input() && !self.is_image {
-            if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
-                self.pause_play();
+            Action::AuditOutput => {
+                self.audit = match self.audit {
+                    Some(_) => None,
+                    None => self.run_audit(),
+                }
             }
-            if !self.ranges.is_empty() {
-                if ctx.input(|i| i.key_pressed(egui::Key::I)) {
-                    self.ranges[self.current_range_idx].start_time = self.current_time;
+            Action::RenameOutputs => {
+                self.rename = match self.rename {
+                    Some(_) => None,
+                    None => self.plan_rename(),
+                }
+            }
+            Action::ProjectRules => self.show_rules = !self.show_rules,
+            Action::LinkedProjects => {
+                self.links_status = None;
+                self.links = match (&self.links, &self.output_folder) {
+                    (None, Some(out_dir)) => match material::Registry::load(out_dir) {
+                        Ok(registry) => Some(registry.links),
+                        Err(e) => {
+                            *self.export_error.lock().unwrap() = Some(e);
+                            None
+                        }
+                    },
+                    _ => None,
                 }
-                if ctx.input(|i| i.key_pressed(egui::Key::O)) {
-                    self.ranges[self.current_range_idx].end_time = self.current_time;
+            }
+            Action::DatasetVersions => {
+                self.versions = match (&self.versions, &self.output_folder) {
+                    (None, Some(out_dir)) => Some(versions::Panel::new(out_dir)),
+                    _ => None,
                 }
-                if ctx.input(|i| i.key_pressed(egui::Key::R)) {
-                    let range = &self.ranges[self.current_range_idx];
-                    self.current_time = range.start_time;
-                    self.play_state = PlayState::PlayingUntil(range.end_time);
+            }
+            Action::PackageDataset => {
+                self.package = match (&self.package, &self.output_folder) {
+                    (None, Some(_)) => Some(package::Options::default()),
+                    _ => None,
+                }
+            }
+            Action::PreviewCommands => {
+                if self.command_preview.take().is_none() {
+                    self.preview_commands();
+                }
+            }
+            Action::UploadDataset => self.show_upload = !self.show_upload,
+            Action::VerifyChecksums => {
+                if !self.is_exporting.load(atomic::Ordering::SeqCst) {
+                    self.verify_checksums();
+                }
+            }
+            Action::ShowLog => self.show_log = !self.show_log,
+            Action::EncoderSettings => self.show_encoder = !self.show_encoder,
+            Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            Action::CommandPalette => self.palette.toggle(),
+            _ if self.ranges.is_empty() => {}
+            Action::SetStart => self.ranges[self.current_range_idx].start_time = self.current_time,
+            Action::SetEnd => self.ranges[self.current_range_idx].end_time = self.current_time,
+            Action::PreviewRange => {
+                let range = &self.ranges[self.current_range_idx];
+                self.current_time = range.start_time;
+                self.play_state = PlayState::PlayingUntil(range.end_time);
+            }
+            Action::ClearCrop => self.ranges[self.current_range_idx].crop_rect_norm = None,
+            // Setting the default a second time takes it back
+            Action::SetDefaultCrop => {
+                let crop = &self.ranges[self.current_range_idx].crop_rect_norm;
+                self.default_crop = match crop {
+                    Some(_) if self.default_crop == *crop => None,
+                    _ => crop.clone(),
                 }
             }
-            if ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft)) {
-                self.prev_frame(ctx);
+            Action::MatchCrop => self.match_crop(),
+            Action::CutCheck => self.show_cut_check = !self.show_cut_check,
+            Action::TrimPreview => {
+                if !self.is_checking_trim.load(atomic::Ordering::SeqCst) {
+                    self.run_trim_preview(ctx);
+                }
             }
-            if ctx.input(|i| i.key_pressed(egui::Key::ArrowRight)) {
-                self.next_frame(ctx);
+            Action::RemoveRange => {
+                self.crop_proposal = None;
+                self.ranges.remove(self.current_range_idx);
+                self.current_range_idx = self
+                    .current_range_idx
+                    .clamp(0, self.ranges.len().saturating_sub(1));
             }
         }
+        if matches!(
+            action,
+            Action::AddRange | Action::RemoveRange | Action::RandomizeSplits
+        ) {
+            self.check_stale_outputs();
+        }
+        // Auto crop stamps itself, it can fail
+        if matches!(
+            action,
+            Action::AddRange | Action::SetStart | Action::SetEnd | Action::ClearCrop
+        ) {
+            self.touch();
+        }
+    }
 
-        // Panels
-        egui::TopBottomPanel::top("top").show(ctx, |ui| {
+    /// Looks for outputs the open file exported earlier that no range produces any more, after
+    /// ranges were removed or renamed (split or range count changed).
+    fn check_stale_outputs(&mut self) {
+        let (Some(idx), Some(out_dir)) = (self.selected_file_idx, &self.output_folder) else {
+            return;
+        };
+        let Ok(manifest) = manifest::Manifest::load(out_dir) else {
+            return;
+        };
+        let profile = &self.export_profile;
+        let stale =
+            dataset::stale_entries(out_dir, &self.videos[idx], &self.ranges, &manifest, profile);
+        self.stale_outputs = (!stale.is_empty()).then_some(stale);
+    }
+
+    fn show_stale_outputs(&mut self, ctx: &egui::Context) {
+        let (Some(stale), Some(out_dir)) = (&self.stale_outputs, &self.output_folder) else {
+            return;
+        };
+        if self.layout == settings::Layout::Annotator {
+            return;
+        }
+        let (mut choice, mut keep) = (None, false);
+        egui::TopBottomPanel::bottom("stale_outputs").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.button("📁 Input Folder").clicked() {
-                    if let Some(p) = rfd::FileDialog::new().pick_folder() {
-                        self.input_folder = Some(p.clone());
-                        self.videos = std::fs::read_dir(p)
-                            .unwrap()
-                            .filter_map(|e| e.ok())
-                            .map(|e| e.path())
-                            .filter(|p| {
-                                p.extension().map_or(false, |ext| {
-                                    let ext = ext.to_ascii_lowercase();
-                                    // 4. Added image extensions here
-                                    ext == "mp4" || ext == "mkv" || ext == "avi" || ext == "mov" || ext == "webm" ||
-                                    ext == "jpg" || ext == "jpeg" || ext == "png" || ext == "bmp" || ext == "webp"
-                                })
-                            })
-                            .collect();
-                    }
-                }
-                ui.label(format!(
-                    "In: {}",
-                    self.input_folder
-                        .as_deref()
-                        .unwrap_or(Path::new("None"))
-                        .display()
-                ));
-                ui.separator();
-                if ui.button("💾 Output Folder").clicked() {
-                    self.output_folder = rfd::FileDialog::new().pick_folder();
-                }
-                ui.label(format!(
-                    "Out: {}",
-                    self.output_folder
-                        .as_deref()
-                        .unwrap_or(Path::new("None"))
-                        .display()
+                ui.label(trf(
+                    "⚠ {} earlier export(s) of this file no longer match a range: {}",
+                    &[
+                        &stale.len(),
+                        &stale.iter().map(|e| e.file.as_str()).collect::<Vec<_>>().join(", "),
+                    ],
                 ));
+                ui.add_enabled_ui(!self.read_only, |ui| {
+                    let trash = ui
+                        .button(tr("🗑 Move to Trash"))
+                        .on_hover_text(tr("Move them to .trash in the output folder"));
+                    if trash.clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button(tr("Delete Files")).clicked() {
+                        choice = Some(false);
+                    }
+                });
+                keep = ui.button(tr("Keep")).clicked();
             });
         });
+        if keep {
+            self.stale_outputs = None;
+        }
+        if let Some(trash) = choice
+            && let Some(stale) = self.stale_outputs.take()
+            && let Err(e) = dataset::discard(out_dir, &stale, trash)
+        {
+            *self.export_error.lock().unwrap() = Some(e);
+        }
+    }
 
-        egui::SidePanel::left("left")
-            .default_width(400.0)
-            .show(ctx, |ui| {
-                ui.heading("Files");
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.set_min_width(400.0);
+    /// Stamps the current range with the annotator after a change.
+    fn touch(&mut self) {
+        if let Some(range) = self.ranges.get_mut(self.current_range_idx) {
+            range.stamp(&self.annotator);
+        }
+    }
 
-                    for (i, v) in self.videos.iter().enumerate() {
-                        let name = v.file_name().unwrap().to_string_lossy();
-                        if ui
-                            .selectable_label(self.selected_file_idx == Some(i), name)
-                            .clicked()
-                        {
-                            file_idx_to_load = Some(i);
+    /// Keyboard crop editing while the viewport has focus: arrows move the crop, Ctrl+arrows
+    /// resize it, Shift makes steps 10 px, Enter creates a centered crop and Delete clears it.
+    fn keyboard_crop(&mut self, ctx: &egui::Context) {
+        let (Some((w, h)), true, false) = (
+            self.frame_size(),
+            self.video_texture.is_some(),
+            self.ranges.is_empty(),
+        ) else {
+            return;
+        };
+        let crop = &mut self.ranges[self.current_range_idx].crop_rect_norm;
+        let before = crop.clone();
+        ctx.input_mut(|i| {
+            let mods = i.modifiers;
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) && crop.is_none() {
+                *crop = Some(SerializableRect {
+                    min_x: 0.25,
+                    min_y: 0.25,
+                    max_x: 0.75,
+                    max_y: 0.75,
+                });
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Delete)
+                || i.consume_key(egui::Modifiers::NONE, egui::Key::Backspace)
+            {
+                *crop = None;
+            }
+            let step = if mods.shift { 10.0 } else { 1.0 };
+            for (key, dx, dy) in [
+                (egui::Key::ArrowLeft, -1.0, 0.0),
+                (egui::Key::ArrowRight, 1.0, 0.0),
+                (egui::Key::ArrowUp, 0.0, -1.0),
+                (egui::Key::ArrowDown, 0.0, 1.0),
+            ] {
+                // Consumed even without a crop so the arrows don't fall through to frame stepping
+                if i.consume_key(mods, key)
+                    && let Some(c) = crop.as_mut()
+                {
+                    *c = c.nudged(dx * step / w as f32, dy * step / h as f32, mods.command);
+                }
+            }
+        });
+        if *crop != before {
+            self.touch();
+        }
+    }
+
+    /// Ranges of the open file, without the untouched whole-file range a video opens with,
+    /// which is not an annotation.
+    fn annotated_ranges(&self) -> Vec<VideoRange> {
+        self.ranges
+            .iter()
+            .filter(|r| !r.is_untouched(self.duration))
+            .cloned()
+            .collect()
+    }
+
+    /// Annotated ranges of every file in the folder: the open file's as they are now, the
+    /// others' from their sidecars. Also returns the sidecars that could not be read.
+    fn folder_annotations(&self) -> (Vec<(PathBuf, Vec<VideoRange>)>, Vec<String>) {
+        let mut annotations = Vec::new();
+        let mut errors = Vec::new();
+        for (i, media) in self.videos.iter().enumerate() {
+            if self.selected_file_idx == Some(i) {
+                annotations.push((media.clone(), self.annotated_ranges()));
+                continue;
+            }
+            match sidecar::load(media) {
+                None => {}
+                Some(Ok(ranges)) => annotations.push((media.clone(), ranges)),
+                Some(Err(e)) => errors.push(e),
+            }
+        }
+        (annotations, errors)
+    }
+
+    fn compute_balance(&self) -> balance::Balance {
+        let (annotations, errors) = self.folder_annotations();
+        let mut balance = balance::compute(annotations.iter().flat_map(|(_, ranges)| ranges));
+        balance.errors = errors;
+        balance
+    }
+
+    fn show_balance(&mut self, ctx: &egui::Context) {
+        let Some(table) = &self.balance else {
+            return;
+        };
+        let mut open = true;
+        if balance::show(ctx, &mut open, table) {
+            self.balance = Some(self.compute_balance());
+        }
+        if !open {
+            self.balance = None;
+        }
+    }
+
+    /// Assigns a split to every range of the open file that has none yet, weighing in the
+    /// splits already given out across the folder (the sidecars of the other files) so the
+    /// totals approach `split_ratios`.
+    fn randomize_splits(&mut self) {
+        let mut assigned = [0; 3];
+        let mut count = |ranges: &[VideoRange]| {
+            for split in ranges.iter().filter_map(|r| r.split) {
+                assigned[split as usize] += 1;
+            }
+        };
+        count(&self.ranges);
+        for (i, media) in self.videos.iter().enumerate() {
+            if self.selected_file_idx != Some(i)
+                && let Some(Ok(ranges)) = sidecar::load(media)
+            {
+                count(&ranges);
+            }
+        }
+        let remaining = self.ranges.iter().filter(|r| r.split.is_none()).count();
+        let mut splits =
+            split::fill_remaining(assigned, remaining, self.split_ratios, fastrand::u64(..))
+                .into_iter();
+        for range in self.ranges.iter_mut().filter(|r| r.split.is_none()) {
+            range.split = splits.next();
+            range.stamp(&self.annotator);
+        }
+    }
+
+    /// Plans merging the sidecars in `theirs_dir` (another annotator's copy of the input folder)
+    /// into ours, matching media by file name. Nothing is written until the merge is applied.
+    fn start_merge(&mut self, theirs_dir: PathBuf) {
+        // Ours are read from the sidecars
+        self.save_sidecar();
+        let mut session = MergeSession {
+            source: theirs_dir,
+            plans: Vec::new(),
+            errors: Vec::new(),
+        };
+        for (i, media) in self.videos.iter().enumerate() {
+            let theirs_media = session.source.join(media.file_name().unwrap());
+            let theirs = match sidecar::load(&theirs_media) {
+                None => continue,
+                Some(Ok(ranges)) => ranges,
+                Some(Err(e)) => {
+                    session.errors.push(e);
+                    continue;
+                }
+            };
+            let ours = if self.selected_file_idx == Some(i) {
+                self.annotated_ranges()
+            } else {
+                match sidecar::load(media) {
+                    None => Vec::new(),
+                    Some(Ok(ranges)) => ranges,
+                    Some(Err(e)) => {
+                        session.errors.push(e);
+                        continue;
+                    }
+                }
+            };
+            session.plans.push((media.clone(), merge::plan(ours, theirs)));
+        }
+        self.merge = Some(session);
+    }
+
+    fn show_merge(&mut self, ctx: &egui::Context) {
+        let Some(session) = &mut self.merge else {
+            return;
+        };
+        let (mut open, mut apply) = (true, false);
+        egui::Window::new(tr("🔀 Merge Annotations"))
+            .id(egui::Id::new("merge_annotations"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.label(trf("From: {}", &[&session.source.display()]));
+                for err in &session.errors {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                if session.plans.is_empty() {
+                    ui.label(tr("No annotations for files in the input folder were found there."));
+                }
+                egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+                    for (media, plan) in &mut session.plans {
+                        ui.separator();
+                        ui.strong(media.file_name().unwrap_or_default().to_string_lossy());
+                        ui.label(trf(
+                            "{} added, {} identical, {} conflicting",
+                            &[&plan.added.len(), &plan.duplicates, &plan.conflicts.len()],
+                        ));
+                        for conflict in plan.conflicts.iter_mut() {
+                            ui.group(|ui| {
+                                ui.label(trf("Mine: {}", &[&range_summary(&plan.ours[conflict.ours])]));
+                                ui.label(trf("Theirs: {}", &[&range_summary(&conflict.theirs)]));
+                                ui.horizontal(|ui| {
+                                    ui.radio_value(&mut conflict.choice, merge::Choice::KeepMine, tr("Keep mine"));
+                                    ui.radio_value(&mut conflict.choice, merge::Choice::TakeTheirs, tr("Take theirs"));
+                                    ui.radio_value(&mut conflict.choice, merge::Choice::KeepBoth, tr("Keep both"));
+                                });
+                            });
+                        }
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui
+                        .add_enabled(!session.plans.is_empty(), egui::Button::new(tr("Apply Merge")))
+                        .on_hover_text(tr("Writes the merged .vdtc.json sidecars into the input folder"))
+                        .clicked();
+                });
+            });
+
+        if apply {
+            let session = self.merge.take().unwrap();
+            let mut errors = Vec::new();
+            for (media, plan) in session.plans {
+                let merged = plan.resolve();
+                if let Err(e) = sidecar::save(&media, &merged) {
+                    errors.push(e);
+                    continue;
+                }
+                if self.selected_file_idx.map(|i| &self.videos[i]) == Some(&media) {
+                    self.ranges = merged;
+                    self.current_range_idx = 0;
+                    self.sidecar_snapshot = sidecar::to_json(&self.annotated_ranges()).ok();
+                }
+            }
+            if !errors.is_empty() {
+                *self.export_error.lock().unwrap() = Some(errors.join("\n"));
+            }
+        } else if !open {
+            self.merge = None;
+        }
+    }
+
+    /// Writes the folders, the open file and the annotations of every file in the input folder
+    /// to the project file `path`.
+    fn save_project(&mut self, path: PathBuf) {
+        let Some(input) = self.input_folder.clone() else {
+            return;
+        };
+        self.save_sidecar();
+        let (annotations, errors) = self.folder_annotations();
+        // A project missing a file's annotations would drop them when opened elsewhere
+        if !errors.is_empty() {
+            *self.export_error.lock().unwrap() = Some(errors.join("\n"));
+            return;
+        }
+        let file = self.selected_file_idx.and_then(|i| self.videos.get(i));
+        let project = project::Project::new(input, self.output_folder.clone(), file.map(|f| f.as_path()), annotations);
+        match project::save(&path, &project) {
+            Ok(()) => self.project_file = Some(path),
+            Err(e) => *self.export_error.lock().unwrap() = Some(e),
+        }
+    }
+
+    /// Opens the folders of the project file `path` and writes its annotations to the sidecars.
+    /// If that would replace other annotations, or some of its files are gone, the restore waits
+    /// for confirmation.
+    fn open_project(&mut self, path: PathBuf) -> Result<(), String> {
+        let project = project::load(&path)?;
+        if !project.input.is_dir() {
+            return Err(trf("Input folder {} not found", &[&project.input.display()]));
+        }
+        self.project_restore = None;
+        self.open_input_folder(project.input.clone());
+        self.output_folder = project.output.clone();
+        self.project_file = Some(path);
+        let restore = project.restore(&self.videos);
+        if restore.needs_confirmation() {
+            self.project_restore = Some(restore);
+        } else {
+            self.apply_restore(restore);
+        }
+        Ok(())
+    }
+
+    /// Writes the sidecars of `restore` and opens the project's file.
+    fn apply_restore(&mut self, restore: project::Restore) {
+        let mut errors = restore.missing.iter().map(|name| trf("{} is not in the input folder", &[name])).collect::<Vec<_>>();
+        for write in &restore.writes {
+            if let Err(e) = sidecar::save(&write.media, &write.ranges) {
+                errors.push(e);
+            }
+        }
+        if !errors.is_empty() {
+            *self.export_error.lock().unwrap() = Some(errors.join("\n"));
+        }
+        self.open_project_file(restore.file);
+    }
+
+    fn open_project_file(&mut self, file: Option<String>) {
+        let idx = file.and_then(|name| self.videos.iter().position(|v| v.file_name().is_some_and(|n| *n == *name)));
+        self.pending_file = idx.or((!self.videos.is_empty()).then_some(0));
+    }
+
+    fn show_project_restore(&mut self, ctx: &egui::Context) {
+        let Some(restore) = &self.project_restore else {
+            return;
+        };
+        let (mut open, mut apply) = (true, false);
+        egui::Window::new(tr("📂 Open Project"))
+            .id(egui::Id::new("project_restore"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    let replaced: Vec<_> = restore.writes.iter().filter(|w| w.replaces).collect();
+                    if !replaced.is_empty() {
+                        ui.label(tr("These files have other annotations than the project, which will be replaced:"));
+                        for write in replaced {
+                            ui.monospace(write.media.file_name().unwrap_or_default().to_string_lossy());
+                        }
+                    }
+                    if !restore.missing.is_empty() {
+                        ui.label(tr("These annotated files of the project are not in the input folder:"));
+                        for name in &restore.missing {
+                            ui.monospace(name);
+                        }
+                    }
+                });
+                ui.separator();
+                apply = ui
+                    .button(tr("Restore Project Annotations"))
+                    .on_hover_text(tr("Writes the project's annotations into the .vdtc.json sidecars"))
+                    .clicked();
+            });
+
+        if apply {
+            let restore = self.project_restore.take().unwrap();
+            self.apply_restore(restore);
+        } else if !open {
+            // The folders stay open, with the annotations they have
+            let file = self.project_restore.take().and_then(|r| r.file);
+            self.open_project_file(file);
+        }
+    }
+
+    fn open_input_folder(&mut self, p: PathBuf) {
+        self.save_sidecar();
+        // The open file belongs to the old folder, its ranges must not be saved into the new one
+        self.sidecar_snapshot = None;
+        self.input_folder = Some(p.clone());
+        self.rules_status = None;
+        self.export_profile.rules = rules::load(&p).unwrap_or_else(|e| {
+            self.rules_status = Some(e);
+            rules::Rules::default()
+        });
+        self.metrics_status = self.metrics.open(&p).err();
+        self.videos = std::fs::read_dir(p)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension().is_some_and(|ext| {
+                    let ext = ext.to_ascii_lowercase();
+                    // 4. Added image extensions here
+                    ext == "mp4" || ext == "mkv" || ext == "avi" || ext == "mov" || ext == "webm" ||
+                    ext == "jpg" || ext == "jpeg" || ext == "png" || ext == "bmp" || ext == "webp" ||
+                    ext == remote::EXTENSION
+                })
+            })
+            .collect();
+        self.marked.clear();
+        self.thumbnails.clear();
+        self.analyzer.start(self.videos.clone());
+    }
+
+    /// Opens the folders and the file given on the command line. The output folder is created
+    /// by the first export if it doesn't exist yet.
+    fn apply_args(&mut self, args: cli::Args) -> Result<(), String> {
+        if let Some(path) = args.project {
+            self.open_project(path)?;
+        }
+        if let Some(dir) = args.input {
+            if !dir.is_dir() {
+                return Err(format!("Input folder {} not found", dir.display()));
+            }
+            self.open_input_folder(dir);
+        }
+        if let Some(dir) = args.output {
+            self.output_folder = Some(dir);
+        }
+        if let Some(name) = args.file {
+            let idx = self
+                .videos
+                .iter()
+                .position(|v| v.file_name().is_some_and(|n| n == name.as_str()))
+                .ok_or_else(|| format!("{} is not a media file in the input folder", name))?;
+            match &mut self.project_restore {
+                // Opened once the restore is confirmed or cancelled
+                Some(restore) => restore.file = Some(name),
+                None => self.pending_file = Some(idx),
+            }
+        }
+        Ok(())
+    }
+
+    // Step by frame index rather than adding 1/fps, which drifts off frame boundaries
+    fn prev_frame(&mut self, ctx: &egui::Context) {
+        self.step_frames(ctx, -1);
+    }
+    fn next_frame(&mut self, ctx: &egui::Context) {
+        self.step_frames(ctx, 1);
+    }
+    fn step_frames(&mut self, ctx: &egui::Context, frames: i32) {
+        let frame = timing::frame_at(self.current_time, self.native_fps);
+        self.current_time = timing::frame_time(frame + frames, self.native_fps).clamp(0.0, self.duration);
+        self.update_frame(ctx);
+    }
+
+    /// Decodes the preview for a scrub position at most SCRUB_DECODES_PER_SECOND times a second.
+    fn scrub_decode(&mut self, ctx: &egui::Context) {
+        if !self.scrub_pending {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let due = self.scrub_decoded_at.map_or(now, |at| at + 1.0 / SCRUB_DECODES_PER_SECOND);
+        if now >= due {
+            self.scrub_pending = false;
+            self.scrub_decoded_at = Some(now);
+            self.update_frame(ctx);
+        } else {
+            // The pointer may rest before the next decode is due
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(due - now));
+        }
+    }
+
+    /// Time a frame box entry points at; None for a range that doesn't exist.
+    fn frame_entry_time(&self, entry: timing::FrameEntry) -> Option<f64> {
+        let range_start = |i: usize| {
+            let start = self.ranges.get(i)?.start_time;
+            Some(timing::frame_at(start, self.native_fps))
+        };
+        Some(match entry {
+            timing::FrameEntry::Native(frame) => timing::frame_time(frame, self.native_fps),
+            timing::FrameEntry::FromRangeStart(offset) => {
+                timing::frame_time(range_start(self.current_range_idx)? + offset, self.native_fps)
+            }
+            // Exported clips start on the range's first frame and run at the output rate
+            timing::FrameEntry::Clip { range, frame } => {
+                let rate = export::frame_rate(&self.export_profile, self.ranges.get(range)?);
+                timing::frame_time(range_start(range)?, self.native_fps) + frame as f64 / rate.resolve(self.native_fps)
+            }
+        })
+    }
+
+    fn update_frame(&mut self, ctx: &egui::Context) {
+        let mut frame = core::Mat::default();
+        let mut valid_frame = false;
+        let playing = self.is_playing();
+
+        // 2. Safely read from either the VideoCapture or the static Image Mat
+        if let Some(ref mut media) = self.media {
+            match media {
+                MediaSource::Video(cap) => {
+                    let mut frame_pos = timing::frame_at(self.current_time, self.native_fps);
+                    if playing {
+                        // Time advances as usual, only the frames in between aren't shown
+                        frame_pos -= frame_pos % self.preview_step;
+                    }
+                    // While scrubbing, land on the keyframe before the slider: no decoding
+                    // from an earlier keyframe, so long GOPs stay responsive
+                    if self.scrubbing
+                        && let Some(keyframe) =
+                            self.keyframes.as_ref().and_then(|k| k.before(self.current_time))
+                    {
+                        frame_pos = timing::frame_at(keyframe, self.native_fps);
+                    }
+                    if (playing || self.scrubbing) && self.decoded_frame == Some(frame_pos) {
+                        return;
+                    }
+                    // The capture already sits after the last decoded frame; for a small step
+                    // forward, decoding the frames in between beats seeking back to a keyframe
+                    match self.decoded_frame.map(|decoded| frame_pos - decoded - 1) {
+                        Some(0) => {}
+                        Some(gap @ 1..=8) => {
+                            for _ in 0..gap {
+                                let _ = cap.grab();
+                            }
+                        }
+                        _ => {
+                            let _ = cap.set(videoio::CAP_PROP_POS_FRAMES, frame_pos as f64);
+                        }
+                    }
+                    self.decoded_frame = None;
+                    if cap.read(&mut frame).unwrap_or(false) && !frame.empty() {
+                        valid_frame = true;
+                        self.decoded_frame = Some(frame_pos);
+                    }
+                }
+                MediaSource::Image(still) => {
+                    if !still.preview.empty() {
+                        still.preview.copy_to(&mut frame).unwrap();
+                        valid_frame = true;
+                    }
+                }
+            }
+        }
+
+        if valid_frame {
+            // Gray, BGRA and high bit depth frames would garble the RGB texture otherwise
+            let Ok(frame) = color::to_bgr8(&frame).and_then(|frame| self.color.apply(frame)) else {
+                return;
+            };
+            let mut rgb_frame = core::Mat::default();
+
+            opencv_has_inherent_feature_algorithm_hint! { {
+                    let _ = imgproc::cvt_color(
+                        &frame,
+                        &mut rgb_frame,
+                        imgproc::COLOR_BGR2RGB,
+                        0,
+                        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+                    );
+                } else {
+                    let _ = imgproc::cvt_color(
+                        &frame,
+                        &mut rgb_frame,
+                        imgproc::COLOR_BGR2RGB,
+                        0
+                    );
+                }
+            }
+            let size = rgb_frame.size().unwrap();
+            let data = rgb_frame.data_bytes().unwrap();
+            let color_image =
+                egui::ColorImage::from_rgb([size.width as usize, size.height as usize], data);
+            // Update the texture in place while the size stays the same, so playback doesn't
+            // allocate a new texture (and free the old one) for every frame
+            match &mut self.video_texture {
+                Some(texture) if texture.size() == color_image.size => {
+                    texture.set(color_image, Default::default());
+                }
+                _ => {
+                    self.video_texture =
+                        Some(ctx.load_texture("video-frame", color_image, Default::default()));
+                }
+            }
+            self.current_frame = Some(frame);
+            self.training_texture = None;
+        }
+    }
+
+    /// Training view of the current range's crop of the current frame, made on first use.
+    fn training_texture(&mut self, ctx: &egui::Context) -> Option<egui::TextureId> {
+        let crop = self.ranges.get(self.current_range_idx)?.crop_rect_norm.clone();
+        let resolution = self.training_resolution;
+        if let Some((made_for, res, texture)) = &self.training_texture
+            && *made_for == crop
+            && *res == resolution
+        {
+            return Some(texture.id());
+        }
+        let frame = self.current_frame.as_ref()?;
+        let (w, h) = self.frame_size()?;
+        let norm = crop.clone().unwrap_or(SerializableRect::FULL);
+        // The preview can be smaller than the source (proxies, huge stills); the output size
+        // follows the source
+        let (cw, ch, _, _) = norm.to_pixels(w, h);
+        let (pw, ph, px, py) = norm.to_pixels(frame.cols() as f64, frame.rows() as f64);
+        if cw <= 0 || ch <= 0 || pw <= 0 || ph <= 0 {
+            return None;
+        }
+        let output = tiles::output_size(cw as f64, ch as f64, resolution);
+        let image = tiles::training_view(frame, core::Rect::new(px, py, pw, ph), output).ok()?;
+        // Nearest neighbour, so the pixels the model gets stay visible instead of being smoothed
+        let texture = ctx.load_texture("training-view", image, egui::TextureOptions::NEAREST);
+        let id = texture.id();
+        self.training_texture = Some((crop, resolution, texture));
+        Some(id)
+    }
+
+    /// Full-resolution size of the open media for crop math; the preview of a huge still is
+    /// smaller.
+    fn frame_size(&self) -> Option<(f64, f64)> {
+        match self.media.as_ref()? {
+            MediaSource::Video(cap) => Some((
+                cap.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap_or(1920.0),
+                cap.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(1080.0),
+            )),
+            MediaSource::Image(still) => Some(still.size),
+        }
+    }
+
+    fn auto_crop(&mut self) {
+        let Some(frame) = &self.current_frame else {
+            return;
+        };
+        if self.ranges.is_empty() {
+            return;
+        }
+        // The choices are display aspects; the frame is in stored (possibly anamorphic) pixels
+        let aspect = AUTO_CROP_ASPECTS[self.auto_crop_aspect_idx].1 / self.pixel_aspect as f32;
+        match saliency::suggest_crop(frame, aspect) {
+            Ok(rect) => {
+                self.ranges[self.current_range_idx].crop_rect_norm = Some(rect);
+                self.touch();
+            }
+            Err(e) => tracing::warn!(target: logs::ANALYSIS, "Auto crop failed: {}", e),
+        }
+    }
+
+    /// Proposes the crop of the previous range (or else the next one) moved to where its content
+    /// is in the current frame. The neighbour's frame closest to this range is the reference.
+    fn match_crop(&mut self) {
+        let idx = self.current_range_idx;
+        let neighbour = [idx.checked_sub(1), Some(idx + 1)]
+            .into_iter()
+            .flatten()
+            .find_map(|i| Some((i, self.ranges.get(i)?.crop_rect_norm.clone()?)));
+        let (Some((other, crop)), Some(target), Some(MediaSource::Video(cap))) =
+            (neighbour, &self.current_frame, &mut self.media)
+        else {
+            self.preview_note = Some(tr("Neither neighbour range has a crop to match").to_string());
+            return;
+        };
+        let time = if other < idx {
+            self.ranges[other].end_time - 1.0 / self.native_fps
+        } else {
+            self.ranges[other].start_time
+        };
+        let proposal = match Self::read_frame(cap, timing::frame_at(time.max(0.0), self.native_fps)) {
+            Some(reference) => color::to_bgr8(&reference)
+                .and_then(|reference| propagate::match_crop(&reference, &crop, target)),
+            None => Ok(None),
+        };
+        // The capture moved away from the shown frame; the next update seeks back
+        self.decoded_frame = None;
+        match proposal {
+            Ok(Some(proposal)) => self.crop_proposal = Some((idx, proposal)),
+            Ok(None) => {
+                self.preview_note = Some(trf("The crop of range {} was not found in this frame", &[&other]));
+            }
+            Err(e) => tracing::warn!(target: logs::ANALYSIS, "Crop matching failed: {}", e),
+        }
+    }
+
+    /// Native frame `frame` of the preview capture, which is left wherever it lands.
+    fn read_frame(cap: &mut videoio::VideoCapture, frame: i32) -> Option<core::Mat> {
+        if frame < 0 {
+            return None;
+        }
+        let _ = cap.set(videoio::CAP_PROP_POS_FRAMES, frame as f64);
+        let mut mat = core::Mat::default();
+        (cap.read(&mut mat).unwrap_or(false) && !mat.empty()).then_some(mat)
+    }
+
+    fn show_cut_check(&mut self, ctx: &egui::Context) {
+        if !self.show_cut_check || self.is_image {
+            return;
+        }
+        let Some(range) = self.ranges.get(self.current_range_idx) else {
+            return;
+        };
+        let times = (range.start_time, range.end_time);
+        if self.cut_check.as_ref().is_none_or(|c| c.times != times)
+            && let Some(MediaSource::Video(cap)) = &mut self.media
+        {
+            let frames = boundary::frames(
+                timing::frame_at(times.0, self.native_fps),
+                timing::frame_at(times.1, self.native_fps),
+            );
+            let textures = frames.map(|frame| {
+                let image = boundary::image(&Self::read_frame(cap, frame)?, self.color).ok()?;
+                Some(ctx.load_texture("cut-check", image, Default::default()))
+            });
+            self.decoded_frame = None;
+            self.cut_check = Some(boundary::Check { times, frames, textures });
+        }
+        if let Some(check) = &self.cut_check {
+            boundary::show(ctx, &mut self.show_cut_check, check);
+        }
+    }
+
+    fn run_text_scan(&self) {
+        let (Some(idx), Some(model_path)) = (self.selected_file_idx, &self.text_model_path) else {
+            return;
+        };
+        let media = self.videos[idx].clone();
+        let model_path = model_path.clone();
+        let is_image = self.is_image;
+        let ranges = self.ranges.clone();
+        let running = self.is_scanning_text.clone();
+        let result = self.text_scan_result.clone();
+
+        running.store(true, atomic::Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let scans = text_detect::scan_ranges(&media, is_image, &ranges, &model_path);
+            *result.lock().unwrap() = Some((media, ranges, scans));
+            running.store(false, atomic::Ordering::SeqCst);
+        });
+    }
+
+    fn apply_text_scan_result(&mut self) {
+        let Some((media, scanned, scans)) = self.text_scan_result.lock().unwrap().take() else {
+            return;
+        };
+        // Drop results for a file that is no longer loaded, or ranges added, removed, moved or
+        // edited while the scan ran
+        if self.selected_file_idx.map(|i| &self.videos[i]) != Some(&media) {
+            return;
+        }
+        let same = |a: &VideoRange, b: &VideoRange| {
+            a.start_time == b.start_time && a.end_time == b.end_time && a.crop_rect_norm == b.crop_rect_norm
+        };
+        if scanned.len() != self.ranges.len() || !scanned.iter().zip(&self.ranges).all(|(a, b)| same(a, b)) {
+            return;
+        }
+        match scans {
+            Ok(scans) => {
+                self.text_scan_error = None;
+                for (range, scan) in self.ranges.iter_mut().zip(scans) {
+                    range.text_scan = Some(scan);
+                }
+            }
+            Err(e) => self.text_scan_error = Some(e),
+        }
+    }
+
+    /// Transcribes the speech of the current range on a worker thread.
+    fn run_transcription(&self) {
+        let (Some(idx), Some(model)) = (self.selected_file_idx, &self.whisper_model) else {
+            return;
+        };
+        let Some(range) = self.ranges.get(self.current_range_idx).cloned() else {
+            return;
+        };
+        let media = self.videos[idx].clone();
+        let model = model.clone();
+        let range_idx = self.current_range_idx;
+        let running = self.is_transcribing.clone();
+        let result = self.transcript_result.clone();
+
+        running.store(true, atomic::Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let text = transcribe::range(&media, &range, &model);
+            *result.lock().unwrap() = Some((media, range_idx, text));
+            running.store(false, atomic::Ordering::SeqCst);
+        });
+    }
+
+    /// Finds the black and frozen frames at the ends of the current range on a worker thread,
+    /// as the export would with edge trimming on.
+    fn run_trim_preview(&self, ctx: &egui::Context) {
+        let Some(idx) = self.selected_file_idx else {
+            return;
+        };
+        let Some(range) = self.ranges.get(self.current_range_idx).cloned() else {
+            return;
+        };
+        let (media, range_idx, fps) = (self.videos[idx].clone(), self.current_range_idx, self.native_fps);
+        let (running, result, ctx) = (self.is_checking_trim.clone(), self.trim_preview.clone(), ctx.clone());
+
+        running.store(true, atomic::Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let trim = trim::edges(&media, &range, fps);
+            *result.lock().unwrap() = Some((media, range_idx, range, trim));
+            running.store(false, atomic::Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// The edge trim preview of the current range, if one was made for it as it is now.
+    fn current_trim_preview(&self) -> Option<Result<trim::Trim, String>> {
+        let media = self.videos.get(self.selected_file_idx?)?;
+        let range = self.ranges.get(self.current_range_idx)?;
+        let preview = self.trim_preview.lock().unwrap();
+        let (checked, idx, checked_range, trim) = preview.as_ref()?;
+        (checked == media
+            && *idx == self.current_range_idx
+            && checked_range.start_time == range.start_time
+            && checked_range.end_time == range.end_time)
+            .then(|| trim.clone())
+    }
+
+    /// Appends a finished transcript to the note of the range it was made for.
+    fn apply_transcript_result(&mut self) {
+        let Some((media, range_idx, text)) = self.transcript_result.lock().unwrap().take() else {
+            return;
+        };
+        if self.selected_file_idx.map(|i| &self.videos[i]) != Some(&media) {
+            return;
+        }
+        match text {
+            Ok(text) if text.is_empty() => self.transcript_error = Some(tr("No speech recognized.").to_string()),
+            Ok(text) => {
+                self.transcript_error = None;
+                if let Some(range) = self.ranges.get_mut(range_idx) {
+                    if !range.note.trim().is_empty() {
+                        range.note.push('\n');
+                    }
+                    range.note += &text;
+                    range.stamp(&self.annotator);
+                }
+            }
+            Err(e) => self.transcript_error = Some(e),
+        }
+    }
+
+    fn start_proxy(&mut self, source: &Path, hdr: bool) {
+        let source = source.to_path_buf();
+        let running = self.is_building_proxy.clone();
+        let result = self.proxy_result.clone();
+        self.preview_note = Some(tr("Building 8-bit preview proxy…").to_string());
+
+        running.store(true, atomic::Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let proxy = proxy::build(&source, hdr);
+            *result.lock().unwrap() = Some((source, proxy));
+            running.store(false, atomic::Ordering::SeqCst);
+        });
+    }
+
+    fn apply_proxy_result(&mut self, ctx: &egui::Context) {
+        let Some((source, proxy)) = self.proxy_result.lock().unwrap().take() else {
+            return;
+        };
+        if self.selected_file_idx.map(|i| &self.videos[i]) != Some(&source) {
+            return;
+        }
+        let cap = proxy.and_then(|p| {
+            videoio::VideoCapture::from_file(p.to_str().unwrap(), videoio::CAP_ANY)
+                .ok()
+                .filter(|c| c.is_opened().unwrap_or(false))
+                .map(|cap| (p.clone(), cap))
+                .ok_or_else(|| trf("Could not open preview proxy {}", &[&format!("{:?}", p)]))
+        });
+        match cap {
+            Ok((proxy, cap)) => {
+                self.media = Some(MediaSource::Video(cap));
+                self.decoded_frame = None;
+                // The proxy has keyframes of its own
+                self.start_keyframe_index(&proxy);
+                self.preview_note =
+                    Some(tr("Preview: 8-bit proxy (export uses the original)").to_string());
+                self.update_frame(ctx);
+            }
+            Err(e) => {
+                tracing::warn!(target: logs::DECODE, "{}", e);
+                self.preview_note = Some(e);
+            }
+        }
+    }
+
+    /// Indexes the keyframes of `file`, the file the preview capture reads from.
+    fn start_keyframe_index(&mut self, file: &Path) {
+        let file = file.to_path_buf();
+        let result = self.keyframe_result.clone();
+        self.preview_file = Some(file.clone());
+        self.keyframes = None;
+        std::thread::spawn(move || {
+            let index = keyframes::build(&file);
+            *result.lock().unwrap() = Some((file, index));
+        });
+    }
+
+    fn apply_keyframe_result(&mut self) {
+        let Some((file, index)) = self.keyframe_result.lock().unwrap().take() else {
+            return;
+        };
+        // Drop indexes of a file that is no longer previewed
+        if self.preview_file.as_ref() != Some(&file) {
+            return;
+        }
+        match index {
+            Ok(index) => self.keyframes = Some(index),
+            Err(e) => tracing::warn!(target: logs::DECODE, "Keyframe index unavailable: {}", e),
+        }
+    }
+
+    fn start_subtitle_load(&mut self, file: &Path) {
+        let file = file.to_path_buf();
+        let result = self.subtitle_result.clone();
+        self.subtitles = None;
+        std::thread::spawn(move || {
+            let track = subtitles::load(&file);
+            *result.lock().unwrap() = Some((file, track));
+        });
+    }
+
+    fn apply_subtitle_result(&mut self) {
+        let Some((file, track)) = self.subtitle_result.lock().unwrap().take() else {
+            return;
+        };
+        if self.selected_file_idx.and_then(|i| self.videos.get(i)) != Some(&file) {
+            return;
+        }
+        match track {
+            Ok(track) => self.subtitles = track,
+            Err(e) => tracing::warn!(target: logs::DECODE, "Subtitles unavailable: {}", e),
+        }
+    }
+
+    /// Text of range `i` in the range list: times and frames, warnings, split, tags and who
+    /// changed it last.
+    fn range_label(&self, i: usize) -> String {
+        let range = &self.ranges[i];
+        let mut label_text = if self.is_image {
+            trf("Crop {}", &[&i])
+        } else {
+            let duration = range.end_time - range.start_time;
+            let rate = export::frame_rate(&self.export_profile, range).resolve(self.native_fps);
+            let frame_count = (duration * rate).round() as i32;
+            let start_frame = timing::frame_at(range.start_time, self.native_fps);
+            let end_frame = timing::frame_at(range.end_time, self.native_fps);
+
+            trf(
+                "R{}: {}s - {}s ({}s)\n      {} - {} ({} frames)",
+                &[
+                    &i,
+                    &format!("{:.1}", range.start_time),
+                    &format!("{:.1}", range.end_time),
+                    &format!("{:.1}", duration),
+                    &start_frame,
+                    &end_frame,
+                    &frame_count,
+                ],
+            )
+        };
+
+        if let Some(scan) = &range.text_scan
+            && scan.hits(range.crop_rect_norm.as_ref())
+        {
+            label_text += &trf(
+                "\n⚠ Text in {}/{} sampled frames",
+                &[&scan.frames_with_text, &scan.frames_sampled],
+            );
+        }
+
+        if !self.is_image && range.is_untouched(self.duration) {
+            label_text += tr("\n⚠ Untouched: the whole video, no crop or note");
+        }
+
+        for known in self.exported_material(range) {
+            let source = format!("{} R{}", known.span.source, known.span.range);
+            label_text += &match &known.project {
+                Some(project) => trf(
+                    "\n⚠ Already exported as {} in {}",
+                    &[&source, &project.display()],
+                ),
+                None => trf("\n⚠ Already exported as {}", &[&source]),
+            };
+        }
+
+        if let Some(split) = range.split {
+            label_text += &format!("  [{}]", tr(split.label()));
+        }
+        if !range.tags.is_empty() {
+            label_text += &format!("\n🏷 {}", range.tags.join(", "));
+        }
+
+        if let Some(modified) = range.modified_label() {
+            label_text += &format!("\n{}", modified);
+        }
+        label_text
+    }
+
+    /// Exported material that `range` of the open file mostly repeats, by the file's content
+    /// hash. Nothing until the background analysis has hashed the file.
+    fn exported_material(&self, range: &VideoRange) -> Vec<&material::Known> {
+        let (Some(idx), Some((_, _, known))) = (self.selected_file_idx, &self.material) else {
+            return Vec::new();
+        };
+        let media = &self.videos[idx];
+        let Some(analysis::Status::Done(report)) = self.analyzer.status(media) else {
+            return Vec::new();
+        };
+        let (Some(hash), Some(source)) = (report.hash, media.file_name()) else {
+            return Vec::new();
+        };
+        let source = source.to_string_lossy();
+        material::overlaps(known, &hash, &source, range.start_time, range.end_time)
+    }
+
+    /// Decodes the hover preview of range `i` on a worker thread, one range at a time.
+    fn request_range_preview(&mut self, i: usize) {
+        let (Some(file), Some(range)) = (&self.preview_file, self.ranges.get(i)) else {
+            return;
+        };
+        let key = range_preview::key(range);
+        if self.range_preview_pending.is_some() || self.range_previews.contains_key(&key) {
+            return;
+        }
+        self.range_preview_pending = Some(key);
+        let (file, range, result) = (file.clone(), range.clone(), self.range_preview_result.clone());
+        let pipeline = self.color;
+        std::thread::spawn(move || {
+            let frames = range_preview::decode(&file, &range, pipeline);
+            *result.lock().unwrap() = Some((file, key, frames));
+        });
+    }
+
+    fn apply_range_preview_result(&mut self, ctx: &egui::Context) {
+        let Some((file, key, frames)) = self.range_preview_result.lock().unwrap().take() else {
+            return;
+        };
+        self.range_preview_pending = None;
+        if self.preview_file.as_ref() != Some(&file) {
+            return;
+        }
+        // An empty entry keeps a failing range from being decoded again on every hover
+        let frames = frames.unwrap_or_else(|e| {
+            tracing::warn!(target: logs::DECODE, "Range preview failed: {}", e);
+            Vec::new()
+        });
+        let textures = frames
+            .into_iter()
+            .map(|frame| ctx.load_texture("range-preview", frame, Default::default()))
+            .collect();
+        self.range_previews.insert(key, textures);
+    }
+
+    /// Opens file `idx` on a worker thread. The current file stays on screen until it's ready;
+    /// picking another file meanwhile abandons this load.
+    fn start_loading(&mut self, ctx: &egui::Context, idx: usize) {
+        let path = self.videos[idx].clone();
+        if !path.exists() {
+            self.missing.insert(path);
+            self.relink = Some(idx);
+            return;
+        }
+        let max_side = ctx.input(|i| i.max_texture_side).min(MAX_STILL_PREVIEW_SIDE);
+        let slot = Arc::new(Mutex::new(None));
+        self.loading = Some((idx, path.clone(), slot.clone()));
+        std::thread::spawn(move || {
+            let opened = open_media(&path, max_side);
+            *slot.lock().unwrap() = Some(opened);
+        });
+    }
+
+    fn apply_loaded_media(&mut self, ctx: &egui::Context) {
+        let Some((idx, path, slot)) = &self.loading else {
+            return;
+        };
+        let Some(opened) = slot.lock().unwrap().take() else {
+            return;
+        };
+        let (idx, path) = (*idx, path.clone());
+        self.loading = None;
+        // The folder was reopened while loading
+        if self.videos.get(idx) != Some(&path) {
+            return;
+        }
+
+        if let Some(previous) = self.selected_file_idx.and_then(|i| self.videos.get(i)) {
+            self.session.record(previous, &self.annotated_ranges());
+        }
+        self.save_sidecar();
+        self.metrics.count("files_opened");
+        if let Err(e) = self.metrics.flush() {
+            tracing::warn!(target: logs::UI, "{}", e);
+        }
+        self.selected_file_idx = Some(idx);
+        self.stale_outputs = None;
+        self.view = view::View::default();
+        self.timeline = timeline::Timeline::default();
+        self.preview_file = None;
+        self.keyframes = None;
+        self.decoded_frame = None;
+        self.video_texture = None;
+        self.is_image = opened.is_image;
+        self.native_fps = opened.native_fps;
+        self.pixel_aspect = opened.pixel_aspect;
+        self.color = opened.color;
+        self.duration = opened.duration;
+        self.preview_note = opened.preview_note;
+        self.chapters = opened.chapters;
+        self.ranges = vec![VideoRange {
+            end_time: self.duration,
+            note: opened.note,
+            ..Default::default()
+        }];
+        self.current_range_idx = 0;
+        self.default_crop = None;
+        self.crop_proposal = None;
+        self.cut_check = None;
+        self.range_previews.clear();
+        self.current_time = 0.0;
+        self.media = opened.media;
+        // Indexing keyframes, reading subtitles and building a proxy go through the whole file,
+        // which for a remote source means downloading it; the preview works without them
+        let is_remote = remote::is_link(&path);
+        if matches!(self.media, Some(MediaSource::Video(_))) && !is_remote {
+            self.start_keyframe_index(&path);
+        }
+        if let Some(hdr) = opened.proxy.filter(|_| !is_remote) {
+            self.start_proxy(&path, hdr);
+        }
+        if self.is_image || is_remote {
+            self.subtitles = None;
+        } else {
+            self.start_subtitle_load(&path);
+        }
+        self.update_frame(ctx);
+
+        // Saved annotations replace the defaults
+        let readable = !matches!(opened.sidecar, Some(Err(_)));
+        match opened.sidecar {
+            Some(Ok(ranges)) if !ranges.is_empty() => {
+                self.ranges = ranges;
+                self.current_range_idx = 0;
+            }
+            Some(Err(e)) => self.preview_note = Some(e),
+            _ => {}
+        }
+        self.sidecar_snapshot = sidecar::to_json(&self.annotated_ranges()).ok().filter(|_| readable);
+        self.session.record(&path, &self.annotated_ranges());
+    }
+
+    /// Writes the open file's annotations to its sidecar if they changed since it was loaded or
+    /// last saved. A file without annotations only gets a sidecar once it has some.
+    fn save_sidecar(&mut self) {
+        let Some(media) = self.selected_file_idx.and_then(|i| self.videos.get(i)) else {
+            return;
+        };
+        let ranges = self.annotated_ranges();
+        let Ok(json) = sidecar::to_json(&ranges) else {
+            return;
+        };
+        if self.read_only || self.sidecar_snapshot.is_none() {
+            return;
+        }
+        if self.sidecar_snapshot.as_ref() != Some(&json) {
+            if let Err(e) = sidecar::save(media, &ranges) {
+                // The journal keeps them until a save succeeds
+                *self.export_error.lock().unwrap() = Some(e);
+                return;
+            }
+            self.sidecar_snapshot = Some(json);
+        }
+        if self.journal.take().is_some() {
+            journal::clear(&journal::path());
+        }
+    }
+
+    /// Writes the open file's unsaved annotations to the crash recovery journal every
+    /// `journal::INTERVAL` seconds, if they changed since the last write.
+    fn autosave(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        // A journal waiting to be restored is not overwritten
+        if now - self.journal_checked_at < journal::INTERVAL || self.read_only || self.recovery.is_some() {
+            return;
+        }
+        self.journal_checked_at = now;
+        let Some(media) = self.selected_file_idx.and_then(|i| self.videos.get(i)) else {
+            return;
+        };
+        let ranges = self.annotated_ranges();
+        let Ok(json) = sidecar::to_json(&ranges) else {
+            return;
+        };
+        if self.sidecar_snapshot.as_ref().is_none_or(|saved| *saved == json) || self.journal.as_ref() == Some(&json) {
+            return;
+        }
+        let entry = journal::Journal {
+            input: self.input_folder.clone(),
+            output: self.output_folder.clone(),
+            media: media.clone(),
+            ranges,
+            written_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        };
+        match journal::save(&journal::path(), &entry) {
+            Ok(()) => self.journal = Some(json),
+            Err(e) => tracing::warn!(target: logs::UI, "{}", e),
+        }
+    }
+
+    /// Opens what later launches handed over and brings the window to the front.
+    fn apply_handoffs(&mut self, ctx: &egui::Context) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        let requests = instance.take_requests();
+        if requests.is_empty() {
+            return;
+        }
+        for args in requests {
+            if let Err(e) = self.apply_args(args) {
+                self.preview_note = Some(e);
+            }
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// Background jobs that write files or run FFmpeg, by name.
+    fn running_jobs(&self) -> Vec<&'static str> {
+        let running = |flag: &AtomicBool| flag.load(atomic::Ordering::SeqCst);
+        let mut jobs = Vec::new();
+        if running(&self.is_exporting) {
+            jobs.push(tr("Export"));
+        }
+        if running(&self.is_qa_exporting) {
+            jobs.push(tr("QA sample export"));
+        }
+        if running(&self.is_reel_exporting) {
+            jobs.push(tr("Reel export"));
+        }
+        if self.analyzer.is_running() {
+            jobs.push(tr("File analysis"));
+        }
+        jobs
+    }
+
+    /// Holds closing the window while jobs are running, so FFmpeg isn't orphaned mid-clip, and
+    /// closes it once they are done when the user chose to quit.
+    fn handle_close(&mut self, ctx: &egui::Context) {
+        let idle = self.running_jobs().is_empty();
+        if self.quit_when_idle {
+            // A second close request while waiting goes through; on_exit still kills FFmpeg
+            if idle {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            } else {
+                ctx.request_repaint_after(std::time::Duration::from_millis(200));
+            }
+        } else if !idle && ctx.input(|i| i.viewport().close_requested()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.close_prompt = true;
+        }
+    }
+
+    /// Asks how to quit while jobs are running: after the current clip, right away (killing
+    /// FFmpeg and removing what it was writing) or not at all.
+    fn show_close_prompt(&mut self, ctx: &egui::Context) {
+        if !self.close_prompt {
+            return;
+        }
+        let jobs = self.running_jobs().join(", ");
+        let (mut finish, mut stop, mut stay) = (false, false, false);
+        egui::Window::new(tr("⚠ Jobs Still Running"))
+            .id(egui::Id::new("close_prompt"))
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.quit_when_idle {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(trf("Quitting once these are done: {}", &[&jobs]));
+                    });
+                    stop = ui.button(tr("⏹ Stop Now and Quit")).clicked();
+                    return;
+                }
+                ui.label(trf("Still running: {}", &[&jobs]));
+                ui.horizontal(|ui| {
+                    finish = ui
+                        .button(tr("Finish Current Clip, Then Quit"))
+                        .on_hover_text(tr("Skips the remaining ranges; QA samples and reels run to the end"))
+                        .clicked();
+                    stop = ui
+                        .button(tr("⏹ Stop Now and Quit"))
+                        .on_hover_text(tr("Kills FFmpeg and removes the clip it was writing"))
+                        .clicked();
+                    stay = ui.button(tr("Keep Running")).clicked();
+                });
+            });
+        if finish || stop {
+            self.schedule.set_cancelled(true);
+            // Stops after the task in progress, so the cache never holds half a result
+            self.analyzer.stop();
+            self.quit_when_idle = true;
+        }
+        if stop {
+            self.progress.kill();
+            self.reel_progress.kill();
+        }
+        if stay {
+            self.close_prompt = false;
+        }
+    }
+
+    /// Offers to restore the annotations in the journal of a session that didn't end cleanly.
+    /// Restoring writes them to the media's sidecar, then opens its folder and the file.
+    fn show_recovery(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = &self.recovery else {
+            return;
+        };
+        let (mut restore, mut discard) = (false, false);
+        egui::Window::new(tr("⚠ Unsaved Annotations"))
+            .id(egui::Id::new("recovery"))
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "The last session ended without saving {} ranges of {} (autosaved {}).",
+                    &[&recovery.ranges.len(), &recovery.media.display(), &recovery.written_at],
+                ));
+                if self.read_only {
+                    ui.label(tr("Start without --read-only to restore them."));
+                }
+                ui.horizontal(|ui| {
+                    restore = ui
+                        .add_enabled(!self.read_only, egui::Button::new(tr("Restore")))
+                        .on_hover_text(tr("Write them to the file's sidecar and open the file"))
+                        .clicked();
+                    discard = ui.add_enabled(!self.read_only, egui::Button::new(tr("Discard"))).clicked();
+                });
+            });
+        if restore {
+            let recovery = self.recovery.take().unwrap();
+            if let Err(e) = sidecar::save(&recovery.media, &recovery.ranges) {
+                *self.export_error.lock().unwrap() = Some(e);
+                self.recovery = Some(recovery);
+                return;
+            }
+            journal::clear(&journal::path());
+            // Folders given on the command line stay
+            if self.input_folder.is_none()
+                && let Some(dir) = recovery.input.filter(|d| d.is_dir())
+            {
+                self.open_input_folder(dir);
+            }
+            if self.output_folder.is_none() {
+                self.output_folder = recovery.output;
+            }
+            if let Some(idx) = self.videos.iter().position(|v| *v == recovery.media) {
+                // Reloaded from the restored sidecar, which the stale ranges must not overwrite
+                if self.selected_file_idx == Some(idx) {
+                    self.sidecar_snapshot = None;
+                }
+                self.pending_file = Some(idx);
+            }
+        } else if discard {
+            self.recovery = None;
+            journal::clear(&journal::path());
+        }
+    }
+
+    fn show_session(&mut self, ctx: &egui::Context) {
+        if !self.show_session {
+            return;
+        }
+        if let Some(file) = self.selected_file_idx.and_then(|i| self.videos.get(i)) {
+            self.session.record(file, &self.annotated_ranges());
+        }
+        let (metrics, status) = (&self.metrics, &mut self.metrics_status);
+        stats::show(ctx, &mut self.show_session, &self.session.summary(), |ui| {
+            metrics::ui(ui, metrics, status)
+        });
+    }
+
+    /// Refreshes which files of the list are gone, every few seconds. Offers to relink the open
+    /// file when it disappears.
+    fn check_missing(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        if now - self.missing_checked_at < 2.0 {
+            return;
+        }
+        self.missing_checked_at = now;
+        let missing: HashSet<PathBuf> = self.videos.iter().filter(|p| !p.exists()).cloned().collect();
+        if let Some(idx) = self.selected_file_idx
+            && missing.contains(&self.videos[idx])
+            && !self.missing.contains(&self.videos[idx])
+        {
+            self.relink = Some(idx);
+        }
+        self.missing = missing;
+    }
+
+    /// Points list entry `idx` at `new`, taking its caption and sidecar along when they didn't
+    /// move with it.
+    fn relink_media(&mut self, ctx: &egui::Context, idx: usize, new: PathBuf) {
+        let old = std::mem::replace(&mut self.videos[idx], new.clone());
+        self.missing.remove(&old);
+        for (from, to) in [
+            (sidecar::path_for(&old), sidecar::path_for(&new)),
+            (old.with_extension("txt"), new.with_extension("txt")),
+        ] {
+            if from.exists()
+                && !to.exists()
+                && fs::rename(&from, &to).or_else(|_| fs::copy(&from, &to).map(|_| ())).is_err()
+            {
+                self.preview_note = Some(trf("Could not move {} to {}", &[&from.display(), &to.display()]));
+            }
+        }
+        // Keep the ranges of the open file; only reopen when nothing could be shown
+        if self.selected_file_idx != Some(idx) || self.media.is_none() {
+            self.start_loading(ctx, idx);
+        }
+    }
+
+    fn show_relink(&mut self, ctx: &egui::Context) {
+        let Some(idx) = self.relink else {
+            return;
+        };
+        let path = self.videos[idx].clone();
+        let (mut open, mut locate, mut cancel) = (true, false, false);
+        egui::Window::new(tr("⚠ File Not Found"))
+            .id(egui::Id::new("relink"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(trf("{} was moved or deleted.", &[&path.display()]));
+                ui.label(tr("Locate it to keep working with its annotations."));
+                ui.horizontal(|ui| {
+                    locate = ui.button(tr("📂 Locate…")).clicked();
+                    cancel = ui.button(tr("Cancel")).clicked();
+                });
+            });
+        if locate {
+            let mut dialog = rfd::FileDialog::new();
+            if let Some(name) = path.file_name() {
+                dialog = dialog.set_file_name(name.to_string_lossy());
+            }
+            if let Some(dir) = path.parent().filter(|d| d.is_dir()).or(self.input_folder.as_deref()) {
+                dialog = dialog.set_directory(dir);
+            }
+            if let Some(new) = dialog.pick_file() {
+                self.relink = None;
+                self.relink_media(ctx, idx, new);
+            }
+        } else if cancel || !open {
+            self.relink = None;
+        }
+    }
+
+    /// Samples `qa_percent` of the annotated ranges across the whole input folder (the open
+    /// file's current ranges, the sidecars of all others) into a review folder for spot checks.
+    fn run_qa_export(&self) {
+        let Some(out_dir) = self.output_folder.clone() else {
+            *self.qa_status.lock().unwrap() = Some(tr("Choose an output folder first.").into());
+            return;
+        };
+        let (annotations, mut errors) = self.folder_annotations();
+        let mut candidates = Vec::new();
+        for (media, ranges) in annotations {
+            candidates.extend(ranges.into_iter().enumerate().map(|(range_idx, range)| {
+                qa::Sample {
+                    media: media.clone(),
+                    range_idx,
+                    range,
+                }
+            }));
+        }
+        if candidates.is_empty() {
+            errors.insert(0, tr("No annotated ranges to sample.").into());
+            *self.qa_status.lock().unwrap() = Some(errors.join("\n"));
+            return;
+        }
+
+        let total = candidates.len();
+        // A fresh seed each run, recorded in the review list so a sample can be reproduced
+        let seed = fastrand::u64(..);
+        let percent = self.qa_percent;
+        let samples = qa::pick(candidates, percent, seed);
+        self.is_qa_exporting.store(true, atomic::Ordering::SeqCst);
+        *self.qa_status.lock().unwrap() = None;
+        let busy = self.is_qa_exporting.clone();
+        let status = self.qa_status.clone();
+        std::thread::spawn(move || {
+            let mut lines = vec![match qa::export(&samples, &out_dir, percent, seed) {
+                Ok(dir) => trf(
+                    "Sampled {} of {} ranges into {}",
+                    &[&samples.len(), &total, &dir.display()],
+                ),
+                Err(e) => e,
+            }];
+            lines.extend(errors);
+            *status.lock().unwrap() = Some(lines.join("\n"));
+            busy.store(false, atomic::Ordering::SeqCst);
+        });
+    }
+
+    /// Asks GitHub for the latest release on a worker thread.
+    fn check_for_updates(&self, ctx: &egui::Context) {
+        self.is_checking_update.store(true, atomic::Ordering::SeqCst);
+        let busy = self.is_checking_update.clone();
+        let result = self.update_check.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let outcome = update::check();
+            match &outcome {
+                Ok(found) => tracing::info!(target: logs::UI, "Update check: {:?}", found),
+                Err(e) => tracing::warn!(target: logs::UI, "Update check failed: {}", e),
+            }
+            *result.lock().unwrap() = Some(outcome);
+            busy.store(false, atomic::Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Cuts the ranges of the open file, or of every annotated file of the folder, into one reel
+    /// in the output folder.
+    fn run_reel_export(&self, whole_folder: bool) {
+        let Some(out_dir) = self.output_folder.clone() else {
+            *self.reel_status.lock().unwrap() = Some(tr("Choose an output folder first.").into());
+            return;
+        };
+        let (annotations, mut errors) = match self.selected_file_idx {
+            _ if whole_folder => self.folder_annotations(),
+            Some(idx) => (vec![(self.videos[idx].clone(), self.annotated_ranges())], Vec::new()),
+            None => (Vec::new(), Vec::new()),
+        };
+        let profile = self.export_profile.clone();
+        let separators = self.reel_separators;
+        self.is_reel_exporting.store(true, atomic::Ordering::SeqCst);
+        *self.reel_status.lock().unwrap() = None;
+        let busy = self.is_reel_exporting.clone();
+        let status = self.reel_status.clone();
+        let progress = self.reel_progress.clone();
+        std::thread::spawn(move || {
+            let mut jobs = Vec::new();
+            for (media, ranges) in annotations {
+                match dataset::Job::probe(media, ranges, profile.clone()) {
+                    Ok(job) => jobs.push(job),
+                    Err(e) => errors.push(e),
+                }
+            }
+            let mut lines = vec![match reel::export(&jobs, &out_dir, separators, profile.container, &progress) {
+                Ok((file, count)) => trf("Wrote a reel of {} ranges to {}", &[&count, &file.display()]),
+                Err(e) => e,
+            }];
+            lines.extend(errors);
+            *status.lock().unwrap() = Some(lines.join("\n"));
+            busy.store(false, atomic::Ordering::SeqCst);
+        });
+    }
+
+    fn run_export(&mut self) {
+        let (Some(idx), Some(out_dir)) = (self.selected_file_idx, &self.output_folder) else {
+            return;
+        };
+        if !self.videos[idx].exists() {
+            self.relink = Some(idx);
+            return;
+        }
+        let untouched: Vec<String> = (0..self.ranges.len())
+            .filter(|i| !self.is_image && self.ranges[*i].is_untouched(self.duration))
+            .map(|i| i.to_string())
+            .collect();
+        if !untouched.is_empty() {
+            let confirmed = rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title(tr("Run Export"))
+                .set_description(trf(
+                    "Range(s) {} still cover the whole video without crop, note or tags. Export anyway?",
+                    &[&untouched.join(", ")],
+                ))
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if confirmed != rfd::MessageDialogResult::Yes {
+                return;
+            }
+        }
+        if !self.confirm_collisions(out_dir, &[(&self.videos[idx], self.ranges.as_slice())]) {
+            return;
+        }
+        let job = self.open_job(idx);
+        let out_dir = out_dir.clone();
+        let schedule = self.schedule.clone();
+        let progress = self.progress.clone();
+
+        self.spawn_dataset_export(out_dir.clone(), move || {
+            let mut manifest = match manifest::Manifest::load(&out_dir) {
+                Ok(manifest) => manifest,
+                Err(e) => return Some(e),
+            };
+            let source = job.source_name();
+            let previous: Vec<_> =
+                manifest.entries.iter().filter(|e| e.source == source).cloned().collect();
+            let outcome = dataset::export_media(&job, &out_dir, &previous, &schedule, &progress);
+            tracing::info!(target: logs::EXPORT, "{} of {} ranges unchanged", outcome.unchanged, job.ranges.len());
+            let message = outcome.message();
+            let fresh: Vec<_> = outcome.entries.iter().flat_map(|e| e.files()).cloned().collect();
+            material::record(&out_dir, &job.input, &outcome.entries);
+            manifest.replace_source(&source, outcome.entries);
+            if let Err(e) = manifest.save(&out_dir) {
+                return message.or(Some(e));
+            }
+            checksum::record(&out_dir, &manifest, &fresh);
+            message
+        });
+    }
+
+    /// The open file `idx` as an export job, with its current ranges.
+    fn open_job(&self, idx: usize) -> dataset::Job {
+        let frame_size = self.frame_size().unwrap_or((1920.0, 1080.0));
+        dataset::Job {
+            input: self.videos[idx].clone(),
+            ranges: self.ranges.clone(),
+            fps: self.native_fps,
+            frame_size,
+            pixel_aspect: self.pixel_aspect,
+            profile: self.export_profile.clone(),
+        }
+    }
+
+    /// Works out the FFmpeg commands `run_export` would run for the open file, on a worker
+    /// thread since edge trimming and keyframe snapping read the source, and opens the window
+    /// listing them.
+    fn preview_commands(&mut self) {
+        let (Some(idx), Some(out_dir)) = (self.selected_file_idx, self.output_folder.clone()) else {
+            return;
+        };
+        let job = self.open_job(idx);
+        let commands = Arc::new(Mutex::new(None));
+        self.command_preview = Some(commands.clone());
+        std::thread::spawn(move || {
+            let lines = match manifest::Manifest::load(&out_dir) {
+                Ok(manifest) => {
+                    let source = job.source_name();
+                    let previous: Vec<_> =
+                        manifest.entries.into_iter().filter(|e| e.source == source).collect();
+                    dataset::dry_run(&job, &out_dir, &previous)
+                }
+                Err(e) => vec![e],
+            };
+            *commands.lock().unwrap() = Some(lines);
+        });
+    }
+
+    fn show_command_preview(&mut self, ctx: &egui::Context) {
+        let Some(commands) = self.command_preview.clone() else {
+            return;
+        };
+        let mut open = true;
+        let lines = commands.lock().unwrap().clone();
+        egui::Window::new(tr("🔍 Export Commands"))
+            .open(&mut open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| {
+                let Some(lines) = lines else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr("Working out the commands..."));
+                    });
+                    ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    ui.label(tr("What exporting the open file would run; nothing has been run."));
+                    if ui.button(tr("📋 Copy")).clicked() {
+                        ctx.copy_text(lines.join("\n"));
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                    for line in &lines {
+                        ui.label(egui::RichText::new(line).monospace());
+                    }
+                });
+            });
+        if !open {
+            self.command_preview = None;
+        }
+    }
+
+    /// Lists the outputs of `sources` whose names are taken by files their ranges didn't write
+    /// and what the collision policy does with them. False when the user backs out.
+    fn confirm_collisions(&self, out_dir: &Path, sources: &[(&PathBuf, &[VideoRange])]) -> bool {
+        // An unreadable manifest fails the export itself with the reason
+        let Ok(manifest) = manifest::Manifest::load(out_dir) else {
+            return true;
+        };
+        let mut taken = Vec::new();
+        for (media, ranges) in sources {
+            let source = media.file_name().unwrap().to_string_lossy();
+            let previous: Vec<_> =
+                manifest.entries.iter().filter(|e| e.source == source).cloned().collect();
+            taken.extend(dataset::collisions(out_dir, media, ranges, &previous, &self.export_profile));
+        }
+        if taken.is_empty() {
+            return true;
+        }
+        const SHOWN: usize = 20;
+        let mut list = taken.iter().take(SHOWN).cloned().collect::<Vec<_>>().join("\n");
+        if taken.len() > SHOWN {
+            list.push_str(&trf("\n… and {} more", &[&(taken.len() - SHOWN)]));
+        }
+        let confirmed = rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Warning)
+            .set_title(tr("Run Export"))
+            .set_description(trf(
+                "{} output(s) already exist and were not written by these ranges:\n{}\n\n{} Export anyway?",
+                &[&taken.len(), &list, &tr(self.export_profile.collisions.consequence())],
+            ))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        confirmed == rfd::MessageDialogResult::Yes
+    }
+
+    /// Files whose last batch export failed, some of their ranges or all of it.
+    fn failed_exports(&self) -> Vec<PathBuf> {
+        let batch = self.batch_status.lock().unwrap();
+        self.videos
+            .iter()
+            .filter(|media| matches!(batch.get(*media), Some(dataset::BatchStatus::Failed(_))))
+            .cloned()
+            .collect()
+    }
+
+    /// Exports `files` one after the other: the open file with its current ranges, the others
+    /// from their sidecars. The file list shows how each one is doing. Ranges that are up to date
+    /// aren't encoded again, so exporting failed files again only retries what failed.
+    fn run_batch_export(&mut self, files: &[PathBuf]) {
+        let Some(out_dir) = self.output_folder.clone() else {
+            return;
+        };
+        let sources: Vec<_> = self
+            .videos
+            .iter()
+            .enumerate()
+            .filter(|(_, media)| files.contains(*media))
+            .map(|(i, media)| {
+                let ranges = if self.selected_file_idx == Some(i) {
+                    Ok(self.ranges.clone())
+                } else {
+                    sidecar::load(media).unwrap_or_else(|| Err(tr("No saved ranges").to_string()))
+                };
+                (media.clone(), ranges)
+            })
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+        let loaded: Vec<_> = sources
+            .iter()
+            .filter_map(|(media, ranges)| ranges.as_ref().ok().map(|r| (media, r.as_slice())))
+            .collect();
+        if !self.confirm_collisions(&out_dir, &loaded) {
+            return;
+        }
+        let status = self.batch_status.clone();
+        *status.lock().unwrap() = sources
+            .iter()
+            .map(|(media, _)| (media.clone(), dataset::BatchStatus::Queued))
+            .collect();
+        let profile = self.export_profile.clone();
+        let parallel = self.parallel_exports;
+        let schedule = self.schedule.clone();
+        let progress = self.progress.clone();
+        self.spawn_dataset_export(out_dir.clone(), move || {
+            let report = |media: &Path, s| {
+                status.lock().unwrap().insert(media.to_path_buf(), s);
+            };
+            let messages =
+                dataset::export_batch(sources, &profile, &out_dir, parallel, &schedule, &progress, report);
+            (!messages.is_empty()).then(|| messages.join("\n"))
+        });
+    }
+
+    /// Wipes what the manifest lists and exports every annotated file of the folder again: the
+    /// open file with its current ranges, the others from their sidecars.
+    fn rebuild_dataset(&self) {
+        let Some(out_dir) = self.output_folder.clone() else {
+            return;
+        };
+        let confirmed = rfd::MessageDialog::new()
+            .set_title(tr("Rebuild Dataset"))
+            .set_description(trf(
+                "Delete every file listed in the manifest in {} and export all annotated files again?",
+                &[&out_dir.display()],
+            ))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return;
+        }
+
+        // The rebuild deletes all old outputs first, so a missing source would lose its clips
+        let sources = self.export_sources();
+        let missing: Vec<_> = sources.iter().map(|(media, _)| media).filter(|m| !m.exists()).collect();
+        if !missing.is_empty() {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title(tr("Rebuild Dataset"))
+                .set_description(trf(
+                    "These source files are missing; relink them before rebuilding:\n{}",
+                    &[&missing.iter().map(|m| m.display().to_string()).collect::<Vec<_>>().join("\n")],
+                ))
+                .show();
+            return;
+        }
+        let schedule = self.schedule.clone();
+        let progress = self.progress.clone();
+        let profile = self.export_profile.clone();
+        let parallel = self.parallel_exports;
+        self.spawn_dataset_export(out_dir.clone(), move || {
+            // Files that fail to load are reported but don't stop the others
+            let mut messages = Vec::new();
+            let mut jobs = Vec::new();
+            for (media, ranges) in sources {
+                match ranges.and_then(|ranges| dataset::Job::probe(media, ranges, profile.clone())) {
+                    Ok(job) => jobs.push(job),
+                    Err(e) => messages.push(e),
+                }
+            }
+            match dataset::rebuild(jobs, &out_dir, parallel, &schedule, &progress) {
+                Ok(failed) => messages.extend(failed),
+                Err(e) => messages.push(e),
+            }
+            (!messages.is_empty()).then(|| messages.join("\n"))
+        });
+    }
+
+    /// Ranges each file of the folder exports: the open file's current ranges and the sidecars
+    /// of the others. Files without a sidecar are left out.
+    fn export_sources(&self) -> Vec<(PathBuf, Result<Vec<VideoRange>, String>)> {
+        let mut sources = Vec::new();
+        for (i, media) in self.videos.iter().enumerate() {
+            if self.selected_file_idx == Some(i) {
+                sources.push((media.clone(), Ok(self.ranges.clone())));
+            } else if let Some(ranges) = sidecar::load(media) {
+                sources.push((media.clone(), ranges));
+            }
+        }
+        sources
+    }
+
+    fn run_audit(&self) -> Option<audit::Report> {
+        let out_dir = self.output_folder.as_ref()?;
+        let mut errors = Vec::new();
+        let annotated: Vec<_> = self
+            .export_sources()
+            .into_iter()
+            .filter_map(|(media, ranges)| ranges.map_err(|e| errors.push(e)).ok().map(|r| (media, r)))
+            .collect();
+        let project = audit::Project {
+            media: &self.videos,
+            annotated: &annotated,
+            profile: &self.export_profile,
+        };
+        let mut report = audit::run(out_dir, &project);
+        report.errors.extend(errors);
+        Some(report)
+    }
+
+    fn show_audit(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.audit else {
+            return;
+        };
+        let mut open = true;
+        let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
+        if audit::show(ctx, &mut open, report, !self.read_only && !exporting) {
+            let (Some(out_dir), annotated) = (&self.output_folder, self.export_sources()) else {
+                return;
+            };
+            // Sidecars that failed to load were reported already; their outputs aren't touched
+            let annotated: Vec<_> = annotated
+                .into_iter()
+                .filter_map(|(media, ranges)| ranges.ok().map(|r| (media, r)))
+                .collect();
+            let project = audit::Project {
+                media: &self.videos,
+                annotated: &annotated,
+                profile: &self.export_profile,
+            };
+            let cleaned = match audit::clean(out_dir, report, &project) {
+                Ok(fixed) => trf("Fixed {} issue(s).", &[&fixed]),
+                Err(e) => e,
+            };
+            self.audit = self.run_audit();
+            if let Some(report) = &mut self.audit {
+                report.cleaned = Some(cleaned);
+            }
+        }
+        if !open {
+            self.audit = None;
+        }
+    }
+
+    fn plan_rename(&self) -> Option<rename::Plan> {
+        let out_dir = self.output_folder.as_ref()?;
+        let mut errors = Vec::new();
+        let annotated: Vec<_> = self
+            .export_sources()
+            .into_iter()
+            .filter_map(|(media, ranges)| ranges.map_err(|e| errors.push(e)).ok().map(|r| (media, r)))
+            .collect();
+        let project = audit::Project {
+            media: &self.videos,
+            annotated: &annotated,
+            profile: &self.export_profile,
+        };
+        let mut plan = rename::plan(out_dir, &project);
+        plan.errors.extend(errors);
+        Some(plan)
+    }
+
+    fn show_rename(&mut self, ctx: &egui::Context) {
+        let Some(plan) = &self.rename else {
+            return;
+        };
+        let mut open = true;
+        let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
+        if rename::show(ctx, &mut open, plan, !self.read_only && !exporting)
+            && let Some(out_dir) = &self.output_folder
+        {
+            let applied = match rename::apply(out_dir, plan) {
+                Ok(changed) => trf("Changed {} output(s).", &[&changed]),
+                Err(e) => e,
+            };
+            self.rename = self.plan_rename();
+            if let Some(plan) = &mut self.rename {
+                plan.applied = Some(applied);
+            }
+        }
+        if !open {
+            self.rename = None;
+        }
+    }
+
+    fn show_rules(&mut self, ctx: &egui::Context) {
+        if !self.show_rules {
+            return;
+        }
+        let mut open = true;
+        let can_save = self.input_folder.is_some() && !self.read_only;
+        let rules = &mut self.export_profile.rules;
+        if rules::show(ctx, &mut open, rules, self.rules_status.as_deref(), can_save)
+            && let Some(dir) = &self.input_folder
+        {
+            self.rules_status = Some(match rules::save(dir, rules) {
+                Ok(()) => tr("Rules saved.").to_string(),
+                Err(e) => e,
+            });
+        }
+        self.show_rules = open;
+    }
+
+    /// Loads the material registry of the output folder and its links when the folder changed,
+    /// and again once an export that may have added to it is done.
+    fn refresh_material(&mut self) {
+        let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
+        if let Some((_, outdated, _)) = &mut self.material {
+            *outdated |= exporting;
+        }
+        let Some(out_dir) = &self.output_folder else {
+            self.material = None;
+            return;
+        };
+        if let Some((dir, outdated, _)) = &self.material
+            && dir == out_dir
+            && (exporting || !outdated)
+        {
+            return;
+        }
+        let known = material::known(out_dir).unwrap_or_else(|e| {
+            tracing::warn!(target: logs::UI, "{}", e);
+            Vec::new()
+        });
+        self.material = Some((out_dir.clone(), exporting, known));
+    }
+
+    /// Window listing the projects whose exported material new ranges are checked against.
+    fn show_links(&mut self, ctx: &egui::Context) {
+        let (Some(links), Some(out_dir)) = (&self.links, self.output_folder.clone()) else {
+            return;
+        };
+        let (mut open, mut edited) = (true, None);
+        egui::Window::new(tr("🔗 Linked Projects"))
+            .id(egui::Id::new("linked_projects"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr(
+                    "New ranges are checked against the material exported into these output folders, e.g. earlier dataset versions.",
+                ));
+                for (i, link) in links.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(link.display().to_string());
+                        if ui.add_enabled(!self.read_only, egui::Button::new("✖")).clicked() {
+                            let mut links = links.clone();
+                            links.remove(i);
+                            edited = Some(links);
+                        }
+                    });
+                }
+                if links.is_empty() {
+                    ui.weak(tr("No linked projects."));
+                }
+                let add = ui
+                    .add_enabled(!self.read_only, egui::Button::new(tr("➕ Link Output Folder…")))
+                    .clicked();
+                if add
+                    && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                    && dir != out_dir
+                    && !links.contains(&dir)
+                {
+                    if !dir.join(material::FILE_NAME).exists() {
+                        self.links_status = Some(trf(
+                            "{} has no {} yet; it counts once something is exported there.",
+                            &[&dir.display(), &material::FILE_NAME],
+                        ));
+                    }
+                    edited = Some(links.iter().cloned().chain([dir]).collect());
+                }
+                ui.add_space(4.0);
+                ui.weak(trf("Stored in {} in the output folder.", &[&material::FILE_NAME]));
+                if let Some(status) = &self.links_status {
+                    ui.label(status);
+                }
+            });
+        if let Some(links) = edited {
+            let saved = material::Registry::load(&out_dir).and_then(|mut registry| {
+                registry.links = links.clone();
+                registry.save(&out_dir)
+            });
+            match saved {
+                Ok(()) => self.links = Some(links),
+                Err(e) => self.links_status = Some(e),
+            }
+            self.material = None;
+        }
+        if !open {
+            self.links = None;
+        }
+    }
+
+    /// The annotations of the folder and the dataset in `out_dir` as they are now.
+    fn capture_version(&self, label: &str, out_dir: &Path) -> Result<versions::Snapshot, String> {
+        let Some(input) = self.input_folder.clone() else {
+            return Err(tr("Open an input folder first").to_string());
+        };
+        let (annotations, errors) = self.folder_annotations();
+        // A version missing a file's annotations would show them as removed
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        let project = project::Project::new(input, Some(out_dir.to_path_buf()), None, annotations);
+        versions::Snapshot::capture(label, project, out_dir)
+    }
+
+    fn show_versions(&mut self, ctx: &egui::Context) {
+        let (Some(mut panel), Some(out_dir)) = (self.versions.take(), self.output_folder.clone()) else {
+            return;
+        };
+        let mut open = true;
+        let load = |version: &Option<String>| match version {
+            Some(label) => versions::load(&out_dir, label),
+            None => self.capture_version(tr("Current"), &out_dir),
+        };
+        let name = |version: &Option<String>| version.clone().unwrap_or_else(|| tr("Current").to_string());
+        match versions::show(ctx, &mut open, &mut panel, !self.read_only) {
+            Some(versions::Request::Snapshot) => {
+                let label = panel.label.trim().to_string();
+                let saved = self
+                    .capture_version(&label, &out_dir)
+                    .and_then(|snapshot| versions::save(&out_dir, &snapshot));
+                let status = match saved {
+                    Ok(()) => trf("Saved version {}.", &[&label]),
+                    Err(e) => e,
+                };
+                panel = versions::Panel {
+                    status: Some(status),
+                    diff: panel.diff,
+                    ..versions::Panel::new(&out_dir)
+                };
+            }
+            Some(versions::Request::Compare) => {
+                match load(&panel.from).and_then(|from| Ok((from, load(&panel.to)?))) {
+                    Ok((from, to)) => {
+                        panel.diff = Some((name(&panel.from), name(&panel.to), versions::diff(&from, &to)));
+                        panel.status = None;
+                    }
+                    Err(e) => panel.status = Some(e),
+                }
+            }
+            Some(versions::Request::ExportDelta) if self.is_exporting.load(atomic::Ordering::SeqCst) => {
+                panel.status = Some(tr("Wait for the running export to finish").to_string());
+            }
+            Some(versions::Request::ExportDelta) => {
+                let Some(target) = rfd::FileDialog::new().set_title(tr("Empty folder for the difference")).pick_folder()
+                else {
+                    self.versions = Some(panel);
+                    return;
+                };
+                match load(&panel.from).and_then(|from| Ok((from, load(&panel.to)?))) {
+                    Ok((from, to)) => {
+                        let (from_name, to_name) = (name(&panel.from), name(&panel.to));
+                        panel.status = Some(trf("Copying the difference {} → {}…", &[&from_name, &to_name]));
+                        self.spawn_export(move || {
+                            let (copied, skipped) = match versions::export_delta(&out_dir, &from, &to, &target) {
+                                Ok(result) => result,
+                                Err(e) => return Some(e),
+                            };
+                            tracing::info!(target: logs::EXPORT, copied, skipped = skipped.len(), "Exported the difference {} → {}", from_name, to_name);
+                            let mut lines = vec![trf("Copied {} clip(s) to {}.", &[&copied, &target.display()])];
+                            lines.extend(skipped);
+                            Some(lines.join("\n"))
+                        });
+                    }
+                    Err(e) => panel.status = Some(e),
+                }
+            }
+            None => {}
+        }
+        if open {
+            self.versions = Some(panel);
+        }
+    }
+
+    /// Checks a dataset folder (the output folder or a copy of it) against its `checksums.txt`
+    /// and lists the files that don't match.
+    fn verify_checksums(&self) {
+        let mut dialog = rfd::FileDialog::new().set_title(tr("Dataset folder to verify"));
+        if let Some(out_dir) = &self.output_folder {
+            dialog = dialog.set_directory(out_dir);
+        }
+        let Some(dir) = dialog.pick_folder() else {
+            return;
+        };
+        let schedule = self.schedule.clone();
+        self.spawn_export(move || {
+            let verification = match checksum::verify(&dir, &schedule) {
+                Ok(verification) => verification,
+                Err(e) => return Some(e),
+            };
+            let problems = verification.problems();
+            tracing::info!(target: logs::EXPORT, dir = ?dir, checked = verification.checked, problems = problems.len(), "Checksums verified");
+            if problems.is_empty() {
+                return Some(trf("All {} files in {} match their checksums.", &[&verification.checked, &dir.display()]));
+            }
+            const SHOWN: usize = 20;
+            let mut lines = vec![trf("{} file(s) failed verification:", &[&problems.len()])];
+            lines.extend(problems.iter().take(SHOWN).cloned());
+            let mut text = lines.join("\n");
+            if problems.len() > SHOWN {
+                text.push_str(&trf("\n… and {} more", &[&(problems.len() - SHOWN)]));
+            }
+            Some(text)
+        });
+    }
+
+    fn show_upload(&mut self, ctx: &egui::Context) {
+        if !self.show_upload {
+            return;
+        }
+        let mut open = true;
+        let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
+        let can_upload = !exporting && self.output_folder.is_some();
+        if upload::show(ctx, &mut open, &mut self.upload_destination, &mut self.upload_secret, can_upload)
+            && let Some(out_dir) = self.output_folder.clone()
+        {
+            let destination = self.upload_destination.clone();
+            let secret = self.upload_secret.clone();
+            let schedule = self.schedule.clone();
+            self.spawn_export(move || match upload::upload(&out_dir, &destination, &secret, &schedule) {
+                Ok(summary) => Some(summary.message(&destination)),
+                Err(e) => Some(e),
+            });
+        }
+        self.show_upload = open;
+    }
+
+    fn show_add_remote(&mut self, ctx: &egui::Context) {
+        let (Some(mut url), Some(dir)) = (self.add_remote.take(), self.input_folder.clone()) else {
+            return;
+        };
+        let mut open = true;
+        if remote::show(ctx, &mut open, &mut url, self.add_remote_error.as_deref(), !self.read_only) {
+            match remote::write_link(&dir, &url) {
+                Ok(link) => {
+                    tracing::info!(target: logs::UI, file = ?link, "Remote source added");
+                    self.open_input_folder(dir);
+                    self.pending_file = self.videos.iter().position(|v| *v == link);
+                    self.add_remote_error = None;
+                    return;
+                }
+                Err(e) => self.add_remote_error = Some(e),
+            }
+        }
+        if open {
+            self.add_remote = Some(url);
+        }
+    }
+
+    fn show_package(&mut self, ctx: &egui::Context) {
+        let (Some(mut options), Some(out_dir)) = (self.package.take(), self.output_folder.clone()) else {
+            return;
+        };
+        let mut open = true;
+        let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
+        if package::show(ctx, &mut open, &mut options, !exporting) {
+            let extension = options.format.extension();
+            let target = rfd::FileDialog::new()
+                .add_filter(options.format.label(), &[extension])
+                .set_file_name(format!("dataset.{}", extension))
+                .save_file();
+            if let Some(target) = target {
+                let target = target.with_extension(extension);
+                let packaging = options.clone();
+                let schedule = self.schedule.clone();
+                self.spawn_export(move || match package::package(&out_dir, &target, &packaging, &schedule) {
+                    Ok(archives) => {
+                        let dir = target.parent().unwrap_or(&target);
+                        Some(trf("Packaged the dataset into {} archive(s) in {}.", &[&archives.len(), &dir.display()]))
+                    }
+                    Err(e) => Some(e),
+                });
+            }
+        }
+        if open {
+            self.package = Some(options);
+        }
+    }
+
+    fn show_encoder(&mut self, ctx: &egui::Context) {
+        if !self.show_encoder {
+            return;
+        }
+        let mut open = true;
+        let exporting = self.is_exporting.load(std::sync::atomic::Ordering::SeqCst);
+        let container = self.export_profile.container;
+        let encoder = &mut self.export_profile.encoder;
+        egui::Window::new(tr("🎛 Encoder Settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!exporting, |ui| {
+                    egui::Grid::new("encoder_settings").num_columns(2).show(ui, |ui| {
+                        ui.label(tr("Codec:"));
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("encoder_codec")
+                                .selected_text(encoder.codec.label())
+                                .show_ui(ui, |ui| {
+                                    for codec in export::Codec::ALL {
+                                        ui.selectable_value(&mut encoder.codec, codec, codec.label());
+                                    }
+                                });
+                            let used = encoder.codec_for(container);
+                            if used != encoder.codec {
+                                ui.weak(trf("{} clips are {}", &[&container.label(), &used.label()]));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Quality:"));
+                        ui.horizontal(|ui| {
+                            let max_crf = encoder.codec_for(container).max_crf();
+                            let quality = &mut encoder.quality;
+                            let quality_label = |q: &export::Quality| match q {
+                                export::Quality::Default => tr("Encoder default"),
+                                export::Quality::Crf(_) => tr("Constant quality (CRF)"),
+                                export::Quality::Bitrate(_) => tr("Bitrate"),
+                            };
+                            egui::ComboBox::from_id_salt("encoder_quality")
+                                .selected_text(quality_label(quality))
+                                .show_ui(ui, |ui| {
+                                    for option in [
+                                        export::Quality::Default,
+                                        export::Quality::Crf(23),
+                                        export::Quality::Bitrate(4000),
+                                    ] {
+                                        let selected = std::mem::discriminant(quality) == std::mem::discriminant(&option);
+                                        if ui.selectable_label(selected, quality_label(&option)).clicked() && !selected {
+                                            *quality = option;
+                                        }
+                                    }
+                                });
+                            match quality {
+                                export::Quality::Default => {}
+                                export::Quality::Crf(crf) => {
+                                    ui.add(egui::DragValue::new(crf).range(0..=max_crf))
+                                        .on_hover_text(tr("Lower is better and larger; around 18 looks lossless"));
+                                }
+                                export::Quality::Bitrate(kbps) => {
+                                    ui.add(egui::DragValue::new(kbps).range(100..=100_000).speed(50).suffix(" kbit/s"));
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Preset:"));
+                        egui::ComboBox::from_id_salt("encoder_preset")
+                            .selected_text(encoder.preset.name())
+                            .show_ui(ui, |ui| {
+                                for preset in export::Preset::ALL {
+                                    ui.selectable_value(&mut encoder.preset, preset, preset.name());
+                                }
+                            })
+                            .response
+                            .on_hover_text(tr(
+                                "Slower presets make smaller files at the same quality; VP9 and hardware encoders get the nearest speed setting",
+                            ));
+                        ui.end_row();
+
+                        ui.label(tr("Hardware:"));
+                        ui.horizontal(|ui| {
+                            let available = hardware::available();
+                            let choice = &mut encoder.hardware;
+                            let hardware_label = |h: Option<hardware::Hardware>| h.map_or(tr("CPU"), |h| h.label());
+                            egui::ComboBox::from_id_salt("encoder_hardware")
+                                .selected_text(hardware_label(*choice))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(choice, None, hardware_label(None));
+                                    for option in hardware::Hardware::ALL {
+                                        let works = available.as_ref().is_none_or(|a| a.contains(&option));
+                                        ui.add_enabled_ui(works, |ui| {
+                                            ui.selectable_value(choice, Some(option), option.label());
+                                        })
+                                        .response
+                                        .on_disabled_hover_text(tr(
+                                            "Not available with this FFmpeg, driver or graphics card",
+                                        ));
+                                    }
+                                })
+                                .response
+                                .on_hover_text(tr(
+                                    "Encode H.264 and H.265 on the graphics card, much faster than on the CPU but larger at the same quality",
+                                ));
+                            if available.is_none() {
+                                ui.spinner().on_hover_text(tr("Checking which hardware encoders work…"));
+                            } else if let Some(chosen) = *choice
+                                && !available.as_ref().is_some_and(|a| a.contains(&chosen))
+                            {
+                                ui.colored_label(ui.visuals().warn_fg_color, tr("⚠ Not available here"));
+                            } else if choice.is_some() && encoder.hardware_for(container).is_none() {
+                                ui.weak(tr("VP9 is encoded on the CPU"));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Pixel format:"));
+                        let format = &mut encoder.pixel_format;
+                        let format_label = |f: Option<export::PixelFormat>| f.map_or(tr("Encoder's choice"), |f| f.name());
+                        egui::ComboBox::from_id_salt("encoder_pixel_format")
+                            .selected_text(format_label(*format))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(format, None, format_label(None));
+                                for option in export::PixelFormat::ALL {
+                                    ui.selectable_value(format, Some(option), option.name());
+                                }
+                            })
+                            .response
+                            .on_hover_text(tr("yuv420p plays everywhere; the others keep more color but not every player or loader takes them"));
+                        ui.end_row();
+                    });
+                    if ui.button(tr("Reset to Defaults")).clicked() {
+                        *encoder = export::Encoder::default();
+                    }
+                });
+                ui.weak(tr("Clips exported before are encoded again with new settings."));
+            });
+        self.show_encoder = open;
+    }
+
+    /// Writes the container and encoder settings once they changed, but not while a value is
+    /// still being dragged.
+    fn save_settings(&mut self, ctx: &egui::Context) {
+        let current = settings::Settings {
+            container: self.export_profile.container,
+            encoder: self.export_profile.encoder,
+            parallel_exports: self.parallel_exports,
+            upload: self.upload_destination.clone(),
+            layout: self.layout,
+        };
+        if current == self.saved_settings || ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        if let Err(e) = settings::save(&settings::path(), &current) {
+            tracing::warn!(target: logs::UI, "{}", e);
+        }
+        self.saved_settings = current;
+    }
+
+    /// `spawn_export` for an export into `out_dir`, which is uploaded afterwards if uploads after
+    /// exports are on and the export wasn't cancelled.
+    fn spawn_dataset_export(&self, out_dir: PathBuf, work: impl FnOnce() -> Option<String> + Send + 'static) {
+        let destination = &self.upload_destination;
+        let upload = (destination.after_export && destination.is_complete())
+            .then(|| (destination.clone(), self.upload_secret.clone()));
+        let schedule = self.schedule.clone();
+        self.spawn_export(move || {
+            let message = work();
+            let Some((destination, secret)) = upload.filter(|_| !schedule.is_cancelled()) else {
+                return message;
+            };
+            let uploaded = match upload::upload(&out_dir, &destination, &secret, &schedule) {
+                Ok(summary) => summary.message(&destination),
+                Err(e) => e,
+            };
+            Some(message.map_or(uploaded.clone(), |m| format!("{}\n{}", m, uploaded)))
+        });
+    }
+
+    /// Runs `work` on a worker thread with the export button disabled, showing the message it
+    /// returns below the button.
+    fn spawn_export(&self, work: impl FnOnce() -> Option<String> + Send + 'static) {
+        self.is_exporting
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        *self.export_error.lock().unwrap() = None;
+        self.progress.clear_log();
+        self.schedule.set_paused(false);
+        self.schedule.set_cancelled(false);
+        let (hour, minute) = self.schedule_time;
+        self.schedule.set_start_at(
+            chrono::NaiveTime::from_hms_opt(hour, minute, 0)
+                .filter(|_| self.schedule_enabled)
+                .map(|at| schedule::next_occurrence(chrono::Local::now().naive_local(), at)),
+        );
+        let schedule = self.schedule.clone();
+
+        let exp_err = self.export_error.clone();
+        struct DropGuard(Arc<AtomicBool>);
+        impl Drop for DropGuard {
+            fn drop(&mut self) {
+                self.0.store(false, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+        let guard = DropGuard(self.is_exporting.clone());
+        let metrics = self.metrics.clone();
+        let progress = self.progress.clone();
+
+        std::thread::spawn(move || {
+            let _guard = guard;
+            // Nothing is deleted or written before the scheduled start
+            schedule.wait();
+            let message = if schedule.is_cancelled() {
+                Some(tr("Export cancelled").to_string())
+            } else {
+                work()
+            };
+            progress.clear();
+            metrics.count("exports");
+            if message.is_some() {
+                metrics.count("export_problems");
+            }
+            if let Err(e) = metrics.flush() {
+                tracing::warn!(target: logs::UI, "{}", e);
+            }
+            match &message {
+                Some(message) => tracing::error!(target: logs::EXPORT, "Export finished with problems: {}", message),
+                None => tracing::info!(target: logs::EXPORT, "All exports finished."),
+            }
+            *exp_err.lock().unwrap() = message;
+        });
+    }
+}
+
+impl eframe::App for VideoApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut file_idx_to_load = None;
+        self.apply_text_scan_result();
+        self.apply_transcript_result();
+        self.apply_loaded_media(ctx);
+        self.check_missing(ctx);
+        self.refresh_material();
+        self.autosave(ctx);
+        self.apply_proxy_result(ctx);
+        self.apply_keyframe_result();
+        self.apply_subtitle_result();
+        self.apply_range_preview_result(ctx);
+        self.handle_close(ctx);
+        self.apply_handoffs(ctx);
+        #[cfg(feature = "gamepad")]
+        self.handle_gamepad(ctx);
+
+        // Keyboard Logic (timeline actions are ignored for images to prevent accidental scrubbing).
+        // Shortcuts stay active while a button or list entry has focus, except for the keys the
+        // focused widget uses itself; only typing into a text field suspends them.
+        let focused = ctx.memory(|m| m.focused());
+        let typing =
+            focused.is_some_and(|id| egui::text_edit::TextEditState::load(ctx, id).is_some());
+        let viewport_focused = focused == Some(viewport_id());
+        if viewport_focused && !self.read_only {
+            self.keyboard_crop(ctx);
+        }
+        ctx.input(|i| self.key_repeat.release(|key| i.key_down(key)));
+        if !typing && !self.palette.open {
+            for &action in Action::ALL {
+                let widget_keys = matches!(
+                    action,
+                    Action::PlayPause | Action::PrevFrame | Action::NextFrame
+                );
+                if widget_keys && focused.is_some() && !viewport_focused {
+                    continue;
+                }
+                if let Some(shortcut) = action.shortcut()
+                    && ctx.input_mut(|i| i.consume_shortcut(&shortcut))
+                {
+                    // Holding an arrow key speeds up; the buttons always step single frames
+                    match action {
+                        Action::PrevFrame | Action::NextFrame if !self.is_image => {
+                            let frames = self.key_repeat.press(shortcut.logical_key);
+                            let sign = if action == Action::PrevFrame { -1 } else { 1 };
+                            self.step_frames(ctx, sign * frames);
+                        }
+                        _ => self.perform(ctx, action),
+                    }
+                }
+            }
+        }
+        let (is_image, has_media, read_only) =
+            (self.is_image, self.selected_file_idx.is_some(), self.read_only);
+        let annotator = self.layout == settings::Layout::Annotator;
+        let available = |a: Action| {
+            !(a.video_only() && (is_image || !has_media))
+                && !(a.modifies() && read_only)
+                && !(a.engineering() && annotator)
+        };
+        if let Some(action) = self.palette.show(ctx, available) {
+            self.perform(ctx, action);
+        }
+        actions::show_shortcuts(ctx, &mut self.show_shortcuts);
+        logs::show(ctx, &mut self.show_log, &mut self.log_view);
+        self.show_merge(ctx);
+        self.show_project_restore(ctx);
+        self.show_recovery(ctx);
+        self.show_close_prompt(ctx);
+        self.show_balance(ctx);
+        self.show_session(ctx);
+        self.show_audit(ctx);
+        self.show_rename(ctx);
+        self.show_rules(ctx);
+        self.show_links(ctx);
+        self.show_versions(ctx);
+        self.show_package(ctx);
+        self.show_add_remote(ctx);
+        self.show_command_preview(ctx);
+        self.show_upload(ctx);
+        self.show_encoder(ctx);
+        self.save_settings(ctx);
+        self.show_cut_check(ctx);
+        self.show_stale_outputs(ctx);
+        self.show_relink(ctx);
+
+        // Panels
+        egui::TopBottomPanel::top("top").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(tr("📁 Input Folder")).clicked() {
+                    self.perform(ctx, Action::OpenInputFolder);
+                }
+                if ui
+                    .add_enabled(self.input_folder.is_some() && !self.read_only, egui::Button::new(tr("🌐 Add URL")))
+                    .on_hover_text(tr("Add a video on an HTTP(S) server, streamed instead of downloaded"))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::AddRemoteSource);
+                }
+                ui.label(trf(
+                    "In: {}",
+                    &[&self.input_folder
+                        .as_deref()
+                        .unwrap_or(Path::new(tr("None")))
+                        .display()],
+                ));
+                ui.separator();
+                let engineer = self.layout == settings::Layout::Engineer;
+                if engineer && ui.button(tr("💾 Output Folder")).clicked() {
+                    self.perform(ctx, Action::OpenOutputFolder);
+                }
+                ui.separator();
+                if ui
+                    .add_enabled(self.input_folder.is_some(), egui::Button::new(tr("🗂 Save Project")))
+                    .on_hover_text(tr("Save the folders and every file's annotations to one project file"))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::SaveProject);
+                }
+                if ui
+                    .add_enabled(!self.read_only, egui::Button::new(tr("📂 Open Project…")))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::OpenProject);
+                }
+                if engineer
+                    && ui
+                        .add_enabled(!self.read_only, egui::Button::new(tr("🔀 Merge…")))
+                        .on_hover_text(tr("Merge another annotator's annotations for the same files"))
+                        .clicked()
+                {
+                    self.perform(ctx, Action::MergeAnnotations);
+                }
+                if engineer
+                    && ui
+                        .button(tr("📊 Balance"))
+                        .on_hover_text(tr("Clip count and duration per tag and split"))
+                        .clicked()
+                {
+                    self.perform(ctx, Action::ClassBalance);
+                }
+                if ui
+                    .button(tr("⏱ Session"))
+                    .on_hover_text(tr("Files, ranges and annotated time since the app was started"))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::SessionStats);
+                }
+                if engineer {
+                    ui.label(trf(
+                        "Out: {}",
+                        &[&self.output_folder
+                            .as_deref()
+                            .unwrap_or(Path::new(tr("None")))
+                            .display()],
+                    ));
+                }
+                ui.separator();
+                ui.label(tr("Annotator:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.annotator)
+                        .desired_width(100.0)
+                        .hint_text(tr("your name")),
+                )
+                .on_hover_text(tr("Recorded on every range you change"));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if self.read_only {
+                        ui.colored_label(egui::Color32::LIGHT_BLUE, tr("👁 Read-only review"))
+                            .on_hover_text(tr("Started with --read-only: annotations can't be changed or exported"));
+                        ui.separator();
+                    }
+                    let mut language = i18n::language();
+                    egui::ComboBox::from_id_salt("language")
+                        .selected_text(format!("🌐 {}", language.name()))
+                        .show_ui(ui, |ui| {
+                            for l in i18n::Language::ALL {
+                                ui.selectable_value(&mut language, *l, l.name());
+                            }
+                        });
+                    i18n::set_language(language);
+                    egui::ComboBox::from_id_salt("layout")
+                        .selected_text(format!("🧰 {}", tr(self.layout.label())))
+                        .show_ui(ui, |ui| {
+                            for layout in settings::Layout::ALL {
+                                ui.selectable_value(&mut self.layout, layout, tr(layout.label()));
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Annotator shows only ranges, crops and notes; Engineer adds the export, encoder and dataset controls",
+                        ));
+                    if engineer {
+                        ui.menu_button(tr("🗄 Cache"), |ui| {
+                            ui.label(trf("Folder: {}", &[&cache::root().display()]));
+                            ui.label(trf(
+                                "Size: {} MB",
+                                &[&format!("{:.1}", cache::size() as f64 / (1024.0 * 1024.0))],
+                            ));
+                            ui.horizontal(|ui| {
+                                ui.label(tr("Limit:"));
+                                let mut limit = cache::limit_mb();
+                                let changed = ui
+                                    .add(egui::DragValue::new(&mut limit).range(100..=1_000_000).suffix(" MB"))
+                                    .changed();
+                                if changed {
+                                    cache::set_limit_mb(limit);
+                                    cache::evict(None);
+                                }
+                            });
+                            if ui.button(tr("Choose Folder…")).clicked()
+                                && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                            {
+                                cache::set_root(dir);
+                            }
+                            if ui.button(tr("🧹 Clear Cache")).clicked() {
+                                self.preview_note = Some(match cache::clear() {
+                                    Ok(freed) => trf(
+                                        "Cleared {} MB of cached data",
+                                        &[&format!("{:.1}", freed as f64 / (1024.0 * 1024.0))],
+                                    ),
+                                    Err(e) => e,
+                                });
+                            }
+                        });
+                    }
+                    let outdated = matches!(
+                        *self.update_check.lock().unwrap(),
+                        Some(Ok(update::Outcome::Outdated { .. }))
+                    );
+                    let about = if outdated { tr("⚠ Update Available") } else { tr("ℹ About") };
+                    ui.menu_button(about, |ui| {
+                        ui.label(format!("VidDataTrainCrop {}", update::CURRENT));
+                        ui.horizontal(|ui| {
+                            let checking = self.is_checking_update.load(atomic::Ordering::SeqCst);
+                            let check = ui
+                                .add_enabled(!checking, egui::Button::new(tr(Action::CheckForUpdates.label())))
+                                .on_hover_text(tr("Ask GitHub for the latest release; nothing is downloaded"));
+                            if check.clicked() {
+                                self.perform(ctx, Action::CheckForUpdates);
+                            }
+                            if checking {
+                                ui.spinner();
+                            }
+                        });
+                        match self.update_check.lock().unwrap().as_ref() {
+                            Some(Ok(outcome @ update::Outcome::Outdated { url, .. })) => {
+                                ui.colored_label(ui.visuals().warn_fg_color, update::describe(outcome));
+                                ui.hyperlink_to(tr("Release notes and downloads"), url);
+                            }
+                            Some(Ok(outcome)) => {
+                                ui.label(update::describe(outcome));
+                            }
+                            Some(Err(e)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, e);
+                            }
+                            None => {}
                         }
+                    });
+                    if ui
+                        .button(tr("📜 Log"))
+                        .on_hover_text(tr("What the app logged, e.g. why an export failed"))
+                        .clicked()
+                    {
+                        self.perform(ctx, Action::ShowLog);
+                    }
+                    if ui.button(tr("⌨ Shortcuts (F1)")).clicked() {
+                        self.perform(ctx, Action::ShowShortcuts);
+                    }
+                    if ui
+                        .button(tr("🔎 Commands"))
+                        .on_hover_text(ctx.format_shortcut(&Action::CommandPalette.shortcut().unwrap()))
+                        .clicked()
+                    {
+                        self.perform(ctx, Action::CommandPalette);
+                    }
+                });
+            });
+        });
+
+        egui::SidePanel::left("left")
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                ui.heading(tr("Files"));
+                let (analyzed, total) = self.analyzer.progress();
+                if analyzed < total {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(trf("Analyzing files: {} / {}", &[&analyzed, &total]));
+                    });
+                }
+                // Only the rows in view are laid out, so folders of thousands of files stay fast
+                let row_height = ui.spacing().interact_size.y;
+                egui::ScrollArea::vertical().show_rows(ui, row_height, self.videos.len(), |ui, rows| {
+                    ui.set_min_width(400.0);
+
+                    let batch = self.batch_status.lock().unwrap().clone();
+                    for (i, v) in self.videos.iter().enumerate().take(rows.end).skip(rows.start) {
+                        let name = v.file_name().unwrap().to_string_lossy();
+                        let selected = self.selected_file_idx == Some(i);
+                        let status = self.analyzer.status(v);
+                        ui.horizontal(|ui| {
+                            let mut marked = self.marked.contains(v);
+                            if ui
+                                .add_enabled(!self.read_only, egui::Checkbox::without_text(&mut marked))
+                                .on_hover_text(tr("Mark for export"))
+                                .changed()
+                            {
+                                if marked {
+                                    self.marked.insert(v.clone());
+                                } else {
+                                    self.marked.remove(v);
+                                }
+                            }
+                            let clicked = if self.missing.contains(v) {
+                                let text = egui::RichText::new(format!("⚠ {}", name)).color(egui::Color32::RED);
+                                ui.selectable_label(selected, text)
+                                    .on_hover_text(tr("File not found. Click to relink."))
+                                    .clicked()
+                            } else {
+                                let text = match &status {
+                                    Some(analysis::Status::Queued) => format!("{} ·", name),
+                                    Some(analysis::Status::Running(_)) => format!("{} ⏳", name),
+                                    Some(analysis::Status::Done(report)) if !report.errors.is_empty() => format!("{} ⚠", name),
+                                    _ => name.into_owned(),
+                                };
+                                let thumbnails = &mut self.thumbnails;
+                                ui.selectable_label(selected, text)
+                                    .on_hover_ui(|ui| show_analysis(ui, v, status.as_ref(), thumbnails))
+                                    .clicked()
+                            };
+                            if clicked {
+                                file_idx_to_load = Some(i);
+                            }
+                            match batch.get(v) {
+                                Some(dataset::BatchStatus::Queued) => {
+                                    ui.weak(tr("queued"));
+                                }
+                                Some(dataset::BatchStatus::Exporting) => {
+                                    ui.spinner();
+                                }
+                                Some(dataset::BatchStatus::Done(clips)) => {
+                                    ui.weak(trf("{} clips", &[clips]));
+                                }
+                                Some(dataset::BatchStatus::Failed(e)) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, tr("failed")).on_hover_text(e);
+                                }
+                                None => {}
+                            }
+                        });
                     }
                 });
             });
@@ -389,54 +3863,166 @@ impl eframe::App for VideoApp {
         egui::SidePanel::right("right")
             .default_width(220.0)
             .show(ctx, |ui| {
-                ui.heading(if self.is_image { "Active Crops" } else { "Active Ranges" });
-                if ui.button(if self.is_image { "➕ Add Crop" } else { "➕ Add Range" }).clicked() {
-                    self.ranges.push(VideoRange {
-                        start_time: self.current_time,
-                        end_time: self.duration,
-                        crop_rect_norm: None,
-                        note: String::new(),
+                ui.heading(tr(if self.is_image { "Active Crops" } else { "Active Ranges" }));
+                let add_label = tr(if self.is_image { "➕ Add Crop" } else { "➕ Add Range" });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.read_only, egui::Button::new(add_label)).clicked() {
+                        self.perform(ctx, Action::AddRange);
+                    }
+                    if !self.is_image {
+                        ui.add(
+                            egui::DragValue::new(&mut self.default_clip_length)
+                                .range(0.5..=600.0)
+                                .speed(0.1)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text(tr(
+                            "Length of new ranges: they end this long after the playhead, or at the end of the video",
+                        ));
+                    }
+                });
+                if !self.is_image && !self.chapters.is_empty() {
+                    let convert = ui
+                        .add_enabled(
+                            !self.read_only,
+                            egui::Button::new(trf("📑 Chapters to Ranges ({})", &[&self.chapters.len()])),
+                        )
+                        .on_hover_text(tr("Add a range for every chapter of the video, with the chapter title as note"));
+                    if convert.clicked() {
+                        self.perform(ctx, Action::ChaptersToRanges);
+                    }
+                }
+                let scanning = self.is_scanning_text.load(atomic::Ordering::SeqCst);
+                ui.horizontal(|ui| {
+                    let can_scan = !scanning && self.text_model_path.is_some() && !self.read_only;
+                    if ui
+                        .add_enabled(can_scan, egui::Button::new(tr("🔤 Scan for Text")))
+                        .on_disabled_hover_text(tr("Select an EAST model (.pb) first"))
+                        .clicked()
+                    {
+                        self.perform(ctx, Action::ScanText);
+                    }
+                    if scanning {
+                        ui.spinner();
+                    }
+                    if ui.button(tr("EAST model…")).clicked()
+                        && let Some(p) = rfd::FileDialog::new().add_filter("EAST model", &["pb"]).pick_file()
+                    {
+                        self.text_model_path = Some(p);
+                    }
+                });
+                if let Some(err) = &self.text_scan_error {
+                    ui.label(err);
+                }
+                if !self.is_image {
+                    let transcribing = self.is_transcribing.load(atomic::Ordering::SeqCst);
+                    ui.horizontal(|ui| {
+                        let can_transcribe =
+                            !transcribing && self.whisper_model.is_some() && !self.read_only && !self.ranges.is_empty();
+                        if ui
+                            .add_enabled(can_transcribe, egui::Button::new(tr("🎙 Transcribe")))
+                            .on_hover_text(tr("Transcribe the speech of the current range with whisper.cpp and append it to the note"))
+                            .on_disabled_hover_text(tr("Select a Whisper model (ggml .bin) first"))
+                            .clicked()
+                        {
+                            self.perform(ctx, Action::Transcribe);
+                        }
+                        if transcribing {
+                            ui.spinner();
+                        }
+                        if ui.button(tr("Whisper model…")).clicked()
+                            && let Some(p) = rfd::FileDialog::new().add_filter("Whisper model", &["bin"]).pick_file()
+                        {
+                            self.whisper_model = Some(p);
+                        }
+                    });
+                    if let Some(err) = &self.transcript_error {
+                        ui.label(err);
+                    }
+                    let checking = self.is_checking_trim.load(atomic::Ordering::SeqCst);
+                    ui.horizontal(|ui| {
+                        let check = ui
+                            .add_enabled(!checking && !self.ranges.is_empty(), egui::Button::new(tr("✂ Trim Preview")))
+                            .on_hover_text(tr("Find the nearly black or frozen frames at the ends of the current range, which edge trimming drops at export"));
+                        if check.clicked() {
+                            self.perform(ctx, Action::TrimPreview);
+                        }
+                        if checking {
+                            ui.spinner();
+                        }
                     });
-                    self.current_range_idx = self.ranges.len() - 1;
+                    match self.current_trim_preview() {
+                        Some(Ok(trim)) => {
+                            ui.label(trim.describe(self.native_fps));
+                            let apply = ui
+                                .add_enabled(!trim.is_empty() && !self.read_only, egui::Button::new(tr("Apply to Range")))
+                                .on_hover_text(tr("Move the range's start and end past the trimmed frames"));
+                            if apply.clicked() {
+                                let range = &mut self.ranges[self.current_range_idx];
+                                *range = trim.apply(range, self.native_fps);
+                                range.stamp(&self.annotator);
+                            }
+                        }
+                        Some(Err(e)) => {
+                            ui.label(e);
+                        }
+                        None => {}
+                    }
                 }
                 ui.separator();
                 let mut to_remove = None;
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for i in 0..self.ranges.len() {
+                // Only the rows in view are laid out; heights follow from the line counts, so
+                // hundreds of ranges cost no more per frame than a screenful
+                let labels: Vec<String> = (0..self.ranges.len()).map(|i| self.range_label(i)).collect();
+                let line_height = ui.text_style_height(&egui::TextStyle::Button);
+                let padding = 2.0 * ui.spacing().button_padding.y;
+                let gap = ui.spacing().item_spacing.y;
+                let mut tops = Vec::with_capacity(labels.len());
+                let mut total = 0.0;
+                for label in &labels {
+                    tops.push(total);
+                    total += (label.lines().count() as f32 * line_height + padding).max(45.0) + gap;
+                }
+                egui::ScrollArea::vertical().show_viewport(ui, |ui, viewport| {
+                    ui.set_height(total);
+                    let origin = ui.max_rect().min;
+                    let first = tops.partition_point(|top| *top <= viewport.min.y).saturating_sub(1);
+                    let last = tops.partition_point(|top| *top < viewport.max.y);
+                    for (i, label_text) in labels.into_iter().enumerate().take(last).skip(first) {
                         let range = &self.ranges[i];
+                        let bottom = tops.get(i + 1).copied().unwrap_or(total);
+                        let row = egui::Rect::from_min_max(
+                            origin + egui::vec2(0.0, tops[i]),
+                            egui::pos2(ui.max_rect().right(), origin.y + bottom),
+                        );
 
-                        let label_text = if self.is_image {
-                            format!("Crop {}", i)
-                        } else {
-                            let duration = range.end_time - range.start_time;
-                            let frame_count_16fps = (duration * 16.0).round() as i32;
-                            let start_frame = (range.start_time * self.native_fps).round() as i32;
-                            let end_frame = (range.end_time * self.native_fps).round() as i32;
-
-                            format!(
-                                "R{}: {:.1}s - {:.1}s ({:.1}s)\n      {} - {} ({} frames)",
-                                i,
-                                range.start_time,
-                                range.end_time,
-                                duration,
-                                start_frame,
-                                end_frame,
-                                frame_count_16fps
-                            )
-                        };
-
+                        let preview_key = range_preview::key(range);
                         let is_selected = self.current_range_idx == i;
-                        ui.horizontal(|ui| {
+                        ui.scope_builder(egui::UiBuilder::new().max_rect(row), |ui| ui.horizontal(|ui| {
                             let btn = egui::Button::selectable(is_selected, label_text)
                                 .min_size(egui::vec2(ui.available_width() - 50.0, 45.0));
 
-                            if ui.add(btn).clicked() {
+                            let mut btn = ui.add(btn);
+                            if btn.hovered() && !self.is_image {
+                                self.request_range_preview(i);
+                            }
+                            if let Some(frames) = self.range_previews.get(&preview_key).filter(|f| !f.is_empty()) {
+                                // Four frames a second, looping
+                                let shown = (ui.input(|i| i.time) * 4.0) as usize % frames.len();
+                                let texture = frames[shown].clone();
+                                btn = btn.on_hover_ui(|ui| {
+                                    ui.image((texture.id(), texture.size_vec2()));
+                                    ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+                                });
+                            }
+                            if btn.clicked() {
                                 self.current_range_idx = i;
                             }
-                            if ui.button("❌").clicked() {
+                            let remove = ui.add_enabled(!self.read_only, egui::Button::new("❌"));
+                            if accessible(remove, tr("Remove range")).clicked() {
                                 to_remove = Some(i);
                             }
-                        });
+                        }));
                     }
                 });
                 if let Some(idx) = to_remove {
@@ -444,17 +4030,19 @@ impl eframe::App for VideoApp {
                     self.current_range_idx = self
                         .current_range_idx
                         .clamp(0, self.ranges.len().saturating_sub(1));
+                    self.check_stale_outputs();
                 }
             });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let mut avail_size = ui.available_size();
-            avail_size.y = avail_size.y - 280.0;
-            let mut avail_w = avail_size.x;
+            avail_size.y -= 280.0;
+            let avail_w = avail_size.x;
 
             // 1. Determine the display rectangle based on texture aspect ratio
             let rect = if let Some(tex) = &self.video_texture {
-                let tex_size = tex.size_vec2();
+                // Show anamorphic sources with their display aspect, not their stored one
+                let tex_size = tex.size_vec2() * egui::vec2(self.pixel_aspect as f32, 1.0);
                 let scale = (avail_size.x / tex_size.x).min(avail_size.y / tex_size.y);
                 let display_size = tex_size * scale;
 
@@ -466,132 +4054,384 @@ impl eframe::App for VideoApp {
                 let fallback_h = avail_size.x * 0.5625;
                 ui.allocate_exact_size(egui::vec2(avail_size.x, fallback_h), egui::Sense::hover()).0
             };
+            // Snap to physical pixels with this viewport's current scale factor (it changes when
+            // the window moves between monitors). The image is painted into this exact rect and
+            // all crop mapping uses it, so fractional DPI scaling can't shift one against the other.
+            let ppp = ui.painter().pixels_per_point();
+            let rect = rect.round_to_pixels(ppp);
 
             // Allocate the interaction area at the calculated rect
-            let response = ui.interact(rect, ui.id().with("video_interact"), egui::Sense::click_and_drag());
+            let response = ui.interact(rect, viewport_id(), egui::Sense::click_and_drag());
+            let crop_label = match self.ranges.get(self.current_range_idx).and_then(|r| r.crop_rect_norm.as_ref()) {
+                Some(crop) => {
+                    let (w, h) = self.frame_size().unwrap_or_default();
+                    let (cw, ch, cx, cy) = crop.to_pixels(w, h);
+                    trf("Video preview, crop {} × {} px at {}, {}", &[&cw, &ch, &cx, &cy])
+                }
+                None => tr("Video preview").to_string(),
+            };
+            response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, true, &crop_label));
+            if response.has_focus() {
+                // Keep the arrow keys for crop nudging instead of moving focus to neighbours
+                ui.memory_mut(|m| {
+                    m.set_focus_lock_filter(
+                        response.id,
+                        egui::EventFilter {
+                            horizontal_arrows: true,
+                            vertical_arrows: true,
+                            ..Default::default()
+                        },
+                    )
+                });
+            }
+
+            // Pinch or Ctrl + scroll zooms around the fingers / cursor, two fingers or the middle
+            // mouse button pan. A single finger or a pen keeps drawing crops like the mouse does.
+            let multi_touch = ui.input(|i| i.multi_touch()).filter(|t| rect.contains(t.center_pos));
+            if response.contains_pointer() || multi_touch.is_some() {
+                let (zoom_delta, hover) = ui.input(|i| (i.zoom_delta(), i.pointer.hover_pos()));
+                let anchor = multi_touch
+                    .map(|t| t.center_pos)
+                    .or(hover)
+                    .unwrap_or(rect.center());
+                if zoom_delta != 1.0 {
+                    self.view.zoom_at(rect, anchor, zoom_delta);
+                }
+                if let Some(touch) = multi_touch {
+                    self.view.pan(rect, touch.translation_delta);
+                }
+            }
+            if response.dragged_by(egui::PointerButton::Middle) {
+                self.view.pan(rect, response.drag_delta());
+            }
+            if response.double_clicked() {
+                self.view = view::View::default();
+            }
 
             // 2. Paint the background and the image
             if let Some(tex) = &self.video_texture {
                 ui.painter().rect_filled(rect, 0.0, egui::Color32::BLACK); // Black bars area
-                ui.painter().image(
-                    tex.id(),
-                    rect,
-                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                    egui::Color32::WHITE,
-                );
+                ui.painter().image(tex.id(), rect, self.view.uv(), egui::Color32::WHITE);
             } else {
                 ui.painter().rect_filled(rect, 0.0, egui::Color32::BLACK);
             }
+            if self.show_training_view
+                && self.layout == settings::Layout::Engineer
+                && let Some(texture) = self.training_texture(ctx)
+            {
+                let norm = self.ranges[self.current_range_idx].crop_rect_norm.clone().unwrap_or(SerializableRect::FULL);
+                let (min, max) = (egui::pos2(norm.min_x, norm.min_y), egui::pos2(norm.max_x, norm.max_y));
+                let area = egui::Rect::from_min_max(self.view.norm_to_screen(rect, min), self.view.norm_to_screen(rect, max));
+                let full_uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                ui.painter_at(rect).image(texture, area, full_uv, egui::Color32::WHITE);
+            }
+            if self.loading.is_some() {
+                let spinner = egui::Rect::from_center_size(rect.center(), egui::vec2(48.0, 48.0));
+                ui.put(spinner, egui::Spinner::new().size(48.0));
+            }
 
-            // 3. Coordinate mapping (Now uses the correctly aspect-ratioed 'rect')
-            let to_norm = |p: egui::Pos2| {
-                egui::pos2(
-                    (p.x - rect.min.x) / rect.width(),
-                    (p.y - rect.min.y) / rect.height(),
-                )
-            };
-            let from_norm = |p: egui::Pos2| {
-                egui::pos2(
-                    p.x * rect.width() + rect.min.x,
-                    p.y * rect.height() + rect.min.y,
-                )
-            };
+            if response.has_focus() {
+                ui.painter().rect_stroke(rect, 0.0, ui.visuals().selection.stroke, egui::StrokeKind::Outside);
+            }
+
+            // 3. Coordinate mapping (Now uses the correctly aspect-ratioed 'rect' and the zoom)
+            let view = self.view;
+            let to_norm = |p: egui::Pos2| view.screen_to_norm(rect, p);
+            let from_norm = |p: egui::Pos2| view.norm_to_screen(rect, p);
+            // Overlays are clipped to the viewport, zoomed in crops may extend past it
+            let painter = ui.painter_at(rect);
 
             // --- Crop Handling (Remains the same logic, but uses updated rect) ---
             if !self.ranges.is_empty() {
-                if response.drag_started() {
+                // Drawing is disabled in review mode: with no drag start the rect never changes
+                let crop = &mut self.ranges[self.current_range_idx].crop_rect_norm;
+                if response.drag_started_by(egui::PointerButton::Primary) && !self.read_only {
                     self.drag_start_norm = response.interact_pointer_pos().map(to_norm);
+                    self.crop_before_drag = crop.clone();
                 }
-                if response.dragged() {
-                    if let (Some(start), Some(now)) = (
+                // The first finger of a pinch starts a drag; undo it once the second one lands
+                if multi_touch.is_some() && self.drag_start_norm.take().is_some() {
+                    *crop = self.crop_before_drag.take();
+                }
+                if response.dragged_by(egui::PointerButton::Primary)
+                    && let (Some(start), Some(now)) = (
                         self.drag_start_norm,
                         response.interact_pointer_pos().map(to_norm),
-                    ) {
-                        let r = egui::Rect::from_two_pos(start, now);
-                        // Clamp to 0.0-1.0 to prevent cropping outside the image
-                        self.ranges[self.current_range_idx].crop_rect_norm =
-                            Some(SerializableRect {
-                                min_x: r.min.x.clamp(0.0, 1.0),
-                                min_y: r.min.y.clamp(0.0, 1.0),
-                                max_x: r.max.x.clamp(0.0, 1.0),
-                                max_y: r.max.y.clamp(0.0, 1.0),
-                            });
+                    )
+                {
+                    let r = egui::Rect::from_two_pos(start, now);
+                    // Clamp to 0.0-1.0 to prevent cropping outside the image
+                    *crop = Some(SerializableRect {
+                        min_x: r.min.x.clamp(0.0, 1.0),
+                        min_y: r.min.y.clamp(0.0, 1.0),
+                        max_x: r.max.x.clamp(0.0, 1.0),
+                        max_y: r.max.y.clamp(0.0, 1.0),
+                    });
+                    self.touch();
+                }
+
+                if let Some(scan) = &self.ranges[self.current_range_idx].text_scan {
+                    for b in &scan.boxes {
+                        painter.rect_stroke(
+                            egui::Rect::from_min_max(
+                                from_norm(egui::pos2(b.min_x, b.min_y)),
+                                from_norm(egui::pos2(b.max_x, b.max_y)),
+                            ),
+                            0.0,
+                            egui::Stroke::new(1.0, egui::Color32::ORANGE),
+                            egui::StrokeKind::Outside,
+                        );
+                    }
+                }
+
+                // Neighbouring ranges are often consecutive shots of one scene; dashed outlines
+                // of their crops keep the framing continuous
+                if self.show_ghost_crops {
+                    let idx = self.current_range_idx;
+                    let neighbours = [idx.checked_sub(1), Some(idx + 1)].into_iter().flatten();
+                    for ghost in neighbours.filter_map(|i| self.ranges.get(i)?.crop_rect_norm.as_ref()) {
+                        let ghost = egui::Rect::from_min_max(
+                            from_norm(egui::pos2(ghost.min_x, ghost.min_y)),
+                            from_norm(egui::pos2(ghost.max_x, ghost.max_y)),
+                        )
+                        .round_to_pixels(ppp);
+                        let corners = [ghost.left_top(), ghost.right_top(), ghost.right_bottom(), ghost.left_bottom(), ghost.left_top()];
+                        let stroke = egui::Stroke::new(1.0, egui::Color32::WHITE.gamma_multiply(0.5));
+                        painter.extend(egui::Shape::dashed_line(&corners, stroke, 6.0, 4.0));
                     }
                 }
 
+                if let Some((idx, proposal)) = &self.crop_proposal
+                    && *idx == self.current_range_idx
+                {
+                    let norm = &proposal.crop;
+                    let r = egui::Rect::from_min_max(
+                        from_norm(egui::pos2(norm.min_x, norm.min_y)),
+                        from_norm(egui::pos2(norm.max_x, norm.max_y)),
+                    )
+                    .round_to_pixels(ppp);
+                    let corners = [r.left_top(), r.right_top(), r.right_bottom(), r.left_bottom(), r.left_top()];
+                    let stroke = egui::Stroke::new(2.0, egui::Color32::GREEN);
+                    painter.extend(egui::Shape::dashed_line(&corners, stroke, 8.0, 4.0));
+                }
+
                 if let Some(ref norm) = self.ranges[self.current_range_idx].crop_rect_norm {
                     let screen_rect = egui::Rect::from_min_max(
                         from_norm(egui::pos2(norm.min_x, norm.min_y)),
                         from_norm(egui::pos2(norm.max_x, norm.max_y)),
-                    );
-                    ui.painter().rect_stroke(
+                    )
+                    .round_to_pixels(ppp);
+                    painter.rect_stroke(
                         screen_rect,
                         0.0,
                         egui::Stroke::new(2.0, egui::Color32::RED),
                         egui::StrokeKind::Outside,
                     );
                 }
+
+                if self.show_tiles
+                    && self.layout == settings::Layout::Engineer
+                    && let Some((w, h)) = self.frame_size()
+                {
+                    let crop = self.ranges[self.current_range_idx].crop_rect_norm.clone().unwrap_or(SerializableRect::FULL);
+                    let (cw, ch, cx, cy) = crop.to_pixels(w, h);
+                    let (cw, ch) = (cw as f64, ch as f64);
+                    let at = |x: f64, y: f64| from_norm(egui::pos2((x / w) as f32, (y / h) as f32));
+                    let (min, max) = (at(cx as f64, cy as f64), at(cx as f64 + cw, cy as f64 + ch));
+                    // One tile holds as many source pixels as the trainer keeps of the short side
+                    let stroke = egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE.gamma_multiply(0.7));
+                    for x in tiles::lines(cw, self.training_resolution) {
+                        let x = at(cx as f64 + x, 0.0).x;
+                        painter.line_segment([egui::pos2(x, min.y), egui::pos2(x, max.y)], stroke);
+                    }
+                    for y in tiles::lines(ch, self.training_resolution) {
+                        let y = at(0.0, cy as f64 + y).y;
+                        painter.line_segment([egui::pos2(min.x, y), egui::pos2(max.x, y)], stroke);
+                    }
+                    let (ow, oh) = tiles::output_size(cw, ch, self.training_resolution);
+                    let scale = ow / cw.max(1.0);
+                    let text = trf(
+                        "{} × {} px → {} × {} (×{})",
+                        &[&cw, &ch, &ow.round(), &oh.round(), &format!("{:.2}", scale)],
+                    );
+                    let galley = painter.layout_no_wrap(text, egui::FontId::proportional(12.0), egui::Color32::LIGHT_BLUE);
+                    let pos = min.max(rect.min) + egui::vec2(4.0, 4.0);
+                    painter.rect_filled(egui::Rect::from_min_size(pos, galley.size()).expand(2.0), 2.0, egui::Color32::from_black_alpha(160));
+                    painter.galley(pos, galley, egui::Color32::LIGHT_BLUE);
+                }
             }
 
             // 4. Playback Controls / UI below the video
             ui.advance_cursor_after_rect(rect);
+            if self.view.is_zoomed() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("🔍 {:.1}x", self.view.zoom));
+                    if ui.small_button(tr("Reset Zoom")).on_hover_text(tr("Or double-click the video")).clicked() {
+                        self.perform(ctx, Action::ResetZoom);
+                    }
+                });
+            }
+            if let Some((idx, proposal)) = &self.crop_proposal
+                && *idx == self.current_range_idx
+            {
+                let (crop, score) = (proposal.crop.clone(), proposal.score);
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::GREEN,
+                        trf("Proposed crop (match {} %)", &[&(score * 100.0).round()]),
+                    );
+                    if ui.add_enabled(!self.read_only, egui::Button::new(tr("✔ Accept"))).clicked() {
+                        self.ranges[self.current_range_idx].crop_rect_norm = Some(crop);
+                        self.crop_proposal = None;
+                        self.touch();
+                    }
+                    if ui.button(tr("✖ Dismiss")).clicked() {
+                        self.crop_proposal = None;
+                    }
+                });
+            }
+            if let Some(note) = &self.preview_note {
+                ui.horizontal(|ui| {
+                    if self.is_building_proxy.load(atomic::Ordering::SeqCst) {
+                        ui.spinner();
+                    }
+                    ui.colored_label(egui::Color32::YELLOW, note);
+                });
+            }
+            if self.show_subtitles
+                && let Some(cue) = self.subtitles.as_ref().and_then(|t| t.active(self.current_time))
+            {
+                let text = cue.text.clone();
+                ui.horizontal(|ui| {
+                    let copy = ui
+                        .add_enabled(!self.read_only && !self.ranges.is_empty(), egui::Button::new(tr("📋 To Note")))
+                        .on_hover_text(tr("Append the subtitle to the note of the current range"));
+                    if copy.clicked() {
+                        let note = &mut self.ranges[self.current_range_idx].note;
+                        if !note.trim().is_empty() {
+                            note.push('\n');
+                        }
+                        *note += &text;
+                        self.touch();
+                    }
+                    ui.label(egui::RichText::new(text).italics());
+                });
+            }
+            if self.is_playing() && self.preview_step > 1 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    trf(
+                        "Reduced preview: showing 1 in {} frames (export unaffected)",
+                        &[&self.preview_step],
+                    ),
+                );
+            }
             ui.add_space(8.0);
 
             // 5. Hide the timeline/playback info if we are looking at a static image
             if !self.is_image {
                 ui.add_space(8.0);
                 ui.horizontal(|ui| {
-                    ui.label("Native Frame:");
+                    ui.label(tr("Native Frame:"));
 
                     let response = ui.add(
                         egui::TextEdit::singleline(&mut self.frame_text)
                             .desired_width(80.0)
-                    );
+                    ).on_hover_text(tr(
+                        "A native frame, +N / -N native frames from the range start, or rK:N for frame N of range K's exported clip",
+                    ));
 
-                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                        if let Ok(frame_num) = self.frame_text.trim().parse::<i32>() {
-                            self.current_time = (frame_num as f64) / self.native_fps;
-                            self.current_time = self.current_time.clamp(0.0, self.duration);
-                            self.update_frame(ctx);
-                        }
+                    if response.lost_focus()
+                        && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                        && let Some(time) = timing::parse_frame_entry(&self.frame_text).and_then(|e| self.frame_entry_time(e))
+                    {
+                        self.current_time = time.clamp(0.0, self.duration);
+                        self.update_frame(ctx);
                     }
 
                     if !response.has_focus() {
-                        let current_frame = (self.current_time * self.native_fps) as i32;
+                        let current_frame = timing::frame_at(self.current_time, self.native_fps);
                         self.frame_text = current_frame.to_string();
                     }
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(format!("Target 16FPS: {:.1}", self.current_time * 16.0));
+                        let rate = match self.ranges.get(self.current_range_idx) {
+                            Some(range) => export::frame_rate(&self.export_profile, range),
+                            None => self.export_profile.frame_rate,
+                        };
+                        let fps = rate.resolve(self.native_fps);
+                        let shown = format!("{:.2}", fps);
+                        ui.label(trf(
+                            "Target {} FPS: {}",
+                            &[&shown.trim_end_matches('0').trim_end_matches('.'), &format!("{:.1}", self.current_time * fps)],
+                        ));
                     });
                 });
 
                 let track_width = avail_w - 60.0;
                 ui.spacing_mut().slider_width = track_width;
 
+                // Playback and frame steps scroll a zoomed timeline along
+                if !self.scrubbing {
+                    self.timeline.follow(self.duration, self.current_time);
+                }
+                let (window_start, window_end) = self.timeline.window(self.duration);
                 let slider_res = ui.add(
-                    egui::Slider::new(&mut self.current_time, 0.0..=self.duration)
+                    egui::Slider::new(&mut self.current_time, window_start..=window_end)
+                        .clamping(egui::SliderClamping::Edits)
                         .show_value(true)
                         .suffix("s"),
                 );
+                self.scrubbing = slider_res.dragged();
                 if slider_res.changed() {
+                    if self.scrubbing {
+                        self.scrub_pending = true;
+                    } else {
+                        self.update_frame(ctx);
+                    }
+                }
+                if self.scrubbing {
+                    self.scrub_decode(ctx);
+                }
+                if slider_res.drag_stopped() {
+                    // Replace the keyframe preview with the exact frame
+                    self.scrubbing = false;
+                    self.scrub_pending = false;
+                    self.scrub_decoded_at = None;
                     self.update_frame(ctx);
                 }
 
+                let rect = slider_res.rect;
+                let window = window_end - window_start;
+                let time_to_x = |time: f64| {
+                    let pct = if window > 0.0 { ((time - window_start) / window) as f32 } else { 0.0 };
+                    rect.min.x + pct * track_width
+                };
+
+                // Mouse wheel over the slider zooms around the pointer, sideways scrolling pans
+                if slider_res.hovered() {
+                    let (scroll, hover) = ui.input(|i| (i.smooth_scroll_delta, i.pointer.hover_pos()));
+                    if scroll.y != 0.0
+                        && let Some(pos) = hover
+                    {
+                        let share = ((pos.x - rect.min.x) / track_width).clamp(0.0, 1.0) as f64;
+                        let anchor = window_start + share * window;
+                        self.timeline.zoom_at(self.duration, anchor, (scroll.y as f64 / 200.0).exp());
+                    }
+                    if scroll.x != 0.0 {
+                        self.timeline.pan(self.duration, -(scroll.x / track_width) as f64 * window);
+                    }
+                }
+
                 if !self.ranges.is_empty() {
                     let range = &self.ranges[self.current_range_idx];
-                    let rect = slider_res.rect;
-
-                    let time_to_x = |time: f64| {
-                        let pct = (time / self.duration) as f32;
-                        rect.min.x + pct * track_width
-                    };
+                    let visible = |time: f64| (window_start..=window_end).contains(&time);
 
                     let painter = ui.painter();
                     let stroke_start = egui::Stroke::new(2.0, egui::Color32::GREEN);
                     let stroke_end = egui::Stroke::new(2.0, egui::Color32::RED);
 
-                    if range.start_time > 0.0 {
+                    if range.start_time > 0.0 && visible(range.start_time) {
                         let x = time_to_x(range.start_time);
                         painter.line_segment(
                             [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
@@ -599,7 +4439,7 @@ impl eframe::App for VideoApp {
                         );
                     }
 
-                    if range.end_time < self.duration {
+                    if range.end_time < self.duration && visible(range.end_time) {
                         let x = time_to_x(range.end_time);
                         painter.line_segment(
                             [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
@@ -607,55 +4447,211 @@ impl eframe::App for VideoApp {
                         );
                     }
 
-                    let start_x = time_to_x(range.start_time);
-                    let end_x = time_to_x(range.end_time);
-                    painter.rect_filled(
-                        egui::Rect::from_min_max(
-                            egui::pos2(start_x, rect.center().y - 2.0),
-                            egui::pos2(end_x, rect.center().y + 2.0),
-                        ),
-                        0.0,
-                        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 40),
+                    let start_x = time_to_x(range.start_time.max(window_start));
+                    let end_x = time_to_x(range.end_time.min(window_end));
+                    if start_x < end_x {
+                        painter.rect_filled(
+                            egui::Rect::from_min_max(
+                                egui::pos2(start_x, rect.center().y - 2.0),
+                                egui::pos2(end_x, rect.center().y + 2.0),
+                            ),
+                            0.0,
+                            egui::Color32::from_rgba_unmultiplied(255, 255, 255, 40),
+                        );
+                    }
+                }
+
+                // Chapter starts from the container, named on hover
+                let pointer = ui.input(|i| i.pointer.hover_pos());
+                let mut hovered_chapter = None;
+                for chapter in &self.chapters {
+                    if !(window_start..=window_end).contains(&chapter.start) {
+                        continue;
+                    }
+                    let x = time_to_x(chapter.start);
+                    ui.painter().line_segment(
+                        [egui::pos2(x, rect.min.y), egui::pos2(x, rect.center().y)],
+                        egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 160, 0)),
+                    );
+                    if slider_res.hovered()
+                        && pointer.is_some_and(|p| (p.x - x).abs() <= 3.0)
+                        && !chapter.title.is_empty()
+                    {
+                        hovered_chapter = Some(chapter.title.clone());
+                    }
+                }
+                if let Some(title) = hovered_chapter {
+                    slider_res.clone().on_hover_text(title);
+                }
+
+                // Mini-map of the whole source, also while zoomed: every range (the current one
+                // brighter), chapter starts, the zoom window and the playhead. Clicking or
+                // dragging seeks there, previewing like the timeline does.
+                if self.duration > 0.0 {
+                    let (overview, response) =
+                        ui.allocate_exact_size(egui::vec2(track_width, 10.0), egui::Sense::click_and_drag());
+                    let full_x = |time: f64| overview.min.x + (time / self.duration) as f32 * track_width;
+                    let painter = ui.painter();
+                    painter.rect_filled(overview, 2.0, ui.visuals().extreme_bg_color);
+                    for (i, range) in self.ranges.iter().enumerate() {
+                        let alpha = if i == self.current_range_idx { 110 } else { 40 };
+                        painter.rect_filled(
+                            egui::Rect::from_x_y_ranges(full_x(range.start_time)..=full_x(range.end_time), overview.y_range()),
+                            0.0,
+                            egui::Color32::from_rgba_unmultiplied(255, 255, 255, alpha),
+                        );
+                    }
+                    for chapter in &self.chapters {
+                        let x = full_x(chapter.start);
+                        painter.line_segment(
+                            [egui::pos2(x, overview.min.y), egui::pos2(x, overview.center().y)],
+                            egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 160, 0)),
+                        );
+                    }
+                    if self.timeline.is_zoomed() {
+                        painter.rect_stroke(
+                            egui::Rect::from_x_y_ranges(full_x(window_start)..=full_x(window_end), overview.y_range()),
+                            2.0,
+                            egui::Stroke::new(1.0, ui.visuals().selection.bg_fill),
+                            egui::StrokeKind::Inside,
+                        );
+                    }
+                    let x = full_x(self.current_time);
+                    painter.line_segment(
+                        [egui::pos2(x, overview.min.y), egui::pos2(x, overview.max.y)],
+                        egui::Stroke::new(1.0, ui.visuals().text_color()),
                     );
+                    if let Some(pos) = response.interact_pointer_pos()
+                        && (response.clicked() || response.dragged())
+                    {
+                        let time = ((pos.x - overview.min.x) / track_width).clamp(0.0, 1.0) as f64 * self.duration;
+                        self.current_time = time;
+                        self.timeline.center_on(self.duration, time);
+                        if response.dragged() {
+                            self.scrubbing = true;
+                            self.scrub_pending = true;
+                            self.scrub_decode(ctx);
+                        } else {
+                            self.update_frame(ctx);
+                        }
+                    }
+                    if response.drag_stopped() {
+                        self.scrubbing = false;
+                        self.scrub_decoded_at = None;
+                        self.update_frame(ctx);
+                    }
+                }
+                if self.timeline.is_zoomed() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("🔍 {:.0}x", self.timeline.zoom()));
+                        if ui.small_button(tr("Whole Timeline")).clicked() {
+                            self.timeline = timeline::Timeline::default();
+                        }
+                    });
                 }
             } // end if !self.is_image
 
             ui.add_space(8.0);
             ui.horizontal(|ui| {
                 if !self.is_image {
-                    if ui.button("⏪").clicked() {
+                    if accessible(ui.button("⏪"), tr(Action::PrevFrame.label())).clicked() {
                         self.prev_frame(ctx);
                     }
-                    if ui
-                        .button(if self.is_playing() { "⏸" } else { "▶" })
-                        .clicked()
-                    {
+                    let play = ui.button(if self.is_playing() { "⏸" } else { "▶" });
+                    if accessible(play, tr(Action::PlayPause.label())).clicked() {
                         self.pause_play();
                     }
-                    if ui.button("⏩").clicked() {
+                    if accessible(ui.button("⏩"), tr(Action::NextFrame.label())).clicked() {
                         self.next_frame(ctx);
                     }
+                    let step_label = |step: i32| match step {
+                        1 => tr("Every frame").to_string(),
+                        n => trf("1 in {} frames", &[&n]),
+                    };
+                    egui::ComboBox::from_id_salt("preview_step")
+                        .selected_text(step_label(self.preview_step))
+                        .show_ui(ui, |ui| {
+                            for step in 1..=3 {
+                                ui.selectable_value(&mut self.preview_step, step, step_label(step));
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Playback preview quality. Showing fewer frames helps on slow machines; paused frames and exports are unaffected.",
+                        ));
+                    if self.subtitles.is_some() {
+                        ui.checkbox(&mut self.show_subtitles, tr("Subtitles"))
+                            .on_hover_text(tr("Show the current subtitle under the video"));
+                    }
                     ui.separator();
                 }
 
                 if !self.ranges.is_empty() {
-                    if !self.is_image {
-                        if ui.button("Set Start").clicked() {
-                            self.ranges[self.current_range_idx].start_time = self.current_time;
+                    let editable = !self.read_only;
+                    ui.add_enabled_ui(editable, |ui| {
+                        if !self.is_image {
+                            if ui.button(tr("Set Start")).clicked() {
+                                self.perform(ctx, Action::SetStart);
+                            }
+                            if ui.button(tr("Set End")).clicked() {
+                                self.perform(ctx, Action::SetEnd);
+                            }
+                        }
+                        if ui.button(tr("Clear Crop")).clicked() {
+                            self.perform(ctx, Action::ClearCrop);
                         }
-                        if ui.button("Set End").clicked() {
-                            self.ranges[self.current_range_idx].end_time = self.current_time;
+                        let crop = &self.ranges[self.current_range_idx].crop_rect_norm;
+                        let is_default = crop.is_some() && *crop == self.default_crop;
+                        let default = ui
+                            .add_enabled(crop.is_some(), egui::Button::new(tr("📌 File Default")).selected(is_default))
+                            .on_hover_text(tr("New ranges of this file start with this crop. Click again to stop."));
+                        if default.clicked() {
+                            self.perform(ctx, Action::SetDefaultCrop);
                         }
+                        if ui.button(tr("✨ Auto Crop (A)")).clicked() {
+                            self.perform(ctx, Action::AutoCrop);
+                        }
+                        if !self.is_image && self.ranges.len() > 1 {
+                            let matched = ui.button(tr("🎯 Match Crop")).on_hover_text(tr(
+                                "Find the previous (or next) range's crop in this frame and propose it moved there, for slight reframing between shots",
+                            ));
+                            if matched.clicked() {
+                                self.perform(ctx, Action::MatchCrop);
+                            }
+                        }
+                        egui::ComboBox::from_id_salt("auto_crop_aspect")
+                            .width(60.0)
+                            .selected_text(AUTO_CROP_ASPECTS[self.auto_crop_aspect_idx].0)
+                            .show_ui(ui, |ui| {
+                                for (i, (name, _)) in AUTO_CROP_ASPECTS.iter().enumerate() {
+                                    ui.selectable_value(&mut self.auto_crop_aspect_idx, i, *name);
+                                }
+                            });
+                    });
+                    if self.ranges.len() > 1 {
+                        ui.checkbox(&mut self.show_ghost_crops, tr("Ghost crops"))
+                            .on_hover_text(tr("Outline the crops of the previous and next range"));
                     }
-                    if ui.button("Clear Crop").clicked() {
-                        self.ranges[self.current_range_idx].crop_rect_norm = None;
+                    if self.layout == settings::Layout::Engineer {
+                        ui.checkbox(&mut self.show_tiles, tr("Tiles"))
+                            .on_hover_text(tr("Grid of training-resolution tiles over the crop: one tile is what the short side is scaled down to"));
+                        if self.show_tiles {
+                            ui.add(egui::DragValue::new(&mut self.training_resolution).range(64..=4096).speed(8.0).suffix(" px"))
+                                .on_hover_text(tr("Training resolution (short side)"));
+                        }
+                        ui.checkbox(&mut self.show_training_view, tr("Training view"))
+                            .on_hover_text(tr("Show the crop scaled down to the training resolution and back up, as the model sees it"));
                     }
                     if !self.is_image {
                         ui.separator();
-                        if ui.add(egui::Button::new("🔁 Play Range (R)")).clicked() {
-                            let range = &self.ranges[self.current_range_idx];
-                            self.current_time = range.start_time;
-                            self.play_state = PlayState::PlayingUntil(range.end_time);
+                        if ui.add(egui::Button::new(tr("🔁 Play Range (R)"))).clicked() {
+                            self.perform(ctx, Action::PreviewRange);
+                        }
+                        let check = ui
+                            .add(egui::Button::new(tr("🔲 Cut Check")).selected(self.show_cut_check))
+                            .on_hover_text(tr("Show the frames on both sides of the range's start and end, to check a cut lands right before a scene change"));
+                        if check.clicked() {
+                            self.perform(ctx, Action::CutCheck);
                         }
                     }
                 }
@@ -664,123 +4660,800 @@ impl eframe::App for VideoApp {
             if !self.ranges.is_empty() {
                 ui.add_space(10.0);
                 ui.label(if self.is_image {
-                    format!("Note for Crop {}:", self.current_range_idx)
+                    trf("Note for Crop {}:", &[&self.current_range_idx])
                 } else {
-                    format!("Note for Range {}:", self.current_range_idx)
+                    trf("Note for Range {}:", &[&self.current_range_idx])
                 });
 
-                ui.add(
-                    egui::TextEdit::multiline(&mut self.ranges[self.current_range_idx].note)
-                        .desired_width(avail_w)
-                        .desired_rows(5),
-                );
+                let note = &mut self.ranges[self.current_range_idx].note;
+                let first_line = note.lines().next().unwrap_or_default().to_string();
+                // A &str buffer keeps the note selectable and copyable but not editable
+                let mut read_only_note = note.as_str();
+                let buffer: &mut dyn egui::TextBuffer =
+                    if self.read_only { &mut read_only_note } else { note };
+                let edited = ui
+                    .add(
+                        egui::TextEdit::multiline(buffer)
+                            .desired_width(avail_w)
+                            .desired_rows(5),
+                    )
+                    .changed();
+                if edited {
+                    self.touch();
+                    // With folders named after notes, the first line decides where the output goes
+                    let note = &self.ranges[self.current_range_idx].note;
+                    if self.export_profile.folders == export::Folders::NoteLine
+                        && note.lines().next().unwrap_or_default() != first_line
+                    {
+                        self.check_stale_outputs();
+                    }
+                }
+
+                ui.add_space(6.0);
+                ui.add_enabled_ui(!self.read_only, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(tr("Tags:"));
+                        let tags = &mut self.ranges[self.current_range_idx].tags;
+                        let rules = &self.export_profile.rules;
+                        let mut changed = false;
+                        let mut remove = None;
+                        for (i, tag) in tags.iter().enumerate() {
+                            let chip = egui::RichText::new(format!("{} ✖", tag));
+                            let chip = if rules.is_banned(tag) {
+                                ui.button(chip.color(ui.visuals().error_fg_color))
+                                    .on_hover_text(tr("Banned by the project rules; not exported"))
+                            } else {
+                                ui.button(chip)
+                            };
+                            if accessible(chip, &trf("Remove tag {}", &[tag])).clicked() {
+                                remove = Some(i);
+                            }
+                        }
+                        if let Some(i) = remove {
+                            tags.remove(i);
+                            changed = true;
+                        }
+                        let edit = ui.add(
+                            egui::TextEdit::singleline(&mut self.new_tag)
+                                .desired_width(120.0)
+                                .hint_text(tr("add tag, Enter")),
+                        );
+                        if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            let tag = self.new_tag.trim().to_string();
+                            if !tag.is_empty() && !tags.contains(&tag) {
+                                tags.push(tag);
+                                changed = true;
+                            }
+                            self.new_tag.clear();
+                            edit.request_focus();
+                        }
+                        if changed {
+                            self.touch();
+                            // The first tag names the class folder the output goes into
+                            self.check_stale_outputs();
+                        }
+                    });
+                    // Splits and frame rates are the dataset's business, not the annotator's
+                    let engineer = self.layout == settings::Layout::Engineer;
+                    if engineer {
+                        ui.horizontal(|ui| {
+                            ui.label(tr("Split:"));
+                            let current = self.ranges[self.current_range_idx].split;
+                            let mut picked = current;
+                            egui::ComboBox::from_id_salt("range_split")
+                                .selected_text(current.map_or("—", |s| tr(s.label())))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut picked, None, "—");
+                                    for split in split::Split::ALL {
+                                        ui.selectable_value(&mut picked, Some(split), tr(split.label()));
+                                    }
+                                });
+                            if picked != current {
+                                self.ranges[self.current_range_idx].split = picked;
+                                self.touch();
+                                self.check_stale_outputs();
+                            }
+                            let whole_file = ui
+                                .button(tr("Apply to File"))
+                                .on_hover_text(tr("Give every range of this file the same split"));
+                            if whole_file.clicked() {
+                                for range in &mut self.ranges {
+                                    if range.split != picked {
+                                        range.split = picked;
+                                        range.stamp(&self.annotator);
+                                    }
+                                }
+                                self.check_stale_outputs();
+                            }
+                            ui.separator();
+                            ui.label(tr("Ratios:"));
+                            for (ratio, split) in self.split_ratios.iter_mut().zip(split::Split::ALL) {
+                                let value = ui.add(
+                                    egui::DragValue::new(ratio)
+                                        .range(0.0..=100.0)
+                                        .speed(0.5)
+                                        .suffix(" %"),
+                                );
+                                accessible(value, tr(split.label()));
+                            }
+                            if ui.button(tr(Action::RandomizeSplits.label())).clicked() {
+                                self.perform(ctx, Action::RandomizeSplits);
+                            }
+                        });
+                    }
+                    if !self.is_image && engineer {
+                        ui.horizontal(|ui| {
+                            let default = self.export_profile.frame_rate;
+                            let fps = &mut self.ranges[self.current_range_idx].fps;
+                            let mut own = fps.is_some();
+                            let mut changed = ui
+                                .checkbox(&mut own, tr("Own frame rate"))
+                                .on_hover_text(trf("Export this range at another rate than the export setting ({})", &[&default.label()]))
+                                .changed();
+                            if changed {
+                                *fps = own.then_some(default);
+                            }
+                            if let Some(rate) = fps {
+                                changed |= frame_rate_picker(ui, "range_fps", rate);
+                            }
+                            if changed {
+                                self.touch();
+                            }
+                        });
+                    }
+                });
             }
 
+            if self.layout == settings::Layout::Annotator {
+                return;
+            }
             ui.add_space(10.0);
             let exporting = self.is_exporting.load(atomic::Ordering::SeqCst);
 
-            ui.add_enabled_ui(!exporting, |ui| {
-                let btn_text = if exporting {
+            ui.add_enabled_ui(!exporting && !self.read_only, |ui| {
+                let btn_text = tr(if exporting {
                     "⏳ Exporting..."
                 } else {
                     "🚀 RUN EXPORT ALL"
-                };
+                });
                 if ui
                     .add_sized([avail_w, 40.0], egui::Button::new(btn_text))
                     .clicked()
                 {
                     self.run_export();
                 }
+                let preview = ui
+                    .add_enabled(
+                        self.output_folder.is_some() && self.selected_file_idx.is_some(),
+                        egui::Button::new(tr("🔍 Preview Export Commands")),
+                    )
+                    .on_hover_text(tr(
+                        "List the FFmpeg commands the export would run for the open file, without running them",
+                    ));
+                if preview.clicked() {
+                    self.perform(ctx, Action::PreviewCommands);
+                }
+                let marked = ui
+                    .add_enabled(
+                        self.output_folder.is_some() && !self.marked.is_empty(),
+                        egui::Button::new(trf("📦 Export Marked ({})", &[&self.marked.len()])),
+                    )
+                    .on_hover_text(tr(
+                        "Export the files ticked in the file list one after the other, each with its saved ranges",
+                    ));
+                if marked.clicked() {
+                    self.perform(ctx, Action::ExportMarked);
+                }
+                let failed = self.failed_exports().len();
+                if failed > 0 {
+                    let retry = ui
+                        .add_enabled(
+                            self.output_folder.is_some(),
+                            egui::Button::new(trf("🔁 Retry Failed ({})", &[&failed])),
+                        )
+                        .on_hover_text(tr(
+                            "Export the files whose last batch export failed again; only their failed ranges are encoded",
+                        ));
+                    if retry.clicked() {
+                        self.perform(ctx, Action::RetryFailed);
+                    }
+                }
+                let rebuild = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("♻ Rebuild Dataset")))
+                    .on_hover_text(tr(
+                        "Delete the files listed in the manifest and export every annotated file of the folder again",
+                    ));
+                if rebuild.clicked() {
+                    self.perform(ctx, Action::RebuildDataset);
+                }
+            });
+            ui.horizontal(|ui| {
+                let audit = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("🩺 Audit Output")))
+                    .on_hover_text(tr(
+                        "Check the output folder for orphan, missing and stale files and outdated captions",
+                    ));
+                if audit.clicked() {
+                    self.perform(ctx, Action::AuditOutput);
+                }
+                let rename = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("✏ Rename Outputs")))
+                    .on_hover_text(tr(
+                        "Apply the current output names and caption template to the exported files without encoding them again",
+                    ));
+                if rename.clicked() {
+                    self.perform(ctx, Action::RenameOutputs);
+                }
+                let rules = ui.button(tr("📜 Project Rules")).on_hover_text(tr(
+                    "Trigger words, banned tags and tag order of the input folder, enforced at export",
+                ));
+                if rules.clicked() {
+                    self.perform(ctx, Action::ProjectRules);
+                }
+                let links = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("🔗 Linked Projects")))
+                    .on_hover_text(tr(
+                        "Other output folders whose exported material new ranges are checked against",
+                    ));
+                if links.clicked() {
+                    self.perform(ctx, Action::LinkedProjects);
+                }
+                let versions = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("🏷 Versions")))
+                    .on_hover_text(tr(
+                        "Snapshot the dataset under a version label and compare versions",
+                    ));
+                if versions.clicked() {
+                    self.perform(ctx, Action::DatasetVersions);
+                }
+                let package = ui
+                    .add_enabled(self.output_folder.is_some(), egui::Button::new(tr("📦 Package")))
+                    .on_hover_text(tr(
+                        "Zip or tar the exported files with the manifest, optionally in volumes with checksums, for upload",
+                    ));
+                if package.clicked() {
+                    self.perform(ctx, Action::PackageDataset);
+                }
+                let verify = ui
+                    .add_enabled(!exporting, egui::Button::new(tr("🔐 Verify")))
+                    .on_hover_text(tr(
+                        "Check the output folder or a copy of it against its checksums.txt, e.g. after a transfer",
+                    ));
+                if verify.clicked() {
+                    self.perform(ctx, Action::VerifyChecksums);
+                }
+                let upload = ui
+                    .button(tr("☁ Upload"))
+                    .on_hover_text(tr("Push the exported files and the manifest to an S3-compatible bucket"));
+                if upload.clicked() {
+                    self.perform(ctx, Action::UploadDataset);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Frame rate:"));
+                ui.add_enabled_ui(!exporting, |ui| {
+                    frame_rate_picker(ui, "export_fps", &mut self.export_profile.frame_rate);
+                    ui.checkbox(&mut self.export_profile.stream_copy, tr("Stream copy"))
+                        .on_hover_text(tr(
+                            "Cut ranges without a crop at the native frame rate without encoding: instant and lossless, but the cuts move to the nearest keyframes",
+                        ));
+                    ui.checkbox(&mut self.export_profile.trim_edges, tr("Trim edges"))
+                        .on_hover_text(tr(
+                            "Drop nearly black or frozen frames (fades, freeze frames) at both ends of each range, up to 3 seconds each; ✂ Trim Preview shows what goes",
+                        ));
+                });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Output:"));
+                ui.add_enabled_ui(!exporting, |ui| {
+                    let frames = &mut self.export_profile.frames;
+                    let frames_label = |format: Option<export::FrameFormat>| format.map_or(tr("Clips"), |f| tr(f.label()));
+                    egui::ComboBox::from_id_salt("export_frames")
+                        .selected_text(frames_label(*frames))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(frames, None, frames_label(None));
+                            for option in export::FrameFormat::ALL {
+                                ui.selectable_value(frames, Some(option), tr(option.label()));
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Frame sequences put the numbered frames of each range (000000.png, …) into a folder of their own, cropped and at the export frame rate",
+                        ));
+                });
+                let clips = self.export_profile.frames.is_none();
+                ui.label(tr("Container:"));
+                ui.add_enabled_ui(!exporting, |ui| {
+                    ui.add_enabled_ui(clips, |ui| {
+                        let container = &mut self.export_profile.container;
+                        egui::ComboBox::from_id_salt("export_container")
+                            .selected_text(container.label())
+                            .show_ui(ui, |ui| {
+                                for option in export::Container::ALL {
+                                    ui.selectable_value(container, option, option.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text(tr("WebM clips are always VP9; MP4 and MKV use the codec of the encoder settings"));
+                        if ui.button(tr("🎛 Encoder…")).on_hover_text(tr("Codec, quality, preset and pixel format")).clicked() {
+                            self.perform(ctx, Action::EncoderSettings);
+                        }
+                    });
+                    let mp4 = clips && self.export_profile.container == export::Container::Mp4;
+                    ui.add_enabled(mp4, egui::Checkbox::new(&mut self.export_profile.web_optimized, tr("Web-optimized")))
+                        .on_hover_text(tr(
+                            "Put the MP4 index first and a keyframe every second, so clips stream and scrub over HTTP",
+                        ));
+                    let folders = &mut self.export_profile.folders;
+                    egui::ComboBox::from_id_salt("export_folders")
+                        .selected_text(tr(folders.label()))
+                        .show_ui(ui, |ui| {
+                            for option in export::Folders::ALL {
+                                ui.selectable_value(folders, option, tr(option.label()));
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Put each clip into a folder named after its first tag (in the project rules' tag order) or the first line of its note, inside its split folder",
+                        ));
+                    ui.label(tr("Existing:"));
+                    let collisions = &mut self.export_profile.collisions;
+                    egui::ComboBox::from_id_salt("export_collisions")
+                        .selected_text(tr(collisions.label()))
+                        .show_ui(ui, |ui| {
+                            for option in export::Collisions::ALL {
+                                ui.selectable_value(collisions, option, tr(option.label()));
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "What to do when an output name is taken by a file the range didn't write: overwrite it, skip the range or add _001, _002, … to the new name",
+                        ));
+                    ui.separator();
+                    ui.label(tr("Audio:"));
+                    let audio = &mut self.export_profile.audio;
+                    let audio_label = |format: Option<export::AudioFormat>| format.map_or(tr("No audio"), |f| f.label());
+                    egui::ComboBox::from_id_salt("export_audio")
+                        .selected_text(audio_label(*audio))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(audio, None, audio_label(None));
+                            for option in export::AudioFormat::ALL {
+                                ui.selectable_value(audio, Some(option), option.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Also write each clip's audio to a file with the same name, for audio-visual and lip-sync datasets",
+                        ));
+                    ui.checkbox(&mut self.export_profile.loudnorm, tr("Normalize loudness"))
+                        .on_hover_text(tr(
+                            "EBU R128 loudness normalization (-23 LUFS), so clips from different sources sound equally loud",
+                        ));
+                    ui.separator();
+                    ui.label(tr("Preview:"));
+                    let preview = &mut self.export_profile.preview;
+                    let preview_label = |format: Option<export::PreviewFormat>| format.map_or(tr("No preview"), |f| f.label());
+                    egui::ComboBox::from_id_salt("export_preview")
+                        .selected_text(preview_label(*preview))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(preview, None, preview_label(None));
+                            for option in export::PreviewFormat::ALL {
+                                ui.selectable_value(preview, Some(option), option.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Also write a small looping animation of each clip's first seconds, for dataset cards and quick browsing",
+                        ));
+                    let mut sprites = self.export_profile.sprite_grid.is_some();
+                    ui.checkbox(&mut sprites, tr("Sprite sheet"))
+                        .on_hover_text(trf(
+                            "Also write a grid of frames spread over each clip, {} px per tile, for galleries and models that take sprite sheets",
+                            &[&export::SPRITE_TILE],
+                        ));
+                    let grid = &mut self.export_profile.sprite_grid;
+                    *grid = sprites.then(|| grid.unwrap_or(4));
+                    if let Some(grid) = grid {
+                        ui.add(
+                            egui::DragValue::new(grid)
+                                .range(2..=export::SPRITE_MAX_GRID)
+                                .custom_formatter(|v, _| format!("{0}×{0}", v)),
+                        );
+                    }
+                });
+                ui.separator();
+                ui.add_enabled(!exporting, egui::Checkbox::new(&mut self.schedule_enabled, tr("Start at")))
+                    .on_hover_text(tr("Hold exports until this time, e.g. to encode overnight"));
+                let (hour, minute) = &mut self.schedule_time;
+                ui.add_enabled(
+                    !exporting && self.schedule_enabled,
+                    egui::DragValue::new(hour).range(0..=23).custom_formatter(|v, _| format!("{:02}", v)),
+                );
+                ui.label(":");
+                ui.add_enabled(
+                    !exporting && self.schedule_enabled,
+                    egui::DragValue::new(minute).range(0..=59).custom_formatter(|v, _| format!("{:02}", v)),
+                );
+                ui.separator();
+                ui.label(tr("Parallel:"));
+                ui.add_enabled(
+                    !exporting,
+                    egui::DragValue::new(&mut self.parallel_exports).range(1..=pool::max_limit()),
+                )
+                .on_hover_text(tr(
+                    "Files a batch export or rebuild encodes at once, one FFmpeg each. More use more cores on short clips.",
+                ));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Caption:"));
+                ui.add_enabled(
+                    !exporting,
+                    egui::TextEdit::singleline(&mut self.export_profile.caption_template).desired_width(220.0),
+                )
+                .on_hover_text(tr(
+                    "Caption file text. Tokens: {note}, {tags}, {source} (file name), {range}, {split}. Use Rename Outputs to apply a change to an existing export.",
+                ));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Names:"));
+                ui.add_enabled(
+                    !exporting,
+                    egui::TextEdit::singleline(&mut self.export_profile.name_template)
+                        .hint_text("{stem}_range{range}")
+                        .desired_width(220.0),
+                )
+                .on_hover_text(tr(
+                    "Output file names. Tokens: {stem}, {range}, {start_frame}, {end_frame}, {tag}, {date} (last change). Empty for the default. Use Rename Outputs to apply a change to an existing export.",
+                ));
+                let template = &self.export_profile.name_template;
+                if !template.trim().is_empty() && !template.contains("{stem}") {
+                    ui.label("⚠").on_hover_text(tr(
+                        "Without {stem}, outputs of different files can get the same name and overwrite each other",
+                    ));
+                }
             });
 
             if exporting {
+                let waiting_for = self
+                    .schedule
+                    .start_at()
+                    .filter(|at| chrono::Local::now().naive_local() < *at);
                 ui.horizontal(|ui| {
                     ui.spinner();
-                    ui.label("Processing ranges with FFmpeg...");
+                    if let Some(at) = waiting_for {
+                        ui.label(trf("🕙 Export scheduled for {}", &[&at.format("%H:%M")]));
+                        if ui.button(tr("Start Now")).clicked() {
+                            self.schedule.set_start_at(None);
+                        }
+                    } else if self.schedule.is_paused() {
+                        ui.label(tr("Paused; the current clip finishes first"));
+                    } else {
+                        ui.label(tr("Processing ranges with FFmpeg..."));
+                    }
+                });
+                for progress in self.progress.all() {
+                    show_export_progress(ui, &progress);
+                }
+                ui.horizontal(|ui| {
+                    let paused = self.schedule.is_paused();
+                    if ui.button(tr(if paused { "▶ Resume Export" } else { "⏸ Pause Export" })).clicked() {
+                        self.schedule.set_paused(!paused);
+                    }
+                    let cancelling = self.schedule.is_cancelled();
+                    if ui
+                        .add_enabled(!cancelling, egui::Button::new(tr("⏹ Cancel Export")))
+                        .on_hover_text(tr("Stops FFmpeg and skips the remaining ranges; clips already written are kept"))
+                        .clicked()
+                    {
+                        self.schedule.set_cancelled(true);
+                        self.progress.kill();
+                    }
                 });
             }
 
-            let mut err_guard = self.export_error.lock().unwrap();
+            let err_guard = self.export_error.lock().unwrap();
             if let Some(err) = err_guard.as_ref() {
                 ui.label(err);
             }
-        });
-
-        // 6. Handle loading the new media depending on its extension
-        if let Some(idx) = file_idx_to_load {
-            self.selected_file_idx = Some(idx);
-            let path = &self.videos[idx];
-
-            // Read note from .txt file if it already exists
-            let p = path.with_extension("txt");
-            let note = if p.exists() {
-                fs::read_to_string(p).unwrap_or_default()
-            } else {
-                String::new()
-            };
-
-            let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+            drop(err_guard);
+            show_export_log(ui, &self.progress.log());
 
-            self.is_image = matches!(
-                ext.as_str(),
-                "jpg" | "jpeg" | "png" | "bmp" | "webp"
-            );
+            ui.add_space(10.0);
+            ui.separator();
+            let qa_running = self.is_qa_exporting.load(atomic::Ordering::SeqCst);
+            ui.horizontal(|ui| {
+                ui.label(tr("QA sample:"));
+                ui.add(
+                    egui::DragValue::new(&mut self.qa_percent)
+                        .range(1.0..=100.0)
+                        .speed(0.5)
+                        .suffix(" %"),
+                );
+                let button = ui.add_enabled(
+                    !qa_running && self.output_folder.is_some(),
+                    egui::Button::new(tr("🧪 QA Sample Export")),
+                );
+                if button
+                    .on_hover_text(tr(
+                        "Randomly pick this share of all annotated ranges in the folder and write a review list with contact sheets",
+                    ))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::QaExport);
+                }
+            });
+            if qa_running {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr("Writing contact sheets..."));
+                });
+            }
+            if let Some(status) = self.qa_status.lock().unwrap().as_ref() {
+                ui.label(status);
+            }
 
-            if self.is_image {
-                // Load using imgcodecs instead of VideoCapture
-                if let Ok(mat) = imgcodecs::imread(path.to_str().unwrap(), imgcodecs::IMREAD_COLOR) {
-                    self.native_fps = 1.0;
-                    self.duration = 0.0;
-                    self.ranges = vec![VideoRange {
-                        start_time: 0.0,
-                        end_time: 0.0,
-                        crop_rect_norm: None,
-                        note: note,
-                    }];
-                    self.current_range_idx = 0;
-                    self.current_time = 0.0;
-                    self.media = Some(MediaSource::Image(mat));
-                    self.update_frame(ctx);
+            let reel_running = self.is_reel_exporting.load(atomic::Ordering::SeqCst);
+            ui.horizontal(|ui| {
+                ui.label(tr("Reel:"));
+                ui.checkbox(&mut self.reel_separators, tr("Black frame between ranges"));
+                let enabled = !reel_running && self.output_folder.is_some();
+                if ui
+                    .add_enabled(enabled, egui::Button::new(tr("🎞 This File")))
+                    .on_hover_text(tr("Cut all ranges of this file into one clip in the output folder, e.g. for a review session"))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::ReelFile);
                 }
-            } else {
-                if let Ok(c) = videoio::VideoCapture::from_file(
-                    path.to_str().unwrap(),
-                    videoio::CAP_ANY,
-                ) {
-                    self.native_fps = c.get(videoio::CAP_PROP_FPS).unwrap_or(30.0);
-                    self.duration =
-                        c.get(videoio::CAP_PROP_FRAME_COUNT).unwrap_or(0.0) / self.native_fps;
-                    self.ranges = vec![VideoRange {
-                        start_time: 0.0,
-                        end_time: self.duration,
-                        crop_rect_norm: None,
-                        note: note,
-                    }];
-                    self.current_range_idx = 0;
-                    self.current_time = 0.0;
-                    self.media = Some(MediaSource::Video(c));
-                    self.update_frame(ctx);
+                if ui
+                    .add_enabled(enabled, egui::Button::new(tr("🎞 All Files")))
+                    .on_hover_text(tr(
+                        "Cut the ranges of every annotated file in the folder into one clip in the output folder, e.g. for a single-file eval set",
+                    ))
+                    .clicked()
+                {
+                    self.perform(ctx, Action::ReelFolder);
                 }
+            });
+            if reel_running {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr("Writing reel..."));
+                });
+            }
+            if let Some(status) = self.reel_status.lock().unwrap().as_ref() {
+                ui.label(status);
             }
+        });
+
+        // 6. Open the new media off the UI thread, so slow storage doesn't freeze the window
+        if let Some(idx) = file_idx_to_load.or(self.pending_file.take()) {
+            self.start_loading(ctx, idx);
         }
 
         if self.is_playing() && !self.is_image {
             self.current_time += ctx.input(|i| i.stable_dt) as f64;
-            if let PlayState::PlayingUntil(x) = self.play_state {
-                if x < self.current_time {
-                    self.play_state = PlayState::NotPlaying;
-                }
+            if let PlayState::PlayingUntil(x) = self.play_state
+                && x < self.current_time
+            {
+                self.play_state = PlayState::NotPlaying;
             }
             if self.current_time >= self.duration {
                 self.play_state = PlayState::NotPlaying;
             }
             self.update_frame(ctx);
             ctx.request_repaint();
+        } else if !self.scrubbing
+            && self
+                .decoded_frame
+                .is_some_and(|f| f != timing::frame_at(self.current_time, self.native_fps))
+        {
+            // Playback stopped on a frame the reduced preview skipped
+            self.update_frame(ctx);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Forced quit while jobs run: don't leave FFmpeg running without the app
+        self.schedule.set_cancelled(true);
+        self.progress.kill();
+        self.reel_progress.kill();
+        if let Some(instance) = &self.instance {
+            instance.release();
+        }
+        self.save_sidecar();
+        if let Err(e) = self.metrics.flush() {
+            tracing::warn!(target: logs::UI, "{}", e);
+        }
+    }
+}
+
+/// Keep-native option and rate of a clip frame rate setting. Returns whether it changed.
+fn frame_rate_picker(ui: &mut egui::Ui, id: &str, rate: &mut export::FrameRate) -> bool {
+    let mut native = *rate == export::FrameRate::Native;
+    let mut changed = ui
+        .checkbox(&mut native, tr("Keep native"))
+        .on_hover_text(tr("Keep every frame of the source instead of resampling"))
+        .changed();
+    if changed {
+        *rate = if native {
+            export::FrameRate::Native
+        } else {
+            export::FrameRate::default()
+        };
+    }
+    if let export::FrameRate::Fixed(fps) = rate {
+        changed |= ui
+            .push_id(id, |ui| ui.add(egui::DragValue::new(fps).range(1..=120).suffix(" FPS")))
+            .inner
+            .changed();
+    }
+    changed
+}
+
+/// The progress bar of the running export: the file and range being encoded, how much of the
+/// range is done and the time left for the file.
+/// FFmpeg's output of the running or last export, collapsed until asked for.
+fn show_export_log(ui: &mut egui::Ui, log: &[String]) {
+    if log.is_empty() {
+        return;
+    }
+    ui.collapsing(trf("Export log ({} lines)", &[&log.len()]), |ui| {
+        if ui
+            .button(tr("📋 Copy"))
+            .on_hover_text(tr("Copy the log to the clipboard, e.g. for a bug report"))
+            .clicked()
+        {
+            ui.ctx().copy_text(log.join("\n"));
+        }
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in log {
+                    ui.label(egui::RichText::new(line).monospace().small());
+                }
+            });
+    });
+}
+
+fn show_export_progress(ui: &mut egui::Ui, progress: &progress::Snapshot) {
+    let clock = |seconds: f64| {
+        let seconds = seconds.max(0.0).round() as u64;
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    };
+    let mut text = trf(
+        "{}: range {} of {}",
+        &[&progress.file, &(progress.range + 1), &progress.ranges],
+    );
+    if progress.duration > 0.0 {
+        text += &format!(
+            " · {} / {}",
+            clock(progress.encoded.min(progress.duration)),
+            clock(progress.duration)
+        );
+    }
+    if let Some(eta) = progress.eta() {
+        text += " · ";
+        text += &trf("{} left", &[&clock(eta.as_secs_f64())]);
+    }
+    ui.add(egui::ProgressBar::new(progress.fraction()).text(text));
+}
+
+/// What the background analysis knows about `file`, for the file list's hover.
+fn show_analysis(
+    ui: &mut egui::Ui,
+    file: &Path,
+    status: Option<&analysis::Status>,
+    thumbnails: &mut HashMap<PathBuf, egui::TextureHandle>,
+) {
+    let report = match status {
+        None => return,
+        Some(analysis::Status::Queued) => {
+            ui.weak(tr("Waiting for analysis"));
+            return;
+        }
+        Some(analysis::Status::Running(task)) => {
+            ui.weak(trf("Analyzing: {}", &[&tr(task.label())]));
+            return;
         }
+        Some(analysis::Status::Done(report)) => report,
+    };
+    if let Some(path) = &report.thumbnail {
+        if !thumbnails.contains_key(file)
+            && let Ok(image) = analysis::thumbnail_image(path)
+        {
+            let texture = ui.ctx().load_texture(format!("thumb_{}", file.display()), image, Default::default());
+            thumbnails.insert(file.to_path_buf(), texture);
+        }
+        if let Some(texture) = thumbnails.get(file) {
+            ui.image((texture.id(), texture.size_vec2()));
+        }
+    }
+    if let Some(cuts) = report.scene_cuts {
+        ui.label(trf("Scene cuts: {}", &[&cuts]));
+    }
+    if let Some(peak) = report.peak {
+        ui.label(trf("Audio peak: {} %", &[&format!("{:.0}", peak * 100.0)]));
+    } else if !is_image_file(file) {
+        ui.label(tr("No audio track"));
+    }
+    if let Some(sharpness) = report.sharpness {
+        ui.label(trf("Sharpness: {}", &[&format!("{:.0}", sharpness)]));
+    }
+    if let Some(hash) = &report.hash {
+        ui.weak(trf("Content hash: {}", &[hash]));
+    }
+    for error in &report.errors {
+        ui.colored_label(ui.visuals().error_fg_color, error);
     }
 }
 
+/// Id of the video viewport, which takes keyboard focus for crop editing.
+fn viewport_id() -> egui::Id {
+    egui::Id::new("video_viewport")
+}
+
+/// Gives an icon-only button a tooltip and a screen reader label.
+fn accessible(response: egui::Response, label: &str) -> egui::Response {
+    let enabled = response.enabled();
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, enabled, label));
+    response.on_hover_text(label)
+}
+
 fn main() -> eframe::Result<()> {
+    i18n::set_language(i18n::Language::from_env());
+    let args = cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, cli::USAGE);
+        std::process::exit(2);
+    });
+    // Explicit cache settings in the environment still win
+    if let Some(dir) = portable::data_dir(args.portable) {
+        cache::set_root(dir.join("cache"));
+    }
+    cache::configure_from_env();
+    logs::init();
+    hardware::start_probe();
+    let instance = match instance::start(&instance::path(), &args) {
+        instance::Start::Forwarded => {
+            eprintln!("Opened in the running instance.");
+            std::process::exit(0);
+        }
+        instance::Start::Primary(instance) => Some(instance),
+        instance::Start::Unguarded(e) => {
+            tracing::warn!(target: logs::UI, "Could not guard against a second instance: {}", e);
+            None
+        }
+    };
+    let mut app = VideoApp {
+        read_only: args.read_only,
+        instance,
+        ..Default::default()
+    };
+    match settings::load(&settings::path()) {
+        Ok(saved) => {
+            app.export_profile.container = saved.container;
+            app.export_profile.encoder = saved.encoder;
+            app.parallel_exports = saved.parallel_exports.clamp(1, pool::max_limit());
+            app.upload_destination = saved.upload.clone();
+            app.layout = saved.layout;
+            app.saved_settings = saved;
+        }
+        Err(e) => tracing::warn!(target: logs::UI, "{}", e),
+    }
+    if let Err(e) = app.apply_args(args) {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    }
+    match journal::load(&journal::path()) {
+        Some(Ok(journal)) => app.recovery = Some(journal),
+        Some(Err(e)) => {
+            tracing::warn!(target: logs::UI, "{}", e);
+            journal::clear(&journal::path());
+        }
+        None => {}
+    }
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_maximized(true),
         ..Default::default()
@@ -788,6 +5461,80 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "VidDataTrainCrop",
         options,
-        Box::new(|_cc| Ok(Box::new(VideoApp::default()))),
+        Box::new(|cc| {
+            if let Some(instance) = &app.instance {
+                instance.set_context(cc.egui_ctx.clone());
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_pixels_round_both_edges() {
+        let rect = SerializableRect {
+            min_x: 0.1004,
+            min_y: 0.2,
+            max_x: 0.4997,
+            max_y: 0.7,
+        };
+        // Truncating would start the crop at x = 192, left of the drawn edge at 192.77
+        assert_eq!(rect.to_pixels(1920.0, 1080.0), (766, 540, 193, 216));
+    }
+
+    #[test]
+    fn nudged_crop_stays_inside_the_frame() {
+        let rect = SerializableRect {
+            min_x: 0.5,
+            min_y: 0.5,
+            max_x: 0.9,
+            max_y: 1.0,
+        };
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+        // Moving keeps the size and stops at the frame edge
+        let moved = rect.nudged(0.2, 0.1, false);
+        assert!(close(moved.min_x, 0.6) && close(moved.max_x, 1.0));
+        assert!(close(moved.min_y, 0.5) && close(moved.max_y, 1.0));
+        // Resizing never collapses the crop
+        let shrunk = rect.nudged(-1.0, 0.0, true);
+        assert!(close(shrunk.min_x, 0.5) && close(shrunk.max_x, 0.51));
+    }
+
+    #[test]
+    fn only_the_opening_placeholder_counts_as_untouched() {
+        let placeholder = VideoRange {
+            end_time: 60.0,
+            ..Default::default()
+        };
+        assert!(placeholder.is_untouched(60.0));
+        let noted = VideoRange {
+            note: "a dog".to_string(),
+            ..placeholder.clone()
+        };
+        assert!(!noted.is_untouched(60.0));
+        let trimmed = VideoRange {
+            end_time: 12.5,
+            ..placeholder.clone()
+        };
+        assert!(!trimmed.is_untouched(60.0));
+        let tagged = VideoRange {
+            tags: vec!["dog".to_string()],
+            ..placeholder.clone()
+        };
+        assert!(!tagged.is_untouched(60.0));
+        let split = VideoRange {
+            split: Some(split::Split::Val),
+            ..placeholder.clone()
+        };
+        assert!(!split.is_untouched(60.0));
+        let slowed = VideoRange {
+            fps: Some(export::FrameRate::Fixed(12)),
+            ..placeholder.clone()
+        };
+        assert!(!slowed.is_untouched(60.0));
+    }
+}
@@ -0,0 +1,168 @@
+// The dataset manifest (`manifest.json` in the output folder): one entry per exported clip or
+// still, so training scripts can pick up splits and captions without parsing file names.
+// Exports run per media file, so each export replaces the entries of its own source only.
+use crate::i18n::trf;
+use crate::split::Split;
+use crate::{SerializableRect, clip_stats, migrate};
+use std::path::Path;
+
+pub const FILE_NAME: &str = "manifest.json";
+/// Bumped whenever the format changes incompatibly, with a step added to `STEPS`.
+pub const VERSION: u32 = 1;
+/// Upgrades from older versions, see `migrate::parse`
+const STEPS: &[migrate::Step] = &[];
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    /// Exported file, relative to the output folder with `/` separators
+    pub file: String,
+    /// Caption `.txt` next to it, if the range has a note
+    pub caption: Option<String>,
+    /// File name of the media in the input folder
+    pub source: String,
+    pub range: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub crop: Option<SerializableRect>,
+    pub split: Option<Split>,
+    /// Fingerprint of the export parameters; an unchanged hash means the file is up to date
+    #[serde(default)]
+    pub hash: String,
+    /// Fingerprint of the tags written into the clip; they can change without a new encode
+    #[serde(default)]
+    pub tags: String,
+    /// Name the clip was encoded under, if it was renamed since; `hash` refers to that name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_as: Option<String>,
+    /// Audio file next to the clip, if audio export was on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    /// Animated preview next to the clip, if previews were on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// Sprite sheet next to the clip, if sprite sheets were on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sprite: Option<String>,
+    /// Brightness, contrast, colorfulness and motion of the output, for filtering the dataset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<clip_stats::Stats>,
+}
+
+impl Entry {
+    /// Every file the entry accounts for: the output, its caption, audio, preview and sprite
+    /// sheet.
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.file)
+            .chain(self.caption.as_ref())
+            .chain(self.audio.as_ref())
+            .chain(self.preview.as_ref())
+            .chain(self.sprite.as_ref())
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    version: u32,
+    pub entries: Vec<Entry>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// The manifest in `out_dir`, or an empty one if there is none yet.
+    pub fn load(out_dir: &Path) -> Result<Manifest, String> {
+        let path = out_dir.join(FILE_NAME);
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Ok(Manifest::default());
+        };
+        Manifest::parse(&text).map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+    }
+
+    /// The manifest JSON `text`, upgraded from older versions.
+    fn parse(text: &str) -> Result<Manifest, String> {
+        migrate::parse(text, VERSION, STEPS)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, out_dir: &Path) -> Result<(), String> {
+        let path = out_dir.join(FILE_NAME);
+        let json = self.to_json()?;
+        std::fs::write(&path, json)
+            .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+    }
+
+    /// Replaces all entries exported from `source` with `entries`, keeping the list sorted by
+    /// source and range so the file diffs cleanly between exports.
+    pub fn replace_source(&mut self, source: &str, entries: Vec<Entry>) {
+        self.entries.retain(|e| e.source != source);
+        self.entries.extend(entries);
+        self.entries
+            .sort_by(|a, b| a.source.cmp(&b.source).then(a.range.cmp(&b.range)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(source: &str, range: usize) -> Entry {
+        Entry {
+            file: format!("{}_range{}.mp4", source, range),
+            caption: None,
+            source: source.to_string(),
+            range,
+            start_time: 0.0,
+            end_time: 1.0,
+            crop: None,
+            split: None,
+            hash: String::new(),
+            tags: String::new(),
+            exported_as: None,
+            audio: None,
+            preview: None,
+            sprite: None,
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn replacing_a_source_keeps_the_others_in_order() {
+        let mut manifest = Manifest::default();
+        manifest.replace_source("b.mp4", vec![entry("b.mp4", 0), entry("b.mp4", 1)]);
+        manifest.replace_source("a.mp4", vec![entry("a.mp4", 0)]);
+        manifest.replace_source("b.mp4", vec![entry("b.mp4", 0)]);
+        let keys: Vec<_> = manifest
+            .entries
+            .iter()
+            .map(|e| (e.source.as_str(), e.range))
+            .collect();
+        assert_eq!(keys, [("a.mp4", 0), ("b.mp4", 0)]);
+    }
+
+    /// One manifest per released version; the last one is the current format.
+    const GOLDEN: &[&str] = &[include_str!("../tests/golden/manifest_v1.json")];
+
+    #[test]
+    fn golden_manifests_load_and_the_current_one_round_trips() {
+        assert_eq!(GOLDEN.len(), VERSION as usize);
+        for text in GOLDEN {
+            let manifest = Manifest::parse(text).unwrap();
+            let splits: Vec<_> = manifest.entries.iter().map(|e| e.split).collect();
+            assert_eq!(splits, [Some(Split::Train), None]);
+        }
+        let current = GOLDEN[GOLDEN.len() - 1];
+        let manifest = Manifest::parse(current).unwrap();
+        assert_eq!(manifest.to_json().unwrap(), current.trim_end());
+        assert!(Manifest::parse(r#"{"version": 99, "entries": []}"#).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+// The material registry (`material.json` in the output folder): every exported range as the
+// content hash of its source and its time span. The hash stays the same when a file is renamed
+// or copied into another folder, so a new range over footage that is already in the dataset is
+// flagged. Linked projects (other output folders, such as earlier dataset versions) have their
+// registries checked along with this one, so the same material doesn't end up in several.
+use crate::i18n::trf;
+use crate::{analysis, logs, manifest, migrate};
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "material.json";
+/// Bumped whenever the format changes incompatibly, with a step added to `STEPS`.
+pub const VERSION: u32 = 1;
+/// Upgrades from older versions, see `migrate::parse`
+const STEPS: &[migrate::Step] = &[];
+
+/// Exported material covering at least this share of a range is reported as a duplicate.
+const MIN_OVERLAP: f64 = 0.5;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Span {
+    /// Content hash of the source, see `analysis::source_hash`
+    pub hash: String,
+    /// File name of the source when the range was exported
+    pub source: String,
+    pub range: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+impl Span {
+    /// Share of `start..end` the span covers. Stills have no length and are covered entirely by
+    /// any span of the same image.
+    fn coverage(&self, start: f64, end: f64) -> f64 {
+        if end <= start {
+            return if self.start_time <= start && start <= self.end_time {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        ((self.end_time.min(end) - self.start_time.max(start)) / (end - start)).max(0.0)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Registry {
+    version: u32,
+    /// Output folders of linked projects
+    #[serde(default)]
+    pub links: Vec<PathBuf>,
+    pub spans: Vec<Span>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            links: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+}
+
+impl Registry {
+    /// The registry in `out_dir`, or an empty one if there is none yet.
+    pub fn load(out_dir: &Path) -> Result<Registry, String> {
+        let path = out_dir.join(FILE_NAME);
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Ok(Registry::default());
+        };
+        Registry::parse(&text).map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+    }
+
+    /// The registry JSON `text`, upgraded from older versions.
+    fn parse(text: &str) -> Result<Registry, String> {
+        migrate::parse(text, VERSION, STEPS)
+    }
+
+    fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, out_dir: &Path) -> Result<(), String> {
+        let path = out_dir.join(FILE_NAME);
+        let json = self.to_json()?;
+        std::fs::write(&path, json)
+            .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+    }
+
+    /// Replaces the spans exported from `source` with `spans`, sorted like the manifest.
+    pub fn replace_source(&mut self, source: &str, spans: Vec<Span>) {
+        self.spans.retain(|s| s.source != source);
+        self.spans.extend(spans);
+        self.spans
+            .sort_by(|a, b| a.source.cmp(&b.source).then(a.range.cmp(&b.range)));
+    }
+}
+
+/// The spans of `entries`, the manifest entries just exported from `media`.
+pub fn spans(media: &Path, entries: &[manifest::Entry]) -> Result<Vec<Span>, String> {
+    let hash = analysis::source_hash(media)?;
+    Ok(entries
+        .iter()
+        .map(|e| Span {
+            hash: hash.clone(),
+            source: e.source.clone(),
+            range: e.range,
+            start_time: e.start_time,
+            end_time: e.end_time,
+        })
+        .collect())
+}
+
+/// Records `entries`, exported from `media`, in the registry of `out_dir`. A failure only costs
+/// duplicate warnings later, so it is logged rather than failing the export.
+pub fn record(out_dir: &Path, media: &Path, entries: &[manifest::Entry]) {
+    let source = media.file_name().unwrap().to_string_lossy();
+    let result = spans(media, entries).and_then(|spans| {
+        let mut registry = Registry::load(out_dir)?;
+        registry.replace_source(&source, spans);
+        registry.save(out_dir)
+    });
+    if let Err(e) = result {
+        tracing::warn!(target: logs::EXPORT, file = ?media, "Could not update the material registry: {}", e);
+    }
+}
+
+/// Exported material of this project (`project` None) or of the linked one in that folder.
+pub struct Known {
+    pub project: Option<PathBuf>,
+    pub span: Span,
+}
+
+/// The material exported into `out_dir` and into the projects it links. Linked projects whose
+/// registry can't be read are logged and left out.
+pub fn known(out_dir: &Path) -> Result<Vec<Known>, String> {
+    let registry = Registry::load(out_dir)?;
+    let mut known: Vec<Known> = Vec::new();
+    for link in &registry.links {
+        match Registry::load(link) {
+            Ok(linked) => known.extend(linked.spans.into_iter().map(|span| Known {
+                project: Some(link.clone()),
+                span,
+            })),
+            Err(e) => tracing::warn!(target: logs::UI, "Linked project skipped: {}", e),
+        }
+    }
+    known.extend(registry.spans.into_iter().map(|span| Known {
+        project: None,
+        span,
+    }));
+    Ok(known)
+}
+
+/// Known material covering most of `start..end` of the source with content `hash`. This
+/// project's own spans of `source` are left out; they are the file's ranges being edited.
+pub fn overlaps<'a>(
+    known: &'a [Known],
+    hash: &str,
+    source: &str,
+    start: f64,
+    end: f64,
+) -> Vec<&'a Known> {
+    known
+        .iter()
+        .filter(|k| {
+            k.span.hash == hash
+                && (k.project.is_some() || k.span.source != source)
+                && k.span.coverage(start, end) >= MIN_OVERLAP
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(source: &str, range: usize, start_time: f64, end_time: f64) -> Span {
+        Span {
+            hash: "0123456789abcdef".to_string(),
+            source: source.to_string(),
+            range,
+            start_time,
+            end_time,
+        }
+    }
+
+    #[test]
+    fn overlaps_skip_the_edited_file_and_small_overlaps() {
+        let known = |project: Option<&str>, span| Known {
+            project: project.map(PathBuf::from),
+            span,
+        };
+        let known = [
+            known(None, span("a.mp4", 0, 0.0, 10.0)),
+            known(None, span("copy of a.mp4", 0, 4.0, 8.0)),
+            known(Some("v1"), span("a.mp4", 2, 5.0, 9.0)),
+        ];
+        let hits = |start, end| {
+            overlaps(&known, "0123456789abcdef", "a.mp4", start, end)
+                .iter()
+                .map(|k| (k.span.source.as_str(), k.span.range))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hits(5.0, 8.0), [("copy of a.mp4", 0), ("a.mp4", 2)]);
+        assert!(hits(1.0, 5.0).is_empty());
+        assert_eq!(hits(6.0, 6.0), [("copy of a.mp4", 0), ("a.mp4", 2)]);
+        assert!(overlaps(&known, "other", "b.mp4", 5.0, 8.0).is_empty());
+    }
+
+    #[test]
+    fn replacing_a_source_keeps_the_others_in_order() {
+        let mut registry = Registry::default();
+        registry.replace_source("b.mp4", vec![span("b.mp4", 1, 0.0, 1.0)]);
+        registry.replace_source("a.mp4", vec![span("a.mp4", 0, 0.0, 1.0)]);
+        registry.replace_source("b.mp4", vec![span("b.mp4", 0, 2.0, 3.0)]);
+        assert_eq!(
+            registry.spans,
+            [span("a.mp4", 0, 0.0, 1.0), span("b.mp4", 0, 2.0, 3.0)]
+        );
+    }
+
+    /// One registry per released version; the last one is the current format.
+    const GOLDEN: &[&str] = &[include_str!("../tests/golden/material_v1.json")];
+
+    #[test]
+    fn golden_registries_load_and_the_current_one_round_trips() {
+        assert_eq!(GOLDEN.len(), VERSION as usize);
+        for text in GOLDEN {
+            let registry = Registry::parse(text).unwrap();
+            assert_eq!(registry.links, [PathBuf::from("/data/v1/out")]);
+            assert_eq!(registry.spans[1].range, 1);
+        }
+        let current = GOLDEN[GOLDEN.len() - 1];
+        let registry = Registry::parse(current).unwrap();
+        assert_eq!(registry.to_json().unwrap(), current.trim_end());
+    }
+}
@@ -0,0 +1,165 @@
+// Merging another annotator's ranges for the same media into ours. Ranges have no identity of
+// their own, so they are matched by overlap: identical ones are dropped, ones that don't touch
+// any of ours are added, and overlapping ones that differ become conflicts for the user to pick.
+use crate::VideoRange;
+
+/// Times within this many seconds count as the same boundary (well below one frame).
+const TIME_TOLERANCE: f64 = 1e-3;
+/// Normalized crop edges within this distance count as the same edge (about 2 px at 1080p).
+const CROP_TOLERANCE: f32 = 2e-3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    KeepMine,
+    TakeTheirs,
+    KeepBoth,
+}
+
+pub struct Conflict {
+    /// Index of the overlapping range in `MergePlan::ours`.
+    pub ours: usize,
+    pub theirs: VideoRange,
+    pub choice: Choice,
+}
+
+pub struct MergePlan {
+    pub ours: Vec<VideoRange>,
+    /// Their ranges that don't overlap any of ours, added as they are.
+    pub added: Vec<VideoRange>,
+    /// Their ranges that are identical to one of ours.
+    pub duplicates: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+fn same(a: &VideoRange, b: &VideoRange) -> bool {
+    let crops_match = match (&a.crop_rect_norm, &b.crop_rect_norm) {
+        (Some(x), Some(y)) => [
+            (x.min_x, y.min_x),
+            (x.min_y, y.min_y),
+            (x.max_x, y.max_x),
+            (x.max_y, y.max_y),
+        ]
+        .iter()
+        .all(|(p, q)| (p - q).abs() <= CROP_TOLERANCE),
+        (None, None) => true,
+        _ => false,
+    };
+    (a.start_time - b.start_time).abs() <= TIME_TOLERANCE
+        && (a.end_time - b.end_time).abs() <= TIME_TOLERANCE
+        && crops_match
+        && a.note.trim() == b.note.trim()
+}
+
+/// How much two ranges overlap, or None if they don't. Images have zero-length ranges, so
+/// there the crops have to intersect instead.
+fn overlap(a: &VideoRange, b: &VideoRange) -> Option<f64> {
+    let is_still = |r: &VideoRange| r.end_time - r.start_time <= TIME_TOLERANCE;
+    if is_still(a) && is_still(b) {
+        return match (&a.crop_rect_norm, &b.crop_rect_norm) {
+            (Some(x), Some(y)) => x.intersects(y).then_some(0.0),
+            _ => Some(0.0),
+        };
+    }
+    let shared = a.end_time.min(b.end_time) - a.start_time.max(b.start_time);
+    (shared > 0.0).then_some(shared)
+}
+
+pub fn plan(ours: Vec<VideoRange>, theirs: Vec<VideoRange>) -> MergePlan {
+    let mut plan = MergePlan {
+        ours,
+        added: Vec::new(),
+        duplicates: 0,
+        conflicts: Vec::new(),
+    };
+    for range in theirs {
+        if plan.ours.iter().any(|o| same(o, &range)) {
+            plan.duplicates += 1;
+            continue;
+        }
+        let best = plan
+            .ours
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| overlap(o, &range).map(|shared| (i, shared)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((ours, _)) => plan.conflicts.push(Conflict {
+                ours,
+                theirs: range,
+                choice: Choice::KeepMine,
+            }),
+            None => plan.added.push(range),
+        }
+    }
+    plan
+}
+
+impl MergePlan {
+    /// Applies the chosen resolutions and returns the merged ranges sorted by start time.
+    pub fn resolve(self) -> Vec<VideoRange> {
+        let mut merged: Vec<Option<VideoRange>> = self.ours.into_iter().map(Some).collect();
+        let mut replaced = vec![false; merged.len()];
+        let mut extra = self.added;
+        for conflict in self.conflicts {
+            match conflict.choice {
+                Choice::KeepMine => {}
+                // If several of theirs replace the same range, only the first takes its place
+                Choice::TakeTheirs if !replaced[conflict.ours] => {
+                    replaced[conflict.ours] = true;
+                    merged[conflict.ours] = Some(conflict.theirs);
+                }
+                Choice::TakeTheirs | Choice::KeepBoth => extra.push(conflict.theirs),
+            }
+        }
+        let mut merged: Vec<VideoRange> = merged.into_iter().flatten().chain(extra).collect();
+        merged.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: f64, end: f64, note: &str) -> VideoRange {
+        VideoRange {
+            start_time: start,
+            end_time: end,
+            note: note.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sorts_their_ranges_into_duplicates_additions_and_conflicts() {
+        let ours = vec![range(0.0, 2.0, "a"), range(5.0, 6.0, "b")];
+        let theirs = vec![
+            range(0.0, 2.0, "a "),
+            range(3.0, 4.0, "new"),
+            range(5.5, 7.0, "changed"),
+        ];
+        let plan = plan(ours, theirs);
+        assert_eq!(plan.duplicates, 1);
+        assert_eq!(plan.added.len(), 1);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].ours, 1);
+    }
+
+    #[test]
+    fn resolves_each_choice() {
+        let notes = |choice| {
+            let mut plan = plan(
+                vec![range(0.0, 2.0, "mine")],
+                vec![range(1.0, 3.0, "theirs")],
+            );
+            plan.conflicts[0].choice = choice;
+            plan.resolve()
+                .into_iter()
+                .map(|r| r.note)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(notes(Choice::KeepMine), ["mine"]);
+        assert_eq!(notes(Choice::TakeTheirs), ["theirs"]);
+        assert_eq!(notes(Choice::KeepBoth), ["mine", "theirs"]);
+    }
+}
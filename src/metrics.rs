@@ -0,0 +1,160 @@
+// Opt-in usage metrics. While switched on for an input folder, the app counts exports, export
+// problems, opened files and the commands used in `viddatatraincrop.metrics.json` next to the
+// media. Only counts are kept, no file names or notes, and nothing is ever sent anywhere: a
+// team lead collects the files by hand to see which parts of the workflow get used.
+use crate::i18n::{tr, trf};
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const FILE_NAME: &str = "viddatatraincrop.metrics.json";
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct Counts {
+    #[serde(default)]
+    enabled: bool,
+    /// When counting was first switched on (RFC 3339, UTC)
+    #[serde(default)]
+    since: Option<String>,
+    #[serde(default)]
+    counts: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Input folder the counts belong to
+    dir: Option<PathBuf>,
+    counts: Counts,
+    /// Counted since the last write
+    dirty: bool,
+}
+
+/// Shared between the UI and the export thread; clones count into the same file.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    state: Arc<Mutex<State>>,
+}
+
+fn write(dir: &Path, counts: &Counts) -> Result<(), String> {
+    let path = dir.join(FILE_NAME);
+    let json = serde_json::to_string_pretty(counts).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+impl Metrics {
+    /// Writes the counts of the previous folder and continues with those stored in `dir`.
+    /// Counting stays off unless the folder's file says otherwise.
+    pub fn open(&self, dir: &Path) -> Result<(), String> {
+        let flushed = self.flush();
+        let path = dir.join(FILE_NAME);
+        let counts = match std::fs::read_to_string(&path).map(|t| serde_json::from_str(&t)) {
+            Ok(Ok(counts)) => counts,
+            Ok(Err(e)) => {
+                // A file that can't be read is left alone rather than overwritten
+                *self.state.lock().unwrap() = State::default();
+                return Err(trf("Could not read {}: {}", &[&path.display(), &e]));
+            }
+            Err(_) => Counts::default(),
+        };
+        *self.state.lock().unwrap() = State {
+            dir: Some(dir.to_path_buf()),
+            counts,
+            dirty: false,
+        };
+        flushed
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().counts.enabled
+    }
+
+    /// Switches counting for the open folder on or off. Switching off keeps the counts so far.
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let Some(dir) = state.dir.clone() else {
+            return Ok(());
+        };
+        state.counts.enabled = enabled;
+        if enabled && state.counts.since.is_none() {
+            state.counts.since =
+                Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        }
+        state.dirty = false;
+        write(&dir, &state.counts)
+    }
+
+    /// Adds one to the counter `key`, if counting is on.
+    pub fn count(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.counts.enabled && state.dir.is_some() {
+            *state.counts.counts.entry(key.to_string()).or_default() += 1;
+            state.dirty = true;
+        }
+    }
+
+    /// Writes what was counted since the last write.
+    pub fn flush(&self) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        match &state.dir {
+            Some(dir) if state.dirty => {
+                write(dir, &state.counts)?;
+                state.dirty = false;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The opt-in switch, for the session window.
+pub fn ui(ui: &mut egui::Ui, metrics: &Metrics, status: &mut Option<String>) {
+    let has_folder = metrics.state.lock().unwrap().dir.is_some();
+    let mut enabled = metrics.is_enabled();
+    let response = ui
+        .add_enabled(
+            has_folder,
+            egui::Checkbox::new(&mut enabled, tr("Record usage metrics")),
+        )
+        .on_hover_text(trf(
+            "Counts exports, export problems and the commands used in {} in the input folder. Nothing is sent anywhere.",
+            &[&FILE_NAME],
+        ));
+    if response.changed() {
+        *status = metrics.set_enabled(enabled).err();
+    }
+    if let Some(status) = status {
+        ui.colored_label(ui.visuals().error_fg_color, status.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_while_enabled_and_survive_reopening() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_metrics_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let metrics = Metrics::default();
+        metrics.open(&dir).unwrap();
+        metrics.count("action.AddRange");
+        metrics.set_enabled(true).unwrap();
+        metrics.count("action.AddRange");
+        metrics.clone().count("exports");
+        metrics.flush().unwrap();
+
+        let reopened = Metrics::default();
+        reopened.open(&dir).unwrap();
+        assert!(reopened.is_enabled());
+        let state = reopened.state.lock().unwrap();
+        let counts: Vec<_> = state
+            .counts
+            .counts
+            .iter()
+            .map(|(k, v)| (k.as_str(), *v))
+            .collect();
+        assert_eq!(counts, [("action.AddRange", 1), ("exports", 1)]);
+    }
+}
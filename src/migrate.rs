@@ -0,0 +1,62 @@
+// Reading annotation files written by older versions. Sidecars and manifests are parsed as
+// plain JSON first and brought up to the current format one version at a time, so an archive
+// from any earlier release still opens. A format change bumps the format's version and appends
+// a step; released steps are never edited, or files written in between would upgrade
+// differently. The golden files in `tests/golden` pin every released version.
+use crate::i18n::trf;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Rewrites the JSON of one version into the next.
+pub type Step = fn(&mut Value);
+
+/// Parses `text` as a file of a format now at `version`. `steps[k]` upgrades version k + 1 to
+/// k + 2; files without a version field predate versioning and count as version 1.
+pub fn parse<T: DeserializeOwned>(text: &str, version: u32, steps: &[Step]) -> Result<T, String> {
+    debug_assert_eq!(steps.len() + 1, version as usize);
+    let mut value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let found = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1);
+    if found > version as u64 {
+        return Err(trf("written by a newer version (format {})", &[&found]));
+    }
+    for step in &steps[found as usize - 1..] {
+        step(&mut value);
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_string(), version.into());
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Clip {
+        version: u32,
+        start_time: f64,
+    }
+
+    fn rename_start(value: &mut Value) {
+        let object = value.as_object_mut().unwrap();
+        let start = object.remove("start").unwrap();
+        object.insert("start_time".to_string(), start);
+    }
+
+    #[test]
+    fn older_files_are_upgraded_step_by_step() {
+        let steps: &[Step] = &[rename_start];
+        for text in [r#"{"start": 1.5}"#, r#"{"version": 1, "start": 1.5}"#] {
+            let clip: Clip = parse(text, 2, steps).unwrap();
+            assert_eq!((clip.version, clip.start_time), (2, 1.5));
+        }
+        let current: Clip = parse(r#"{"version": 2, "start_time": 3.0}"#, 2, steps).unwrap();
+        assert_eq!(current.start_time, 3.0);
+        assert!(parse::<Clip>(r#"{"version": 3, "start_time": 3.0}"#, 2, steps).is_err());
+    }
+}
@@ -0,0 +1,618 @@
+// Packaging the output folder for upload to a training cluster: the files the manifest lists go
+// into a zip or tar archive with `manifest.json` at its root. Large datasets can be split into
+// volumes of a maximum size, each a complete archive with the manifest of its own clips, and a
+// `SHA256SUMS` file next to the archives lets the other end check them with `sha256sum -c`.
+// Clips and images are compressed already, so the archives store files as they are.
+use crate::i18n::{tr, trf};
+use crate::manifest::{self, Manifest};
+use crate::schedule::Schedule;
+use crate::{checksum, dataset, logs};
+use eframe::egui;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Lists the SHA-256 of every archive written, next to them.
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Format {
+    #[default]
+    Zip,
+    /// POSIX tar, with PAX headers for long names and large files
+    Tar,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Zip, Format::Tar];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Zip => "Zip",
+            Format::Tar => "Tar",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::Tar => "tar",
+        }
+    }
+}
+
+/// How to package; also the package window's state while it is open.
+#[derive(Clone)]
+pub struct Options {
+    pub format: Format,
+    /// Largest volume in MiB; None writes a single archive
+    pub volume_mib: Option<u64>,
+    pub checksums: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            format: Format::default(),
+            volume_mib: None,
+            checksums: true,
+        }
+    }
+}
+
+/// A file going into an archive: its name there and where it is now.
+struct Member {
+    name: String,
+    path: PathBuf,
+    size: u64,
+}
+
+/// Adds the files in `dir` (named `name` in the archive) and its subfolders to `members`.
+fn walk(dir: &Path, name: &str, members: &mut Vec<Member>) -> std::io::Result<()> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    children.sort_by_key(|c| c.file_name());
+    for child in children {
+        let name = format!("{}/{}", name, child.file_name().to_string_lossy());
+        let path = child.path();
+        if path.is_dir() {
+            walk(&path, &name, members)?;
+        } else {
+            let size = child.metadata()?.len();
+            members.push(Member { name, path, size });
+        }
+    }
+    Ok(())
+}
+
+/// The files of `entry` in `out_dir`, frame sequence folders as their frames. Files that are
+/// gone are left out; the audit reports them. Paths outside `out_dir` are refused.
+fn members(out_dir: &Path, entry: &manifest::Entry) -> Result<Vec<Member>, String> {
+    let mut members = Vec::new();
+    for file in entry.files() {
+        let path = dataset::manifest_path(out_dir, file)?;
+        if path.is_dir() {
+            walk(&path, file, &mut members)
+                .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+        } else if let Ok(metadata) = path.metadata() {
+            members.push(Member {
+                name: file.clone(),
+                path,
+                size: metadata.len(),
+            });
+        }
+    }
+    Ok(members)
+}
+
+/// `items` (with their sizes in bytes) split into volumes of at most `limit` bytes, in order.
+/// An item larger than the limit gets a volume of its own; None keeps everything in one.
+fn volumes<T>(items: Vec<(T, u64)>, limit: Option<u64>) -> Vec<Vec<T>> {
+    let mut volumes: Vec<Vec<T>> = Vec::new();
+    let mut size = 0;
+    for (item, item_size) in items {
+        let fits = limit.is_none_or(|limit| size + item_size <= limit);
+        match volumes.last_mut() {
+            Some(volume) if fits => volume.push(item),
+            _ => {
+                volumes.push(vec![item]);
+                size = 0;
+            }
+        }
+        size += item_size;
+    }
+    volumes
+}
+
+/// Where volume `i` of `count` goes: `target` itself for a single archive, else
+/// `dataset.part01.zip`, `dataset.part02.zip`, … next to it.
+fn volume_path(target: &Path, i: usize, count: usize) -> PathBuf {
+    if count == 1 {
+        return target.to_path_buf();
+    }
+    let digits = count.to_string().len().max(2);
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = target.extension().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(
+        "{}.part{:0width$}.{}",
+        stem,
+        i + 1,
+        ext,
+        width = digits
+    ))
+}
+
+/// Packages the dataset in `out_dir` into `target` (split into volumes next to it if the
+/// options say so) and writes the checksums. Stops between files once `schedule` is
+/// cancelled. Returns the archives written.
+pub fn package(
+    out_dir: &Path,
+    target: &Path,
+    options: &Options,
+    schedule: &Schedule,
+) -> Result<Vec<PathBuf>, String> {
+    let manifest = Manifest::load(out_dir)?;
+    if manifest.entries.is_empty() {
+        return Err(tr("The manifest lists no clips to package").to_string());
+    }
+    let mut entries = Vec::new();
+    for entry in manifest.entries {
+        // Every entry is checked before an archive is written
+        let members = members(out_dir, &entry)?;
+        let size = members.iter().map(|m| m.size).sum();
+        entries.push(((entry, members), size));
+    }
+    let limit = options.volume_mib.map(|mib| mib.max(1) << 20);
+    let volumes = volumes(entries, limit);
+    let mut written = Vec::new();
+    for (i, volume) in volumes.iter().enumerate() {
+        let path = volume_path(target, i, volumes.len());
+        let mut manifest = Manifest::default();
+        manifest.entries = volume.iter().map(|(entry, _)| entry.clone()).collect();
+        let members = volume.iter().flat_map(|(_, members)| members);
+        let result = write_archive(
+            &path,
+            options.format,
+            &manifest.to_json()?,
+            members,
+            schedule,
+        );
+        if let Err(e) = result {
+            // Half an archive is worse than none
+            let _ = std::fs::remove_file(&path);
+            return Err(trf("Could not write {}: {}", &[&path.display(), &e]));
+        }
+        tracing::info!(target: logs::EXPORT, file = ?path, clips = manifest.entries.len(), "Archive written");
+        written.push(path);
+    }
+    if options.checksums {
+        let mut sums = String::new();
+        for path in &written {
+            let digest = checksum::file_sha256(path)
+                .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+            let name = path.file_name().unwrap().to_string_lossy();
+            sums.push_str(&checksum::line(&digest, &name));
+        }
+        let sums_path = target.with_file_name(CHECKSUMS_FILE);
+        std::fs::write(&sums_path, sums)
+            .map_err(|e| trf("Could not write {}: {}", &[&sums_path.display(), &e]))?;
+    }
+    Ok(written)
+}
+
+fn write_archive<'a>(
+    path: &Path,
+    format: Format,
+    manifest: &str,
+    members: impl Iterator<Item = &'a Member>,
+    schedule: &Schedule,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut archive = Archive::new(format, BufWriter::new(file));
+    let now = SystemTime::now();
+    archive
+        .add(
+            manifest::FILE_NAME,
+            &mut manifest.as_bytes(),
+            manifest.len() as u64,
+            now,
+        )
+        .map_err(|e| e.to_string())?;
+    for member in members {
+        if schedule.is_cancelled() {
+            return Err(tr("Export cancelled").to_string());
+        }
+        let mut file = File::open(&member.path).map_err(|e| e.to_string())?;
+        let modified = file.metadata().and_then(|m| m.modified()).unwrap_or(now);
+        archive
+            .add(&member.name, &mut file, member.size, modified)
+            .map_err(|e| format!("{}: {}", member.name, e))?;
+    }
+    archive.finish().map_err(|e| e.to_string())
+}
+
+fn too_large(what: &str) -> std::io::Error {
+    std::io::Error::other(trf(
+        "{} is too large for a zip archive; use volumes or tar",
+        &[&what],
+    ))
+}
+
+/// An archive being written, one file after the other.
+struct Archive<W: Write + Seek> {
+    format: Format,
+    out: W,
+    /// Zip: the central directory, written at the end
+    central: Vec<u8>,
+    count: usize,
+}
+
+impl<W: Write + Seek> Archive<W> {
+    fn new(format: Format, out: W) -> Self {
+        Self {
+            format,
+            out,
+            central: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Adds `size` bytes from `data` as `name`. Fails if `data` holds a different amount, such
+    /// as a file that changed while it was packaged.
+    fn add(
+        &mut self,
+        name: &str,
+        data: &mut dyn Read,
+        size: u64,
+        modified: SystemTime,
+    ) -> std::io::Result<()> {
+        self.count += 1;
+        match self.format {
+            Format::Zip => self.add_zip(name, data, size, modified),
+            Format::Tar => self.add_tar(name, data, size, modified),
+        }
+    }
+
+    /// Copies `data` over, checking it is `size` bytes long; returns its CRC-32.
+    fn copy(&mut self, data: &mut dyn Read, size: u64) -> std::io::Result<u32> {
+        let mut crc = Crc32::default();
+        let mut buffer = vec![0; 1 << 16];
+        let mut copied = 0;
+        loop {
+            let n = data.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            crc.update(&buffer[..n]);
+            self.out.write_all(&buffer[..n])?;
+            copied += n as u64;
+        }
+        if copied != size {
+            return Err(std::io::Error::other(tr(
+                "The file changed while it was packaged",
+            )));
+        }
+        Ok(crc.finish())
+    }
+
+    fn add_tar(
+        &mut self,
+        name: &str,
+        data: &mut dyn Read,
+        size: u64,
+        modified: SystemTime,
+    ) -> std::io::Result<()> {
+        let mtime = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        // Names over 100 bytes and files of 8 GiB and more need a PAX header first
+        let mut records = String::new();
+        if name.len() > 100 {
+            records.push_str(&pax_record("path", name));
+        }
+        if size > 0o77777777777 {
+            records.push_str(&pax_record("size", &size.to_string()));
+        }
+        if !records.is_empty() {
+            let pax_name = format!("PaxHeaders/{}", self.count);
+            let header = tar_header(&pax_name, records.len() as u64, mtime, b'x');
+            self.out.write_all(&header)?;
+            self.out.write_all(records.as_bytes())?;
+            self.out
+                .write_all(&vec![0; padding(records.len() as u64)])?;
+        }
+        let short: String = name
+            .chars()
+            .scan(0, |len, c| {
+                *len += c.len_utf8();
+                (*len <= 100).then_some(c)
+            })
+            .collect();
+        let header_size = if size > 0o77777777777 { 0 } else { size };
+        self.out
+            .write_all(&tar_header(&short, header_size, mtime, b'0'))?;
+        self.copy(data, size)?;
+        self.out.write_all(&vec![0; padding(size)])
+    }
+
+    fn add_zip(
+        &mut self,
+        name: &str,
+        data: &mut dyn Read,
+        size: u64,
+        modified: SystemTime,
+    ) -> std::io::Result<()> {
+        let offset = self.out.stream_position()?;
+        let (offset32, size32) = (
+            u32::try_from(offset).map_err(|_| too_large(tr("The archive")))?,
+            u32::try_from(size).map_err(|_| too_large(name))?,
+        );
+        let (time, date) = dos_time(modified);
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large(name))?;
+        // Version 2.0, UTF-8 names, stored
+        let fields = |header: &mut Vec<u8>, crc: u32| {
+            for value in [20u16, 0x0800, 0, time, date] {
+                header.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [crc, size32, size32] {
+                header.extend_from_slice(&value.to_le_bytes());
+            }
+            header.extend_from_slice(&name_len.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+        };
+        let mut local = 0x04034b50u32.to_le_bytes().to_vec();
+        fields(&mut local, 0);
+        local.extend_from_slice(name.as_bytes());
+        self.out.write_all(&local)?;
+        let crc = self.copy(data, size)?;
+        // The CRC is only known now
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(offset + 14))?;
+        self.out.write_all(&crc.to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(end))?;
+
+        self.central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        // Made by version 2.0
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        fields(&mut self.central, crc);
+        // No comment, disk 0, no attributes
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&offset32.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        match self.format {
+            Format::Tar => self.out.write_all(&[0; 1024])?,
+            Format::Zip => {
+                let offset = self.out.stream_position()?;
+                let too_large = || too_large(tr("The archive"));
+                let count = u16::try_from(self.count).map_err(|_| too_large())?;
+                let offset = u32::try_from(offset).map_err(|_| too_large())?;
+                let size = u32::try_from(self.central.len()).map_err(|_| too_large())?;
+                self.out.write_all(&self.central)?;
+                let mut end = 0x06054b50u32.to_le_bytes().to_vec();
+                for value in [0u16, 0, count, count] {
+                    end.extend_from_slice(&value.to_le_bytes());
+                }
+                end.extend_from_slice(&size.to_le_bytes());
+                end.extend_from_slice(&offset.to_le_bytes());
+                end.extend_from_slice(&0u16.to_le_bytes());
+                self.out.write_all(&end)?;
+            }
+        }
+        self.out.flush()
+    }
+}
+
+/// Zeros after `size` bytes of tar data, up to the next 512-byte block.
+fn padding(size: u64) -> usize {
+    ((512 - size % 512) % 512) as usize
+}
+
+/// A PAX extended header record: `<length> <key>=<value>\n`, the length counting itself.
+fn pax_record(key: &str, value: &str) -> String {
+    let rest = key.len() + value.len() + 3;
+    let mut length = rest + rest.to_string().len();
+    if length.to_string().len() != rest.to_string().len() {
+        length += 1;
+    }
+    format!("{} {}={}\n", length, key, value)
+}
+
+/// A ustar header block for a regular file (`kind` `0`) or a PAX header (`x`).
+fn tar_header(name: &str, size: u64, mtime: u64, kind: u8) -> [u8; 512] {
+    let mut header = [0u8; 512];
+    let mut put = |at: usize, field: &[u8]| header[at..at + field.len()].copy_from_slice(field);
+    put(0, name.as_bytes());
+    put(100, b"0000644\0");
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{:011o}\0", size).as_bytes());
+    put(
+        136,
+        format!("{:011o}\0", mtime.min(0o77777777777)).as_bytes(),
+    );
+    put(148, b"        ");
+    put(156, &[kind]);
+    put(257, b"ustar\0");
+    put(263, b"00");
+    let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    header
+}
+
+/// MS-DOS time and date, as zip stores them: local time, two-second steps, from 1980.
+fn dos_time(time: SystemTime) -> (u16, u16) {
+    use chrono::{Datelike, Timelike};
+    let time = chrono::DateTime::<chrono::Local>::from(time).naive_local();
+    if time.year() < 1980 {
+        return (0, 0x21);
+    }
+    let dos_time = ((time.hour() << 11) | (time.minute() << 5) | (time.second() / 2)) as u16;
+    let dos_date =
+        ((time.year() as u32 - 1980).min(127) << 9 | time.month() << 5 | time.day()) as u16;
+    (dos_time, dos_date)
+}
+
+/// CRC-32 as zip uses it (IEEE, reflected).
+struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32(!0)
+    }
+}
+
+impl Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                self.0 = (self.0 >> 1) ^ (0xedb88320 & (self.0 & 1).wrapping_neg());
+            }
+        }
+    }
+
+    fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+/// Draws the package window; true when the user asked to package.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    options: &mut Options,
+    can_package: bool,
+) -> bool {
+    let mut requested = false;
+    egui::Window::new(tr("📦 Package Dataset"))
+        .id(egui::Id::new("package_dataset"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Format:"));
+                for format in Format::ALL {
+                    ui.selectable_value(&mut options.format, format, format.label());
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut split = options.volume_mib.is_some();
+                ui.checkbox(&mut split, tr("Split into volumes of"))
+                    .on_hover_text(tr(
+                        "Each volume is a complete archive with the manifest of its own clips; a clip's files stay together",
+                    ));
+                let volume = &mut options.volume_mib;
+                *volume = split.then(|| volume.unwrap_or(4096));
+                if let Some(mib) = volume {
+                    ui.add(egui::DragValue::new(mib).range(1..=1 << 20).suffix(" MiB"));
+                }
+            });
+            ui.checkbox(&mut options.checksums, tr("Write checksums"))
+                .on_hover_text(trf(
+                    "List the SHA-256 of every archive in {} next to them, for sha256sum -c",
+                    &[&CHECKSUMS_FILE],
+                ));
+            let button = ui.add_enabled(can_package, egui::Button::new(tr("Package…")));
+            if button.clicked() {
+                requested = true;
+            }
+            ui.add_space(4.0);
+            ui.weak(tr(
+                "Packs the files the manifest lists, with manifest.json at the root. Clips are stored as they are.",
+            ));
+        });
+    requested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_keep_the_order_and_give_large_items_their_own() {
+        let items = vec![("a", 40), ("b", 50), ("c", 200), ("d", 10), ("e", 90)];
+        assert_eq!(
+            volumes(items.clone(), Some(100)),
+            [vec!["a", "b"], vec!["c"], vec!["d", "e"]]
+        );
+        assert_eq!(volumes(items, None), [vec!["a", "b", "c", "d", "e"]]);
+        let target = Path::new("up/dataset.zip");
+        assert_eq!(volume_path(target, 0, 1), target);
+        assert_eq!(
+            volume_path(target, 1, 3),
+            Path::new("up/dataset.part02.zip")
+        );
+    }
+
+    #[test]
+    fn archives_have_valid_headers_and_checksums() {
+        assert_eq!(pax_record("path", "a"), "9 path=a\n");
+        let long = pax_record("path", &"x".repeat(94));
+        assert!(long.starts_with("104 ") && long.len() == 104);
+        let mut crc = Crc32::default();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xcbf43926);
+
+        let write = |format| {
+            let mut archive = Archive::new(format, std::io::Cursor::new(Vec::new()));
+            let epoch = SystemTime::UNIX_EPOCH;
+            archive.add("a.txt", &mut &b"hello"[..], 5, epoch).unwrap();
+            assert!(archive.add("b.txt", &mut &b"hi"[..], 5, epoch).is_err());
+            archive.finish().unwrap();
+        };
+        write(Format::Zip);
+        write(Format::Tar);
+
+        let mut archive = Archive::new(Format::Zip, std::io::Cursor::new(Vec::new()));
+        archive
+            .add("a.txt", &mut &b"hello"[..], 5, SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let Archive { out, central, .. } = archive;
+        let bytes = out.into_inner();
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        assert_eq!(&bytes[14..18], &0x3610a686u32.to_le_bytes());
+        assert_eq!(&bytes[30..35], b"a.txt");
+        assert_eq!(&central[..4], b"PK\x01\x02");
+
+        let header = tar_header("a.txt", 5, 0, b'0');
+        let sum: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u32
+                } else {
+                    b as u32
+                }
+            })
+            .sum();
+        assert_eq!(&header[148..154], format!("{:06o}", sum).as_bytes());
+        assert_eq!(&header[124..136], b"00000000005\0");
+    }
+
+    #[test]
+    fn manifests_listing_files_outside_the_output_folder_are_not_packaged() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_package_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        std::fs::write(dir.join("secret.txt"), "key").unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"version": 1, "entries": [
+                {"file": "../secret.txt", "source": "a.mp4", "range": 0, "start_time": 0, "end_time": 1}
+            ]}"#,
+        )
+        .unwrap();
+        manifest.save(&out_dir).unwrap();
+        let target = dir.join("dataset.zip");
+        let result = package(&out_dir, &target, &Options::default(), &Schedule::default());
+        assert!(result.is_err());
+        assert!(!target.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
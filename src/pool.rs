@@ -0,0 +1,70 @@
+// Parallel exports: jobs (one file each) run on up to N worker threads, each picking up the next
+// job once it is done with one. An export is mostly FFmpeg, which leaves most cores idle on short
+// clips when only one runs at a time. Every worker exports with a progress handle of its own.
+use std::sync::Mutex;
+
+/// How many jobs run at once by default: one, the way exports always ran.
+pub const DEFAULT_LIMIT: usize = 1;
+
+/// The most jobs that make sense on this machine: one per core.
+pub fn max_limit() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Runs `work` on every job with at most `limit` of them at a time and returns their results in
+/// the order of `jobs`. `work` gets the index of the worker running it (below `limit`) along
+/// with the job, to report its progress under.
+pub fn run<J: Send, R: Send>(
+    jobs: Vec<J>,
+    limit: usize,
+    work: impl Fn(usize, J) -> R + Sync,
+) -> Vec<R> {
+    let count = jobs.len();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<Option<R>>>());
+    std::thread::scope(|scope| {
+        for worker in 0..limit.clamp(1, count.max(1)) {
+            let (queue, results, work) = (&queue, &results, &work);
+            scope.spawn(move || {
+                while let Some((i, job)) = next(queue) {
+                    let result = work(worker, job);
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every job ran"))
+        .collect()
+}
+
+/// The next job off `queue`, without holding the lock while it runs.
+fn next<T>(queue: &Mutex<impl Iterator<Item = T>>) -> Option<T> {
+    queue.lock().unwrap().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn runs_at_most_the_limit_at_once_and_keeps_the_order() {
+        let (running, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let results = run((0..12).collect(), 3, |worker, job: u32| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+            assert!(worker < 3);
+            job * 2
+        });
+        assert_eq!(results, (0..12).map(|j| j * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(run(Vec::<u32>::new(), 4, |_, j| j).is_empty());
+    }
+}
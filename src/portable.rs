@@ -0,0 +1,38 @@
+// Portable mode, for running off a USB stick on locked-down annotation machines. Everything the
+// app keeps for itself (the cache, the autosave journal, the settings, the logs and the
+// single-instance endpoint) goes into a folder next to the executable instead of the per-user OS
+// folders, so nothing is written to the machine's profile. Turned on with --portable or by a
+// marker file next to the executable, which a stick can simply ship with.
+use std::path::{Path, PathBuf};
+
+/// Marker file next to the executable that turns portable mode on
+pub const MARKER: &str = "viddatatraincrop.portable";
+/// Folder next to the executable holding the app's data in portable mode
+const DATA_DIR: &str = "viddatatraincrop-data";
+
+fn data_dir_in(exe_dir: &Path, requested: bool) -> Option<PathBuf> {
+    (requested || exe_dir.join(MARKER).is_file()).then(|| exe_dir.join(DATA_DIR))
+}
+
+/// Where the app keeps its data when running portable, either because it was `requested` on
+/// the command line or because the marker file is present. None outside portable mode.
+pub fn data_dir(requested: bool) -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    data_dir_in(exe.parent()?, requested)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_or_flag_turns_portable_mode_on() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_portable_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(data_dir_in(&dir, false), None);
+        assert_eq!(data_dir_in(&dir, true), Some(dir.join(DATA_DIR)));
+        std::fs::write(dir.join(MARKER), "").unwrap();
+        assert_eq!(data_dir_in(&dir, false), Some(dir.join(DATA_DIR)));
+    }
+}
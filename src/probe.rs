@@ -0,0 +1,273 @@
+// Media metadata from ffprobe, for values OpenCV's VideoCapture reports unreliably.
+use crate::remote;
+use opencv::{prelude::*, videoio};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs ffprobe on the first video stream and returns the `key=value` pairs it prints for
+/// `entries` (ffprobe's -show_entries syntax, e.g. "stream=avg_frame_rate:format=duration").
+fn show_entries(path: &Path, entries: &str) -> Option<HashMap<String, String>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", entries])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(remote::input(path))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .collect(),
+    )
+}
+
+/// Parses ffprobe rates such as "30000/1001" or "25".
+fn parse_rate(rate: &str) -> Option<f64> {
+    let fps = match rate.split_once('/') {
+        Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok()?,
+        None => rate.parse().ok()?,
+    };
+    is_valid_fps(fps).then_some(fps)
+}
+
+/// Rejects the 0, NaN and timebase-like values containers report when the rate is unknown.
+pub fn is_valid_fps(fps: f64) -> bool {
+    fps.is_finite() && fps > 0.0 && fps <= 1000.0
+}
+
+/// Frame rate as reported by ffprobe, preferring the average over the base rate.
+pub fn video_fps(path: &Path) -> Option<f64> {
+    let entries = show_entries(path, "stream=avg_frame_rate,r_frame_rate")?;
+    ["avg_frame_rate", "r_frame_rate"]
+        .iter()
+        .find_map(|key| entries.get(*key).and_then(|r| parse_rate(r)))
+}
+
+/// Container duration in seconds as reported by ffprobe.
+pub fn duration(path: &Path) -> Option<f64> {
+    let entries = show_entries(path, "format=duration")?;
+    entries
+        .get("duration")
+        .and_then(|d| d.parse::<f64>().ok())
+        .filter(|d| d.is_finite() && *d > 0.0)
+}
+
+/// Width and height of the first video stream, or of an image, as stored (before rotation).
+pub fn frame_size(path: &Path) -> Option<(i32, i32)> {
+    let entries = show_entries(path, "stream=width,height")?;
+    let dim = |key: &str| entries.get(key)?.parse::<i32>().ok().filter(|v| *v > 0);
+    Some((dim("width")?, dim("height")?))
+}
+
+/// Sample (pixel) aspect ratio, e.g. 64:45 for anamorphic PAL DVDs; 1.0 when unknown.
+pub fn sample_aspect_ratio(path: &Path) -> f64 {
+    show_entries(path, "stream=sample_aspect_ratio")
+        .and_then(|e| e.get("sample_aspect_ratio").and_then(|r| parse_ratio(r)))
+        .unwrap_or(1.0)
+}
+
+fn parse_ratio(ratio: &str) -> Option<f64> {
+    let (num, den) = ratio.split_once(':')?;
+    let value = num.parse::<f64>().ok()? / den.parse::<f64>().ok()?;
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+/// Pixel format and color tags of the first video stream; tags are empty or "unknown" when
+/// the file has none.
+pub struct ColorInfo {
+    pub pix_fmt: String,
+    pub transfer: String,
+    /// YCbCr matrix, e.g. bt709 or smpte170m
+    pub matrix: String,
+    pub primaries: String,
+}
+
+impl ColorInfo {
+    /// More than 8 bits per component, e.g. yuv420p10le, p010le or rgb48le. Read from the
+    /// number FFmpeg's format names end in, which only some formats spend on the depth: nv12
+    /// and yuv410p are 8-bit.
+    pub fn is_high_bit_depth(&self) -> bool {
+        let name = self.pix_fmt.trim();
+        let name = name
+            .strip_suffix("le")
+            .or_else(|| name.strip_suffix("be"))
+            .unwrap_or(name);
+        let head = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        let bits: u32 = name[head.len()..].parse().unwrap_or(0);
+        match head {
+            // Planar, float and gray formats end in their depth: yuv420p10, gbrpf32, gray12
+            "gray" | "ya" | "xyz" => bits > 8,
+            _ if head.ends_with('p') || head.ends_with('f') => bits > 8,
+            // Semi-planar and packed YUV name depth and layout: p010, p216, y210, xv30, ayuv64
+            "p" | "y" | "xv" | "ayuv" => bits > 8,
+            "nv" => bits == 20,
+            // Packed RGB names the bits per pixel: rgb24 is 8-bit, rgb48 and rgba64 are not
+            "rgb" | "bgr" => bits == 48,
+            "rgba" | "bgra" => bits == 64,
+            "x2rgb" | "x2bgr" => true,
+            _ => false,
+        }
+    }
+
+    /// PQ (HDR10) or HLG transfer, which needs tonemapping for an SDR preview.
+    pub fn is_hdr(&self) -> bool {
+        matches!(self.transfer.as_str(), "smpte2084" | "arib-std-b67")
+    }
+}
+
+pub fn color_info(path: &Path) -> Option<ColorInfo> {
+    let entries = show_entries(path, "stream=pix_fmt,color_transfer,color_space,color_primaries")?;
+    let entry = |key: &str| entries.get(key).cloned().unwrap_or_default();
+    Some(ColorInfo {
+        pix_fmt: entry("pix_fmt"),
+        transfer: entry("color_transfer"),
+        matrix: entry("color_space"),
+        primaries: entry("color_primaries"),
+    })
+}
+
+/// A chapter marker from the container (mkv/mp4 chapters).
+#[derive(Clone)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// Chapters from `ffprobe -show_chapters -of json` output, sorted by start.
+fn parse_chapters(json: &str) -> Vec<Chapter> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    let time = |c: &serde_json::Value, key: &str| {
+        let time = c.get(key)?.as_str()?.parse::<f64>().ok()?;
+        time.is_finite().then_some(time)
+    };
+    let mut chapters: Vec<Chapter> = value["chapters"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| {
+            let (start, end) = (time(c, "start_time")?, time(c, "end_time")?);
+            let title = c["tags"]["title"].as_str().unwrap_or_default();
+            (end > start).then(|| Chapter {
+                start: start.max(0.0),
+                end,
+                title: title.trim().to_string(),
+            })
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    chapters
+}
+
+/// Chapter markers of a video; empty when it has none.
+pub fn chapters(path: &Path) -> Vec<Chapter> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_chapters", "-of", "json"])
+        .arg(remote::input(path))
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_chapters(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Estimates the frame rate from the timestamps of the first decoded frames, then rewinds.
+pub fn fps_from_timestamps(cap: &mut videoio::VideoCapture) -> Option<f64> {
+    let mut frame = opencv::core::Mat::default();
+    let mut stamps = vec![];
+    for _ in 0..30 {
+        if !cap.read(&mut frame).unwrap_or(false) {
+            break;
+        }
+        stamps.push(cap.get(videoio::CAP_PROP_POS_MSEC).ok()?);
+    }
+    let _ = cap.set(videoio::CAP_PROP_POS_FRAMES, 0.0);
+
+    let mut deltas: Vec<f64> = stamps
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|d| *d > 0.0)
+        .collect();
+    if deltas.is_empty() {
+        return None;
+    }
+    deltas.sort_by(f64::total_cmp);
+    let fps = 1000.0 / deltas[deltas.len() / 2];
+    is_valid_fps(fps).then_some(fps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!(parse_rate("25"), Some(25.0));
+        assert_eq!(parse_rate("30000/1001"), Some(30000.0 / 1001.0));
+        assert_eq!(parse_rate("0/0"), None);
+        assert_eq!(parse_rate("90000/1"), None);
+        assert_eq!(parse_rate("N/A"), None);
+    }
+
+    #[test]
+    fn high_bit_depth_comes_from_the_format_name() {
+        let high = |pix_fmt: &str| {
+            ColorInfo {
+                pix_fmt: pix_fmt.to_string(),
+                transfer: String::new(),
+                matrix: String::new(),
+                primaries: String::new(),
+            }
+            .is_high_bit_depth()
+        };
+        for pix_fmt in [
+            "yuv420p10le",
+            "yuv422p12be",
+            "gbrap16le",
+            "gray10le",
+            "p010le",
+            "y210le",
+            "rgb48le",
+            "rgba64le",
+        ] {
+            assert!(high(pix_fmt), "{}", pix_fmt);
+        }
+        for pix_fmt in [
+            "yuv420p", "yuvj422p", "yuv410p", "nv12", "nv16", "rgb24", "rgba", "rgb565le", "",
+        ] {
+            assert!(!high(pix_fmt), "{}", pix_fmt);
+        }
+    }
+
+    #[test]
+    fn parses_aspect_ratios() {
+        assert_eq!(parse_ratio("1:1"), Some(1.0));
+        assert_eq!(parse_ratio("64:45"), Some(64.0 / 45.0));
+        assert_eq!(parse_ratio("0:1"), None);
+        assert_eq!(parse_ratio("N/A"), None);
+    }
+
+    #[test]
+    fn parses_chapters() {
+        let json = r#"{"chapters": [
+            {"start_time": "95.500000", "end_time": "180.000000", "tags": {"title": "Dogs "}},
+            {"start_time": "0.000000", "end_time": "95.500000"},
+            {"start_time": "180.000000", "end_time": "180.000000"}
+        ]}"#;
+        let chapters = parse_chapters(json);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!((chapters[0].start, chapters[0].end), (0.0, 95.5));
+        assert_eq!(chapters[0].title, "");
+        assert_eq!(chapters[1].title, "Dogs");
+        assert!(parse_chapters("{}").is_empty());
+    }
+}
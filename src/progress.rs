@@ -0,0 +1,276 @@
+// Export progress. FFmpeg is run with `-progress pipe:1`, which prints how far into the output it
+// is every half second; the export thread records that here and the UI turns it into a progress
+// bar with the current range, the encoded time and an estimate of the time left for the file.
+// The running FFmpeg is kept here too, so cancelling an export can stop it mid-clip. A parallel
+// export reports through one handle per worker (see `worker`), each with a bar of its own.
+// What FFmpeg prints to stderr is kept in a log of its last lines, shown in the UI so a failed
+// export can be diagnosed (and reported) without a terminal.
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Lines of FFmpeg output the log keeps
+pub const LOG_LINES: usize = 500;
+
+#[derive(Clone, Default)]
+pub struct Progress {
+    /// One entry per worker
+    state: Arc<Mutex<Vec<Option<Snapshot>>>>,
+    /// The FFmpeg processes `run` is reading from, by process id
+    children: Arc<Mutex<HashMap<u32, Child>>>,
+    /// The worker this handle reports for
+    slot: usize,
+    /// The last `LOG_LINES` lines FFmpeg printed, of all workers, each command led by its
+    /// command line
+    log: Arc<Mutex<VecDeque<String>>>,
+}
+
+/// How far the export of one file is.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub file: String,
+    /// Index of the range being exported
+    pub range: usize,
+    pub ranges: usize,
+    /// Seconds of the current range encoded so far
+    pub encoded: f64,
+    /// Length of the current range in seconds, 0 for stills
+    pub duration: f64,
+    /// Lengths of all ranges of the file
+    durations: Vec<f64>,
+    started: Instant,
+}
+
+impl Snapshot {
+    /// Share of the file's ranges that is done, weighted by their length.
+    pub fn fraction(&self) -> f32 {
+        // Stills count as a second each
+        let weight = |d: &f64| if *d > 0.0 { *d } else { 1.0 };
+        let total: f64 = self.durations.iter().map(weight).sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let done: f64 = self.durations[..self.range].iter().map(weight).sum();
+        ((done + self.encoded.min(self.duration)) / total) as f32
+    }
+
+    /// Time left for the file at the pace so far, once there is enough to go by.
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction() as f64;
+        (fraction > 0.01).then(|| self.started.elapsed().mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+impl Progress {
+    /// A handle for worker `slot` of a parallel export. It shares the processes (so `kill`
+    /// stops them all) but reports a file of its own.
+    pub fn worker(&self, slot: usize) -> Progress {
+        Progress {
+            slot,
+            ..self.clone()
+        }
+    }
+
+    /// The file this handle's worker is exporting.
+    pub fn get(&self) -> Option<Snapshot> {
+        self.state.lock().unwrap().get(self.slot).cloned().flatten()
+    }
+
+    /// The files every worker is exporting, by worker.
+    pub fn all(&self) -> Vec<Snapshot> {
+        self.state
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Option<Snapshot>)) {
+        let mut state = self.state.lock().unwrap();
+        if state.len() <= self.slot {
+            state.resize(self.slot + 1, None);
+        }
+        change(&mut state[self.slot]);
+    }
+
+    /// Starts a file whose ranges are `durations` seconds long (0 for stills).
+    pub fn start_file(&self, file: String, durations: Vec<f64>) {
+        self.update(|state| {
+            *state = Some(Snapshot {
+                file,
+                range: 0,
+                ranges: durations.len(),
+                encoded: 0.0,
+                duration: durations.first().copied().unwrap_or_default(),
+                durations,
+                started: Instant::now(),
+            })
+        });
+    }
+
+    /// Moves on to range `range` of the current file.
+    pub fn start_range(&self, range: usize) {
+        self.update(|state| {
+            if let Some(s) = state {
+                s.range = range.min(s.ranges);
+                s.encoded = 0.0;
+                s.duration = s.durations.get(range).copied().unwrap_or_default();
+            }
+        });
+    }
+
+    fn set_encoded(&self, seconds: f64) {
+        self.update(|state| {
+            if let Some(s) = state {
+                s.encoded = seconds;
+            }
+        });
+    }
+
+    /// Forgets the files of all workers.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().clear();
+    }
+
+    /// The FFmpeg output kept since the log was last cleared, oldest first.
+    pub fn log(&self) -> Vec<String> {
+        self.log.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Empties the log, before an export starts.
+    pub fn clear_log(&self) {
+        self.log.lock().unwrap().clear();
+    }
+
+    fn record(&self, line: String) {
+        let mut log = self.log.lock().unwrap();
+        if log.len() == LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(line);
+    }
+
+    /// Stops the FFmpeg processes `run` is waiting for, if any; `run` then returns their failed
+    /// status.
+    pub fn kill(&self) {
+        for child in self.children.lock().unwrap().values_mut() {
+            let _ = child.kill();
+        }
+    }
+
+    /// Runs `cmd` (an FFmpeg command) to completion, reporting how much of the output it has
+    /// written. What FFmpeg prints to stderr goes to the log.
+    pub fn run(&self, cmd: &Command) -> std::io::Result<ExitStatus> {
+        // A copy with the progress options up front, so the command (and its hash) stays as is
+        let mut reporting = Command::new(cmd.get_program());
+        reporting
+            .args(["-progress", "pipe:1", "-nostats", "-hide_banner"])
+            .args(cmd.get_args())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = reporting.spawn()?;
+        self.record(command_line(cmd));
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let id = child.id();
+        self.children.lock().unwrap().insert(id, child);
+        // Both end when FFmpeg exits or is killed; stderr is read alongside so neither pipe
+        // fills up and blocks FFmpeg
+        std::thread::scope(|scope| {
+            if let Some(stderr) = stderr {
+                scope.spawn(|| {
+                    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                        self.record(line);
+                    }
+                });
+            }
+            if let Some(stdout) = stdout {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if let Some(seconds) = out_time(&line) {
+                        self.set_encoded(seconds);
+                    }
+                }
+            }
+        });
+        let child = self.children.lock().unwrap().remove(&id);
+        child.expect("the child was stored above").wait()
+    }
+}
+
+/// `cmd` as it would be typed into a shell, as it leads its output in the log.
+pub fn command_line(cmd: &Command) -> String {
+    let mut line = format!("$ {}", cmd.get_program().to_string_lossy());
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+            line += &format!(" '{}'", arg.replace('\'', "'\\''"));
+        } else {
+            line += &format!(" {}", arg);
+        }
+    }
+    line
+}
+
+/// The output time in seconds in a line of FFmpeg's `-progress` output. `out_time_ms` is in
+/// microseconds too, despite its name; it is all that older versions print.
+fn out_time(line: &str) -> Option<f64> {
+    let (key, value) = line.trim().split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => value.parse::<i64>().ok().map(|us| us.max(0) as f64 / 1e6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_progress_lines_and_weights_ranges() {
+        assert_eq!(out_time("out_time_us=2500000"), Some(2.5));
+        assert_eq!(out_time("out_time_ms=1000000\n"), Some(1.0));
+        assert_eq!(out_time("out_time_us=N/A"), None);
+        assert_eq!(out_time("out_time_us=-23220"), Some(0.0));
+        assert_eq!(out_time("progress=continue"), None);
+
+        let progress = Progress::default();
+        assert!(progress.get().is_none());
+        progress.start_file("a.mp4".to_string(), vec![2.0, 6.0]);
+        progress.start_range(1);
+        progress.set_encoded(3.0);
+        let snapshot = progress.get().unwrap();
+        assert_eq!((snapshot.range, snapshot.ranges), (1, 2));
+        assert_eq!(snapshot.fraction(), 5.0 / 8.0);
+        let other = progress.worker(1);
+        other.start_file("b.mp4".to_string(), vec![1.0]);
+        assert_eq!(progress.get().unwrap().file, "a.mp4");
+        let files: Vec<_> = progress.all().into_iter().map(|s| s.file).collect();
+        assert_eq!(files, ["a.mp4", "b.mp4"]);
+        progress.clear();
+        assert!(progress.get().is_none() && other.get().is_none());
+    }
+
+    #[test]
+    fn the_log_keeps_the_last_lines_of_all_workers() {
+        let progress = Progress::default();
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-i", "my clip.mp4", "out.mp4"]);
+        progress.record(command_line(&cmd));
+        assert_eq!(progress.log(), ["$ ffmpeg -i 'my clip.mp4' out.mp4"]);
+        for i in 0..LOG_LINES {
+            progress.worker(i % 2).record(i.to_string());
+        }
+        let log = progress.log();
+        assert_eq!(log.len(), LOG_LINES);
+        assert_eq!(log[0], "0");
+        assert_eq!(log[LOG_LINES - 1], (LOG_LINES - 1).to_string());
+        progress.clear();
+        assert_eq!(progress.log().len(), LOG_LINES);
+        progress.clear_log();
+        assert!(progress.log().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+// Project files (`project.vdtc`) for curation that runs over several days or moves between
+// machines: the input and output folders, the open file and the annotations of every file in
+// the input folder in one file. The sidecars stay the working copy; saving a project collects
+// them and opening one writes them back.
+use crate::i18n::trf;
+use crate::{VideoRange, migrate, sidecar};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Suggested name when saving a project into the input folder
+pub const FILE_NAME: &str = "project.vdtc";
+pub const EXTENSION: &str = "vdtc";
+/// Bumped whenever the format changes incompatibly, with a step added to `STEPS`.
+pub const VERSION: u32 = 1;
+/// Upgrades from older versions, see `migrate::parse`
+const STEPS: &[migrate::Step] = &[];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Project {
+    version: u32,
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    /// File name of the media that was open, inside the input folder
+    pub file: Option<String>,
+    /// Annotated ranges by media file name; files without annotations are left out
+    pub files: BTreeMap<String, Vec<VideoRange>>,
+}
+
+/// A sidecar to write when opening a project.
+pub struct Write {
+    pub media: PathBuf,
+    pub ranges: Vec<VideoRange>,
+    /// The sidecar has other annotations (or can't be read) and would be replaced
+    pub replaces: bool,
+}
+
+/// What opening a project changes in its input folder.
+pub struct Restore {
+    pub writes: Vec<Write>,
+    /// Annotated files of the project that are not in the folder any more
+    pub missing: Vec<String>,
+    pub file: Option<String>,
+}
+
+impl Restore {
+    /// Whether opening should wait for confirmation rather than go ahead.
+    pub fn needs_confirmation(&self) -> bool {
+        !self.missing.is_empty() || self.writes.iter().any(|w| w.replaces)
+    }
+}
+
+impl Project {
+    /// A project of the media in `annotations`, all inside `input`.
+    pub fn new(
+        input: PathBuf,
+        output: Option<PathBuf>,
+        file: Option<&Path>,
+        annotations: Vec<(PathBuf, Vec<VideoRange>)>,
+    ) -> Project {
+        let name = |media: &Path| media.file_name().unwrap().to_string_lossy().into_owned();
+        Project {
+            version: VERSION,
+            input,
+            output,
+            file: file.map(name),
+            files: annotations
+                .into_iter()
+                .filter(|(_, ranges)| !ranges.is_empty())
+                .map(|(media, ranges)| (name(&media), ranges))
+                .collect(),
+        }
+    }
+
+    /// The sidecars of `videos` (the media in the project's input folder) that differ from the
+    /// project. Files the project has no annotations for keep theirs.
+    pub fn restore(self, videos: &[PathBuf]) -> Restore {
+        let mut files = self.files;
+        let mut writes = Vec::new();
+        for media in videos {
+            let Some(name) = media.file_name() else {
+                continue;
+            };
+            let Some(ranges) = files.remove(&*name.to_string_lossy()) else {
+                continue;
+            };
+            let replaces = match sidecar::load(media) {
+                None => false,
+                Some(Ok(current)) if sidecar::to_json(&current) == sidecar::to_json(&ranges) => {
+                    continue;
+                }
+                Some(Ok(current)) => !current.is_empty(),
+                Some(Err(_)) => true,
+            };
+            writes.push(Write {
+                media: media.clone(),
+                ranges,
+                replaces,
+            });
+        }
+        Restore {
+            writes,
+            missing: files.into_keys().collect(),
+            file: self.file,
+        }
+    }
+}
+
+/// The project in the JSON `text`, upgraded from older versions.
+fn parse(text: &str) -> Result<Project, String> {
+    migrate::parse(text, VERSION, STEPS)
+}
+
+fn to_json(project: &Project) -> Result<String, String> {
+    serde_json::to_string_pretty(project).map_err(|e| e.to_string())
+}
+
+pub fn load(path: &Path) -> Result<Project, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse(&text))
+        .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+}
+
+pub fn save(path: &Path, project: &Project) -> Result<(), String> {
+    let json = to_json(project)?;
+    std::fs::write(path, json).map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One project per released version; the last one is the current format.
+    const GOLDEN: &[&str] = &[include_str!("../tests/golden/project_v1.json")];
+
+    #[test]
+    fn golden_projects_load_and_the_current_one_round_trips() {
+        assert_eq!(GOLDEN.len(), VERSION as usize);
+        for text in GOLDEN {
+            let project = parse(text).unwrap();
+            assert_eq!(project.file.as_deref(), Some("a.mp4"));
+            assert_eq!(project.files["b.mp4"][0].tags, ["cat"]);
+        }
+        let current = GOLDEN[GOLDEN.len() - 1];
+        assert_eq!(
+            to_json(&parse(current).unwrap()).unwrap(),
+            current.trim_end()
+        );
+    }
+
+    #[test]
+    fn restoring_writes_only_differing_sidecars() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_project_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let videos: Vec<_> = ["same.mp4", "new.mp4", "edited.mp4", "plain.mp4"]
+            .iter()
+            .map(|name| dir.join(name))
+            .collect();
+        let range = |note: &str| VideoRange {
+            end_time: 2.0,
+            note: note.to_string(),
+            ..Default::default()
+        };
+        sidecar::save(&videos[0], &[range("kept")]).unwrap();
+        sidecar::save(&videos[2], &[range("changed since")]).unwrap();
+        let project = Project::new(
+            dir.clone(),
+            None,
+            None,
+            vec![
+                (videos[0].clone(), vec![range("kept")]),
+                (videos[1].clone(), vec![range("new")]),
+                (videos[2].clone(), vec![range("saved")]),
+                (dir.join("gone.mp4"), vec![range("gone")]),
+            ],
+        );
+
+        let restore = project.restore(&videos);
+        let writes: Vec<_> = restore
+            .writes
+            .iter()
+            .map(|w| (w.media.file_name().unwrap().to_str().unwrap(), w.replaces))
+            .collect();
+        assert_eq!(writes, [("new.mp4", false), ("edited.mp4", true)]);
+        assert_eq!(restore.missing, ["gone.mp4"]);
+        assert!(restore.needs_confirmation());
+    }
+}
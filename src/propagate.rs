@@ -0,0 +1,118 @@
+// Carrying a crop over to another shot of the same scene. When the camera reframes slightly
+// between ranges, the neighbour's crop no longer sits on the subject; template matching finds
+// where its content went in the current frame and proposes the crop moved there.
+use crate::SerializableRect;
+use opencv::{core, imgproc, prelude::*};
+
+/// Frames are matched at most this wide; plenty for a shift and fast enough to run on a click.
+const WORK_WIDTH: i32 = 640;
+/// Share of the crop left out on every side of the template, so the match has room to move
+/// even when the crop fills the frame.
+const INSET: f32 = 0.15;
+/// Normalized correlation below which the content is considered not found.
+const MIN_SCORE: f64 = 0.6;
+
+pub struct Proposal {
+    pub crop: SerializableRect,
+    /// Normalized correlation of the match, 1.0 being identical
+    pub score: f64,
+}
+
+fn prepare(frame: &core::Mat, size: core::Size) -> opencv::Result<core::Mat> {
+    let mut gray = core::Mat::default();
+    imgproc::cvt_color_def(frame, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    let mut scaled = core::Mat::default();
+    imgproc::resize(&gray, &mut scaled, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    Ok(scaled)
+}
+
+/// Where the content of `crop` in the BGR `reference` frame is in the BGR `target` frame, as
+/// `crop` moved there (same size, kept inside the frame). None when it isn't found.
+pub fn match_crop(
+    reference: &core::Mat,
+    crop: &SerializableRect,
+    target: &core::Mat,
+) -> opencv::Result<Option<Proposal>> {
+    // Both frames go to the same size, so proxies and full-size frames can be compared
+    let width = target.cols().min(WORK_WIDTH);
+    let height = (target.rows() as f64 * width as f64 / target.cols().max(1) as f64).round();
+    let size = core::Size::new(width.max(1), (height as i32).max(1));
+    let reference = prepare(reference, size)?;
+    let target = prepare(target, size)?;
+
+    let (iw, ih) = (
+        (crop.max_x - crop.min_x) * INSET,
+        (crop.max_y - crop.min_y) * INSET,
+    );
+    let inner = SerializableRect {
+        min_x: crop.min_x + iw,
+        min_y: crop.min_y + ih,
+        max_x: crop.max_x - iw,
+        max_y: crop.max_y - ih,
+    };
+    let (tw, th, tx, ty) = inner.to_pixels(size.width as f64, size.height as f64);
+    if tw < 8 || th < 8 {
+        return Ok(None);
+    }
+    let template = reference.roi(core::Rect::new(tx, ty, tw, th))?;
+    let mut scores = core::Mat::default();
+    imgproc::match_template_def(&target, &template, &mut scores, imgproc::TM_CCOEFF_NORMED)?;
+    let mut score = 0.0;
+    let mut at = core::Point::default();
+    core::min_max_loc(
+        &scores,
+        None,
+        Some(&mut score),
+        None,
+        Some(&mut at),
+        &core::no_array(),
+    )?;
+    if score < MIN_SCORE {
+        return Ok(None);
+    }
+    let dx = (at.x - tx) as f32 / size.width as f32;
+    let dy = (at.y - ty) as f32 / size.height as f32;
+    Ok(Some(Proposal {
+        crop: crop.nudged(dx, dy, false),
+        score,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_a_camera_pan() {
+        let mut scene =
+            core::Mat::new_rows_cols_with_default(100, 220, core::CV_8UC3, core::Scalar::all(0.0))
+                .unwrap();
+        core::randu(
+            &mut scene,
+            &core::Scalar::all(0.0),
+            &core::Scalar::all(255.0),
+        )
+        .unwrap();
+        // The camera moves 10 px right, so the content moves 10 px left
+        let reference = scene
+            .roi(core::Rect::new(0, 0, 200, 100))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let target = scene
+            .roi(core::Rect::new(10, 0, 200, 100))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let crop = SerializableRect {
+            min_x: 0.4,
+            min_y: 0.2,
+            max_x: 0.8,
+            max_y: 0.8,
+        };
+        let proposal = match_crop(&reference, &crop, &target).unwrap().unwrap();
+        assert!((proposal.crop.min_x - 0.35).abs() < 1e-3);
+        assert!((proposal.crop.min_y - 0.2).abs() < 1e-3);
+        assert!(proposal.score > 0.99);
+    }
+}
@@ -0,0 +1,56 @@
+// 8-bit preview proxies for sources OpenCV cannot display faithfully (10-bit, HDR).
+// Only the preview uses them; exports always read the original file.
+use crate::i18n::trf;
+use crate::{cache, remote};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tonemaps PQ/HLG to BT.709 SDR. Needs an FFmpeg build with zimg.
+const TONEMAP_FILTER: &str = "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+    tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p";
+
+fn proxy_path(source: &Path) -> PathBuf {
+    cache::dir("proxies").join(format!("{}.mp4", cache::source_key(source)))
+}
+
+fn transcode(source: &Path, out: &Path, filter: Option<&str>) -> bool {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error", "-i"])
+        .arg(remote::input(source));
+    // Keep the original timestamps so preview times map 1:1 onto the source
+    cmd.args(["-map", "0:v:0", "-an", "-sn", "-fps_mode", "passthrough"]);
+    match filter {
+        Some(filter) => cmd.arg("-vf").arg(filter),
+        None => cmd.args(["-sws_dither", "ed", "-pix_fmt", "yuv420p"]),
+    };
+    cmd.args(["-c:v", "libx264", "-preset", "ultrafast", "-crf", "16"])
+        .arg(out);
+    cmd.status().is_ok_and(|s| s.success())
+}
+
+/// Returns a cached 8-bit proxy of `source`, transcoding it first if needed. Blocks; run on
+/// a worker thread. HDR sources are tonemapped, falling back to a plain dithered conversion.
+pub fn build(source: &Path, hdr: bool) -> Result<PathBuf, String> {
+    let out = proxy_path(source);
+    if out.exists() {
+        cache::touch(&out);
+        return Ok(out);
+    }
+    let dir = out.parent().unwrap();
+    std::fs::create_dir_all(dir).map_err(|e| trf("Failed to create proxy folder: {}", &[&e]))?;
+
+    // Write to a temp name first so an interrupted transcode is never picked up as a proxy
+    let partial = out.with_extension("partial.mp4");
+    let ok = (hdr && transcode(source, &partial, Some(TONEMAP_FILTER)))
+        || transcode(source, &partial, None);
+    if !ok {
+        let _ = std::fs::remove_file(&partial);
+        return Err(trf(
+            "FFmpeg could not create a preview proxy for {}",
+            &[&format!("{:?}", source)],
+        ));
+    }
+    std::fs::rename(&partial, &out).map_err(|e| trf("Failed to store proxy: {}", &[&e]))?;
+    cache::evict(Some(&out));
+    Ok(out)
+}
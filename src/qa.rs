@@ -0,0 +1,233 @@
+// QA spot-check export: a random sample of the annotated ranges of the whole input folder,
+// written as a review list (review.csv) plus one contact sheet per sampled range.
+use crate::i18n::trf;
+use crate::{VideoRange, color, is_image_file, remote, timing};
+use opencv::{core, imgcodecs, imgproc, prelude::*, videoio};
+use std::path::{Path, PathBuf};
+
+/// Contact sheet layout: frames spread evenly over the range.
+const SHEET_COLS: i32 = 3;
+const SHEET_ROWS: i32 = 2;
+const TILE_WIDTH: i32 = 320;
+
+pub struct Sample {
+    pub media: PathBuf,
+    pub range_idx: usize,
+    pub range: VideoRange,
+}
+
+/// Picks `percent` of `candidates` (at least one, if there are any) with a seeded shuffle, so
+/// the same seed reproduces the same review list. The result is ordered by file and time.
+pub fn pick(mut candidates: Vec<Sample>, percent: f32, seed: u64) -> Vec<Sample> {
+    if candidates.is_empty() {
+        return candidates;
+    }
+    let count =
+        ((candidates.len() as f32 * percent / 100.0).ceil() as usize).clamp(1, candidates.len());
+    fastrand::Rng::with_seed(seed).shuffle(&mut candidates);
+    candidates.truncate(count);
+    candidates.sort_by(|a, b| {
+        a.media
+            .cmp(&b.media)
+            .then(a.range.start_time.total_cmp(&b.range.start_time))
+    });
+    candidates
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Full frames with the crop outlined and the timestamp in the corner, so both the crop and
+/// the content of the range can be judged at a glance.
+fn contact_sheet(sample: &Sample) -> opencv::Result<core::Mat> {
+    let media = sample.media.to_string_lossy();
+    let range = &sample.range;
+    let frames: Vec<(f64, core::Mat)> = if is_image_file(&sample.media) {
+        let mat = imgcodecs::imread(&media, imgcodecs::IMREAD_COLOR | imgcodecs::IMREAD_ANYDEPTH)?;
+        vec![(0.0, mat)]
+    } else {
+        let input = remote::input(&sample.media);
+        let mut cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)?;
+        let fps = cap.get(videoio::CAP_PROP_FPS)?;
+        let fps = if fps > 0.0 { fps } else { 30.0 };
+        let count = (SHEET_COLS * SHEET_ROWS) as usize;
+        (0..count)
+            .filter_map(|k| {
+                let t = range.start_time
+                    + (range.end_time - range.start_time) * (k as f64 + 0.5) / count as f64;
+                let _ = cap.set(
+                    videoio::CAP_PROP_POS_FRAMES,
+                    timing::frame_at(t, fps) as f64,
+                );
+                let mut frame = core::Mat::default();
+                (cap.read(&mut frame).unwrap_or(false) && !frame.empty()).then_some((t, frame))
+            })
+            .collect()
+    };
+    let Some((_, first)) = frames.first() else {
+        return Err(opencv::Error::new(core::StsError, "no frames decoded"));
+    };
+
+    let size = first.size()?;
+    let tile_h = (TILE_WIDTH as f64 * size.height as f64 / size.width as f64).round() as i32;
+    let cols = SHEET_COLS.min(frames.len() as i32);
+    let rows = (frames.len() as i32 + cols - 1) / cols;
+    let mut sheet = core::Mat::new_rows_cols_with_default(
+        rows * tile_h,
+        cols * TILE_WIDTH,
+        core::CV_8UC3,
+        core::Scalar::all(0.0),
+    )?;
+
+    for (k, (t, frame)) in frames.iter().enumerate() {
+        let mut tile = core::Mat::default();
+        imgproc::resize(
+            &color::to_bgr8(frame)?,
+            &mut tile,
+            core::Size::new(TILE_WIDTH, tile_h),
+            0.0,
+            0.0,
+            imgproc::INTER_AREA,
+        )?;
+        if let Some(crop) = &range.crop_rect_norm {
+            let (cw, ch, cx, cy) = crop.to_pixels(TILE_WIDTH as f64, tile_h as f64);
+            imgproc::rectangle(
+                &mut tile,
+                core::Rect::new(cx, cy, cw, ch),
+                core::Scalar::new(0.0, 0.0, 255.0, 0.0),
+                2,
+                imgproc::LINE_8,
+                0,
+            )?;
+        }
+        imgproc::put_text(
+            &mut tile,
+            &format!("{:.2}s", t),
+            core::Point::new(6, 18),
+            imgproc::FONT_HERSHEY_SIMPLEX,
+            0.5,
+            core::Scalar::new(255.0, 255.0, 255.0, 0.0),
+            1,
+            imgproc::LINE_AA,
+            false,
+        )?;
+        let (col, row) = (k as i32 % cols, k as i32 / cols);
+        let mut slot = sheet.roi_mut(core::Rect::new(
+            col * TILE_WIDTH,
+            row * tile_h,
+            TILE_WIDTH,
+            tile_h,
+        ))?;
+        tile.copy_to(&mut slot)?;
+    }
+    Ok(sheet)
+}
+
+/// Writes the review list and contact sheets into a new folder below `out_dir` and returns it.
+/// Runs on a worker thread. A contact sheet that fails is noted in the list, not fatal.
+pub fn export(
+    samples: &[Sample],
+    out_dir: &Path,
+    percent: f32,
+    seed: u64,
+) -> Result<PathBuf, String> {
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let dir = out_dir.join(format!("qa_{}", stamp));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| trf("Could not write {}: {}", &[&dir.display(), &e]))?;
+
+    let mut csv = format!("# {}% sample, seed {}\n", percent, seed);
+    csv +=
+        "sample,file,range,start_s,end_s,crop,annotator,modified_at,note,contact_sheet,verdict\n";
+    for (n, sample) in samples.iter().enumerate() {
+        let name = sample
+            .media
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let sheet_name = format!(
+            "{:03}_{}_r{}.jpg",
+            n,
+            Path::new(name.as_ref())
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            sample.range_idx
+        );
+        let sheet = match contact_sheet(sample) {
+            Ok(mat) => {
+                let path = dir.join(&sheet_name).to_string_lossy().to_string();
+                match imgcodecs::imwrite(&path, &mat, &core::Vector::new()) {
+                    Ok(true) => sheet_name,
+                    Ok(false) => "write failed".to_string(),
+                    Err(e) => format!("write failed: {}", e),
+                }
+            }
+            Err(e) => format!("decode failed: {}", e),
+        };
+        let range = &sample.range;
+        let crop = range.crop_rect_norm.as_ref().map_or(String::new(), |c| {
+            format!(
+                "{:.4} {:.4} {:.4} {:.4}",
+                c.min_x, c.min_y, c.max_x, c.max_y
+            )
+        });
+        let fields = [
+            n.to_string(),
+            name.to_string(),
+            sample.range_idx.to_string(),
+            format!("{:.3}", range.start_time),
+            format!("{:.3}", range.end_time),
+            crop,
+            range.modified_by.clone().unwrap_or_default(),
+            range.modified_at.clone().unwrap_or_default(),
+            range.note.clone(),
+            sheet,
+            String::new(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv += &row.join(",");
+        csv.push('\n');
+    }
+    let list = dir.join("review.csv");
+    std::fs::write(&list, csv)
+        .map_err(|e| trf("Could not write {}: {}", &[&list.display(), &e]))?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(n: usize) -> Vec<Sample> {
+        (0..n)
+            .map(|i| Sample {
+                media: PathBuf::from(format!("{:02}.mp4", i)),
+                range_idx: 0,
+                range: VideoRange::default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn picks_a_reproducible_share() {
+        let names = |v: Vec<Sample>| v.into_iter().map(|s| s.media).collect::<Vec<_>>();
+        assert_eq!(pick(samples(40), 10.0, 7).len(), 4);
+        assert_eq!(pick(samples(3), 1.0, 7).len(), 1);
+        assert_eq!(
+            names(pick(samples(40), 25.0, 7)),
+            names(pick(samples(40), 25.0, 7))
+        );
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, \"b\"\nc"), "\"a, \"\"b\"\"\nc\"");
+    }
+}
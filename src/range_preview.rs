@@ -0,0 +1,91 @@
+// Animated previews for the range list. Hovering a range shows a few small frames spread
+// across it, cropped like the export, so ranges can be told apart without selecting and
+// scrubbing each one. Frames are decoded once per range on a worker thread and kept until the
+// range's times or crop change.
+use crate::{VideoRange, color, remote};
+use eframe::egui;
+use opencv::{core, imgproc, prelude::*, videoio};
+use std::path::Path;
+
+/// Frames per preview
+const FRAMES: usize = 8;
+/// Width of the preview frames in pixels
+const WIDTH: f64 = 160.0;
+
+/// Identifies what a preview shows: the range's times and crop.
+pub type Key = (u64, u64, Option<[u32; 4]>);
+
+pub fn key(range: &VideoRange) -> Key {
+    let crop = range
+        .crop_rect_norm
+        .as_ref()
+        .map(|c| [c.min_x, c.min_y, c.max_x, c.max_y].map(f32::to_bits));
+    (range.start_time.to_bits(), range.end_time.to_bits(), crop)
+}
+
+/// Times of the preview frames: the middles of `count` equal slices of the range.
+pub fn frame_times(start: f64, end: f64, count: usize) -> Vec<f64> {
+    let step = (end - start).max(0.0) / count as f64;
+    (0..count)
+        .map(|k| start + (k as f64 + 0.5) * step)
+        .collect()
+}
+
+fn frame_image(
+    frame: &core::Mat,
+    range: &VideoRange,
+    pipeline: color::Pipeline,
+) -> opencv::Result<egui::ColorImage> {
+    let frame = color::to_bgr8(frame)?;
+    let size = frame.size()?;
+    let region = match &range.crop_rect_norm {
+        Some(norm) => {
+            let (cw, ch, cx, cy) = norm.to_pixels(size.width as f64, size.height as f64);
+            frame.roi(core::Rect::new(cx, cy, cw, ch))?.try_clone()?
+        }
+        None => frame,
+    };
+    let scale = WIDTH / region.cols().max(1) as f64;
+    let height = ((region.rows() as f64 * scale).round() as i32).max(1);
+    let mut scaled = core::Mat::default();
+    let size = core::Size::new(WIDTH as i32, height);
+    imgproc::resize(&region, &mut scaled, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    // Corrected after scaling, on far fewer pixels
+    let scaled = pipeline.apply(scaled)?;
+    let mut rgb = core::Mat::default();
+    imgproc::cvt_color_def(&scaled, &mut rgb, imgproc::COLOR_BGR2RGB)?;
+    let size = [rgb.cols() as usize, rgb.rows() as usize];
+    Ok(egui::ColorImage::from_rgb(size, rgb.data_bytes()?))
+}
+
+/// Decodes the preview frames of `range` from `file`, with the colors corrected by `pipeline`.
+/// Blocks; run on a worker thread.
+pub fn decode(
+    file: &Path,
+    range: &VideoRange,
+    pipeline: color::Pipeline,
+) -> Result<Vec<egui::ColorImage>, String> {
+    let input = remote::input(file);
+    let mut cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)
+        .map_err(|e| e.to_string())?;
+    let mut frames = Vec::new();
+    for time in frame_times(range.start_time, range.end_time, FRAMES) {
+        let mut frame = core::Mat::default();
+        let _ = cap.set(videoio::CAP_PROP_POS_MSEC, time * 1000.0);
+        if cap.read(&mut frame).unwrap_or(false) && !frame.empty() {
+            frames.push(frame_image(&frame, range, pipeline).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_sit_in_the_middle_of_equal_slices() {
+        assert_eq!(frame_times(10.0, 14.0, 4), [10.5, 11.5, 12.5, 13.5]);
+        assert_eq!(frame_times(3.0, 3.0, 2), [3.0, 3.0]);
+    }
+}
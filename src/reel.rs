@@ -0,0 +1,224 @@
+// Review reels: every range of a file, or of the whole folder, cut into one continuous clip for
+// review sessions and single-file eval sets. Ranges are letterboxed to a common size, optionally
+// with a black frame between them. Each range is encoded to a segment of its own first and the
+// segments are then joined without encoding again, so a reel of hundreds of ranges never has
+// FFmpeg hold hundreds of inputs open at once.
+use crate::dataset::Job;
+use crate::export::{self, Container, DEFAULT_FPS, FrameRate};
+use crate::i18n::{tr, trf};
+use crate::progress::Progress;
+use crate::{VideoRange, is_image_file, remote, timing};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn source(job: &Job) -> export::Source<'_> {
+    export::Source {
+        path: &job.input,
+        is_img: is_image_file(&job.input),
+        fps: job.fps,
+        frame_size: job.frame_size,
+        pixel_aspect: job.pixel_aspect,
+    }
+}
+
+/// Size every range is letterboxed into: wide and tall enough for the largest of them.
+pub fn reel_size(jobs: &[Job]) -> Option<(i32, i32)> {
+    jobs.iter()
+        .flat_map(|job| {
+            let source = source(job);
+            job.ranges
+                .iter()
+                .map(|r| export::output_size(&source, r))
+                .collect::<Vec<_>>()
+        })
+        .reduce(|(w, h), (cw, ch)| (w.max(cw), h.max(ch)))
+        .map(|(w, h)| (w.max(2) & !1, h.max(2) & !1))
+}
+
+/// Scales a clip into `size` keeping its shape, padding the rest black.
+fn letterbox((w, h): (i32, i32)) -> String {
+    format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,format=yuv420p",
+    )
+}
+
+/// Reels are for looking through, so they always encode at the fastest settings.
+fn encode_args(cmd: &mut Command, container: Container, out_file: &Path) {
+    cmd.arg("-an")
+        .args(export::Encoder::default().args(container))
+        .arg(out_file);
+}
+
+/// Encodes `range` of `source` letterboxed into `size`. Stills are held for a second.
+fn segment_command(
+    source: &export::Source,
+    range: &VideoRange,
+    size: (i32, i32),
+    container: Container,
+    out_file: &Path,
+) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-v", "error"]);
+    if source.is_img {
+        let rate = DEFAULT_FPS.to_string();
+        cmd.args(["-loop", "1", "-framerate", &rate, "-t", "1"]);
+    } else {
+        cmd.arg("-ss")
+            .arg(timing::export_start(range.start_time, source.fps).to_string())
+            .arg("-to")
+            .arg(timing::export_end(range.end_time, source.fps).to_string());
+    }
+    cmd.arg("-i").arg(remote::input(source.path));
+    let mut filters = export::video_filters(source, range, FrameRate::Fixed(DEFAULT_FPS));
+    filters.push(letterbox(size));
+    cmd.arg("-vf").arg(filters.join(","));
+    encode_args(&mut cmd, container, out_file);
+    cmd
+}
+
+/// A single black frame of `size`.
+fn separator_command(size: (i32, i32), container: Container, out_file: &Path) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    let color = format!("color=c=black:s={}x{}:r={}", size.0, size.1, DEFAULT_FPS);
+    cmd.args(["-y", "-v", "error", "-f", "lavfi", "-i", &color])
+        .args(["-frames:v", "1", "-vf", &letterbox(size)]);
+    encode_args(&mut cmd, container, out_file);
+    cmd
+}
+
+/// Input list for FFmpeg's concat demuxer.
+pub fn concat_list(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .map(|f| {
+            let path = f.to_string_lossy().replace('\'', "'\\''");
+            format!("file '{}'\n", path)
+        })
+        .collect()
+}
+
+fn run(cmd: Command, what: &dyn std::fmt::Display, progress: &Progress) -> Result<(), String> {
+    match progress.run(&cmd) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(trf(
+            "FFmpeg failed on {} with exit code: {}",
+            &[what, &format!("{:?}", status.code())],
+        )),
+        Err(e) => Err(trf("Failed to start FFmpeg: {}", &[&e])),
+    }
+}
+
+/// Writes every range of `jobs`, in order, into one reel in `out_dir` and returns its path and
+/// the number of ranges in it. Runs on a worker thread; killing the FFmpeg of `progress` stops
+/// it without leaving a partial reel behind.
+pub fn export(
+    jobs: &[Job],
+    out_dir: &Path,
+    separators: bool,
+    container: Container,
+    progress: &Progress,
+) -> Result<(PathBuf, usize), String> {
+    let size = reel_size(jobs).ok_or_else(|| tr("No ranges to put in a reel.").to_string())?;
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let out_file = out_dir.join(format!("reel_{}.{}", stamp, container.extension()));
+    let work = std::env::temp_dir().join(format!("viddatatraincrop_reel_{}", stamp));
+    std::fs::create_dir_all(&work)
+        .and_then(|_| std::fs::create_dir_all(out_dir))
+        .map_err(|e| trf("Could not write {}: {}", &[&work.display(), &e]))?;
+
+    let result = (|| {
+        let segment = |n: usize| work.join(format!("{:04}.{}", n, container.extension()));
+        let mut files = Vec::new();
+        let separator = segment(0);
+        if separators {
+            let what = tr("the separator frame");
+            run(
+                separator_command(size, container, &separator),
+                &what,
+                progress,
+            )?;
+        }
+        let mut count = 0;
+        for job in jobs {
+            let source = source(job);
+            for (i, range) in job.ranges.iter().enumerate() {
+                if separators && count > 0 {
+                    files.push(separator.clone());
+                }
+                count += 1;
+                let file = segment(count);
+                let what = trf("range {} of {}", &[&i, &job.source_name()]);
+                run(
+                    segment_command(&source, range, size, container, &file),
+                    &what,
+                    progress,
+                )?;
+                files.push(file);
+            }
+        }
+
+        let list = work.join("list.txt");
+        std::fs::write(&list, concat_list(&files))
+            .map_err(|e| trf("Could not write {}: {}", &[&list.display(), &e]))?;
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-v", "error", "-f", "concat", "-safe", "0", "-i"])
+            .arg(&list)
+            .args(["-c", "copy"])
+            .arg(&out_file);
+        run(cmd, &out_file.display(), progress)?;
+        Ok((out_file.clone(), count))
+    })();
+    let _ = std::fs::remove_dir_all(&work);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&out_file);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SerializableRect;
+
+    fn job(name: &str, frame_size: (f64, f64), crops: &[Option<SerializableRect>]) -> Job {
+        Job {
+            input: PathBuf::from(name),
+            ranges: crops
+                .iter()
+                .map(|crop| VideoRange {
+                    crop_rect_norm: crop.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            fps: 25.0,
+            frame_size,
+            pixel_aspect: 1.0,
+            profile: export::Profile::default(),
+        }
+    }
+
+    #[test]
+    fn reel_fits_the_largest_range() {
+        let half = SerializableRect {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 0.5,
+            max_y: 1.0,
+        };
+        let jobs = [
+            job("a.mp4", (1920.0, 1080.0), &[Some(half)]),
+            job("b.mp4", (720.0, 1280.0), &[None]),
+        ];
+        assert_eq!(reel_size(&jobs), Some((960, 1280)));
+        assert_eq!(reel_size(&[job("c.mp4", (640.0, 480.0), &[])]), None);
+    }
+
+    #[test]
+    fn concat_list_quotes_paths() {
+        let files = [PathBuf::from("/tmp/a.mp4"), PathBuf::from("/tmp/it's.mp4")];
+        assert_eq!(
+            concat_list(&files),
+            "file '/tmp/a.mp4'\nfile '/tmp/it'\\''s.mp4'\n"
+        );
+    }
+}
@@ -0,0 +1,488 @@
+// Remote sources. Footage on an HTTP(S) server is added to the input folder as a link file
+// (`<name>.url`, the Internet shortcut format browsers save, or just the URL on one line) and
+// listed, annotated and exported like a local file. FFmpeg and OpenCV don't read the server
+// directly but a small HTTP server on localhost that answers their range requests from a cache
+// of fixed-size chunks, fetching the missing ones with curl. Seeking around a long recording only
+// downloads the parts that were looked at, and looking at them again downloads nothing. The
+// chunks live in the app's cache folder and are evicted with everything else in it.
+use crate::dataset::fnv1a;
+use crate::i18n::{tr, trf};
+use crate::{cache, logs};
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+pub const EXTENSION: &str = "url";
+/// Bytes fetched from the server at a time and cached as one file
+const CHUNK: u64 = 4 << 20;
+/// Seconds curl may take to connect; a chunk may take as long as it needs
+const CONNECT_TIMEOUT: &str = "15";
+
+/// Port of the local server, started with the first stream
+static PORT: OnceLock<Result<u16, String>> = OnceLock::new();
+/// URLs the local server streams, by the id in its paths
+static URLS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+/// Numbers partial chunk files, so two requests fetching the same chunk don't share one
+static PARTIAL: AtomicU64 = AtomicU64::new(0);
+
+pub fn is_link(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(EXTENSION))
+}
+
+/// The URL in the text of a link file: the `URL=` line of an Internet shortcut, or else the
+/// first line. None unless it is an HTTP(S) URL.
+fn parse_link(text: &str) -> Option<String> {
+    let url = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("URL="))
+        .or_else(|| text.lines().map(str::trim).find(|line| !line.is_empty()))?
+        .trim();
+    (url.starts_with("http://") || url.starts_with("https://")).then(|| url.to_string())
+}
+
+/// The URL the link file at `path` points to.
+pub fn read_link(path: &Path) -> Result<String, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+    parse_link(&text).ok_or_else(|| trf("{} holds no HTTP(S) URL", &[&path.display()]))
+}
+
+/// `text` with its `%XX` escapes decoded.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Name of the link file for `url`: the last part of its path without the extension, so the
+/// exported clips are named as they would be for a downloaded copy.
+fn link_stem(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let last = percent_decode(
+        path.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default(),
+    );
+    let stem = Path::new(&last)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "remote".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Writes a link to `url` into `dir` and returns its path. An existing file is never replaced.
+pub fn write_link(dir: &Path, url: &str) -> Result<PathBuf, String> {
+    let url = parse_link(url).ok_or_else(|| trf("Not an HTTP(S) URL: {}", &[&url.trim()]))?;
+    let path = dir.join(format!("{}.{}", link_stem(&url), EXTENSION));
+    if path.exists() {
+        return Err(trf("{} already exists", &[&path.display()]));
+    }
+    std::fs::write(&path, format!("[InternetShortcut]\r\nURL={}\r\n", url))
+        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))?;
+    Ok(path)
+}
+
+/// What FFmpeg and OpenCV should open for the media at `path`: the local stream of a link, the
+/// path itself for everything else. A link that can't be streamed is logged and returned as
+/// it is, which the caller then fails to open.
+pub fn input(path: &Path) -> PathBuf {
+    if !is_link(path) {
+        return path.to_path_buf();
+    }
+    match read_link(path).and_then(|url| stream(&url)) {
+        Ok(local) => PathBuf::from(local),
+        Err(e) => {
+            tracing::warn!(target: logs::DECODE, file = ?path, "{}", e);
+            path.to_path_buf()
+        }
+    }
+}
+
+/// The localhost URL streaming `url`, starting the local server first if needed. It keeps the
+/// extension, which some demuxers go by.
+fn stream(url: &str) -> Result<String, String> {
+    let port = PORT.get_or_init(start).clone()?;
+    let id = format!("{:016x}", fnv1a(url.as_bytes()));
+    URLS.lock().unwrap().insert(id.clone(), url.to_string());
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let ext = Path::new(path.rsplit('/').next().unwrap_or_default())
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    Ok(format!("http://127.0.0.1:{}/{}{}", port, id, ext))
+}
+
+/// Starts the local server on a free port, one thread per connection.
+fn start() -> Result<u16, String> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .map_err(|e| trf("Could not start the stream server: {}", &[&e]))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    std::thread::spawn(move || {
+        for connection in listener.incoming().filter_map(|c| c.ok()) {
+            std::thread::spawn(move || {
+                if let Err(e) = serve(connection) {
+                    tracing::warn!(target: logs::DECODE, "Stream request failed: {}", e);
+                }
+            });
+        }
+    });
+    tracing::info!(target: logs::DECODE, port, "Stream server started");
+    Ok(port)
+}
+
+/// What a request asks for.
+#[derive(Debug, PartialEq)]
+struct Request {
+    /// HEAD rather than GET: the headers without the body
+    head: bool,
+    id: String,
+    /// Value of the `Range` header
+    range: Option<String>,
+}
+
+/// The request in the head of an HTTP request, None for anything but GET and HEAD.
+fn parse_request(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let mut request = lines.next()?.split_whitespace();
+    let head = match request.next()? {
+        "GET" => false,
+        "HEAD" => true,
+        _ => return None,
+    };
+    let path = request.next()?.trim_start_matches('/');
+    let id = path.split(['.', '/', '?']).next()?.to_string();
+    let range = lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("range")
+            .then(|| value.trim().to_string())
+    });
+    Some(Request { head, id, range })
+}
+
+/// The bytes `start..end` a `Range` header value asks for out of `len`, None if it can't be
+/// satisfied. Only the single ranges players send are understood.
+fn byte_range(value: &str, len: u64) -> Option<(u64, u64)> {
+    let (first, last) = value.strip_prefix("bytes=")?.trim().split_once('-')?;
+    let (start, end) = match (first.trim(), last.trim()) {
+        ("", suffix) => (len.saturating_sub(suffix.parse().ok()?), len),
+        (first, "") => (first.parse().ok()?, len),
+        (first, last) => (
+            first.parse().ok()?,
+            last.parse::<u64>().ok()?.saturating_add(1),
+        ),
+    };
+    let end = end.min(len);
+    (start < end).then_some((start, end))
+}
+
+/// Answers one request and closes the connection.
+fn serve(connection: TcpStream) -> Result<(), String> {
+    let mut reader = BufReader::new(connection.try_clone().map_err(|e| e.to_string())?);
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        let read = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if read == 0 || line.trim().is_empty() {
+            break;
+        }
+        head += &line;
+    }
+    let mut out = connection;
+    let reply = |out: &mut TcpStream, status: &str, headers: &[String]| {
+        let mut text = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+        for header in headers {
+            text += header;
+            text += "\r\n";
+        }
+        text += "\r\n";
+        out.write_all(text.as_bytes()).map_err(|e| e.to_string())
+    };
+    let Some(request) = parse_request(&head) else {
+        return reply(&mut out, "400 Bad Request", &[]);
+    };
+    let Some(url) = URLS.lock().unwrap().get(&request.id).cloned() else {
+        return reply(&mut out, "404 Not Found", &[]);
+    };
+    let remote = match Remote::open(&request.id, &url) {
+        Ok(remote) => remote,
+        Err(e) => {
+            reply(&mut out, "502 Bad Gateway", &[])?;
+            return Err(e);
+        }
+    };
+    let len = remote.len;
+    let (status, start, end) = match &request.range {
+        None => ("200 OK", 0, len),
+        Some(value) => match byte_range(value, len) {
+            Some((start, end)) => ("206 Partial Content", start, end),
+            None => {
+                let headers = [format!("Content-Range: bytes */{}", len)];
+                return reply(&mut out, "416 Range Not Satisfiable", &headers);
+            }
+        },
+    };
+    let mut headers = vec![
+        "Accept-Ranges: bytes".to_string(),
+        "Content-Type: application/octet-stream".to_string(),
+        format!("Content-Length: {}", end - start),
+    ];
+    if request.range.is_some() {
+        headers.push(format!(
+            "Content-Range: bytes {}-{}/{}",
+            start,
+            end - 1,
+            len
+        ));
+    }
+    reply(&mut out, status, &headers)?;
+    if request.head {
+        return Ok(());
+    }
+    let mut pos = start;
+    while pos < end {
+        let chunk = remote.chunk(pos / CHUNK)?;
+        let offset = (pos % CHUNK) as usize;
+        let count = ((end - pos) as usize).min(chunk.len().saturating_sub(offset));
+        if count == 0 {
+            break;
+        }
+        // Players drop the connection when they seek elsewhere; that is no error
+        if out.write_all(&chunk[offset..offset + count]).is_err() {
+            return Ok(());
+        }
+        pos += count as u64;
+    }
+    Ok(())
+}
+
+/// The chunk cache of one remote file: its size, and a file per chunk fetched so far.
+struct Remote {
+    url: String,
+    dir: PathBuf,
+    len: u64,
+}
+
+impl Remote {
+    /// The cache of `url`, asking the server for its size the first time.
+    fn open(id: &str, url: &str) -> Result<Remote, String> {
+        let dir = cache::dir("remote").join(id);
+        let size = dir.join("size");
+        let cached = std::fs::read_to_string(&size)
+            .ok()
+            .and_then(|text| text.trim().parse().ok());
+        let len = match cached {
+            Some(len) => {
+                cache::touch(&size);
+                len
+            }
+            None => {
+                let len = content_length(url)?;
+                std::fs::create_dir_all(&dir)
+                    .map_err(|e| trf("Could not create {}: {}", &[&dir.display(), &e]))?;
+                if std::fs::write(&size, len.to_string()).is_ok() {
+                    cache::evict(Some(&size));
+                }
+                len
+            }
+        };
+        Ok(Remote {
+            url: url.to_string(),
+            dir,
+            len,
+        })
+    }
+
+    /// Chunk `index`, from the cache or fetched from the server.
+    fn chunk(&self, index: u64) -> Result<Vec<u8>, String> {
+        let path = self.dir.join(format!("{}.chunk", index));
+        if let Ok(data) = std::fs::read(&path) {
+            cache::touch(&path);
+            return Ok(data);
+        }
+        let start = index * CHUNK;
+        let end = (start + CHUNK).min(self.len);
+        let data = fetch(&self.url, start, end)?;
+        if data.len() as u64 != end - start {
+            return Err(trf(
+                "{} answered with {} bytes instead of {}; the server may not support range requests",
+                &[&self.url, &data.len(), &(end - start)],
+            ));
+        }
+        // Written under a temporary name so a chunk is never read half written
+        let n = PARTIAL.fetch_add(1, Ordering::Relaxed);
+        let partial = self.dir.join(format!("{}.{}.partial", index, n));
+        if std::fs::create_dir_all(&self.dir).is_ok()
+            && std::fs::write(&partial, &data).is_ok()
+            && std::fs::rename(&partial, &path).is_ok()
+        {
+            cache::evict(Some(&path));
+        } else {
+            let _ = std::fs::remove_file(&partial);
+        }
+        tracing::debug!(target: logs::DECODE, url = self.url, index, "Fetched chunk");
+        Ok(data)
+    }
+}
+
+fn curl() -> Command {
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--connect-timeout", CONNECT_TIMEOUT]);
+    cmd
+}
+
+/// Size of the file at `url` from the `Content-Length` of a HEAD request, the last one when
+/// it was redirected.
+fn content_length(url: &str) -> Result<u64, String> {
+    let output = curl()
+        .arg("--head")
+        .arg(url)
+        .output()
+        .map_err(|e| trf("Could not run curl: {}", &[&e]))?;
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(trf("Could not reach {}: {}", &[&url, &reason]));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())?
+        })
+        .ok_or_else(|| trf("{} did not say how large it is", &[&url]))
+}
+
+/// Bytes `start..end` of the file at `url`.
+fn fetch(url: &str, start: u64, end: u64) -> Result<Vec<u8>, String> {
+    let output = curl()
+        .arg("--range")
+        .arg(format!("{}-{}", start, end - 1))
+        .arg(url)
+        .output()
+        .map_err(|e| trf("Could not run curl: {}", &[&e]))?;
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(trf("Could not reach {}: {}", &[&url, &reason]));
+    }
+    Ok(output.stdout)
+}
+
+/// The window for adding a remote source, with the error of the last attempt. Returns true when
+/// the URL is to be added.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    url: &mut String,
+    error: Option<&str>,
+    can_add: bool,
+) -> bool {
+    let mut requested = false;
+    egui::Window::new(tr("🌐 Add Remote Source"))
+        .id(egui::Id::new("add_remote_source"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("URL:"));
+                ui.add(
+                    egui::TextEdit::singleline(url)
+                        .hint_text("https://media.example/footage/day1.mp4")
+                        .desired_width(360.0),
+                );
+            });
+            let valid = parse_link(url).is_some();
+            if ui
+                .add_enabled(can_add && valid, egui::Button::new(tr("Add")))
+                .clicked()
+            {
+                requested = true;
+            }
+            if let Some(error) = error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(4.0);
+            ui.weak(tr(
+                "Adds a link file to the input folder. The video is streamed for the preview and the export, and the parts that were watched are kept in the cache.",
+            ));
+        });
+    requested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_requests_and_ranges_parse() {
+        let shortcut = "[InternetShortcut]\r\nURL=https://media.local/a/day%201.mp4?t=1\r\n";
+        let url = parse_link(shortcut).unwrap();
+        assert_eq!(url, "https://media.local/a/day%201.mp4?t=1");
+        assert_eq!(link_stem(&url), "day_1");
+        assert_eq!(
+            parse_link("\n http://h/x.mkv \n").as_deref(),
+            Some("http://h/x.mkv")
+        );
+        assert_eq!(parse_link("[InternetShortcut]\nURL=file:///x.mp4"), None);
+        assert_eq!(link_stem("https://media.local/clips/"), "clips");
+
+        let request = parse_request("GET /00ff.mp4 HTTP/1.1\r\nHost: x\r\nrange: bytes=10-\r\n");
+        assert_eq!(
+            request,
+            Some(Request {
+                head: false,
+                id: "00ff".to_string(),
+                range: Some("bytes=10-".to_string()),
+            })
+        );
+        assert_eq!(parse_request("POST /00ff HTTP/1.1\r\n"), None);
+
+        assert_eq!(byte_range("bytes=10-", 100), Some((10, 100)));
+        assert_eq!(byte_range("bytes=10-19", 100), Some((10, 20)));
+        assert_eq!(byte_range("bytes=90-200", 100), Some((90, 100)));
+        assert_eq!(byte_range("bytes=-30", 100), Some((70, 100)));
+        assert_eq!(byte_range("bytes=100-", 100), None);
+        assert_eq!(byte_range("items=0-1", 100), None);
+    }
+}
@@ -0,0 +1,406 @@
+// Batch rename and re-caption: brings an exported output folder in line with the current
+// output names and caption template without encoding anything again. Clips are renamed (into
+// their split subfolder if that changed), captions rewritten and the manifest updated. Only
+// outputs whose range still has the times and crop they were exported with are touched; the
+// others need an export.
+use crate::audit::Project;
+use crate::dataset::{
+    caption_file, caption_text, manifest_path, output_file, relative, without_increment,
+};
+use crate::i18n::{tr, trf};
+use crate::manifest::{Entry, Manifest};
+use crate::{VideoRange, checksum};
+use eframe::egui;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+pub struct Change {
+    /// Index of the entry in the manifest
+    entry: usize,
+    /// Range the output belongs to now
+    range: usize,
+    /// Output file before and after, relative to the output folder
+    pub from: String,
+    pub to: String,
+    /// Caption text under the current template; None removes the caption
+    caption: Option<String>,
+    /// Whether the caption text or file changes
+    recaption: bool,
+}
+
+pub struct Plan {
+    pub changes: Vec<Change>,
+    /// Outputs that can't be brought up to date without an export, with the reason
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+    /// Result of the last apply, shown after the plan is refreshed
+    pub applied: Option<String>,
+}
+
+/// The range `entry` was exported from: its own index if that range is unchanged, else
+/// another range with the same times and crop (ranges before it were deleted or reordered).
+fn matching_range(entry: &Entry, ranges: &[VideoRange]) -> Option<usize> {
+    let same = |r: &VideoRange| {
+        r.start_time == entry.start_time
+            && r.end_time == entry.end_time
+            && r.crop_rect_norm == entry.crop
+    };
+    if ranges.get(entry.range).is_some_and(same) {
+        return Some(entry.range);
+    }
+    ranges.iter().position(same)
+}
+
+fn extension(file: &str) -> &str {
+    file.rsplit_once('.').map_or("", |(_, ext)| ext)
+}
+
+pub fn plan(out_dir: &Path, project: &Project) -> Plan {
+    let mut plan = Plan {
+        changes: Vec::new(),
+        skipped: Vec::new(),
+        errors: Vec::new(),
+        applied: None,
+    };
+    let manifest = match Manifest::load(out_dir) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            plan.errors.push(e);
+            return plan;
+        }
+    };
+    // Nothing is planned for a manifest listing files outside the output folder
+    if let Err(e) = manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.files())
+        .try_for_each(|file| manifest_path(out_dir, file).map(drop))
+    {
+        plan.errors.push(e);
+        return plan;
+    }
+    let mut targets = HashSet::new();
+    let mut candidates = Vec::new();
+    for (idx, entry) in manifest.entries.iter().enumerate() {
+        let Some((media, ranges)) = project
+            .annotated
+            .iter()
+            .find(|(media, _)| media.file_name().is_some_and(|n| *n == *entry.source))
+        else {
+            continue;
+        };
+        let Some(i) = matching_range(entry, ranges) else {
+            plan.skipped.push(trf(
+                "{}: the range changed since the export",
+                &[&entry.file],
+            ));
+            continue;
+        };
+        let range = &ranges[i];
+        let mut target = output_file(out_dir, media, range, i, ranges.len(), project.profile);
+        let mut to = relative(out_dir, &target);
+        // A numbered output keeps its number; its name without one was taken at the export
+        if without_increment(&entry.file).as_deref() == Some(to.as_str()) {
+            target = out_dir.join(&entry.file);
+            to = entry.file.clone();
+        }
+        if let Err(e) = manifest_path(out_dir, &to) {
+            plan.errors.push(e);
+            continue;
+        }
+        if extension(&to) != extension(&entry.file) {
+            plan.skipped
+                .push(trf("{}: the container changed", &[&entry.file]));
+            continue;
+        }
+        if !out_dir.join(&entry.file).exists() {
+            plan.skipped
+                .push(trf("{}: the file is missing", &[&entry.file]));
+            continue;
+        }
+        let caption = caption_text(project.profile, media, i, range);
+        let caption_path = caption
+            .as_ref()
+            .map(|_| relative(out_dir, &caption_file(&target)));
+        let written = entry
+            .caption
+            .as_ref()
+            .and_then(|c| fs::read_to_string(out_dir.join(c)).ok());
+        let recaption = caption_path != entry.caption || written != caption;
+        if to == entry.file && !recaption {
+            continue;
+        }
+        if !targets.insert(to.clone()) {
+            plan.skipped
+                .push(trf("{}: {} is taken", &[&entry.file, &to]));
+            continue;
+        }
+        candidates.push(Change {
+            entry: idx,
+            range: i,
+            from: entry.file.clone(),
+            to,
+            caption,
+            recaption,
+        });
+    }
+
+    // A target may be taken by an output that moves away in the same run
+    let moving: HashSet<String> = candidates
+        .iter()
+        .filter(|c| c.from != c.to)
+        .map(|c| c.from.clone())
+        .collect();
+    for change in candidates {
+        if change.from != change.to
+            && !moving.contains(&change.to)
+            && out_dir.join(&change.to).exists()
+        {
+            plan.skipped
+                .push(trf("{}: {} already exists", &[&change.from, &change.to]));
+        } else {
+            plan.changes.push(change);
+        }
+    }
+    plan
+}
+
+fn rename(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| trf("Could not write {}: {}", &[&dir.display(), &e]))?;
+    }
+    fs::rename(from, to).map_err(|e| trf("Could not rename {}: {}", &[&from.display(), &e]))
+}
+
+/// Applies `plan` and saves the manifest. Clips (with their audio, preview and sprite sheet)
+/// are first moved aside and then to their new names, so outputs can take over each other's
+/// names; old captions are removed and the new ones written from the template. Returns the number of changed outputs.
+pub fn apply(out_dir: &Path, plan: &Plan) -> Result<usize, String> {
+    let mut manifest = Manifest::load(out_dir)?;
+    // The manifest is read again, so it is checked again before anything is moved
+    let files = manifest.entries.iter().flat_map(|e| e.files());
+    for file in files.chain(plan.changes.iter().map(|c| &c.to)) {
+        manifest_path(out_dir, file)?;
+    }
+    let aside = |file: &str| out_dir.join(format!("{}.renaming", file));
+
+    let mut moved = Vec::new();
+    for change in &plan.changes {
+        let entry = &manifest.entries[change.entry];
+        if let Some(caption) = &entry.caption
+            && change.recaption
+        {
+            let _ = fs::remove_file(out_dir.join(caption));
+        }
+        if change.from == change.to {
+            continue;
+        }
+        let companions = entry
+            .audio
+            .iter()
+            .chain(&entry.preview)
+            .chain(&entry.sprite);
+        for file in std::iter::once(&entry.file).chain(companions) {
+            if let Err(e) = rename(&out_dir.join(file), &aside(file)) {
+                // Put back what was moved so far; the manifest still describes it
+                for file in moved {
+                    let _ = rename(&aside(file), &out_dir.join(file));
+                }
+                return Err(e);
+            }
+            moved.push(file);
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut changed = 0;
+    // Files moved or rewritten, whose checksums are hashed again
+    let mut fresh = Vec::new();
+    for change in &plan.changes {
+        let target = out_dir.join(&change.to);
+        if change.from != change.to
+            && let Err(e) = rename(&aside(&change.from), &target)
+        {
+            errors.push(e);
+            continue;
+        }
+        let entry = &mut manifest.entries[change.entry];
+        if change.from != change.to
+            && let Some(audio) = entry.audio.take()
+        {
+            let ext = audio.rsplit_once('.').map_or("", |(_, ext)| ext);
+            let moved = target.with_extension(ext);
+            match rename(&aside(&audio), &moved) {
+                Ok(()) => entry.audio = Some(relative(out_dir, &moved)),
+                Err(e) => errors.push(e),
+            }
+        }
+        // `clip.preview.gif` and `clip.sprite4x4.jpg` keep what follows the clip's name
+        let companions = [
+            (&mut entry.preview, ".preview."),
+            (&mut entry.sprite, ".sprite"),
+        ];
+        for (companion, marker) in companions.into_iter().filter(|_| change.from != change.to) {
+            let Some(file) = companion.take() else {
+                continue;
+            };
+            let ext = file.rsplit_once(marker).map_or("", |(_, ext)| ext);
+            let moved = target.with_extension(format!("{}{}", &marker[1..], ext));
+            match rename(&aside(&file), &moved) {
+                Ok(()) => *companion = Some(relative(out_dir, &moved)),
+                Err(e) => errors.push(e),
+            }
+        }
+        if change.recaption {
+            entry.caption = None;
+            if let Some(text) = &change.caption {
+                let caption = caption_file(&target);
+                match fs::write(&caption, text) {
+                    Ok(()) => entry.caption = Some(relative(out_dir, &caption)),
+                    Err(e) => errors.push(trf("Could not write {}: {}", &[&caption.display(), &e])),
+                }
+            }
+        }
+        if change.from != change.to {
+            // The export hash refers to the name the clip was encoded under
+            let encoded_as = entry.exported_as.take().unwrap_or(change.from.clone());
+            entry.exported_as = Some(encoded_as).filter(|name| *name != change.to);
+            entry.file = change.to.clone();
+        }
+        entry.range = change.range;
+        fresh.extend(entry.files().cloned());
+        changed += 1;
+    }
+    manifest
+        .entries
+        .sort_by(|a, b| a.source.cmp(&b.source).then(a.range.cmp(&b.range)));
+    manifest.save(out_dir)?;
+    checksum::record(out_dir, &manifest, &fresh);
+    if errors.is_empty() {
+        Ok(changed)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Draws the plan; returns true when the user asks to apply it.
+pub fn show(ctx: &egui::Context, open: &mut bool, plan: &Plan, can_apply: bool) -> bool {
+    let mut apply = false;
+    egui::Window::new(tr("✏ Rename / Re-caption Outputs"))
+        .id(egui::Id::new("rename_outputs"))
+        .open(open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            if let Some(applied) = &plan.applied {
+                ui.label(applied);
+            }
+            if plan.changes.is_empty() {
+                ui.label(tr("✅ Output names and captions are up to date."));
+            } else {
+                ui.label(trf("{} output(s) to change:", &[&plan.changes.len()]));
+                egui::ScrollArea::vertical()
+                    .id_salt("rename_changes")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for change in &plan.changes {
+                            if change.from != change.to {
+                                ui.label(format!("{} → {}", change.from, change.to));
+                            }
+                            if change.recaption {
+                                ui.label(trf("Caption of {}", &[&change.to]));
+                            }
+                        }
+                    });
+            }
+            if !plan.skipped.is_empty() {
+                ui.add_space(4.0);
+                ui.label(trf("{} output(s) need an export:", &[&plan.skipped.len()]));
+                egui::ScrollArea::vertical()
+                    .id_salt("rename_skipped")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for skipped in &plan.skipped {
+                            ui.weak(skipped);
+                        }
+                    });
+            }
+            for error in &plan.errors {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(4.0);
+            let button = ui.add_enabled(
+                can_apply && !plan.changes.is_empty(),
+                egui::Button::new(trf("✏ Apply ({})", &[&plan.changes.len()])),
+            );
+            apply = button
+                .on_hover_text(tr(
+                    "Rename the outputs and rewrite their captions. Nothing is encoded again.",
+                ))
+                .clicked();
+        });
+    apply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_range_an_output_was_exported_from() {
+        let range = |start, end| VideoRange {
+            start_time: start,
+            end_time: end,
+            ..Default::default()
+        };
+        let entry = |range, start, end| Entry {
+            file: String::new(),
+            caption: None,
+            source: "clip.mp4".to_string(),
+            range,
+            start_time: start,
+            end_time: end,
+            crop: None,
+            split: None,
+            hash: String::new(),
+            tags: String::new(),
+            exported_as: None,
+            audio: None,
+            preview: None,
+            sprite: None,
+            stats: None,
+        };
+        let ranges = [range(0.0, 2.0), range(5.0, 8.0), range(5.0, 8.0)];
+        assert_eq!(matching_range(&entry(2, 5.0, 8.0), &ranges), Some(2));
+        // The first range was deleted, so the output moved up
+        assert_eq!(matching_range(&entry(1, 0.0, 2.0), &ranges), Some(0));
+        assert_eq!(matching_range(&entry(0, 0.0, 3.0), &ranges), None);
+    }
+
+    #[test]
+    fn manifests_listing_files_outside_the_output_folder_are_refused() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_rename_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"version": 1, "entries": [
+                {"file": "clip_range0.mp4", "caption": "../notes.txt", "source": "clip.mp4",
+                 "range": 0, "start_time": 0, "end_time": 1}
+            ]}"#,
+        )
+        .unwrap();
+        manifest.save(&dir).unwrap();
+        let profile = crate::export::Profile::default();
+        let project = Project {
+            media: &[],
+            annotated: &[],
+            profile: &profile,
+        };
+        let plan = plan(&dir, &project);
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.errors.len(), 1);
+        assert!(apply(&dir, &plan).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
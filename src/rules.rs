@@ -0,0 +1,174 @@
+// Project rules (`viddatatraincrop.rules.json` in the input folder): trigger words every
+// caption has to carry, tags that must never reach the dataset and the order tags are written
+// in. They travel with the folder and are applied at export, so captions stay consistent no
+// matter who annotated a file.
+use crate::VideoRange;
+use crate::i18n::{tr, trf};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "viddatatraincrop.rules.json";
+
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Rules {
+    /// Put at the front of every caption that doesn't contain them yet
+    #[serde(default)]
+    pub trigger_words: Vec<String>,
+    /// Dropped from the tags at export (case-insensitive)
+    #[serde(default)]
+    pub banned_tags: Vec<String>,
+    /// Tags written first, in this order; the others follow as annotated
+    #[serde(default)]
+    pub tag_order: Vec<String>,
+}
+
+fn path_for(dir: &Path) -> PathBuf {
+    dir.join(FILE_NAME)
+}
+
+/// The rules of the input folder `dir`, or none if it has no rules file.
+pub fn load(dir: &Path) -> Result<Rules, String> {
+    let path = path_for(dir);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Ok(Rules::default());
+    };
+    serde_json::from_str(&text).map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+}
+
+pub fn save(dir: &Path, rules: &Rules) -> Result<(), String> {
+    let path = path_for(dir);
+    let json = serde_json::to_string_pretty(rules).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+impl Rules {
+    pub fn is_banned(&self, tag: &str) -> bool {
+        self.banned_tags.iter().any(|b| same(b, tag))
+    }
+
+    /// `range` as it is exported: banned tags removed, the rest ordered.
+    pub fn apply(&self, range: &VideoRange) -> VideoRange {
+        let mut range = range.clone();
+        range.tags.retain(|t| !self.is_banned(t));
+        let rank = |tag: &String| {
+            let pos = self.tag_order.iter().position(|o| same(o, tag));
+            pos.unwrap_or(self.tag_order.len())
+        };
+        // Stable, so unlisted tags keep their annotated order
+        range.tags.sort_by_key(rank);
+        range
+    }
+
+    /// `caption` with the missing trigger words put in front, comma separated.
+    pub fn caption(&self, caption: Option<String>) -> Option<String> {
+        let text = caption.unwrap_or_default();
+        let lower = text.to_lowercase();
+        let missing: Vec<&str> = self
+            .trigger_words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty() && !lower.contains(&w.to_lowercase()))
+            .collect();
+        let text = match (missing.is_empty(), text.trim().is_empty()) {
+            (true, _) => text,
+            (false, true) => missing.join(", "),
+            (false, false) => format!("{}, {}", missing.join(", "), text),
+        };
+        (!text.trim().is_empty()).then_some(text)
+    }
+}
+
+/// Comma-separated list editing for the rules window.
+fn list_edit(ui: &mut egui::Ui, label: &'static str, hint: &'static str, list: &mut Vec<String>) {
+    ui.label(tr(label));
+    let mut text = list.join(", ");
+    let edit = ui.add(egui::TextEdit::singleline(&mut text).desired_width(320.0));
+    if edit.on_hover_text(tr(hint)).changed() {
+        *list = text
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+    }
+    ui.end_row();
+}
+
+/// Draws the rules editor; returns true when the user asks to save.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    rules: &mut Rules,
+    status: Option<&str>,
+    can_save: bool,
+) -> bool {
+    let mut save = false;
+    egui::Window::new(tr("📜 Project Rules"))
+        .id(egui::Id::new("project_rules"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("rules_grid").show(ui, |ui| {
+                list_edit(
+                    ui,
+                    "Trigger words:",
+                    "Put at the front of every caption that doesn't contain them",
+                    &mut rules.trigger_words,
+                );
+                list_edit(
+                    ui,
+                    "Banned tags:",
+                    "Never exported, whoever added them",
+                    &mut rules.banned_tags,
+                );
+                list_edit(
+                    ui,
+                    "Tag order:",
+                    "Tags written first, in this order",
+                    &mut rules.tag_order,
+                );
+            });
+            ui.add_space(4.0);
+            ui.weak(trf("Stored in {} in the input folder.", &[&FILE_NAME]));
+            save = ui
+                .add_enabled(can_save, egui::Button::new(tr("💾 Save Rules")))
+                .clicked();
+            if let Some(status) = status {
+                ui.label(status);
+            }
+        });
+    save
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enforces_tags_and_trigger_words() {
+        let rules = Rules {
+            trigger_words: vec!["ohwx".to_string(), "style".to_string()],
+            banned_tags: vec!["Watermark".to_string()],
+            tag_order: vec!["person".to_string(), "outdoor".to_string()],
+        };
+        let range = VideoRange {
+            tags: ["dog", "outdoor", "watermark", "person"]
+                .map(String::from)
+                .to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(rules.apply(&range).tags, ["person", "outdoor", "dog"]);
+        assert_eq!(
+            rules
+                .caption(Some("A new Style of walk".to_string()))
+                .as_deref(),
+            Some("ohwx, A new Style of walk")
+        );
+        assert_eq!(rules.caption(None).as_deref(), Some("ohwx, style"));
+        assert_eq!(Rules::default().caption(None), None);
+    }
+}
@@ -0,0 +1,135 @@
+// Spectral residual saliency (Hou & Zhang, 2007), the same approach as OpenCV's
+// StaticSaliencySpectralResidual, built from core/imgproc so no contrib module is needed.
+use crate::SerializableRect;
+use opencv::{core, imgproc, prelude::*};
+
+/// The frame is shrunk to a MAP_SIZE x MAP_SIZE square before the FFT, as in the paper.
+const MAP_SIZE: i32 = 64;
+/// Share of the best full-size window's saliency the suggested crop has to keep.
+const COVERAGE: f32 = 0.85;
+
+fn saliency_map(frame: &core::Mat) -> opencv::Result<Vec<f32>> {
+    let mut gray = core::Mat::default();
+    imgproc::cvt_color_def(frame, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    let mut small = core::Mat::default();
+    imgproc::resize(
+        &gray,
+        &mut small,
+        core::Size::new(MAP_SIZE, MAP_SIZE),
+        0.0,
+        0.0,
+        imgproc::INTER_AREA,
+    )?;
+    let mut real = core::Mat::default();
+    small.convert_to(&mut real, core::CV_32F, 1.0 / 255.0, 0.0)?;
+
+    let mut spectrum = core::Mat::default();
+    core::dft(&real, &mut spectrum, core::DFT_COMPLEX_OUTPUT, 0)?;
+    let mut planes = core::Vector::<core::Mat>::new();
+    core::split(&spectrum, &mut planes)?;
+    let mut amplitude = core::Mat::default();
+    let mut phase = core::Mat::default();
+    core::cart_to_polar(&planes.get(0)?, &planes.get(1)?, &mut amplitude, &mut phase, false)?;
+
+    // The residual is the log amplitude minus its local average; keep the phase as is.
+    let mut shifted = core::Mat::default();
+    amplitude.convert_to(&mut shifted, core::CV_32F, 1.0, 1e-6)?;
+    let mut log_amp = core::Mat::default();
+    core::log(&shifted, &mut log_amp)?;
+    let mut avg = core::Mat::default();
+    imgproc::blur_def(&log_amp, &mut avg, core::Size::new(3, 3))?;
+    let mut residual = core::Mat::default();
+    core::subtract_def(&log_amp, &avg, &mut residual)?;
+    let mut residual_amp = core::Mat::default();
+    core::exp(&residual, &mut residual_amp)?;
+
+    let mut re = core::Mat::default();
+    let mut im = core::Mat::default();
+    core::polar_to_cart(&residual_amp, &phase, &mut re, &mut im, false)?;
+    let mut merged = core::Mat::default();
+    core::merge(&core::Vector::<core::Mat>::from_iter([re, im]), &mut merged)?;
+    let mut back = core::Mat::default();
+    core::idft(&merged, &mut back, core::DFT_SCALE | core::DFT_COMPLEX_OUTPUT, 0)?;
+
+    let mut planes = core::Vector::<core::Mat>::new();
+    core::split(&back, &mut planes)?;
+    let mut magnitude = core::Mat::default();
+    core::magnitude(&planes.get(0)?, &planes.get(1)?, &mut magnitude)?;
+    let mut energy = core::Mat::default();
+    core::multiply_def(&magnitude, &magnitude, &mut energy)?;
+    let mut smooth = core::Mat::default();
+    imgproc::gaussian_blur_def(&energy, &mut smooth, core::Size::new(9, 9), 2.5)?;
+
+    Ok(smooth.data_typed::<f32>()?.to_vec())
+}
+
+/// Proposes a crop with the given pixel aspect ratio (width / height) around the most
+/// salient part of `frame`. The result is normalized to 0.0-1.0 like the drawn crops.
+pub fn suggest_crop(frame: &core::Mat, aspect: f32) -> opencv::Result<SerializableRect> {
+    let size = frame.size()?;
+    let map = saliency_map(frame)?;
+    let n = MAP_SIZE as usize;
+
+    // Summed-area table so every window sum is four lookups.
+    let mut sat = vec![0.0f32; (n + 1) * (n + 1)];
+    for y in 0..n {
+        for x in 0..n {
+            sat[(y + 1) * (n + 1) + x + 1] = map[y * n + x] + sat[y * (n + 1) + x + 1]
+                + sat[(y + 1) * (n + 1) + x]
+                - sat[y * (n + 1) + x];
+        }
+    }
+    let window_sum = |x: usize, y: usize, w: usize, h: usize| {
+        sat[(y + h) * (n + 1) + x + w] - sat[y * (n + 1) + x + w] - sat[(y + h) * (n + 1) + x]
+            + sat[y * (n + 1) + x]
+    };
+
+    // Largest window of the requested aspect that fits, in normalized units.
+    let frame_aspect = size.width as f32 / size.height as f32;
+    let (full_w, full_h) = if frame_aspect > aspect {
+        (aspect / frame_aspect, 1.0)
+    } else {
+        (1.0, frame_aspect / aspect)
+    };
+
+    // Best (sum, center) for a window scaled by `scale`.
+    let best_at = |scale: f32| {
+        let w = ((full_w * scale * n as f32).round() as usize).clamp(1, n);
+        let h = ((full_h * scale * n as f32).round() as usize).clamp(1, n);
+        let mut best = (f32::MIN, (0.5, 0.5));
+        for y in 0..=(n - h) {
+            for x in 0..=(n - w) {
+                let sum = window_sum(x, y, w, h);
+                if sum > best.0 {
+                    let cx = (x as f32 + w as f32 / 2.0) / n as f32;
+                    let cy = (y as f32 + h as f32 / 2.0) / n as f32;
+                    best = (sum, (cx, cy));
+                }
+            }
+        }
+        best
+    };
+
+    // Shrink the window as long as it still holds most of what the full-size one does.
+    let (full_sum, mut center) = best_at(1.0);
+    let mut scale = 1.0;
+    for step in 1..=10 {
+        let candidate = 1.0 - step as f32 * 0.05;
+        let (sum, c) = best_at(candidate);
+        if sum < full_sum * COVERAGE {
+            break;
+        }
+        scale = candidate;
+        center = c;
+    }
+
+    let (w, h) = (full_w * scale, full_h * scale);
+    let min_x = (center.0 - w / 2.0).clamp(0.0, 1.0 - w);
+    let min_y = (center.1 - h / 2.0).clamp(0.0, 1.0 - h);
+    Ok(SerializableRect {
+        min_x,
+        min_y,
+        max_x: min_x + w,
+        max_y: min_y + h,
+    })
+}
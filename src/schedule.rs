@@ -0,0 +1,99 @@
+// Export scheduling: a batch export can wait for a start time (e.g. 22:00) and be paused and
+// resumed, so encoding doesn't compete with annotation work during the day. The export thread
+// checks in before each clip; a clip that is already encoding finishes first, unless the export
+// is cancelled, which also stops the running FFmpeg (see `Progress::kill`).
+use chrono::{Local, NaiveDateTime, NaiveTime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+pub struct Schedule {
+    paused: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    /// Local time the export waits for, if scheduled
+    start_at: Arc<Mutex<Option<NaiveDateTime>>>,
+}
+
+/// The next time the clock shows `at`: later today, or tomorrow if that has passed.
+pub fn next_occurrence(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + chrono::Duration::days(1)
+    }
+}
+
+impl Schedule {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Stops the export before its next clip; `set_cancelled(false)` arms it for the next one.
+    pub fn set_cancelled(&self, cancelled: bool) {
+        self.cancelled.store(cancelled, Ordering::SeqCst);
+    }
+
+    pub fn start_at(&self) -> Option<NaiveDateTime> {
+        *self.start_at.lock().unwrap()
+    }
+
+    pub fn set_start_at(&self, at: Option<NaiveDateTime>) {
+        *self.start_at.lock().unwrap() = at;
+    }
+
+    /// Whether the export is held back by the start time or a pause right now.
+    pub fn is_holding(&self) -> bool {
+        self.is_paused()
+            || self
+                .start_at()
+                .is_some_and(|at| Local::now().naive_local() < at)
+    }
+
+    /// Blocks the export thread while it is held back. Clearing the start time starts the
+    /// export right away, cancelling it returns right away too.
+    pub fn wait(&self) {
+        while self.is_holding() && !self.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_for_today_or_tomorrow() {
+        let at = |d, h, m| {
+            chrono::NaiveDate::from_ymd_opt(2024, 3, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        let ten_pm = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+        assert_eq!(next_occurrence(at(5, 9, 30), ten_pm), at(5, 22, 0));
+        assert_eq!(next_occurrence(at(5, 22, 0), ten_pm), at(6, 22, 0));
+        assert_eq!(next_occurrence(at(5, 23, 15), ten_pm), at(6, 22, 0));
+    }
+
+    #[test]
+    fn cancelling_releases_a_held_export() {
+        let schedule = Schedule::default();
+        schedule.set_paused(true);
+        let waiting = schedule.clone();
+        let thread = std::thread::spawn(move || waiting.wait());
+        schedule.set_cancelled(true);
+        thread.join().unwrap();
+        assert!(schedule.is_holding() && schedule.is_cancelled());
+    }
+}
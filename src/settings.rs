@@ -0,0 +1,115 @@
+// Settings kept between sessions. Annotations live in sidecars and projects, but how clips are
+// encoded and how the window is laid out belong to the user rather than to a folder, so the
+// container, the encoder settings, the number of parallel exports, the upload destination
+// (without its secret key) and the layout are written to a file in the app's data folder
+// whenever they change and read back at startup.
+use crate::i18n::trf;
+use crate::{cache, export, pool, upload};
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "settings.json";
+
+/// What the window shows. Annotators only need ranges, crops and notes; the export, encoder and
+/// dataset controls are for whoever builds the dataset, and contractors get lost in them.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    Annotator,
+    #[default]
+    Engineer,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 2] = [Layout::Annotator, Layout::Engineer];
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Layout::Annotator => "Annotator",
+            Layout::Engineer => "Engineer",
+        }
+    }
+}
+
+/// Settings missing from the file (written by an older version) keep their defaults.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub container: export::Container,
+    pub encoder: export::Encoder,
+    /// Files exported at once, see `pool`
+    pub parallel_exports: usize,
+    pub upload: upload::Destination,
+    pub layout: Layout,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            container: export::Container::default(),
+            encoder: export::Encoder::default(),
+            parallel_exports: pool::DEFAULT_LIMIT,
+            upload: upload::Destination::default(),
+            layout: Layout::default(),
+        }
+    }
+}
+
+/// The settings file in the app's data folder (next to the cache, so it follows portable mode).
+pub fn path() -> PathBuf {
+    cache::root().join(FILE_NAME)
+}
+
+/// The saved settings; defaults if there are none yet.
+pub fn load(path: &Path) -> Result<Settings, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e])),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(trf("Could not read {}: {}", &[&path.display(), &e])),
+    }
+}
+
+pub fn save(path: &Path, settings: &Settings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, json))
+        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_and_default_when_missing() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_settings_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join(FILE_NAME);
+        assert_eq!(load(&path).unwrap(), Settings::default());
+        let settings = Settings {
+            container: export::Container::Mkv,
+            encoder: export::Encoder {
+                codec: export::Codec::H265,
+                quality: export::Quality::Crf(22),
+                ..Default::default()
+            },
+            parallel_exports: 4,
+            upload: upload::Destination {
+                endpoint: "http://minio.local:9000".to_string(),
+                bucket: "clips".to_string(),
+                after_export: true,
+                ..Default::default()
+            },
+            layout: Layout::Annotator,
+        };
+        save(&path, &settings).unwrap();
+        assert_eq!(load(&path).unwrap(), settings);
+        std::fs::write(&path, "{\"container\": \"webm\"}").unwrap();
+        assert_eq!(load(&path).unwrap().encoder, export::Encoder::default());
+        assert_eq!(load(&path).unwrap().parallel_exports, pool::DEFAULT_LIMIT);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load(&path).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+// Per-media annotation files (`video.mp4.vdtc.json`) holding the ranges, crops and notes of one
+// file. They sit next to the media so a folder can be handed to an annotator and back as a set.
+use crate::i18n::trf;
+use crate::{VideoRange, migrate};
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the format changes incompatibly, with a step added to `STEPS`.
+pub const VERSION: u32 = 1;
+/// Upgrades from older versions, see `migrate::parse`
+const STEPS: &[migrate::Step] = &[];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Sidecar {
+    version: u32,
+    ranges: Vec<VideoRange>,
+}
+
+pub fn path_for(media: &Path) -> PathBuf {
+    media.with_added_extension("vdtc.json")
+}
+
+/// Ranges in the sidecar JSON `text`, upgraded from older versions.
+fn parse(text: &str) -> Result<Vec<VideoRange>, String> {
+    migrate::parse::<Sidecar>(text, VERSION, STEPS).map(|sidecar| sidecar.ranges)
+}
+
+/// The sidecar JSON holding `ranges`.
+pub fn to_json(ranges: &[VideoRange]) -> Result<String, String> {
+    let sidecar = Sidecar {
+        version: VERSION,
+        ranges: ranges.to_vec(),
+    };
+    serde_json::to_string_pretty(&sidecar).map_err(|e| e.to_string())
+}
+
+/// Ranges stored for `media`, or None when it has no sidecar yet.
+pub fn load(media: &Path) -> Option<Result<Vec<VideoRange>, String>> {
+    let path = path_for(media);
+    let text = std::fs::read_to_string(&path).ok()?;
+    Some(parse(&text).map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e])))
+}
+
+pub fn save(media: &Path, ranges: &[VideoRange]) -> Result<(), String> {
+    let path = path_for(media);
+    let json = to_json(ranges)?;
+    std::fs::write(&path, json).map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One sidecar per released version; the last one is the current format.
+    const GOLDEN: &[&str] = &[include_str!("../tests/golden/sidecar_v1.json")];
+
+    #[test]
+    fn golden_sidecars_load_and_the_current_one_round_trips() {
+        assert_eq!(GOLDEN.len(), VERSION as usize);
+        for text in GOLDEN {
+            let ranges = parse(text).unwrap();
+            assert_eq!(ranges.len(), 2);
+            assert_eq!(ranges[1].tags, ["cat", "indoor"]);
+        }
+        let current = GOLDEN[GOLDEN.len() - 1];
+        assert_eq!(
+            to_json(&parse(current).unwrap()).unwrap(),
+            current.trim_end()
+        );
+        assert!(parse(r#"{"version": 99, "ranges": []}"#).is_err());
+    }
+}
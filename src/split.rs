@@ -0,0 +1,116 @@
+// Dataset splits. Each range can be assigned to train, val or test; exports then go into a
+// subfolder per split and the manifest records it.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+    Train,
+    Val,
+    Test,
+}
+
+impl Split {
+    pub const ALL: [Split; 3] = [Split::Train, Split::Val, Split::Test];
+
+    /// Folder and manifest name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Val => "val",
+            Split::Test => "test",
+        }
+    }
+
+    /// English label; pass it through `tr` for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Split::Train => "Train",
+            Split::Val => "Validation",
+            Split::Test => "Test",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Splits for `remaining` unassigned ranges so that, together with the `assigned` counts per
+/// split, the totals come as close to `ratios` as possible. Splits that are already over their
+/// share get nothing more. The order is shuffled with `seed`.
+pub fn fill_remaining(
+    assigned: [usize; 3],
+    remaining: usize,
+    ratios: [f32; 3],
+    seed: u64,
+) -> Vec<Split> {
+    let ratio_sum: f32 = ratios.iter().map(|r| r.max(0.0)).sum();
+    let ratios = if ratio_sum > 0.0 {
+        ratios.map(|r| r.max(0.0) / ratio_sum)
+    } else {
+        [1.0 / 3.0; 3]
+    };
+    let total = (assigned.iter().sum::<usize>() + remaining) as f32;
+    let mut deficits = [0.0f32; 3];
+    for i in 0..3 {
+        deficits[i] = (ratios[i] * total - assigned[i] as f32).max(0.0);
+    }
+    let deficit_sum: f32 = deficits.iter().sum();
+    if deficit_sum <= 0.0 {
+        deficits = ratios;
+    }
+    let deficit_sum: f32 = deficits.iter().sum();
+
+    // Largest remainder rounding, so the counts always add up to `remaining`
+    let quotas = deficits.map(|d| d / deficit_sum * remaining as f32);
+    let mut counts = quotas.map(|q| q.floor() as usize);
+    let mut by_remainder = [0, 1, 2];
+    by_remainder.sort_by(|&a, &b| {
+        (quotas[b] - quotas[b].floor()).total_cmp(&(quotas[a] - quotas[a].floor()))
+    });
+    for &i in by_remainder
+        .iter()
+        .cycle()
+        .take(remaining.saturating_sub(counts.iter().sum()))
+    {
+        counts[i] += 1;
+    }
+
+    let mut splits: Vec<Split> = Split::ALL
+        .iter()
+        .flat_map(|s| std::iter::repeat_n(*s, counts[s.index()]))
+        .collect();
+    fastrand::Rng::with_seed(seed).shuffle(&mut splits);
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(splits: &[Split]) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for s in splits {
+            counts[s.index()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn fills_up_to_the_ratios() {
+        assert_eq!(
+            counts(&fill_remaining([0, 0, 0], 10, [80.0, 10.0, 10.0], 1)),
+            [8, 1, 1]
+        );
+        // Train already has more than its share, so the rest goes to val and test
+        assert_eq!(
+            counts(&fill_remaining([9, 0, 0], 2, [80.0, 10.0, 10.0], 1)),
+            [0, 1, 1]
+        );
+        assert_eq!(fill_remaining([0, 0, 0], 7, [1.0, 1.0, 0.0], 3).len(), 7);
+        assert_eq!(
+            counts(&fill_remaining([0, 0, 0], 3, [0.0; 3], 3)),
+            [1, 1, 1]
+        );
+    }
+}
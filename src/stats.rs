@@ -0,0 +1,160 @@
+// Session statistics: files reviewed, ranges created and annotated duration since the app was
+// started, with the hourly rate, for planning annotation work. Kept in memory only; the copy
+// button hands a summary to whatever log or sheet the team keeps.
+use crate::VideoRange;
+use crate::i18n::tr;
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub struct Session {
+    started: Instant,
+    ranges_created: usize,
+    /// Annotated seconds per file opened this session: (when opened, as last seen)
+    annotated: HashMap<PathBuf, (f64, f64)>,
+}
+
+pub struct Summary {
+    pub elapsed: Duration,
+    pub files: usize,
+    pub ranges_created: usize,
+    pub ranges_per_hour: f64,
+    /// Seconds of annotated ranges added this session
+    pub annotated: f64,
+}
+
+fn annotated_seconds(ranges: &[VideoRange]) -> f64 {
+    ranges
+        .iter()
+        .map(|r| (r.end_time - r.start_time).max(0.0))
+        .sum()
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            ranges_created: 0,
+            annotated: HashMap::new(),
+        }
+    }
+
+    pub fn range_created(&mut self) {
+        self.ranges_created += 1;
+    }
+
+    /// Records the annotations of `file`; the first call per file is the baseline the work of
+    /// this session is measured against.
+    pub fn record(&mut self, file: &Path, ranges: &[VideoRange]) {
+        let seconds = annotated_seconds(ranges);
+        self.annotated
+            .entry(file.to_path_buf())
+            .and_modify(|(_, last)| *last = seconds)
+            .or_insert((seconds, seconds));
+    }
+
+    pub fn summary(&self) -> Summary {
+        self.summary_after(self.started.elapsed())
+    }
+
+    fn summary_after(&self, elapsed: Duration) -> Summary {
+        let hours = elapsed.as_secs_f64() / 3600.0;
+        let added: f64 = self
+            .annotated
+            .values()
+            .map(|(at_open, last)| last - at_open)
+            .sum();
+        Summary {
+            elapsed,
+            files: self.annotated.len(),
+            ranges_created: self.ranges_created,
+            ranges_per_hour: if hours > 0.0 {
+                self.ranges_created as f64 / hours
+            } else {
+                0.0
+            },
+            annotated: added.max(0.0),
+        }
+    }
+}
+
+fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+impl Summary {
+    fn rows(&self) -> [(&'static str, String); 5] {
+        [
+            ("Session time", clock(self.elapsed.as_secs_f64())),
+            ("Files reviewed", self.files.to_string()),
+            ("Ranges created", self.ranges_created.to_string()),
+            ("Ranges per hour", format!("{:.1}", self.ranges_per_hour)),
+            ("Annotated duration", clock(self.annotated)),
+        ]
+    }
+}
+
+/// `extra` adds controls below the summary.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    summary: &Summary,
+    extra: impl FnOnce(&mut egui::Ui),
+) {
+    egui::Window::new(tr("⏱ Session"))
+        .id(egui::Id::new("session_stats"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("session_grid").show(ui, |ui| {
+                for (label, value) in summary.rows() {
+                    ui.label(tr(label));
+                    ui.strong(value);
+                    ui.end_row();
+                }
+            });
+            ui.add_space(4.0);
+            if ui.button(tr("📋 Copy Summary")).clicked() {
+                let text: Vec<String> = summary
+                    .rows()
+                    .iter()
+                    .map(|(label, value)| format!("{}: {}", tr(label), value))
+                    .collect();
+                ctx.copy_text(text.join("\n"));
+            }
+            ui.separator();
+            extra(ui);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_the_work_of_this_session() {
+        let range = |start, end| VideoRange {
+            start_time: start,
+            end_time: end,
+            ..Default::default()
+        };
+        let mut session = Session::new();
+        session.record(Path::new("a.mp4"), &[range(0.0, 10.0)]);
+        session.record(Path::new("a.mp4"), &[range(0.0, 10.0), range(20.0, 25.0)]);
+        session.record(Path::new("b.mp4"), &[]);
+        session.record(Path::new("b.mp4"), &[range(1.0, 4.0)]);
+        (0..3).for_each(|_| session.range_created());
+        let summary = session.summary_after(Duration::from_secs(1800));
+        assert_eq!(summary.files, 2);
+        assert!((summary.annotated - 8.0).abs() < 1e-9);
+        assert!((summary.ranges_per_hour - 6.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,91 @@
+// Loading stills for the preview. Panorama scans and 100 MP photos exceed the GPU's texture
+// size limit (and are slow to upload and analyze at full size), so large images are decoded
+// at a reduced size for display. Crops are normalized, and the export reads the original, so
+// only the full-resolution size has to be kept for the pixel math.
+use crate::i18n::trf;
+use opencv::{core, imgcodecs, imgproc, prelude::*};
+use std::path::Path;
+
+pub struct Still {
+    /// What the viewport shows, at most `max_side` pixels on its longer side
+    pub preview: core::Mat,
+    /// Width and height of the original image
+    pub size: (f64, f64),
+}
+
+impl Still {
+    pub fn is_downscaled(&self) -> bool {
+        self.preview.cols() as f64 != self.size.0 || self.preview.rows() as f64 != self.size.1
+    }
+}
+
+/// The largest of OpenCV's decode-time reductions (1/2, 1/4, 1/8) that fits `longest` into
+/// `max_side` pixels, or the strongest one if none does.
+fn reduction(longest: i32, max_side: i32) -> i32 {
+    [1, 2, 4]
+        .into_iter()
+        .find(|factor| longest / factor <= max_side)
+        .unwrap_or(8)
+}
+
+/// Loads `path` for display. Reduced decoding lets JPEG skip most of the work and never holds
+/// the full-size pixels; it yields 8 bit, so only images that have to be reduced lose the
+/// high bit depth kept for dithering.
+pub fn load(path: &Path, max_side: i32) -> Result<Still, String> {
+    let name = path.to_str().unwrap();
+    let error = || trf("Could not open {}", &[&path.display()]);
+    let full = crate::probe::frame_size(path);
+    let factor = full.map_or(1, |(w, h)| reduction(w.max(h), max_side));
+    let flags = match factor {
+        1 => imgcodecs::IMREAD_COLOR | imgcodecs::IMREAD_ANYDEPTH,
+        2 => imgcodecs::IMREAD_REDUCED_COLOR_2,
+        4 => imgcodecs::IMREAD_REDUCED_COLOR_4,
+        _ => imgcodecs::IMREAD_REDUCED_COLOR_8,
+    };
+    let mut preview = imgcodecs::imread(name, flags).map_err(|_| error())?;
+    if preview.empty() {
+        return Err(error());
+    }
+    let (pw, ph) = (preview.cols(), preview.rows());
+    let size = match full {
+        // imread applies the EXIF orientation, ffprobe reports the stored size
+        Some((w, h)) if (w > h) != (pw > ph) && w != h => (h as f64, w as f64),
+        Some((w, h)) => (w as f64, h as f64),
+        None => (pw as f64, ph as f64),
+    };
+
+    // Beyond 1/8 (or when the size was unknown), scale the rest of the way down
+    let longest = pw.max(ph);
+    if longest > max_side {
+        let scale = max_side as f64 / longest as f64;
+        let mut resized = core::Mat::default();
+        imgproc::resize(
+            &preview,
+            &mut resized,
+            core::Size::new(
+                ((pw as f64 * scale).round() as i32).max(1),
+                ((ph as f64 * scale).round() as i32).max(1),
+            ),
+            0.0,
+            0.0,
+            imgproc::INTER_AREA,
+        )
+        .map_err(|e| e.to_string())?;
+        preview = resized;
+    }
+    Ok(Still { preview, size })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_mildest_reduction_that_fits() {
+        assert_eq!(reduction(4000, 4096), 1);
+        assert_eq!(reduction(8192, 4096), 2);
+        assert_eq!(reduction(12000, 4096), 4);
+        assert_eq!(reduction(30000, 4096), 8);
+        assert_eq!(reduction(60000, 4096), 8);
+    }
+}
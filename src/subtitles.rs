@@ -0,0 +1,195 @@
+// Subtitles for context while annotating: a sidecar `.srt`/`.ass` next to the media (also with
+// a language suffix, e.g. `clip.en.srt`), or else the first text subtitle track of the
+// container, which FFmpeg converts to SRT once and the cache keeps. Dialogue is often the
+// caption a range wants, so the active cue can be copied into the note.
+use crate::cache;
+use crate::i18n::trf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+pub struct Track {
+    /// Sorted by start
+    cues: Vec<Cue>,
+}
+
+impl Track {
+    fn new(mut cues: Vec<Cue>) -> Option<Track> {
+        cues.retain(|c| c.end > c.start && !c.text.is_empty());
+        cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+        (!cues.is_empty()).then_some(Track { cues })
+    }
+
+    /// The cue shown at `time`; the latest one if cues overlap.
+    pub fn active(&self, time: f64) -> Option<&Cue> {
+        let idx = self.cues.partition_point(|c| c.start <= time);
+        self.cues[..idx].iter().rev().find(|c| c.end > time)
+    }
+}
+
+/// "01:02:03,456" (SRT) or "1:02:03.45" (ASS) in seconds.
+fn parse_time(text: &str) -> Option<f64> {
+    let text = text.trim().replace(',', ".");
+    let mut parts = text.rsplitn(3, ':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let hours: f64 = parts.next().map_or(Some(0.0), |h| h.parse().ok())?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Drops `<i>`-style tags and ASS `{\an8}` override blocks.
+fn strip_markup(text: &str) -> String {
+    let mut out = String::new();
+    let mut depth = None;
+    for c in text.chars() {
+        match (depth, c) {
+            (None, '<') => depth = Some('>'),
+            (None, '{') => depth = Some('}'),
+            (Some(close), c) if c == close => depth = None,
+            (None, c) => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+fn parse_srt(text: &str) -> Vec<Cue> {
+    let text = text.replace("\r\n", "\n");
+    text.split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+            let (start, end) = lines.next()?.split_once("-->")?;
+            // Position hints may follow the end time
+            let end = end.split_whitespace().next()?;
+            let text: Vec<String> = lines.map(strip_markup).filter(|l| !l.is_empty()).collect();
+            Some(Cue {
+                start: parse_time(start)?,
+                end: parse_time(end)?,
+                text: text.join("\n"),
+            })
+        })
+        .collect()
+}
+
+fn parse_ass(text: &str) -> Vec<Cue> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("Dialogue:"))
+        .filter_map(|line| {
+            // Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+            let fields: Vec<&str> = line.splitn(10, ',').collect();
+            let text = fields.get(9)?.replace("\\N", "\n").replace("\\n", "\n");
+            Some(Cue {
+                start: parse_time(fields[1])?,
+                end: parse_time(fields[2])?,
+                text: strip_markup(&text),
+            })
+        })
+        .collect()
+}
+
+/// Subtitle files next to `source`: `clip.srt`, `clip.ass` or `clip.<lang>.srt`.
+fn sidecar(source: &Path) -> Option<PathBuf> {
+    let stem = source.file_stem()?.to_string_lossy().to_string();
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(source.parent()?)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            let ext = p
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            let subtitle = matches!(ext.as_str(), "srt" | "ass" | "ssa");
+            subtitle && name.starts_with(&format!("{}.", stem))
+        })
+        .collect();
+    // The plain name wins over language variants
+    candidates.sort_by_key(|p| p.file_name().map(|n| n.len()));
+    candidates.into_iter().next()
+}
+
+fn parse_file(path: &Path, text: &str) -> Vec<Cue> {
+    let ext = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if ext == "srt" {
+        parse_srt(text)
+    } else {
+        parse_ass(text)
+    }
+}
+
+/// First text subtitle track of `source` as SRT, converted by FFmpeg and cached. None when the
+/// container has no subtitle track FFmpeg can turn into text.
+fn embedded(source: &Path) -> Result<Option<String>, String> {
+    let path = cache::dir("subtitles").join(format!("{}.srt", cache::source_key(source)));
+    if let Ok(text) = std::fs::read_to_string(&path) {
+        cache::touch(&path);
+        return Ok(Some(text));
+    }
+    let streams = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "s"])
+        .args(["-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(source)
+        .output()
+        .map_err(|e| trf("Failed to start ffprobe: {}", &[&e]))?;
+    if String::from_utf8_lossy(&streams.stdout).trim().is_empty() {
+        return Ok(None);
+    }
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(source)
+        .args(["-map", "0:s:0", "-f", "srt", "-"])
+        .output()
+        .map_err(|e| trf("Failed to start FFmpeg: {}", &[&e]))?;
+    // Picture-based subtitles (PGS, DVD) can't be converted to text
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let text = String::from_utf8_lossy(&output.stdout).to_string();
+    if std::fs::create_dir_all(path.parent().unwrap()).is_ok()
+        && std::fs::write(&path, &text).is_ok()
+    {
+        cache::evict(Some(&path));
+    }
+    Ok(Some(text))
+}
+
+/// Subtitles of `source`, or None if it has none. Blocks; run on a worker thread.
+pub fn load(source: &Path) -> Result<Option<Track>, String> {
+    if let Some(path) = sidecar(source) {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+        return Ok(Track::new(parse_file(&path, &text)));
+    }
+    Ok(embedded(source)?.and_then(|text| Track::new(parse_srt(&text))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_srt_and_ass_cues() {
+        let srt = "1\r\n00:00:01,500 --> 00:00:03,000\r\n<i>Hello</i>\r\nthere\r\n\r\n\
+                   2\r\n00:01:00,000 --> 00:01:02,250 X1:10\r\nBye\r\n";
+        let track = Track::new(parse_srt(srt)).unwrap();
+        assert_eq!(track.active(2.0).unwrap().text, "Hello\nthere");
+        assert!(track.active(3.5).is_none());
+        assert_eq!(track.active(61.0).unwrap().text, "Bye");
+
+        let ass = "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,0:00:05.00,0:00:07.50,Default,,0,0,0,,{\\an8}Top, line\\Nnext\n";
+        let track = Track::new(parse_ass(ass)).unwrap();
+        assert_eq!(track.active(6.0).unwrap().text, "Top, line\nnext");
+    }
+}
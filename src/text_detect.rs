@@ -0,0 +1,126 @@
+// Burned-in text detection (subtitles, watermarks) with the EAST model through OpenCV DNN.
+// The model file is not shipped; point the app at frozen_east_text_detection.pb.
+use crate::i18n::trf;
+use crate::{SerializableRect, VideoRange, logs, remote};
+use opencv::{core, dnn, imgcodecs, prelude::*, videoio};
+use std::path::Path;
+
+/// Frames sampled per range, spread evenly between start and end.
+const SAMPLES_PER_RANGE: usize = 5;
+/// EAST needs input dimensions that are multiples of 32.
+const INPUT_SIZE: i32 = 320;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct TextScan {
+    pub frames_sampled: usize,
+    pub frames_with_text: usize,
+    /// Normalized boxes of all text found in the sampled frames.
+    pub boxes: Vec<SerializableRect>,
+}
+
+impl TextScan {
+    /// True if any detected text lands inside `crop` (or anywhere when there is no crop).
+    pub fn hits(&self, crop: Option<&SerializableRect>) -> bool {
+        match crop {
+            Some(crop) => self.boxes.iter().any(|b| b.intersects(crop)),
+            None => !self.boxes.is_empty(),
+        }
+    }
+}
+
+struct TextDetector {
+    model: dnn::TextDetectionModel_EAST,
+}
+
+impl TextDetector {
+    fn from_file(model_path: &Path) -> opencv::Result<Self> {
+        let mut model = dnn::TextDetectionModel_EAST::from_file(&model_path.to_string_lossy(), "")?;
+        model.set_confidence_threshold(0.5)?;
+        model.set_nms_threshold(0.4)?;
+        model.set_input_params(
+            1.0,
+            core::Size::new(INPUT_SIZE, INPUT_SIZE),
+            core::Scalar::new(123.68, 116.78, 103.94, 0.0),
+            true,
+            false,
+        )?;
+        Ok(Self { model })
+    }
+
+    fn detect(&self, frame: &core::Mat) -> opencv::Result<Vec<SerializableRect>> {
+        let size = frame.size()?;
+        let (w, h) = (size.width as f32, size.height as f32);
+        let mut quads = core::Vector::<core::Vector<core::Point>>::new();
+        self.model.detect(frame, &mut quads)?;
+        Ok(quads
+            .iter()
+            .map(|quad| {
+                let xs = quad.iter().map(|p| p.x as f32 / w);
+                let ys = quad.iter().map(|p| p.y as f32 / h);
+                SerializableRect {
+                    min_x: xs.clone().fold(1.0, f32::min).clamp(0.0, 1.0),
+                    min_y: ys.clone().fold(1.0, f32::min).clamp(0.0, 1.0),
+                    max_x: xs.fold(0.0, f32::max).clamp(0.0, 1.0),
+                    max_y: ys.fold(0.0, f32::max).clamp(0.0, 1.0),
+                }
+            })
+            .collect())
+    }
+}
+
+fn scan_frames(detector: &TextDetector, frames: impl Iterator<Item = core::Mat>) -> TextScan {
+    let mut scan = TextScan {
+        frames_sampled: 0,
+        frames_with_text: 0,
+        boxes: Vec::new(),
+    };
+    for frame in frames {
+        scan.frames_sampled += 1;
+        match detector.detect(&frame) {
+            Ok(boxes) if !boxes.is_empty() => {
+                scan.frames_with_text += 1;
+                scan.boxes.extend(boxes);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(target: logs::ANALYSIS, "Text detection failed: {}", e),
+        }
+    }
+    scan
+}
+
+/// Opens `media` on its own and scans sampled frames of every range. Runs on a worker thread.
+pub fn scan_ranges(
+    media: &Path,
+    is_image: bool,
+    ranges: &[VideoRange],
+    model_path: &Path,
+) -> Result<Vec<TextScan>, String> {
+    let detector =
+        TextDetector::from_file(model_path).map_err(|e| trf("Failed to load EAST model: {}", &[&e]))?;
+    let media_str = media.to_string_lossy();
+
+    if is_image {
+        let mat = imgcodecs::imread(&media_str, imgcodecs::IMREAD_COLOR)
+            .map_err(|e| trf("Failed to read image: {}", &[&e]))?;
+        let scan = scan_frames(&detector, std::iter::once(mat));
+        return Ok(ranges.iter().map(|_| scan.clone()).collect());
+    }
+
+    let input = remote::input(media);
+    let mut cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)
+        .map_err(|e| trf("Failed to open video: {}", &[&e]))?;
+    Ok(ranges
+        .iter()
+        .map(|range| {
+            let frames = (0..SAMPLES_PER_RANGE).filter_map(|k| {
+                let t = range.start_time
+                    + (range.end_time - range.start_time) * (k as f64 + 0.5)
+                        / SAMPLES_PER_RANGE as f64;
+                let _ = cap.set(videoio::CAP_PROP_POS_MSEC, t * 1000.0);
+                let mut frame = core::Mat::default();
+                (cap.read(&mut frame).unwrap_or(false) && !frame.empty()).then_some(frame)
+            });
+            scan_frames(&detector, frames)
+        })
+        .collect())
+}
@@ -0,0 +1,62 @@
+// How a crop maps onto the training resolution. Trainers scale every sample so its short side
+// matches the resolution (512 px by default); a grid of resolution-sized tiles over the crop
+// shows at a glance how much a crop shrinks, and so how much of its detail survives. The
+// training view goes further and shows the crop at that size.
+use eframe::egui;
+use opencv::{core, imgproc, prelude::*};
+
+pub const DEFAULT_RESOLUTION: u32 = 512;
+
+/// Size of a `width` × `height` crop once its short side is scaled to `resolution`.
+pub fn output_size(width: f64, height: f64, resolution: u32) -> (f64, f64) {
+    let scale = resolution as f64 / width.min(height).max(1.0);
+    (width * scale, height * scale)
+}
+
+/// Offsets in pixels from the crop's left or top edge at which the tile lines fall, for a crop
+/// `length` pixels long.
+pub fn lines(length: f64, resolution: u32) -> impl Iterator<Item = f64> {
+    let step = resolution.max(1) as f64;
+    (1..)
+        .map(move |k| k as f64 * step)
+        .take_while(move |&offset| offset < length)
+}
+
+/// The `crop` of the BGR `frame` (in the frame's pixels) scaled to `output`, the way a trainer
+/// downsamples it, as an RGB image for display.
+pub fn training_view(
+    frame: &core::Mat,
+    crop: core::Rect,
+    output: (f64, f64),
+) -> opencv::Result<egui::ColorImage> {
+    let region = core::Mat::roi(frame, crop)?;
+    let size = core::Size::new(
+        (output.0.round() as i32).max(1),
+        (output.1.round() as i32).max(1),
+    );
+    let interpolation = if size.width < crop.width {
+        imgproc::INTER_AREA
+    } else {
+        imgproc::INTER_CUBIC
+    };
+    let mut scaled = core::Mat::default();
+    imgproc::resize(&region, &mut scaled, size, 0.0, 0.0, interpolation)?;
+    let mut rgb = core::Mat::default();
+    imgproc::cvt_color_def(&scaled, &mut rgb, imgproc::COLOR_BGR2RGB)?;
+    let size = [rgb.cols() as usize, rgb.rows() as usize];
+    Ok(egui::ColorImage::from_rgb(size, rgb.data_bytes()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_follow_the_short_side() {
+        let (w, h) = output_size(1920.0, 1080.0, 512);
+        assert!((w - 910.2).abs() < 0.1 && h == 512.0);
+        assert_eq!(output_size(256.0, 512.0, 512), (512.0, 1024.0));
+        assert_eq!(lines(1100.0, 512).collect::<Vec<_>>(), [512.0, 1024.0]);
+        assert_eq!(lines(512.0, 512).count(), 0);
+    }
+}
@@ -0,0 +1,118 @@
+// Zoom and scroll state of the timeline. Zoomed out, one slider pixel of an hour-long source
+// spans seconds; zooming in narrows the slider to a window of the source so boundaries can be
+// set frame by frame. Times stay absolute; this only decides which part the slider covers.
+
+/// Narrowest window, so the slider never gets finer than a few frames per pixel.
+const MIN_WINDOW: f64 = 2.0;
+
+#[derive(Clone, Copy)]
+pub struct Timeline {
+    /// 1.0 shows the whole source.
+    zoom: f64,
+    /// Time at the left end of the slider
+    start: f64,
+    /// Playhead at the last `follow`, so the window only scrolls when the playhead moves
+    followed: Option<f64>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            start: 0.0,
+            followed: None,
+        }
+    }
+}
+
+impl Timeline {
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > 1.0
+    }
+
+    /// First and last time the slider shows.
+    pub fn window(&self, duration: f64) -> (f64, f64) {
+        let length = duration / self.zoom;
+        (self.start, self.start + length)
+    }
+
+    /// Multiplies the zoom by `factor`, keeping `anchor` at the same slider position.
+    pub fn zoom_at(&mut self, duration: f64, anchor: f64, factor: f64) {
+        let (start, end) = self.window(duration);
+        let share = if end > start {
+            ((anchor - start) / (end - start)).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        let max_zoom = (duration / MIN_WINDOW).max(1.0);
+        self.zoom = (self.zoom * factor).clamp(1.0, max_zoom);
+        self.start = anchor - share * duration / self.zoom;
+        self.clamp(duration);
+    }
+
+    /// Scrolls the window by `seconds`.
+    pub fn pan(&mut self, duration: f64, seconds: f64) {
+        self.start += seconds;
+        self.clamp(duration);
+    }
+
+    /// Centers the window on `time`.
+    pub fn center_on(&mut self, duration: f64, time: f64) {
+        let (start, end) = self.window(duration);
+        self.start = time - (end - start) / 2.0;
+        self.clamp(duration);
+    }
+
+    /// Scrolls just far enough that `time` is in the window once the playhead moved there, e.g.
+    /// while playing. A window scrolled away from a resting playhead stays put.
+    pub fn follow(&mut self, duration: f64, time: f64) {
+        if self.followed == Some(time) {
+            return;
+        }
+        self.followed = Some(time);
+        let (start, end) = self.window(duration);
+        if time < start {
+            self.start = time;
+        } else if time > end {
+            self.start = time - (end - start);
+        }
+        self.clamp(duration);
+    }
+
+    fn clamp(&mut self, duration: f64) {
+        let length = duration / self.zoom;
+        self.start = self.start.clamp(0.0, (duration - length).max(0.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_keeps_the_anchor_and_stays_inside_the_source() {
+        let hour = 3600.0;
+        let mut timeline = Timeline::default();
+        timeline.zoom_at(hour, 900.0, 10.0);
+        let (start, end) = timeline.window(hour);
+        assert!((end - start - 360.0).abs() < 1e-9);
+        // 900 s sat a quarter into the slider and still does
+        assert!((start + 0.25 * 360.0 - 900.0).abs() < 1e-9);
+
+        timeline.zoom_at(hour, 900.0, 1e6);
+        let (start, end) = timeline.window(hour);
+        assert!((end - start - MIN_WINDOW).abs() < 1e-9);
+
+        timeline.follow(hour, 3599.0);
+        assert!((timeline.window(hour).1 - 3599.0).abs() < 1e-9);
+        timeline.pan(hour, 100.0);
+        assert!((timeline.window(hour).1 - hour).abs() < 1e-9);
+
+        timeline.zoom_at(hour, 0.0, 1e-6);
+        assert_eq!(timeline.window(hour), (0.0, hour));
+    }
+}
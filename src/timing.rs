@@ -0,0 +1,113 @@
+// Single source of truth for converting between timeline seconds and native frames,
+// shared by the preview (OpenCV) and the export (FFmpeg) so both agree on frame boundaries.
+//
+// OpenCV's FFmpeg backend numbers a decoded frame as round(pts * fps), also for VFR sources,
+// while FFmpeg's accurate seek keeps the first frame with pts >= the seek time. Seeking to
+// half a frame before a frame's nominal time therefore selects exactly the frame OpenCV shows.
+
+/// Guards against times such as 0.1 * 3 landing just below a frame boundary.
+const EPSILON: f64 = 1e-6;
+
+/// Native frame index shown in the preview at `time`.
+pub fn frame_at(time: f64, fps: f64) -> i32 {
+    (time * fps + EPSILON).floor().max(0.0) as i32
+}
+
+/// Nominal timestamp of native frame `frame`.
+pub fn frame_time(frame: i32, fps: f64) -> f64 {
+    frame as f64 / fps
+}
+
+/// FFmpeg `-ss` value whose first output frame is the frame previewed at `time`.
+pub fn export_start(time: f64, fps: f64) -> f64 {
+    ((frame_at(time, fps) as f64 - 0.5) / fps).max(0.0)
+}
+
+/// FFmpeg `-to` value that still includes the frame previewed at `time`.
+pub fn export_end(time: f64, fps: f64) -> f64 {
+    (frame_at(time, fps) as f64 + 0.5) / fps
+}
+
+/// A jump typed into the frame box.
+#[derive(Debug, PartialEq)]
+pub enum FrameEntry {
+    /// Native frame of the source: `120`
+    Native(i32),
+    /// Native frames from the current range's start: `+12`, `-5`
+    FromRangeStart(i32),
+    /// Frame of a range's exported clip, as training pipelines number them: `r2:30`
+    Clip { range: usize, frame: i32 },
+}
+
+pub fn parse_frame_entry(text: &str) -> Option<FrameEntry> {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix(['r', 'R']) {
+        let (range, frame) = rest.split_once(':')?;
+        return Some(FrameEntry::Clip {
+            range: range.trim().parse().ok()?,
+            frame: frame.trim().parse().ok()?,
+        });
+    }
+    if text.starts_with(['+', '-']) {
+        return text.parse().ok().map(FrameEntry::FromRangeStart);
+    }
+    text.parse().ok().map(FrameEntry::Native)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATES: &[f64] = &[
+        16.0,
+        24.0,
+        24000.0 / 1001.0,
+        25.0,
+        30000.0 / 1001.0,
+        30.0,
+        60.0,
+    ];
+
+    #[test]
+    fn frame_time_round_trips() {
+        for &fps in RATES {
+            for frame in 0..10_000 {
+                assert_eq!(frame_at(frame_time(frame, fps), fps), frame, "fps {}", fps);
+            }
+        }
+    }
+
+    #[test]
+    fn export_window_brackets_the_previewed_frames() {
+        for &fps in RATES {
+            for frame in 1..1_000 {
+                let t = frame_time(frame, fps) + 0.3 / fps;
+                let (start, end) = (export_start(t, fps), export_end(t, fps));
+                assert!(start < frame_time(frame, fps) && start > frame_time(frame - 1, fps));
+                assert!(end > frame_time(frame, fps) && end < frame_time(frame + 1, fps));
+            }
+        }
+    }
+
+    #[test]
+    fn parses_frame_entries() {
+        assert_eq!(parse_frame_entry(" 120 "), Some(FrameEntry::Native(120)));
+        assert_eq!(
+            parse_frame_entry("+12"),
+            Some(FrameEntry::FromRangeStart(12))
+        );
+        assert_eq!(
+            parse_frame_entry("-5"),
+            Some(FrameEntry::FromRangeStart(-5))
+        );
+        assert_eq!(
+            parse_frame_entry("r2:30"),
+            Some(FrameEntry::Clip {
+                range: 2,
+                frame: 30
+            })
+        );
+        assert_eq!(parse_frame_entry("r2"), None);
+        assert_eq!(parse_frame_entry("abc"), None);
+    }
+}
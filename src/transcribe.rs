@@ -0,0 +1,92 @@
+// Speech transcription into notes through a local whisper.cpp CLI. For talking-head material
+// the caption is essentially the transcript, so the audio of a range is cut to the 16 kHz mono
+// WAV whisper.cpp expects and its plain-text output goes into the note. Nothing is bundled:
+// the binary (`whisper-cli`, or VIDDATATRAINCROP_WHISPER) and a ggml model have to be present.
+use crate::i18n::trf;
+use crate::{VideoRange, remote};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The whisper.cpp executable to run.
+fn program() -> PathBuf {
+    std::env::var_os("VIDDATATRAINCROP_WHISPER")
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| PathBuf::from("whisper-cli"), PathBuf::from)
+}
+
+/// Model from VIDDATATRAINCROP_WHISPER_MODEL, so it needn't be picked every session.
+pub fn model_from_env() -> Option<PathBuf> {
+    std::env::var_os("VIDDATATRAINCROP_WHISPER_MODEL")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Joins whisper.cpp's output lines into one paragraph. Silence comes out as markers such as
+/// `[BLANK_AUDIO]` or `(music)`, which aren't speech.
+fn clean(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            let marker = (line.starts_with('[') && line.ends_with(']'))
+                || (line.starts_with('(') && line.ends_with(')'));
+            !line.is_empty() && !marker
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Transcribes the audio of `range` of `media` with the ggml `model`. Blocks; run on a worker
+/// thread. An empty string means no speech was recognized.
+pub fn range(media: &Path, range: &VideoRange, model: &Path) -> Result<String, String> {
+    let wav = std::env::temp_dir().join(format!(
+        "viddatatraincrop-transcribe-{}.wav",
+        std::process::id()
+    ));
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", "-y"])
+        .args(["-ss", &range.start_time.to_string()])
+        .arg("-i")
+        .arg(remote::input(media))
+        .args(["-t", &(range.end_time - range.start_time).to_string()])
+        .args(["-vn", "-ac", "1", "-ar", "16000", "-c:a", "pcm_s16le"])
+        .arg(&wav)
+        .status()
+        .map_err(|e| trf("Failed to start FFmpeg: {}", &[&e]))?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&wav);
+        return Err(trf(
+            "FFmpeg could not extract the audio of {}",
+            &[&media.display()],
+        ));
+    }
+
+    let output = Command::new(program())
+        .arg("-m")
+        .arg(model)
+        .arg("-f")
+        .arg(&wav)
+        .args(["-l", "auto", "-nt", "-np"])
+        .output();
+    let _ = std::fs::remove_file(&wav);
+    let output = output.map_err(|e| trf("Failed to start whisper.cpp: {}", &[&e]))?;
+    if !output.status.success() {
+        return Err(trf(
+            "whisper.cpp failed: {}",
+            &[&String::from_utf8_lossy(&output.stderr).trim()],
+        ));
+    }
+    Ok(clean(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_non_speech_markers() {
+        let output = "\n Welcome back to the channel.\n[BLANK_AUDIO]\n Today we cook.\n (music)\n";
+        assert_eq!(clean(output), "Welcome back to the channel. Today we cook.");
+        assert_eq!(clean("[BLANK_AUDIO]\n"), "");
+    }
+}
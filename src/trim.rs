@@ -0,0 +1,199 @@
+// Edge trimming. Fades from and to black and freeze frames at the ends of a range slip into a
+// surprising number of clips, and a model learns from every one of them. With edge trimming on,
+// the export drops the leading and trailing frames of each range that are nearly black or
+// nearly the same as the frame next to them; the range editor previews what would go. Only the
+// last few seconds at each end are decoded, and results are cached per source and range times.
+use crate::i18n::{tr, trf};
+use crate::{VideoRange, cache, dataset, remote, timing};
+use opencv::{core, imgproc, prelude::*, videoio};
+use std::path::Path;
+
+/// Frames darker than this mean luma (of 255) count as black
+const BLACK_LUMA: f64 = 16.0;
+/// Frames whose mean absolute luma difference to their neighbour is below this count as frozen
+const STATIC_DIFF: f64 = 0.5;
+/// At most this much is trimmed at each end, and never more than half the range
+const MAX_SECONDS: f64 = 3.0;
+/// Frames are compared at this width
+const WIDTH: i32 = 64;
+
+/// Frames to drop at the start and the end of a range.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Trim {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Trim {
+    pub fn is_empty(&self) -> bool {
+        self.start == 0 && self.end == 0
+    }
+
+    /// `range` without the trimmed frames, at `fps`.
+    pub fn apply(&self, range: &VideoRange, fps: f64) -> VideoRange {
+        let mut trimmed = range.clone();
+        if self.start > 0 {
+            let first = timing::frame_at(range.start_time, fps) + self.start;
+            trimmed.start_time = timing::frame_time(first, fps);
+        }
+        if self.end > 0 {
+            let last = last_frame(range, fps) - self.end;
+            trimmed.end_time = timing::frame_time(last, fps);
+        }
+        trimmed
+    }
+
+    /// What the preview shows, e.g. "Trims 12 frames (0.50 s) at the start".
+    pub fn describe(&self, fps: f64) -> String {
+        let part = |frames: i32| format!("{} ({:.2} s)", frames, frames as f64 / fps);
+        match (self.start, self.end) {
+            (0, 0) => tr("No black or frozen frames at the edges").to_string(),
+            (start, 0) => trf("Trims {} frames at the start", &[&part(start)]),
+            (0, end) => trf("Trims {} frames at the end", &[&part(end)]),
+            (start, end) => trf(
+                "Trims {} frames at the start and {} at the end",
+                &[&part(start), &part(end)],
+            ),
+        }
+    }
+}
+
+/// Last native frame `range` exports; the one shown at its end time.
+fn last_frame(range: &VideoRange, fps: f64) -> i32 {
+    timing::frame_at(range.end_time, fps)
+}
+
+/// Mean luma and the frame scaled down to gray, for comparing neighbours.
+struct Sample {
+    luma: f64,
+    gray: core::Mat,
+}
+
+fn sample(frame: &core::Mat) -> opencv::Result<Sample> {
+    let frame = crate::color::to_bgr8(frame)?;
+    let height = ((frame.rows() * WIDTH) / frame.cols().max(1)).max(1);
+    let mut small = core::Mat::default();
+    let size = core::Size::new(WIDTH, height);
+    imgproc::resize(&frame, &mut small, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    let mut gray = core::Mat::default();
+    imgproc::cvt_color_def(&small, &mut gray, imgproc::COLOR_BGR2GRAY)?;
+    let luma = core::mean_def(&gray)?[0];
+    Ok(Sample { luma, gray })
+}
+
+fn difference(a: &Sample, b: &Sample) -> opencv::Result<f64> {
+    let mut diff = core::Mat::default();
+    core::absdiff(&a.gray, &b.gray, &mut diff)?;
+    Ok(core::mean_def(&diff)?[0])
+}
+
+/// How many of `lumas` from the front are dead: black, or frozen (`diffs[i]` is the difference
+/// between frame i and frame i + 1). Of a freeze, the last copy stays.
+fn dead_frames(lumas: &[f64], diffs: &[f64]) -> usize {
+    (0..lumas.len())
+        .take_while(|&i| lumas[i] < BLACK_LUMA || diffs.get(i).is_some_and(|d| *d < STATIC_DIFF))
+        .count()
+}
+
+/// Counts the dead frames of `samples` from the front.
+fn dead(samples: &[Sample]) -> opencv::Result<usize> {
+    let lumas: Vec<f64> = samples.iter().map(|s| s.luma).collect();
+    let diffs = samples
+        .windows(2)
+        .map(|pair| difference(&pair[0], &pair[1]))
+        .collect::<opencv::Result<Vec<_>>>()?;
+    Ok(dead_frames(&lumas, &diffs))
+}
+
+/// Reads up to `count` frames from native frame `first` on.
+fn read(cap: &mut videoio::VideoCapture, first: i32, count: i32) -> opencv::Result<Vec<Sample>> {
+    cap.set(videoio::CAP_PROP_POS_FRAMES, first as f64)?;
+    let mut samples = Vec::new();
+    let mut frame = core::Mat::default();
+    while (samples.len() as i32) < count && cap.read(&mut frame)? && !frame.empty() {
+        samples.push(sample(&frame)?);
+    }
+    Ok(samples)
+}
+
+fn analyze(source: &Path, range: &VideoRange, fps: f64) -> opencv::Result<Trim> {
+    let first = timing::frame_at(range.start_time, fps);
+    let last = last_frame(range, fps);
+    let total = last - first + 1;
+    let window = ((MAX_SECONDS * fps).round() as i32).min(total / 2);
+    if window <= 0 {
+        return Ok(Trim::default());
+    }
+    let input = remote::input(source);
+    let mut cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)?;
+    let start = dead(&read(&mut cap, first, window)?)?;
+    let mut tail = read(&mut cap, last - window + 1, window)?;
+    // The range can end past the last frame of the source; count from the last one read
+    let missing = window - tail.len() as i32;
+    tail.reverse();
+    let end = match dead(&tail)? {
+        0 => 0,
+        end => end as i32 + missing,
+    };
+    let start = start as i32;
+    if start + end >= total {
+        // Nothing would be left; a range black or frozen throughout is left to the annotator
+        return Ok(Trim::default());
+    }
+    Ok(Trim { start, end })
+}
+
+/// The frames to trim from `range` of `source`, cached. Blocks; run on a worker thread or in
+/// the export.
+pub fn edges(source: &Path, range: &VideoRange, fps: f64) -> Result<Trim, String> {
+    let key = format!(
+        "{}_{:016x}.txt",
+        cache::source_key(source),
+        dataset::fnv1a(format!("{}:{}:{}", range.start_time, range.end_time, fps).as_bytes())
+    );
+    let path = cache::dir("trim").join(key);
+    if let Ok(text) = std::fs::read_to_string(&path)
+        && let Some((start, end)) = text.trim().split_once(' ')
+        && let (Ok(start), Ok(end)) = (start.parse(), end.parse())
+    {
+        cache::touch(&path);
+        return Ok(Trim { start, end });
+    }
+    let trim = analyze(source, range, fps).map_err(|e| {
+        trf(
+            "Could not check the edges of {}: {}",
+            &[&source.display(), &e],
+        )
+    })?;
+    if std::fs::create_dir_all(path.parent().unwrap()).is_ok()
+        && std::fs::write(&path, format!("{} {}", trim.start, trim.end)).is_ok()
+    {
+        cache::evict(Some(&path));
+    }
+    Ok(trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trims_black_and_frozen_frames() {
+        // Fade from black, then content
+        assert_eq!(dead_frames(&[2.0, 9.0, 40.0, 80.0], &[7.0, 31.0, 40.0]), 2);
+        // Freeze frame: the last copy stays
+        assert_eq!(dead_frames(&[90.0, 90.0, 90.0, 95.0], &[0.1, 0.2, 6.0]), 2);
+        assert_eq!(dead_frames(&[90.0, 95.0], &[6.0]), 0);
+        assert_eq!(dead_frames(&[1.0, 1.0], &[0.0]), 2);
+
+        let range = VideoRange {
+            start_time: 1.0,
+            end_time: 3.0,
+            ..Default::default()
+        };
+        let trimmed = Trim { start: 5, end: 10 }.apply(&range, 25.0);
+        assert_eq!(timing::frame_at(trimmed.start_time, 25.0), 30);
+        assert_eq!(timing::frame_at(trimmed.end_time, 25.0), 65);
+        assert_eq!(Trim::default().apply(&range, 25.0).end_time, 3.0);
+    }
+}
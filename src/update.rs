@@ -0,0 +1,114 @@
+// Update check. Builds that predate a fix to the crop math keep producing bad clips without
+// anyone noticing, so the About menu can ask GitHub for the latest release and say whether this
+// build is older. It only ever checks when asked and never downloads anything. The request goes
+// through curl, which ships with every desktop OS the app runs on, like FFmpeg does for video.
+use crate::i18n::trf;
+use std::process::Command;
+
+pub const CURRENT: &str = env!("CARGO_PKG_VERSION");
+const LATEST_URL: &str = "https://api.github.com/repos/steffengy/VidDataTrainCrop/releases/latest";
+/// Seconds before the check gives up
+const TIMEOUT: &str = "15";
+
+/// The fields of GitHub's release object the check needs.
+#[derive(serde::Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+/// What the check found.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    UpToDate,
+    Outdated { version: String, url: String },
+}
+
+/// `v1.2.3`, `1.2` or `1.2.3-rc1` as numbers; missing parts count as 0.
+fn parse_version(text: &str) -> Option<[u64; 3]> {
+    let text = text.trim().trim_start_matches('v');
+    let core = text.split(['-', '+']).next()?;
+    let mut parts = [0; 3];
+    for (i, part) in core.split('.').enumerate() {
+        *parts.get_mut(i)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+fn compare(release: Release, current: &str) -> Result<Outcome, String> {
+    let (Some(latest), Some(ours)) = (parse_version(&release.tag_name), parse_version(current))
+    else {
+        return Err(trf("Unrecognized version {}", &[&release.tag_name]));
+    };
+    Ok(if latest > ours {
+        Outcome::Outdated {
+            version: release.tag_name.trim_start_matches('v').to_string(),
+            url: release.html_url,
+        }
+    } else {
+        Outcome::UpToDate
+    })
+}
+
+/// Asks GitHub for the latest release and compares it with this build. Blocks for up to
+/// `TIMEOUT` seconds, so run it off the UI thread.
+pub fn check() -> Result<Outcome, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", TIMEOUT])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args([
+            "--user-agent",
+            concat!("VidDataTrainCrop/", env!("CARGO_PKG_VERSION")),
+        ])
+        .arg(LATEST_URL)
+        .output()
+        .map_err(|e| trf("Could not run curl: {}", &[&e]))?;
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(trf("Could not reach GitHub: {}", &[&reason]));
+    }
+    let release: Release = serde_json::from_slice(&output.stdout)
+        .map_err(|e| trf("Unexpected answer from GitHub: {}", &[&e]))?;
+    compare(release, CURRENT)
+}
+
+/// One line for the About menu.
+pub fn describe(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::UpToDate => trf("Version {} is the latest release.", &[&CURRENT]),
+        Outcome::Outdated { version, .. } => trf(
+            "⚠ Version {} is out, this build is {}. Please update.",
+            &[version, &CURRENT],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_release_tags_with_the_build() {
+        assert_eq!(parse_version("v1.2.3"), Some([1, 2, 3]));
+        assert_eq!(parse_version("0.4"), Some([0, 4, 0]));
+        assert_eq!(parse_version("2.0.1-rc1"), Some([2, 0, 1]));
+        assert_eq!(parse_version("nightly"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+
+        let release = |tag: &str| Release {
+            tag_name: tag.to_string(),
+            html_url: "https://example.com/r".to_string(),
+        };
+        assert_eq!(compare(release("v0.1.0"), "0.1.0"), Ok(Outcome::UpToDate));
+        assert_eq!(compare(release("v0.0.9"), "0.1.0"), Ok(Outcome::UpToDate));
+        assert_eq!(
+            compare(release("v0.10.0"), "0.9.2"),
+            Ok(Outcome::Outdated {
+                version: "0.10.0".to_string(),
+                url: "https://example.com/r".to_string(),
+            })
+        );
+        assert!(compare(release("latest"), "0.1.0").is_err());
+    }
+}
@@ -0,0 +1,386 @@
+// Upload to object storage. Training jobs read from S3 (or MinIO, R2, …) rather than from the
+// machine the dataset was built on, so the clips, captions, manifest and checksums can be pushed
+// to a bucket under a prefix, by hand or after every export. The requests go through curl, like
+// the update check; its `--aws-sigv4` signs them for any S3-compatible endpoint. The secret key
+// comes from `AWS_SECRET_ACCESS_KEY` or is typed in for the session and is never written to
+// disk. What was uploaded is remembered in `upload.json` in the output folder, so later uploads
+// only send the files that changed.
+use crate::checksum;
+use crate::dataset;
+use crate::i18n::{tr, trf};
+use crate::logs;
+use crate::manifest::{self, Manifest};
+use crate::schedule::Schedule;
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub const STATE_FILE: &str = "upload.json";
+/// Attempts per file before it is reported as failed
+const ATTEMPTS: u32 = 3;
+/// Seconds curl may take to connect; the transfer itself may take as long as it needs
+const CONNECT_TIMEOUT: &str = "15";
+/// Region signed for when none is set; most S3-compatible stores accept any
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Where uploads go. Everything but the secret key is kept in the settings.
+#[derive(Clone, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Destination {
+    /// e.g. `https://s3.eu-central-1.amazonaws.com` or `http://minio.local:9000`
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Folder in the bucket the dataset goes into, may be empty
+    pub prefix: String,
+    pub access_key: String,
+    /// Upload the output folder after every export
+    pub after_export: bool,
+}
+
+impl Destination {
+    pub fn is_complete(&self) -> bool {
+        !self.endpoint.trim().is_empty()
+            && !self.bucket.trim().is_empty()
+            && !self.access_key.trim().is_empty()
+    }
+
+    /// `s3://bucket/prefix`, for messages and to tell destinations apart.
+    pub fn describe(&self) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            format!("s3://{}", self.bucket.trim())
+        } else {
+            format!("s3://{}/{}", self.bucket.trim(), prefix)
+        }
+    }
+
+    /// The path-style URL of `file` (relative to the output folder, `/` separated), which every
+    /// S3-compatible store understands.
+    fn url(&self, file: &str) -> String {
+        let key: Vec<_> = self
+            .prefix
+            .split('/')
+            .chain(file.split('/'))
+            .filter(|segment| !segment.is_empty())
+            .map(encode)
+            .collect();
+        format!(
+            "{}/{}/{}",
+            self.endpoint.trim().trim_end_matches('/'),
+            encode(self.bucket.trim()),
+            key.join("/")
+        )
+    }
+}
+
+/// `segment` percent-encoded as S3 signs it: everything but letters, digits and `-._~`.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// What was uploaded where: the SHA-256 of every file as it was sent.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct State {
+    destination: String,
+    files: BTreeMap<String, String>,
+}
+
+impl State {
+    /// The state in `out_dir` if it is for `destination`; a fresh one otherwise.
+    fn load(out_dir: &Path, destination: &str) -> State {
+        std::fs::read_to_string(out_dir.join(STATE_FILE))
+            .ok()
+            .and_then(|text| serde_json::from_str::<State>(&text).ok())
+            .filter(|state| state.destination == destination)
+            .unwrap_or_else(|| State {
+                destination: destination.to_string(),
+                files: BTreeMap::new(),
+            })
+    }
+
+    fn save(&self, out_dir: &Path) -> Result<(), String> {
+        let path = out_dir.join(STATE_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json)
+            .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+    }
+}
+
+/// How an upload went.
+#[derive(Default)]
+pub struct Summary {
+    pub uploaded: usize,
+    /// Files already in the bucket as they are
+    pub unchanged: usize,
+    /// Files that failed every attempt, with why
+    pub failed: Vec<String>,
+}
+
+impl Summary {
+    /// One line for the export message, followed by the failed files.
+    pub fn message(&self, destination: &Destination) -> String {
+        let mut lines = vec![trf(
+            "Uploaded {} file(s) to {}, {} unchanged.",
+            &[&self.uploaded, &destination.describe(), &self.unchanged],
+        )];
+        if !self.failed.is_empty() {
+            lines.push(trf(
+                "{} file(s) failed; the manifest was not uploaded:",
+                &[&self.failed.len()],
+            ));
+            lines.extend(self.failed.iter().cloned());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Sends `path` to `file` at `destination` with one curl request. The credentials go to curl on
+/// stdin, so they don't show up in the process list.
+fn put(
+    destination: &Destination,
+    secret: &str,
+    path: &Path,
+    file: &str,
+    digest: &str,
+) -> Result<(), String> {
+    let region = destination.region.trim();
+    let region = if region.is_empty() {
+        DEFAULT_REGION
+    } else {
+        region
+    };
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail"])
+        .args(["--connect-timeout", CONNECT_TIMEOUT])
+        .arg("--aws-sigv4")
+        .arg(format!("aws:amz:{}:s3", region))
+        .args(["--config", "-"])
+        // Signed along, so the store rejects a file that arrives damaged
+        .arg("--header")
+        .arg(format!("x-amz-content-sha256: {}", digest))
+        .arg("--upload-file")
+        .arg(path)
+        .arg(destination.url(file))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| trf("Could not run curl: {}", &[&e]))?;
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let config = format!(
+        "user = \"{}:{}\"\n",
+        quote(destination.access_key.trim()),
+        quote(secret)
+    );
+    if let Some(mut stdin) = child.stdin.take() {
+        // A failed write shows as curl's own error below
+        let _ = stdin.write_all(config.as_bytes());
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Uploads the dataset in `out_dir` to `destination`: every file the manifest lists that isn't
+/// there as it is yet, then the manifest and the checksums, so readers of the bucket never see a
+/// manifest listing clips that aren't there. Each file is tried `ATTEMPTS` times; files that
+/// fail all of them are reported and keep the manifest back. Stops between files once
+/// `schedule` is cancelled.
+pub fn upload(
+    out_dir: &Path,
+    destination: &Destination,
+    secret: &str,
+    schedule: &Schedule,
+) -> Result<Summary, String> {
+    if !destination.is_complete() {
+        return Err(tr("The upload needs an endpoint, a bucket and an access key").to_string());
+    }
+    let manifest = Manifest::load(out_dir)?;
+    let mut files: Vec<_> = checksum::dataset_files(out_dir, &manifest)
+        .into_iter()
+        .filter(|file| file != manifest::FILE_NAME)
+        .collect();
+    let clips = files.len();
+    files.push(manifest::FILE_NAME.to_string());
+    if out_dir.join(checksum::FILE_NAME).exists() {
+        files.push(checksum::FILE_NAME.to_string());
+    }
+    // All checked before anything is sent, so a manifest can't upload files from elsewhere
+    let paths = files
+        .iter()
+        .map(|file| dataset::manifest_path(out_dir, file))
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut state = State::load(out_dir, &destination.url(""));
+    let mut summary = Summary::default();
+    for (i, (file, path)) in files.iter().zip(paths).enumerate() {
+        if i == clips && !summary.failed.is_empty() {
+            break;
+        }
+        if schedule.is_cancelled() {
+            return Err(tr("Upload cancelled").to_string());
+        }
+        let digest = checksum::file_sha256(&path)
+            .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))?;
+        if state.files.get(file) == Some(&digest) {
+            summary.unchanged += 1;
+            continue;
+        }
+        for attempt in 1..=ATTEMPTS {
+            match put(destination, secret, &path, file, &digest) {
+                Ok(()) => {
+                    tracing::info!(target: logs::EXPORT, file, "Uploaded");
+                    summary.uploaded += 1;
+                    state.files.insert(file.clone(), digest.clone());
+                    // Saved as it goes, so an interrupted upload picks up where it stopped
+                    state.save(out_dir)?;
+                    break;
+                }
+                Err(e) if attempt < ATTEMPTS => {
+                    tracing::warn!(target: logs::EXPORT, file, attempt, "Upload failed, retrying: {}", e);
+                    std::thread::sleep(Duration::from_secs(1 << attempt));
+                }
+                Err(e) => {
+                    tracing::error!(target: logs::EXPORT, file, "Upload failed: {}", e);
+                    summary.failed.push(format!("{}: {}", file, e));
+                }
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// The upload settings window; returns true when the user asks to upload now.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    destination: &mut Destination,
+    secret: &mut String,
+    can_upload: bool,
+) -> bool {
+    let mut requested = false;
+    egui::Window::new(tr("☁ Upload to Object Storage"))
+        .id(egui::Id::new("upload_dataset"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("upload_destination")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(tr("Endpoint:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut destination.endpoint)
+                            .hint_text("https://s3.eu-central-1.amazonaws.com"),
+                    );
+                    ui.end_row();
+                    ui.label(tr("Region:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut destination.region)
+                            .hint_text(DEFAULT_REGION),
+                    );
+                    ui.end_row();
+                    ui.label(tr("Bucket:"));
+                    ui.text_edit_singleline(&mut destination.bucket);
+                    ui.end_row();
+                    ui.label(tr("Prefix:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut destination.prefix)
+                            .hint_text("datasets/v1"),
+                    );
+                    ui.end_row();
+                    ui.label(tr("Access key:"));
+                    ui.text_edit_singleline(&mut destination.access_key);
+                    ui.end_row();
+                    ui.label(tr("Secret key:"));
+                    ui.add(egui::TextEdit::singleline(secret).password(true))
+                        .on_hover_text(tr(
+                            "Kept for this session only; set AWS_SECRET_ACCESS_KEY to have it filled in",
+                        ));
+                    ui.end_row();
+                });
+            ui.checkbox(&mut destination.after_export, tr("Upload after every export"));
+            let ready = destination.is_complete();
+            let button = ui.add_enabled(can_upload && ready, egui::Button::new(tr("Upload Now")));
+            if button.clicked() {
+                requested = true;
+            }
+            ui.add_space(4.0);
+            ui.weak(trf(
+                "Sends the files the manifest lists, then the manifest and checksums, to {}. Files already there as they are are skipped.",
+                &[&destination.describe()],
+            ));
+        });
+    requested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_path_style_with_encoded_keys() {
+        let destination = Destination {
+            endpoint: "http://minio.local:9000/".to_string(),
+            bucket: "clips".to_string(),
+            prefix: "/datasets/v1/".to_string(),
+            access_key: "key".to_string(),
+            ..Default::default()
+        };
+        assert!(destination.is_complete());
+        assert_eq!(destination.describe(), "s3://clips/datasets/v1");
+        assert_eq!(
+            destination.url("train/person walking/a+b_range0.mp4"),
+            "http://minio.local:9000/clips/datasets/v1/train/person%20walking/a%2Bb_range0.mp4"
+        );
+        let root = Destination {
+            prefix: String::new(),
+            ..destination
+        };
+        assert_eq!(
+            root.url("manifest.json"),
+            "http://minio.local:9000/clips/manifest.json"
+        );
+        assert_eq!(encode("ü~"), "%C3%BC~");
+        assert!(!Destination::default().is_complete());
+    }
+
+    #[test]
+    fn refuses_manifests_listing_files_outside_the_output_folder() {
+        let dir = std::env::temp_dir().join("viddatatraincrop_upload_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        std::fs::write(dir.join("secret.txt"), "key").unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"version": 1, "entries": [
+                {"file": "../secret.txt", "source": "a.mp4", "range": 0, "start_time": 0, "end_time": 1}
+            ]}"#,
+        )
+        .unwrap();
+        manifest.save(&out_dir).unwrap();
+        let destination = Destination {
+            endpoint: "http://127.0.0.1:9".to_string(),
+            bucket: "clips".to_string(),
+            access_key: "key".to_string(),
+            ..Default::default()
+        };
+        assert!(upload(&out_dir, &destination, "secret", &Schedule::default()).is_err());
+        assert!(!out_dir.join(STATE_FILE).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,68 @@
+// Post-export check that a clip really starts on the frame the preview showed at the range start.
+use crate::{SerializableRect, remote};
+use opencv::{core, imgproc, prelude::*, videoio};
+use std::path::Path;
+
+/// How many frames on either side of the expected start frame are compared.
+const SEARCH_FRAMES: i32 = 2;
+
+fn read_frame(cap: &mut videoio::VideoCapture, frame_pos: i32) -> opencv::Result<Option<core::Mat>> {
+    if frame_pos < 0 {
+        return Ok(None);
+    }
+    cap.set(videoio::CAP_PROP_POS_FRAMES, frame_pos as f64)?;
+    let mut frame = core::Mat::default();
+    Ok((cap.read(&mut frame)? && !frame.empty()).then_some(frame))
+}
+
+/// Mean absolute difference of `source` (cropped, then scaled to the size of `exported`) and `exported`.
+fn frame_distance(
+    source: &core::Mat,
+    crop: Option<&SerializableRect>,
+    exported: &core::Mat,
+) -> opencv::Result<f64> {
+    let size = source.size()?;
+    let region = match crop {
+        Some(norm) => {
+            let (cw, ch, cx, cy) = norm.to_pixels(size.width as f64, size.height as f64);
+            source.roi(core::Rect::new(cx, cy, cw, ch))?.try_clone()?
+        }
+        None => source.try_clone()?,
+    };
+    let mut scaled = core::Mat::default();
+    imgproc::resize(&region, &mut scaled, exported.size()?, 0.0, 0.0, imgproc::INTER_AREA)?;
+    let mut diff = core::Mat::default();
+    core::absdiff(&scaled, exported, &mut diff)?;
+    let mean = core::mean_def(&diff)?;
+    Ok(mean[0] + mean[1] + mean[2])
+}
+
+/// Returns how many frames the first frame of `exported` is away from `start_frame` of `source`,
+/// or `None` if either file could not be decoded for the comparison.
+pub fn start_offset(
+    source: &Path,
+    start_frame: i32,
+    crop: Option<&SerializableRect>,
+    exported: &Path,
+) -> opencv::Result<Option<i32>> {
+    let mut out_cap = videoio::VideoCapture::from_file(&exported.to_string_lossy(), videoio::CAP_ANY)?;
+    let mut first = core::Mat::default();
+    if !out_cap.read(&mut first)? || first.empty() {
+        return Ok(None);
+    }
+
+    let input = remote::input(source);
+    let mut src_cap = videoio::VideoCapture::from_file(&input.to_string_lossy(), videoio::CAP_ANY)?;
+    let mut best: Option<(i32, f64)> = None;
+    for offset in -SEARCH_FRAMES..=SEARCH_FRAMES {
+        let Some(frame) = read_frame(&mut src_cap, start_frame + offset)? else {
+            continue;
+        };
+        let dist = frame_distance(&frame, crop, &first)?;
+        // Prefer the expected frame on ties (e.g. static shots)
+        if best.is_none_or(|(o, d)| dist < d || (dist == d && offset.abs() < o.abs())) {
+            best = Some((offset, dist));
+        }
+    }
+    Ok(best.map(|(offset, _)| offset))
+}
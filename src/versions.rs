@@ -0,0 +1,591 @@
+// Dataset versions (`versions/<label>.json` in the output folder): the project (annotations of
+// every file), the manifest and the caption texts frozen under a label like `v3`, so a model
+// trained on the dataset can be traced back to what went into it. Two versions, or a version
+// and the current state, are compared range by range, and the difference can be copied into a
+// folder of its own for incremental training.
+use crate::i18n::{tr, trf};
+use crate::manifest::{self, Manifest};
+use crate::{VideoRange, checksum, migrate, project};
+use eframe::egui;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = "versions";
+/// Bumped whenever the format changes incompatibly, with a step added to `STEPS`.
+pub const VERSION: u32 = 1;
+/// Upgrades from older versions, see `migrate::parse`
+const STEPS: &[migrate::Step] = &[];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    pub label: String,
+    /// When it was taken (RFC 3339, UTC)
+    pub created_at: String,
+    pub project: project::Project,
+    pub manifest: Manifest,
+    /// Caption texts by caption file, as the manifest names them
+    pub captions: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    /// The dataset in `out_dir` with the annotations of `project`, as it is now.
+    pub fn capture(
+        label: &str,
+        project: project::Project,
+        out_dir: &Path,
+    ) -> Result<Snapshot, String> {
+        let manifest = Manifest::load(out_dir)?;
+        let captions = manifest
+            .entries
+            .iter()
+            .filter_map(|e| e.caption.as_ref())
+            .filter_map(|c| Some((c.clone(), std::fs::read_to_string(out_dir.join(c)).ok()?)))
+            .collect();
+        Ok(Snapshot {
+            version: VERSION,
+            label: label.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            project,
+            manifest,
+            captions,
+        })
+    }
+
+    /// The snapshot JSON `text`, upgraded from older versions.
+    fn parse(text: &str) -> Result<Snapshot, String> {
+        migrate::parse(text, VERSION, STEPS)
+    }
+
+    fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    fn caption(&self, entry: &manifest::Entry) -> Option<&str> {
+        self.captions
+            .get(entry.caption.as_ref()?)
+            .map(|c| c.as_str())
+    }
+}
+
+fn path_for(out_dir: &Path, label: &str) -> PathBuf {
+    out_dir.join(DIR).join(format!("{}.json", label))
+}
+
+/// Whether `label` can name a version: letters, digits, `.`, `-` and `_`.
+pub fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && !label.starts_with('.')
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+/// Labels of the versions in `out_dir`, oldest first.
+pub fn list(out_dir: &Path) -> Vec<String> {
+    let Ok(dir) = std::fs::read_dir(out_dir.join(DIR)) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<_> = dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|p| {
+            let modified = p.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, p.file_stem()?.to_string_lossy().to_string()))
+        })
+        .collect();
+    versions.sort();
+    versions.into_iter().map(|(_, label)| label).collect()
+}
+
+/// `v1` for the first version, then one more than the highest `v<n>` so far.
+pub fn next_label(labels: &[String]) -> String {
+    let last = labels
+        .iter()
+        .filter_map(|l| l.strip_prefix('v')?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("v{}", last + 1)
+}
+
+pub fn load(out_dir: &Path, label: &str) -> Result<Snapshot, String> {
+    let path = path_for(out_dir, label);
+    std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| Snapshot::parse(&text))
+        .map_err(|e| trf("Could not read {}: {}", &[&path.display(), &e]))
+}
+
+/// Writes `snapshot` into the versions folder of `out_dir`. Versions are never overwritten.
+pub fn save(out_dir: &Path, snapshot: &Snapshot) -> Result<(), String> {
+    if !is_valid_label(&snapshot.label) {
+        return Err(trf("{} is not a valid version label", &[&snapshot.label]));
+    }
+    let path = path_for(out_dir, &snapshot.label);
+    if path.exists() {
+        return Err(trf("Version {} exists already", &[&snapshot.label]));
+    }
+    let json = snapshot.to_json()?;
+    std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| std::fs::write(&path, json))
+        .map_err(|e| trf("Could not write {}: {}", &[&path.display(), &e]))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// What differs, as English labels; pass them through `tr` for display
+    Changed(Vec<&'static str>),
+}
+
+/// How one range differs between two versions.
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    pub source: String,
+    pub range: usize,
+    pub change: Change,
+}
+
+impl Difference {
+    pub fn describe(&self) -> String {
+        let range = format!("{} R{}", self.source, self.range);
+        match &self.change {
+            Change::Added => format!("+ {}", range),
+            Change::Removed => format!("− {}", range),
+            Change::Changed(what) => {
+                let what: Vec<_> = what.iter().map(|w| tr(*w)).collect();
+                format!("~ {}: {}", range, what.join(", "))
+            }
+        }
+    }
+}
+
+fn json(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// What range `i` of `source` looks like in `snapshot`, annotated and exported, for comparing.
+fn parts(snapshot: &Snapshot, source: &str, i: usize) -> [(&'static str, Option<String>); 7] {
+    let range: Option<&VideoRange> = snapshot.project.files.get(source).and_then(|r| r.get(i));
+    let entry = snapshot
+        .manifest
+        .entries
+        .iter()
+        .find(|e| e.source == source && e.range == i);
+    [
+        (
+            "times",
+            range.map(|r| format!("{}-{}", r.start_time, r.end_time)),
+        ),
+        ("crop", range.map(|r| json(&r.crop_rect_norm))),
+        ("split", range.map(|r| json(&r.split))),
+        ("tags", range.map(|r| r.tags.join("\0"))),
+        ("note", range.map(|r| r.note.clone())),
+        (
+            "caption",
+            entry.and_then(|e| snapshot.caption(e)).map(str::to_string),
+        ),
+        ("output", entry.map(|e| e.file.clone())),
+    ]
+}
+
+/// The ranges added, removed and changed from `old` to `new`, by file name and range index.
+/// A range counts when it is annotated or exported in either version; changes are listed
+/// by what differs: times, crop, split, tags, note, caption text and output file.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<Difference> {
+    let ranges = |s: &Snapshot| -> BTreeSet<(String, usize)> {
+        let annotated = s
+            .project
+            .files
+            .iter()
+            .flat_map(|(file, ranges)| (0..ranges.len()).map(|i| (file.clone(), i)));
+        let exported = s
+            .manifest
+            .entries
+            .iter()
+            .map(|e| (e.source.clone(), e.range));
+        annotated.chain(exported).collect()
+    };
+    let (before, after) = (ranges(old), ranges(new));
+    before
+        .union(&after)
+        .filter_map(|key| {
+            let (source, i) = key;
+            let change = match (before.contains(key), after.contains(key)) {
+                (false, _) => Change::Added,
+                (_, false) => Change::Removed,
+                _ => {
+                    let what: Vec<_> = parts(old, source, *i)
+                        .into_iter()
+                        .zip(parts(new, source, *i))
+                        .filter(|(a, b)| a.1 != b.1)
+                        .map(|(a, _)| a.0)
+                        .collect();
+                    if what.is_empty() {
+                        return None;
+                    }
+                    Change::Changed(what)
+                }
+            };
+            Some(Difference {
+                source: source.clone(),
+                range: *i,
+                change,
+            })
+        })
+        .collect()
+}
+
+/// Lists the output files of a difference export that the newer version no longer has.
+pub const REMOVED_FILE: &str = "removed.txt";
+
+/// Copies an output (a file, or a frame sequence folder with its frames) to `to`.
+fn copy_output(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to.parent().unwrap())?;
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_output(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Copies the outputs of the ranges added or changed from `old` to `new` out of `out_dir` into
+/// the empty folder `target`, under their relative paths, with a manifest of just those and
+/// `removed.txt` listing the outputs `old` has and `new` doesn't. An incremental training run
+/// then only needs that folder. Files are taken from the output folder as it is now, so a range
+/// exported differently since `new` was taken (or not exported at all) is left out and
+/// reported. Returns how many ranges were copied and what was left out.
+pub fn export_delta(
+    out_dir: &Path,
+    old: &Snapshot,
+    new: &Snapshot,
+    target: &Path,
+) -> Result<(usize, Vec<String>), String> {
+    if std::fs::read_dir(target).is_ok_and(|mut dir| dir.next().is_some()) {
+        return Err(trf("{} is not empty", &[&target.display()]));
+    }
+    let current = Manifest::load(out_dir)?;
+    let mut delta = Manifest::default();
+    let mut skipped = Vec::new();
+    for difference in diff(old, new) {
+        if difference.change == Change::Removed {
+            continue;
+        }
+        let (source, i) = (&difference.source, difference.range);
+        let Some(entry) = new
+            .manifest
+            .entries
+            .iter()
+            .find(|e| e.source == *source && e.range == i)
+        else {
+            skipped.push(trf("{} R{}: not exported", &[source, &i]));
+            continue;
+        };
+        let unchanged = current
+            .entries
+            .iter()
+            .any(|e| e.source == entry.source && e.file == entry.file && e.hash == entry.hash);
+        if !unchanged {
+            skipped.push(trf(
+                "{} R{}: exported differently since version {}",
+                &[source, &i, &new.label],
+            ));
+            continue;
+        }
+        for file in entry.files() {
+            let from = out_dir.join(file);
+            if from.exists() {
+                copy_output(&from, &target.join(file)).map_err(|e| {
+                    trf(
+                        "Could not write {}: {}",
+                        &[&target.join(file).display(), &e],
+                    )
+                })?;
+            }
+        }
+        delta.entries.push(entry.clone());
+    }
+    let kept: BTreeSet<&String> = new
+        .manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.files())
+        .collect();
+    let removed: BTreeSet<&String> = old
+        .manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.files())
+        .filter(|f| !kept.contains(f))
+        .collect();
+    std::fs::create_dir_all(target)
+        .and_then(|_| {
+            let lines: Vec<&str> = removed.iter().map(|f| f.as_str()).collect();
+            std::fs::write(target.join(REMOVED_FILE), lines.join("\n"))
+        })
+        .map_err(|e| trf("Could not write {}: {}", &[&target.display(), &e]))?;
+    delta.save(target)?;
+    // So the delta can be checked on the other end of its transfer too
+    checksum::update(target, &delta, &[])?;
+    Ok((delta.entries.len(), skipped))
+}
+
+/// The versions window while it is open.
+pub struct Panel {
+    pub labels: Vec<String>,
+    /// Label for the next snapshot
+    pub label: String,
+    /// Versions to compare; None for the current state
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Result of the last comparison, with its labels
+    pub diff: Option<(String, String, Vec<Difference>)>,
+    pub status: Option<String>,
+}
+
+impl Panel {
+    pub fn new(out_dir: &Path) -> Panel {
+        let labels = list(out_dir);
+        Panel {
+            label: next_label(&labels),
+            from: labels.last().cloned(),
+            to: None,
+            labels,
+            diff: None,
+            status: None,
+        }
+    }
+}
+
+pub enum Request {
+    Snapshot,
+    Compare,
+    /// Copy the compared difference into a folder
+    ExportDelta,
+}
+
+/// Draws the versions window; returns what the user asked for.
+pub fn show(
+    ctx: &egui::Context,
+    open: &mut bool,
+    panel: &mut Panel,
+    can_snapshot: bool,
+) -> Option<Request> {
+    let mut request = None;
+    egui::Window::new(tr("🏷 Dataset Versions"))
+        .id(egui::Id::new("dataset_versions"))
+        .open(open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Label:"));
+                ui.add(egui::TextEdit::singleline(&mut panel.label).desired_width(80.0));
+                let valid = is_valid_label(panel.label.trim())
+                    && !panel.labels.contains(&panel.label.trim().to_string());
+                let snapshot = ui
+                    .add_enabled(can_snapshot && valid, egui::Button::new(tr("📸 Snapshot")))
+                    .on_hover_text(tr(
+                        "Freeze the annotations, the manifest and the captions under this label",
+                    ));
+                if snapshot.clicked() {
+                    request = Some(Request::Snapshot);
+                }
+            });
+            ui.separator();
+            let label = |version: &Option<String>| {
+                version.clone().unwrap_or_else(|| tr("Current").to_string())
+            };
+            // A difference stays on screen only while it is that of the picked versions
+            let mut picked = false;
+            ui.horizontal(|ui| {
+                for (id, version) in [
+                    ("version_from", &mut panel.from),
+                    ("version_to", &mut panel.to),
+                ] {
+                    egui::ComboBox::from_id_salt(id)
+                        .selected_text(label(version))
+                        .show_ui(ui, |ui| {
+                            for option in panel.labels.iter().cloned().map(Some).chain([None]) {
+                                let text = label(&option);
+                                picked |= ui.selectable_value(version, option, text).changed();
+                            }
+                        });
+                    if id == "version_from" {
+                        ui.label("→");
+                    }
+                }
+                if ui.button(tr("Compare")).clicked() {
+                    request = Some(Request::Compare);
+                }
+            });
+            if picked {
+                panel.diff = None;
+            }
+            if let Some((from, to, diff)) = &panel.diff {
+                let count =
+                    |pick: fn(&Change) -> bool| diff.iter().filter(|d| pick(&d.change)).count();
+                ui.label(trf(
+                    "{} → {}: {} added, {} removed, {} changed",
+                    &[
+                        from,
+                        to,
+                        &count(|c| *c == Change::Added),
+                        &count(|c| *c == Change::Removed),
+                        &count(|c| matches!(c, Change::Changed(_))),
+                    ],
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        for difference in diff {
+                            ui.label(difference.describe());
+                        }
+                    });
+                let copied = diff.iter().any(|d| d.change != Change::Removed);
+                let export = ui
+                    .add_enabled(copied, egui::Button::new(tr("📦 Export Difference…")))
+                    .on_hover_text(tr(
+                        "Copy the added and changed clips into an empty folder, with their manifest and a list of the removed files",
+                    ));
+                if export.clicked() {
+                    request = Some(Request::ExportDelta);
+                }
+            }
+            if let Some(status) = &panel.status {
+                ui.label(status);
+            }
+            ui.add_space(4.0);
+            ui.weak(trf("Stored in {} in the output folder.", &[&DIR]));
+        });
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(ranges: Vec<VideoRange>, captions: &[(usize, &str)]) -> Snapshot {
+        let entries = captions
+            .iter()
+            .map(|(i, _)| manifest::Entry {
+                file: format!("a_range{}.mp4", i),
+                caption: Some(format!("a_range{}.txt", i)),
+                source: "a.mp4".to_string(),
+                range: *i,
+                start_time: 0.0,
+                end_time: 1.0,
+                crop: None,
+                split: None,
+                hash: String::new(),
+                tags: String::new(),
+                exported_as: None,
+                audio: None,
+                preview: None,
+                sprite: None,
+                stats: None,
+            })
+            .collect();
+        let mut manifest = Manifest::default();
+        manifest.entries = entries;
+        Snapshot {
+            version: VERSION,
+            label: "v1".to_string(),
+            created_at: String::new(),
+            project: project::Project::new(
+                PathBuf::from("in"),
+                None,
+                None,
+                vec![(PathBuf::from("in/a.mp4"), ranges)],
+            ),
+            manifest,
+            captions: captions
+                .iter()
+                .map(|(i, c)| (format!("a_range{}.txt", i), c.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn diffs_list_added_removed_and_changed_ranges() {
+        let range = |end_time: f64, note: &str| VideoRange {
+            end_time,
+            note: note.to_string(),
+            ..Default::default()
+        };
+        let old = snapshot(
+            vec![range(1.0, "cat"), range(2.0, "dog"), range(3.0, "bird")],
+            &[(0, "cat"), (1, "dog"), (2, "bird")],
+        );
+        let new = snapshot(
+            vec![range(1.0, "cat"), range(2.5, "dog")],
+            &[(0, "a cat"), (1, "dog")],
+        );
+        let describe: Vec<_> = diff(&old, &new).iter().map(|d| d.describe()).collect();
+        assert_eq!(
+            describe,
+            ["~ a.mp4 R0: caption", "~ a.mp4 R1: times", "− a.mp4 R2"]
+        );
+        let added: Vec<_> = diff(&new, &old).into_iter().map(|d| d.change).collect();
+        assert_eq!(added[2], Change::Added);
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn delta_exports_copy_added_and_changed_clips_and_list_removed_ones() {
+        let range = |end_time: f64| VideoRange {
+            end_time,
+            ..Default::default()
+        };
+        let old = snapshot(
+            vec![range(1.0), range(2.0), range(3.0)],
+            &[(0, "cat"), (1, "dog"), (2, "bird")],
+        );
+        let new = snapshot(vec![range(1.0), range(2.5)], &[(0, "a cat"), (1, "dog")]);
+
+        let dir = std::env::temp_dir().join("viddatatraincrop_versions_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let out = dir.join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        for entry in &new.manifest.entries {
+            for file in entry.files() {
+                std::fs::write(out.join(file), file).unwrap();
+            }
+        }
+        let mut current = Manifest::default();
+        current.entries = new.manifest.entries.clone();
+        // Range 1 was exported again after the version was taken
+        current.entries[1].hash = "later".to_string();
+        current.save(&out).unwrap();
+
+        let target = dir.join("delta");
+        let (copied, skipped) = export_delta(&out, &old, &new, &target).unwrap();
+        assert_eq!((copied, skipped.len()), (1, 1));
+        assert!(target.join("a_range0.mp4").exists());
+        assert!(!target.join("a_range1.mp4").exists());
+        let delta = Manifest::load(&target).unwrap();
+        assert_eq!(delta.entries.len(), 1);
+        let removed = std::fs::read_to_string(target.join(REMOVED_FILE)).unwrap();
+        assert_eq!(removed, "a_range2.mp4\na_range2.txt");
+        let sums = std::fs::read_to_string(target.join(checksum::FILE_NAME)).unwrap();
+        assert!(sums.contains("  a_range0.mp4\n"));
+        assert!(export_delta(&out, &old, &new, &target).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn labels_count_up_and_stay_file_names() {
+        assert_eq!(next_label(&[]), "v1");
+        let labels = ["v2".to_string(), "final".to_string(), "v10".to_string()];
+        assert_eq!(next_label(&labels), "v11");
+        assert!(is_valid_label("v1.2-rc_1"));
+        assert!(!is_valid_label("../v1"));
+        assert!(!is_valid_label(""));
+    }
+}
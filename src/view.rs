@@ -0,0 +1,102 @@
+// Zoom and pan state of the video viewport. Crops stay normalized to the full frame; this only
+// decides which part of the frame is shown and how screen positions map back onto it.
+use eframe::egui;
+
+const MAX_ZOOM: f32 = 8.0;
+
+#[derive(Clone, Copy)]
+pub struct View {
+    /// 1.0 shows the whole frame.
+    pub zoom: f32,
+    /// Normalized frame position shown at the middle of the viewport.
+    center: egui::Pos2,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            center: egui::pos2(0.5, 0.5),
+        }
+    }
+}
+
+impl View {
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > 1.0
+    }
+
+    /// Visible part of the frame in normalized (texture UV) coordinates.
+    pub fn uv(&self) -> egui::Rect {
+        egui::Rect::from_center_size(self.center, egui::Vec2::splat(1.0 / self.zoom))
+    }
+
+    /// Normalized frame position under `screen` when the frame is shown in `rect`.
+    pub fn screen_to_norm(&self, rect: egui::Rect, screen: egui::Pos2) -> egui::Pos2 {
+        let uv = self.uv();
+        egui::pos2(
+            uv.min.x + (screen.x - rect.min.x) / rect.width() * uv.width(),
+            uv.min.y + (screen.y - rect.min.y) / rect.height() * uv.height(),
+        )
+    }
+
+    pub fn norm_to_screen(&self, rect: egui::Rect, norm: egui::Pos2) -> egui::Pos2 {
+        let uv = self.uv();
+        egui::pos2(
+            rect.min.x + (norm.x - uv.min.x) / uv.width() * rect.width(),
+            rect.min.y + (norm.y - uv.min.y) / uv.height() * rect.height(),
+        )
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the frame point under `anchor` in place.
+    pub fn zoom_at(&mut self, rect: egui::Rect, anchor: egui::Pos2, factor: f32) {
+        let before = self.screen_to_norm(rect, anchor);
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        let after = self.screen_to_norm(rect, anchor);
+        self.center += before - after;
+        self.clamp_center();
+    }
+
+    /// Moves the frame along with a drag of `delta` screen points.
+    pub fn pan(&mut self, rect: egui::Rect, delta: egui::Vec2) {
+        let uv = self.uv();
+        self.center -= egui::vec2(
+            delta.x / rect.width() * uv.width(),
+            delta.y / rect.height() * uv.height(),
+        );
+        self.clamp_center();
+    }
+
+    /// Keeps the visible part inside the frame.
+    fn clamp_center(&mut self) {
+        let half = 0.5 / self.zoom;
+        self.center.x = self.center.x.clamp(half, 1.0 - half);
+        self.center.y = self.center.y.clamp(half, 1.0 - half);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_keeps_anchor_in_place() {
+        let rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(640.0, 360.0));
+        let anchor = egui::pos2(200.0, 100.0);
+        let mut view = View::default();
+        let before = view.screen_to_norm(rect, anchor);
+        view.zoom_at(rect, anchor, 3.0);
+        let after = view.screen_to_norm(rect, anchor);
+        assert!((before - after).length() < 1e-5);
+
+        let corner = view.norm_to_screen(rect, view.screen_to_norm(rect, rect.max));
+        assert!((corner - rect.max).length() < 1e-3);
+
+        // Zooming back out always ends up showing the whole frame again
+        view.zoom_at(rect, rect.max, 0.01);
+        assert_eq!(
+            view.uv(),
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0))
+        );
+    }
+}